@@ -0,0 +1,74 @@
+// `cargo bench --bench fusion`: runs expressions the IR fuses with fusion
+// on and off, and prints how many allocations and how long each takes.
+// Allocations are counted by the global allocator, from parsing the
+// expression to its result.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use htb_apl::runtime::evaluator::Session;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const EXPRESSIONS: [&str; 5] = [
+    "+/2×⍳10000",
+    "4 4 4⍴⍳4*3",
+    "⌈/|(⍳10000)-5000",
+    "+/(100 100⍴⍳10000)×2",
+    "+/(1000 100⍴¯1×⍳10)+1",
+];
+
+const RUNS: u32 = 20;
+
+// The allocations of one run, and the mean time of `RUNS`.
+fn measure(session: &mut Session, source: &str) -> (usize, Duration) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    session.execute(source).unwrap();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        session.execute(source).unwrap();
+    }
+    (allocations, start.elapsed() / RUNS)
+}
+
+fn main() {
+    let mut session = Session::default();
+    println!(
+        "{:<26} {:>12} {:>12} {:>12} {:>12}",
+        "expression", "allocs", "fused", "time", "fused"
+    );
+    for source in EXPRESSIONS {
+        session.set_fusion(false);
+        let (allocations, time) = measure(&mut session, source);
+        session.set_fusion(true);
+        let (fused_allocations, fused_time) = measure(&mut session, source);
+        println!(
+            "{:<26} {:>12} {:>12} {:>12?} {:>12?}",
+            source, allocations, fused_allocations, time, fused_time
+        );
+    }
+}
@@ -0,0 +1,52 @@
+// `cargo bench --bench kernels`: the dyadic scalar functions between
+// vectors of each fixed-width type, so that a change that slows a kernel
+// down, or stops a width reaching its kernel at all, shows up in
+// criterion's comparison with the last run.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use htb_apl::runtime::evaluator::Session;
+
+const LENGTH: usize = 100_000;
+
+// Each width, with the prelude function that converts to it. Values on
+// the left run from 6 to 10 and on the right from 1 to 5, so that `×`
+// doesn't overflow even `i8` and `-` doesn't go below 0 for the unsigned
+// widths.
+const WIDTHS: [(&str, &str); 10] = [
+    ("i8", "to_i8"),
+    ("i16", "to_i16"),
+    ("i32", "to_i32"),
+    ("i64", "to_i64"),
+    ("u8", "to_u8"),
+    ("u16", "to_u16"),
+    ("u32", "to_u32"),
+    ("u64", "to_u64"),
+    ("f32", "to_f"),
+    ("f64", "to_d"),
+];
+
+const FUNCTIONS: [&str; 11] = ["+", "-", "×", "÷", "⌈", "⌊", "|", "=", "≠", "<", ">"];
+
+fn kernels(c: &mut Criterion) {
+    let mut session = Session::with_prelude().unwrap();
+    for (width, convert) in WIDTHS {
+        let setup = format!("x←{0} 5+?{1}⍴5 ⋄ y←{0} ?{1}⍴5", convert, LENGTH);
+        session.execute(&setup).unwrap();
+
+        let mut group = c.benchmark_group(width);
+        group.throughput(Throughput::Elements(LENGTH as u64));
+        for function in FUNCTIONS {
+            let source = format!("x{}y", function);
+            group.bench_with_input(
+                BenchmarkId::from_parameter(function),
+                &source,
+                |b, source| b.iter(|| session.execute(black_box(source)).unwrap()),
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, kernels);
+criterion_main!(benches);
@@ -0,0 +1,15 @@
+#![no_main]
+
+// `cargo fuzz run numeric_literal`: any string either reads as a literal or
+// is a `NumericLiteralError`, and what a literal writes back out reads as
+// the same literal.
+
+use htb_apl::tokenizer::numeric_literal::NumericLiteral;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|source: &str| {
+    if let Ok(literal) = source.parse::<NumericLiteral>() {
+        let written = literal.to_string();
+        assert_eq!(written.parse(), Ok(literal), "{}", written);
+    }
+});
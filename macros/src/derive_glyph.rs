@@ -0,0 +1,223 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Error, Fields, Ident, Lit, LitChar, LitStr, Meta, NestedMeta, Result,
+};
+
+/// What a single `#[glyph('⍴', name = "Rho", valence = "ambivalent")]`
+/// attribute says about its variant.
+struct GlyphAttribute {
+    variant: Ident,
+    char: LitChar,
+    name: LitStr,
+    valence: Ident,
+    ascii: Option<LitStr>,
+}
+
+const VALENCES: &[(&str, &str)] = &[
+    ("niladic", "Niladic"),
+    ("monadic", "Monadic"),
+    ("dyadic", "Dyadic"),
+    ("ambivalent", "Ambivalent"),
+    ("monadic operator", "MonadicOperator"),
+    ("dyadic operator", "DyadicOperator"),
+    ("syntax", "Syntax"),
+];
+
+pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Glyph can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut glyphs: Vec<GlyphAttribute> = Vec::new();
+    for variant in &data.variants {
+        for attr in variant
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("glyph"))
+        {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(Error::new_spanned(
+                    &variant.ident,
+                    "#[glyph] can only be used on unit variants",
+                ));
+            }
+
+            let glyph = parse_attribute(variant.ident.clone(), attr.parse_meta()?)?;
+            if let Some(other) = glyphs
+                .iter()
+                .find(|other| other.char.value() == glyph.char.value())
+            {
+                return Err(Error::new(
+                    glyph.char.span(),
+                    format!(
+                        "glyph {:?} is already used by `{}`",
+                        glyph.char.value(),
+                        other.variant
+                    ),
+                ));
+            }
+            glyphs.push(glyph);
+        }
+    }
+
+    let name = &input.ident;
+    let variants: Vec<_> = glyphs.iter().map(|glyph| &glyph.variant).collect();
+    let chars: Vec<_> = glyphs.iter().map(|glyph| &glyph.char).collect();
+    let names: Vec<_> = glyphs.iter().map(|glyph| &glyph.name).collect();
+    let valences: Vec<_> = glyphs.iter().map(|glyph| &glyph.valence).collect();
+    let ascii: Vec<_> = glyphs
+        .iter()
+        .map(|glyph| match &glyph.ascii {
+            Some(alias) => quote!(Some(#alias)),
+            None => quote!(None),
+        })
+        .collect();
+    let (aliases, aliased): (Vec<_>, Vec<_>) = glyphs
+        .iter()
+        .filter_map(|glyph| Some((glyph.ascii.as_ref()?, &glyph.variant)))
+        .unzip();
+
+    Ok(quote! {
+        #[allow(dead_code)]
+        impl #name {
+            /// Every glyph with a `#[glyph]` variant, in declaration order.
+            pub const GLYPHS: &'static [char] = &[#(#chars),*];
+
+            pub fn from_char(c: char) -> Option<Self> {
+                match c {
+                    #(#chars => Some(#name::#variants),)*
+                    _ => None,
+                }
+            }
+
+            pub fn as_char(&self) -> Option<char> {
+                match self {
+                    #(#name::#variants => Some(#chars),)*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            pub fn name(&self) -> Option<&'static str> {
+                match self {
+                    #(#name::#variants => Some(#names),)*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            pub fn valence(&self) -> Option<Valence> {
+                match self {
+                    #(#name::#variants => Some(Valence::#valences),)*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            pub fn ascii_alias(&self) -> Option<&'static str> {
+                match self {
+                    #(#name::#variants => #ascii,)*
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+
+            pub fn from_ascii_alias(alias: &str) -> Option<Self> {
+                match alias {
+                    #(#aliases => Some(#name::#aliased),)*
+                    _ => None,
+                }
+            }
+        }
+    })
+}
+
+fn parse_attribute(variant: Ident, meta: Meta) -> Result<GlyphAttribute> {
+    let list = match meta {
+        Meta::List(list) => list,
+        meta => {
+            return Err(Error::new_spanned(
+                meta,
+                "expected #[glyph('c', valence = \"...\")]",
+            ))
+        }
+    };
+
+    let mut nested = list.nested.iter();
+    let char = match nested.next() {
+        Some(NestedMeta::Lit(Lit::Char(char))) => char.clone(),
+        Some(other) => {
+            return Err(Error::new_spanned(
+                other,
+                "expected the glyph as a char literal",
+            ))
+        }
+        None => {
+            return Err(Error::new_spanned(
+                &list.path,
+                "expected the glyph as a char literal",
+            ))
+        }
+    };
+
+    let mut name = None;
+    let mut valence = None;
+    let mut ascii = None;
+    for meta in nested {
+        let pair = match meta {
+            NestedMeta::Meta(Meta::NameValue(pair)) => pair,
+            other => return Err(Error::new_spanned(other, "expected `key = \"value\"`")),
+        };
+        let value = match &pair.lit {
+            Lit::Str(value) => value.clone(),
+            other => return Err(Error::new_spanned(other, "expected a string literal")),
+        };
+
+        if pair.path.is_ident("name") {
+            name = Some(value);
+        } else if pair.path.is_ident("valence") {
+            valence = Some(parse_valence(&value)?);
+        } else if pair.path.is_ident("ascii") {
+            ascii = Some(value);
+        } else {
+            return Err(Error::new_spanned(
+                &pair.path,
+                "unknown key, expected `name`, `valence` or `ascii`",
+            ));
+        }
+    }
+
+    Ok(GlyphAttribute {
+        name: name.unwrap_or_else(|| LitStr::new(&variant.to_string(), variant.span())),
+        valence: valence
+            .ok_or_else(|| Error::new_spanned(&list.path, "missing `valence = \"...\"`"))?,
+        variant,
+        char,
+        ascii,
+    })
+}
+
+fn parse_valence(value: &LitStr) -> Result<Ident> {
+    match VALENCES.iter().find(|(key, _)| *key == value.value()) {
+        Some((_, variant)) => Ok(Ident::new(variant, value.span())),
+        None => Err(Error::new(
+            value.span(),
+            format!(
+                "unknown valence {:?}, expected one of {}",
+                value.value(),
+                VALENCES
+                    .iter()
+                    .map(|(key, _)| format!("{:?}", key))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )),
+    }
+}
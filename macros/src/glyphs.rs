@@ -0,0 +1,150 @@
+// The one place a glyph is declared. Everything else (the `Token` enum, the
+// lexer, `destream` and `repeat_code!`) is generated from this table, so
+// adding a glyph is a single line here. `glyph_tokens!` turns each row into a
+// `#[glyph(...)]` variant and leaves the lookups to `#[derive(Glyph)]`.
+
+#[derive(Clone, Copy)]
+pub(crate) enum Valence {
+    Niladic,
+    Monadic,
+    Dyadic,
+    Ambivalent,
+    MonadicOperator,
+    DyadicOperator,
+    Syntax,
+}
+
+impl Valence {
+    /// The spelling `#[glyph(..., valence = "...")]` expects.
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Valence::Niladic => "niladic",
+            Valence::Monadic => "monadic",
+            Valence::Dyadic => "dyadic",
+            Valence::Ambivalent => "ambivalent",
+            Valence::MonadicOperator => "monadic operator",
+            Valence::DyadicOperator => "dyadic operator",
+            Valence::Syntax => "syntax",
+        }
+    }
+}
+
+pub(crate) struct Glyph {
+    pub(crate) char: char,
+    pub(crate) variant: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) valence: Valence,
+    pub(crate) ascii: Option<&'static str>,
+}
+
+const fn glyph(
+    char: char,
+    variant: &'static str,
+    name: &'static str,
+    valence: Valence,
+    ascii: Option<&'static str>,
+) -> Glyph {
+    Glyph {
+        char,
+        variant,
+        name,
+        valence,
+        ascii,
+    }
+}
+
+use Valence::*;
+
+#[rustfmt::skip]
+pub(crate) const GLYPHS: &[Glyph] = &[
+    //    char  variant                 name                       valence           ascii
+    glyph('+', "Plus",                 "plus",                    Ambivalent,       None),
+    glyph('-', "Minus",                "minus",                   Ambivalent,       None),
+    glyph('×', "Times",                "times",                   Ambivalent,       None),
+    glyph('÷', "Divide",               "divide",                  Ambivalent,       Some("%")),
+    glyph('⌈', "Upstile",              "upstile",                 Ambivalent,       None),
+    glyph('⌊', "Downstile",            "downstile",               Ambivalent,       None),
+    glyph('*', "Star",                 "star",                    Ambivalent,       None),
+    glyph('!', "ExclamationMark",      "exclamation mark",        Ambivalent,       None),
+    glyph('|', "Stile",                "stile",                   Ambivalent,       None),
+    glyph('⍟', "Log",                  "log",                     Ambivalent,       None),
+    glyph('○', "Circle",               "circle",                  Ambivalent,       None),
+    glyph('⌹', "Domino",               "domino",                  Ambivalent,       None),
+    glyph('⊥', "UpTack",               "up tack",                 Dyadic,           None),
+    glyph('⊤', "DownTack",             "down tack",               Dyadic,           None),
+    glyph('?', "QuestionMark",         "question mark",           Ambivalent,       None),
+
+    glyph('~', "Tilde",                "tilde",                   Ambivalent,       None),
+    glyph('∧', "LogicalAND",           "logical and",             Dyadic,           None),
+    glyph('∨', "LogicalOR",            "logical or",              Dyadic,           None),
+    glyph('⍲', "LogicalNAND",          "logical nand",            Dyadic,           None),
+    glyph('⍱', "LogicalNOR",           "logical nor",             Dyadic,           None),
+    glyph('<', "LessThan",             "less than",               Dyadic,           None),
+    glyph('>', "GreaterThan",          "greater than",            Dyadic,           None),
+    glyph('≤', "LessThanOrEqualTo",    "less than or equal to",   Dyadic,           Some("<=")),
+    glyph('≥', "GreaterThanOrEqualTo", "greater than or equal to", Dyadic,          Some(">=")),
+    glyph('=', "Equal",                "equal",                   Dyadic,           None),
+    glyph('≠', "NotEqual",             "not equal",               Dyadic,           Some("!=")),
+    glyph('≡', "EqualUnderbar",        "equal underbar",          Ambivalent,       Some("==")),
+    glyph('≢', "EqualUnderbarSlash",   "equal underbar slash",    Ambivalent,       Some("!==")),
+
+    glyph('⍴', "Rho",                  "rho",                     Ambivalent,       None),
+    glyph(',', "Comma",                "comma",                   Ambivalent,       None),
+    glyph('⍪', "CommaBar",             "comma bar",               Ambivalent,       None),
+    glyph('⌽', "CircleStile",          "circle stile",            Ambivalent,       None),
+    glyph('⊖', "CircleBar",            "circle bar",              Ambivalent,       None),
+    glyph('⍉', "Transpose",            "transpose",               Ambivalent,       None),
+    glyph('↑', "UpArrow",              "up arrow",                Ambivalent,       None),
+    glyph('↓', "DownArrow",            "down arrow",              Ambivalent,       None),
+    glyph('⊂', "LeftShoe",             "left shoe",               Ambivalent,       None),
+    glyph('⊆', "LeftShoeUnderbar",     "left shoe underbar",      Ambivalent,       None),
+    glyph('∊', "Epsilon",              "epsilon",                 Ambivalent,       None),
+
+    glyph('⌷', "Squad",                "squad",                   Ambivalent,       None),
+    glyph('⊃', "RightShoe",            "right shoe",              Ambivalent,       None),
+    glyph('/', "Slash",                "slash",                   MonadicOperator,  None),
+    glyph('⌿', "SlashBar",             "slash bar",               MonadicOperator,  None),
+    glyph('\\', "Backslash",           "backslash",               MonadicOperator,  None),
+    glyph('⍀', "BackslashBar",         "backslash bar",           MonadicOperator,  None),
+    glyph('∪', "DownShoe",             "down shoe",               Ambivalent,       None),
+    glyph('∩', "UpShoe",               "up shoe",                 Dyadic,           None),
+    glyph('⊣', "LeftTack",             "left tack",               Ambivalent,       None),
+    glyph('⊢', "RightTack",            "right tack",              Ambivalent,       None),
+
+    glyph('⍳', "Iota",                 "iota",                    Ambivalent,       None),
+    glyph('⍸', "IotaUnderbar",         "iota underbar",           Ambivalent,       None),
+    glyph('⍷', "EpsilonUnderbar",      "epsilon underbar",        Dyadic,           None),
+    glyph('⍋', "GradeUp",              "grade up",                Ambivalent,       None),
+    glyph('⍒', "GradeDown",            "grade down",              Ambivalent,       None),
+
+    glyph('¨', "Diaeresis",            "diaeresis",               MonadicOperator,  None),
+    glyph('⍨', "TildeDiaeresis",       "tilde diaeresis",         MonadicOperator,  None),
+    glyph('⍣', "StarDiaeresis",        "star diaeresis",          DyadicOperator,   None),
+    glyph('.', "Dot",                  "dot",                     DyadicOperator,   None),
+    glyph('∘', "Jot",                  "jot",                     DyadicOperator,   None),
+    glyph('⌸', "QuadEqual",            "quad equal",              MonadicOperator,  None),
+    glyph('⍤', "JotDiaeresis",         "jot diaeresis",           DyadicOperator,   None),
+    glyph('⍥', "CircleDieresis",       "circle diaeresis",        DyadicOperator,   None),
+    glyph('⌺', "QuadDiamond",          "quad diamond",            DyadicOperator,   None),
+    glyph('@', "At",                   "at",                      DyadicOperator,   None),
+    glyph('⍠', "QuadColon",            "quad colon",              DyadicOperator,   None),
+
+    glyph('←', "LeftArrow",            "left arrow",              Syntax,           Some("<-")),
+    glyph('⍬', "Zilde",                "zilde",                   Niladic,          None),
+    glyph('⍎', "Hydrant",              "hydrant",                 Monadic,          None),
+    glyph('⍕', "Thorn",                "thorn",                   Ambivalent,       None),
+    glyph('⋄', "Diamond",              "diamond",                 Syntax,           None),
+    glyph('∇', "Del",                  "del",                     Syntax,           None),
+    glyph('⍺', "Alpha",                "alpha",                   Niladic,          None),
+    glyph('⍵', "Omega",                "omega",                   Niladic,          None),
+
+    glyph(':', "Colon",                "colon",                   Syntax,           None),
+    glyph(';', "Semicolon",            "semicolon",               Syntax,           None),
+
+    glyph('(', "OpenRoundBracket",     "open round bracket",      Syntax,           None),
+    glyph('[', "OpenSquareBracket",    "open square bracket",     Syntax,           None),
+    glyph('{', "OpenCurlyBracket",     "open curly bracket",      Syntax,           None),
+    glyph(')', "CloseRoundBracket",    "close round bracket",     Syntax,           None),
+    glyph(']', "CloseSquareBracket",   "close square bracket",    Syntax,           None),
+    glyph('}', "CloseCurlyBracket",    "close curly bracket",     Syntax,           None),
+];
@@ -1,145 +1,85 @@
-use std::str::FromStr;
+mod derive_glyph;
+mod glyphs;
+mod repeat_code;
 
-use proc_macro::{TokenStream, TokenTree};
-use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use glyphs::GLYPHS;
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput};
 
+/// Repeats its body once per row of a table, replacing the placeholder `$a`
+/// with the row's value and `$b` with the row's name.
+///
+/// Without a table the glyph table is used, so `$a` is the glyph as a `char`
+/// literal and `$b` the name of its `Token` variant. A custom table can be
+/// given in front of the body:
+///
+/// ```ignore
+/// repeat_code! {
+///     table = [(1, One), (2, Two)];
+///     const $b: i32 = $a;
+/// }
+/// ```
 #[proc_macro]
 pub fn repeat_code(input: TokenStream) -> TokenStream {
-    let input = input.to_string();
-
-    let mut output = TokenStream::new();
-
-    for (id, str) in [
-        ("+", "Plus"),
-        ("-", "Minus"),
-        ("×", "Times"),
-        ("÷", "Divide"),
-        ("⌈", "Upstile"),
-        ("⌊", "Downstile"),
-        ("*", "Star"),
-        ("!", "ExclamationMark"),
-        ("|", "Stile"),
-        ("⍟", "Log"),
-        ("○", "Circle"),
-        ("⌹", "Domino"),
-        ("⊥", "UpTack"),
-        ("⊤", "DownTack"),
-        ("?", "QuestionMark"),
-        ("~", "Tilde"),
-        ("∧", "LogicalAND"),
-        ("∨", "LogicalOR"),
-        ("⍲", "LogicalNAND"),
-        ("⍱", "LogicalNOR"),
-        ("<", "LessThan"),
-        (">", "GreaterThan"),
-        ("≤", "LessThanOrEqualTo"),
-        ("≥", "GreaterThanOrEqualTo"),
-        ("=", "Equal"),
-        ("≠", "NotEqual"),
-        ("≡", "EqualUnderbar"),
-        ("≢", "EqualUnderbarSlash"),
-        ("⍴", "Rho"),
-        (",", "Comma"),
-        ("⍪", "CommaBar"),
-        ("⌽", "CircleStile"),
-        ("⊖", "CircleBar"),
-        ("⍉", "Transpose"),
-        ("↑", "UpArrow"),
-        ("↓", "DownArrow"),
-        ("⊂", "LeftShoe"),
-        ("⊆", "LeftShoeUnderbar"),
-        ("∊", "Epsilon"),
-        ("⌷", "Squad"),
-        ("⊃", "RightShoe"),
-        ("/", "Slash"),
-        ("⌿", "SlashBar"),
-        ("\\\\", "Backslash"),
-        ("⍀", "BackslashBar"),
-        ("∪", "DownShoe"),
-        ("∩", "UpShoe"),
-        ("⊣", "LeftTack"),
-        ("⊢", "RightTack"),
-        ("⍳", "Iota"),
-        ("⍸", "IotaUnderbar"),
-        ("⍷", "EpsilonUnderbar"),
-        ("⍋", "GradeUp"),
-        ("⍒", "GradeDown"),
-        ("¨", "Diaeresis"),
-        ("⍨", "TildeDiaeresis"),
-        ("⍣", "StarDiaeresis"),
-        (".", "Dot"),
-        ("∘", "Jot"),
-        ("⌸", "QuadEqual"),
-        ("⍤", "JotDiaeresis"),
-        ("⍥", "CircleDieresis"),
-        ("⌺", "QuadDiamond"),
-        ("@", "At"),
-        ("⍠", "QuadColon"),
-        ("←", "LeftArrow"),
-        ("⍬", "Zilde"),
-        ("⍎", "Hydrant"),
-        ("⍕", "Thorn"),
-        ("⋄", "Diamond"),
-        ("∇", "Del"),
-        ("⍺", "Alpha"),
-        ("⍵", "Omega"),
-        (":", "Colon"),
-        ("(", "OpenRoundBracket"),
-        ("[", "OpenSquareBracket"),
-        ("{", "OpenCurlyBracket"),
-        (")", "CloseRoundBracket"),
-        ("]", "CloseSquareBracket"),
-        ("}", "CloseCurlyBracket"),
-    ] {
-        output.extend(TokenStream::from_str(
-            input.clone().replace("$a", id).replace("$b", str).as_str(),
-        ))
+    match repeat_code::expand(input.into()) {
+        Ok(output) => output.into(),
+        Err(error) => error.to_compile_error().into(),
     }
+}
 
-    output
-    // TODO, make it good
-    // let mut intermediate_stream = TokenStream::new();
-
-    // let mut peek_in = input.into_iter().peekable();
-
-    // let mut a_insert_index = Vec::new();
-    // let mut b_insert_index = Vec::new();
-
-    // while let Some(curr) = peek_in.next() {
-    //     let next = peek_in.peek();
-
-    //     match (curr, next) {
-    //         (a, None) => intermediate_stream.extend(TokenTree::),
-    //         (proc_macro::TokenTree::Group(_), Some(_)) => todo!(),
-    //         (proc_macro::TokenTree::Ident(_), Some(_)) => todo!(),
-    //         (proc_macro::TokenTree::Punct(_), Some(_)) => todo!(),
-    //         (proc_macro::TokenTree::Literal(_), Some(_)) => todo!(),
-    //     }
-    // }
+/// Appends one `#[glyph(...)]` unit variant per entry of the glyph table to
+/// the given enum and derives `Glyph` for it.
+#[proc_macro]
+pub fn glyph_tokens(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
 
-    // println!("values: {:?}", input);
-    // println!("hello_world: {:?}", input.to_string());
-    // TokenStream::from_str("println!(\"Hello world\")").unwrap()
+    let variants = match &mut input.data {
+        Data::Enum(data) => &mut data.variants,
+        _ => {
+            return syn::Error::new_spanned(&input.ident, "glyph_tokens! expects an enum")
+                .to_compile_error()
+                .into()
+        }
+    };
 
-    // // Parse the input tokens into a syntax tree
-    // let input = parse_macro_input!(input as DeriveInput);
+    for glyph in GLYPHS {
+        let ident = format_ident!("{}", glyph.variant);
+        let char = glyph.char;
+        let name = glyph.name;
+        let valence = glyph.valence.key();
+        let doc = format!(" `{}` {}", glyph.char, glyph.name);
 
-    // // Build the output, possibly using quasi-quotation
-    // let expanded = quote! {
-    //     // ...
-    // };
+        variants.push(match glyph.ascii {
+            Some(ascii) => parse_quote! {
+                #[doc = #doc]
+                #[glyph(#char, name = #name, valence = #valence, ascii = #ascii)]
+                #ident
+            },
+            None => parse_quote! {
+                #[doc = #doc]
+                #[glyph(#char, name = #name, valence = #valence)]
+                #ident
+            },
+        });
+    }
+    input.attrs.push(parse_quote!(#[derive(::macros::Glyph)]));
 
-    // // Hand the output tokens back to the compiler
-    // TokenStream::from(expanded)
+    TokenStream::from(quote!(#input))
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::repeat_code;
-
-    #[test]
-    fn it_works() {
-        repeat_code!(hello)
+/// Generates `from_char`, `as_char`, `name`, `valence`, `ascii_alias` and
+/// `from_ascii_alias` for an enum whose unit variants carry
+/// `#[glyph('⍴', name = "Rho", valence = "ambivalent", ascii = "...")]`.
+///
+/// `name` defaults to the variant name and `ascii` is optional. The valence is
+/// one of `niladic`, `monadic`, `dyadic`, `ambivalent`, `monadic operator`,
+/// `dyadic operator` or `syntax`, and is returned as the variant of the same
+/// name on a `Valence` enum that has to be in scope.
+#[proc_macro_derive(Glyph, attributes(glyph))]
+pub fn derive_glyph(input: TokenStream) -> TokenStream {
+    match derive_glyph::expand(parse_macro_input!(input as DeriveInput)) {
+        Ok(output) => output.into(),
+        Err(error) => error.to_compile_error().into(),
     }
 }
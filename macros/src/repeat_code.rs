@@ -0,0 +1,152 @@
+use proc_macro2::{Delimiter, Group, Ident, Literal, Span, TokenStream, TokenTree};
+use syn::{Error, Result};
+
+use crate::glyphs::GLYPHS;
+
+/// One row of the table `repeat_code!` iterates over: `$a` is replaced by
+/// `value` and `$b` by `name`.
+struct Entry {
+    value: TokenTree,
+    name: Ident,
+}
+
+pub(crate) fn expand(input: TokenStream) -> Result<TokenStream> {
+    let (table, body) = split_table(input)?;
+
+    let table = match table {
+        Some(table) => table,
+        None => GLYPHS
+            .iter()
+            .map(|glyph| Entry {
+                value: Literal::character(glyph.char).into(),
+                name: Ident::new(glyph.variant, Span::call_site()),
+            })
+            .collect(),
+    };
+
+    let mut output = TokenStream::new();
+    for entry in &table {
+        output.extend(substitute(body.clone(), entry)?);
+    }
+
+    Ok(output)
+}
+
+/// Splits an optional leading `table = [(value, Name), ...];` off the body.
+fn split_table(input: TokenStream) -> Result<(Option<Vec<Entry>>, TokenStream)> {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+
+    match tokens.as_slice() {
+        [TokenTree::Ident(keyword), TokenTree::Punct(eq), TokenTree::Group(rows), rest @ ..]
+            if keyword == "table" && eq.as_char() == '=' =>
+        {
+            if rows.delimiter() != Delimiter::Bracket {
+                return Err(Error::new(rows.span(), "expected `[` to start the table"));
+            }
+            let body = match rest {
+                [TokenTree::Punct(semi), body @ ..] if semi.as_char() == ';' => body,
+                _ => {
+                    return Err(Error::new(
+                        rows.span_close(),
+                        "expected `;` after the table",
+                    ))
+                }
+            };
+
+            Ok((
+                Some(parse_rows(rows.stream())?),
+                body.iter().cloned().collect(),
+            ))
+        }
+        _ => Ok((None, tokens.into_iter().collect())),
+    }
+}
+
+fn parse_rows(rows: TokenStream) -> Result<Vec<Entry>> {
+    let mut table = Vec::new();
+
+    for row in rows {
+        match row {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                table.push(parse_entry(&group)?)
+            }
+            TokenTree::Punct(punct) if punct.as_char() == ',' => {}
+            other => {
+                return Err(Error::new(
+                    other.span(),
+                    "expected a table entry of the form `(value, Name)`",
+                ))
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+fn parse_entry(group: &Group) -> Result<Entry> {
+    let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+
+    match tokens.as_slice() {
+        [value @ TokenTree::Literal(_), TokenTree::Punct(comma), TokenTree::Ident(name)]
+            if comma.as_char() == ',' =>
+        {
+            Ok(Entry {
+                value: value.clone(),
+                name: name.clone(),
+            })
+        }
+        _ => Err(Error::new(
+            group.span(),
+            "expected a table entry of the form `(value, Name)`",
+        )),
+    }
+}
+
+/// Replaces every `$a`/`$b` placeholder in `body`, recursing into groups.
+/// Anything that only looks like a placeholder (e.g. `"$a"` inside a string
+/// literal) is left untouched.
+fn substitute(body: TokenStream, entry: &Entry) -> Result<TokenStream> {
+    let mut output = TokenStream::new();
+    let mut tokens = body.into_iter();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(dollar) if dollar.as_char() == '$' => {
+                let placeholder = match tokens.next() {
+                    Some(TokenTree::Ident(placeholder)) => placeholder,
+                    _ => {
+                        return Err(Error::new(
+                            dollar.span(),
+                            "expected a placeholder (`$a` or `$b`) after `$`",
+                        ))
+                    }
+                };
+
+                let mut replacement = if placeholder == "a" {
+                    entry.value.clone()
+                } else if placeholder == "b" {
+                    entry.name.clone().into()
+                } else {
+                    return Err(Error::new(
+                        placeholder.span(),
+                        format!(
+                            "unknown placeholder `${}`, expected `$a` or `$b`",
+                            placeholder
+                        ),
+                    ));
+                };
+                replacement.set_span(placeholder.span());
+                output.extend(Some(replacement));
+            }
+            TokenTree::Group(group) => {
+                let mut substituted =
+                    Group::new(group.delimiter(), substitute(group.stream(), entry)?);
+                substituted.set_span(group.span());
+                output.extend(Some(TokenTree::Group(substituted)));
+            }
+            token => output.extend(Some(token)),
+        }
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,51 @@
+use macros::Glyph;
+
+#[derive(Debug, PartialEq)]
+pub enum Valence {
+    Niladic,
+    Monadic,
+    Dyadic,
+    Ambivalent,
+    MonadicOperator,
+    DyadicOperator,
+    Syntax,
+}
+
+#[derive(Debug, PartialEq, Glyph)]
+enum Token {
+    Identifier(String),
+
+    #[glyph('⍴', name = "Rho", valence = "ambivalent")]
+    Rho,
+    #[glyph('←', valence = "syntax", ascii = "<-")]
+    LeftArrow,
+    #[glyph('¨', name = "each", valence = "monadic operator")]
+    Diaeresis,
+}
+
+#[test]
+fn it_maps_chars_to_variants() {
+    assert_eq!(Token::from_char('⍴'), Some(Token::Rho));
+    assert_eq!(Token::from_char('¨'), Some(Token::Diaeresis));
+    assert_eq!(Token::from_char('x'), None);
+
+    assert_eq!(Token::Rho.as_char(), Some('⍴'));
+    assert_eq!(Token::Identifier("x".to_string()).as_char(), None);
+    assert_eq!(Token::GLYPHS, &['⍴', '←', '¨']);
+}
+
+#[test]
+fn it_exposes_metadata() {
+    assert_eq!(Token::Rho.name(), Some("Rho"));
+    assert_eq!(Token::LeftArrow.name(), Some("LeftArrow"));
+    assert_eq!(Token::Diaeresis.name(), Some("each"));
+
+    assert_eq!(Token::Rho.valence(), Some(Valence::Ambivalent));
+    assert_eq!(Token::Diaeresis.valence(), Some(Valence::MonadicOperator));
+    assert_eq!(Token::Identifier("x".to_string()).valence(), None);
+
+    assert_eq!(Token::LeftArrow.ascii_alias(), Some("<-"));
+    assert_eq!(Token::Rho.ascii_alias(), None);
+    assert_eq!(Token::from_ascii_alias("<-"), Some(Token::LeftArrow));
+    assert_eq!(Token::from_ascii_alias("<"), None);
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use macros::repeat_code;
+
+#[test]
+fn it_repeats_over_the_glyph_table() {
+    let mut glyphs = HashMap::new();
+    repeat_code! {
+        glyphs.insert($a, stringify!($b));
+    }
+
+    assert_eq!(glyphs[&'⍴'], "Rho");
+    assert_eq!(glyphs[&'\\'], "Backslash");
+}
+
+#[test]
+fn it_leaves_string_literals_alone() {
+    let string = repeat_code! {
+        table = [(1, One)];
+        "$a is $b"
+    };
+
+    assert_eq!(string, "$a is $b");
+}
+
+#[test]
+fn it_accepts_a_custom_table() {
+    repeat_code! {
+        table = [(1, One), (2, Two), ("three", Three)];
+        #[allow(non_upper_case_globals)]
+        const $b: &str = stringify!($a);
+    }
+
+    assert_eq!(One, "1");
+    assert_eq!(Two, "2");
+    assert_eq!(Three, "\"three\"");
+}
@@ -0,0 +1,15 @@
+use macros::Glyph;
+
+enum Valence {
+    Dyadic,
+}
+
+#[derive(Glyph)]
+enum Token {
+    #[glyph('=', valence = "dyadic")]
+    Equal,
+    #[glyph('=', valence = "dyadic")]
+    AlsoEqual,
+}
+
+fn main() {}
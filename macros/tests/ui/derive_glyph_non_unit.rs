@@ -0,0 +1,13 @@
+use macros::Glyph;
+
+enum Valence {
+    Niladic,
+}
+
+#[derive(Glyph)]
+enum Token {
+    #[glyph('⍬', valence = "niladic")]
+    Zilde(Vec<u8>),
+}
+
+fn main() {}
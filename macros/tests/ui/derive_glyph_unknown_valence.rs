@@ -0,0 +1,11 @@
+use macros::Glyph;
+
+enum Valence {}
+
+#[derive(Glyph)]
+enum Token {
+    #[glyph('⍴', valence = "trivalent")]
+    Rho,
+}
+
+fn main() {}
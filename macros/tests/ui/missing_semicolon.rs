@@ -0,0 +1,8 @@
+use macros::repeat_code;
+
+fn main() {
+    repeat_code! {
+        table = [(1, One)]
+        let _ = $a;
+    }
+}
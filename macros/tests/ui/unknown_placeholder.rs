@@ -0,0 +1,7 @@
+use macros::repeat_code;
+
+fn main() {
+    repeat_code! {
+        let _ = $c;
+    }
+}
@@ -1,7 +1,207 @@
+// The errors APL code can see. Each has the number Dyalog gives it, which
+// `⎕EN` reports, `⎕SIGNAL` raises and `:Trap` and `::` guards catch by.
+// The runtime raises them as they are, wrapped in `anyhow::Error` and
+// perhaps in context such as the module they came from; `Errors::of`
+// finds them again.
+
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum Errors {
     #[error("Unexpected token {0:?} at {1}")]
     UnexpectedToken(crate::tokenizer::Token, crate::tokenizer::Loc),
+    #[error("{0}, in the literal at {1}")]
+    InvalidNumericLiteral(
+        crate::tokenizer::numeric_literal::NumericLiteralError,
+        crate::tokenizer::Loc,
+    ),
+    #[error("SYNTAX ERROR{}", detail(.0))]
+    Syntax(String),
+    #[error("INDEX ERROR{}", detail(.0))]
+    Index(String),
+    #[error("RANK ERROR{}", detail(.0))]
+    Rank(String),
+    #[error("LENGTH ERROR{}", detail(.0))]
+    Length(String),
+    #[error("VALUE ERROR{}", detail(.0))]
+    Value(String),
+    #[error("DOMAIN ERROR{}", detail(.0))]
+    Domain(String),
+    #[error("NONCE ERROR{}", detail(.0))]
+    Nonce(String),
+    // A function called with an argument it doesn't take, which Dyalog
+    // counts as a syntax error.
+    #[error("VALENCE ERROR{}", detail(.0))]
+    Valence(String),
+    // Raised by `⎕SIGNAL` with a number and message of the program's own.
+    #[error("{1}")]
+    Signaled(u32, String),
+}
+
+// A detail after the name of an error, if there is one.
+fn detail(detail: &str) -> String {
+    match detail {
+        "" => String::new(),
+        detail => format!(": {}", detail),
+    }
+}
+
+// The errors with a name of their own, the first with each number being
+// the one `⎕SIGNAL` raises for it.
+const NAMES: [&str; 8] = [
+    "SYNTAX ERROR",
+    "INDEX ERROR",
+    "RANK ERROR",
+    "LENGTH ERROR",
+    "VALUE ERROR",
+    "DOMAIN ERROR",
+    "NONCE ERROR",
+    "VALENCE ERROR",
+];
+
+impl Errors {
+    /// The error named `name`, such as `DOMAIN ERROR`, if there is one.
+    fn named(name: &str, detail: String) -> Option<Errors> {
+        Some(match name {
+            "SYNTAX ERROR" => Errors::Syntax(detail),
+            "INDEX ERROR" => Errors::Index(detail),
+            "RANK ERROR" => Errors::Rank(detail),
+            "LENGTH ERROR" => Errors::Length(detail),
+            "VALUE ERROR" => Errors::Value(detail),
+            "DOMAIN ERROR" => Errors::Domain(detail),
+            "NONCE ERROR" => Errors::Nonce(detail),
+            "VALENCE ERROR" => Errors::Valence(detail),
+            _ => return None,
+        })
+    }
+
+    /// The error `error` was raised as, through any context around it.
+    /// Errors from outside APL, such as a file that can't be read, are
+    /// domain errors.
+    pub fn of(error: &anyhow::Error) -> Errors {
+        match error.downcast_ref::<Errors>() {
+            Some(error) => error.clone(),
+            None => Errors::Domain(error.to_string()),
+        }
+    }
+
+    /// The error `⎕SIGNAL` raises for `number`: the named error if it has
+    /// that number, with `message` if one is given, or else one of the
+    /// program's own.
+    pub fn signal(number: u32, message: Option<String>) -> Errors {
+        let detail = message.clone().unwrap_or_default();
+        let named = NAMES
+            .iter()
+            .filter_map(|name| Errors::named(name, detail.clone()))
+            .find(|error| error.number() == number);
+        match (named, message) {
+            (Some(error), _) => error,
+            (None, Some(message)) => Errors::Signaled(number, message),
+            (None, None) => Errors::Signaled(number, format!("ERROR {}", number)),
+        }
+    }
+
+    /// The number `⎕EN` gives the error.
+    pub fn number(&self) -> u32 {
+        match self {
+            Errors::UnexpectedToken(..)
+            | Errors::InvalidNumericLiteral(..)
+            | Errors::Syntax(_)
+            | Errors::Valence(_) => 2,
+            Errors::Index(_) => 3,
+            Errors::Rank(_) => 4,
+            Errors::Length(_) => 5,
+            Errors::Value(_) => 6,
+            Errors::Domain(_) => 11,
+            Errors::Nonce(_) => 16,
+            Errors::Signaled(number, _) => *number,
+        }
+    }
+
+    /// The name of the error, as the first line of `⎕DM`.
+    pub fn name(&self) -> String {
+        match self {
+            Errors::UnexpectedToken(..) | Errors::InvalidNumericLiteral(..) => {
+                "SYNTAX ERROR".to_string()
+            }
+            Errors::Signaled(_, message) => message.clone(),
+            error => {
+                let message = error.to_string();
+                message.split(": ").next().unwrap().to_string()
+            }
+        }
+    }
+
+    /// What went wrong, beyond the name of the error.
+    pub fn detail(&self) -> String {
+        match self {
+            Errors::Signaled(..) => String::new(),
+            Errors::UnexpectedToken(..) | Errors::InvalidNumericLiteral(..) => self.to_string(),
+            Errors::Syntax(detail)
+            | Errors::Index(detail)
+            | Errors::Rank(detail)
+            | Errors::Length(detail)
+            | Errors::Value(detail)
+            | Errors::Domain(detail)
+            | Errors::Nonce(detail)
+            | Errors::Valence(detail) => detail.clone(),
+        }
+    }
+
+    /// Whether a `:Trap` or `::` guard for the error numbers `codes`
+    /// catches the error; 0 catches every error.
+    pub fn trapped_by(&self, codes: &[u32]) -> bool {
+        codes
+            .iter()
+            .any(|code| *code == 0 || *code == self.number())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Errors;
+
+    #[test]
+    fn it_finds_errors_through_their_context() {
+        let error = Errors::of(&anyhow::anyhow!(Errors::Length("1 2 + 1 2 3".to_string())));
+        assert_eq!(error.number(), 5);
+        assert_eq!(error.name(), "LENGTH ERROR");
+        assert_eq!(error.detail(), "1 2 + 1 2 3");
+        assert_eq!(error.to_string(), "LENGTH ERROR: 1 2 + 1 2 3");
+
+        let wrapped = anyhow::Error::new(Errors::Index("3".to_string()))
+            .context("in ⍎'x[3]'")
+            .context("in module m");
+        assert_eq!(Errors::of(&wrapped).number(), 3);
+        assert_eq!(Errors::of(&wrapped).detail(), "3");
+        assert_eq!(
+            Errors::of(&anyhow::Error::new(Errors::signal(500, None)).context("in ⍎")).number(),
+            500
+        );
+
+        // Only what was raised as an error counts: a message named like
+        // one is still a domain error.
+        assert_eq!(Errors::of(&anyhow::anyhow!("no such file")).number(), 11);
+        let named = Errors::of(&anyhow::anyhow!("RANK ERROR: 1 2"));
+        assert_eq!(
+            (named.number(), named.detail()),
+            (11, "RANK ERROR: 1 2".to_string())
+        );
+    }
+
+    #[test]
+    fn it_signals_errors_by_number() {
+        assert_eq!(Errors::signal(11, None).to_string(), "DOMAIN ERROR");
+        assert_eq!(
+            Errors::of(&anyhow::anyhow!(Errors::Domain(String::new()))).number(),
+            11
+        );
+        assert_eq!(Errors::signal(4, None).name(), "RANK ERROR");
+        let own = Errors::signal(500, Some("oops".to_string()));
+        assert_eq!((own.number(), own.name()), (500, "oops".to_string()));
+        assert_eq!(Errors::signal(501, None).to_string(), "ERROR 501");
+        assert!(own.trapped_by(&[0]));
+        assert!(own.trapped_by(&[11, 500]));
+        assert!(!own.trapped_by(&[11]));
+    }
 }
@@ -0,0 +1,8 @@
+mod errors;
+mod ext;
+mod macro_tests;
+pub mod normalizer;
+pub mod parser;
+pub mod runtime;
+pub mod tokenizer;
+mod typing;
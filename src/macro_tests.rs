@@ -1,14 +1,12 @@
 #[cfg(test)]
 mod tests {
+    use crate::tokenizer::Token;
     use macros::repeat_code;
 
     #[test]
     fn hello_world() {
-        
-        repeat_code!{
-            let s = "$a";
-            println!("{} is $b",s);
+        repeat_code! {
+            assert_eq!(Token::from_char($a), Some(Token::$b));
         }
-        
     }
 }
@@ -1,18 +1,65 @@
-use crate::normalizer::normalize_apl_code;
-use crate::tokenizer::bracket_partitioner::tokenize_to_partition;
-use crate::tokenizer::tokenize;
+// The driver: runs a program, from the file named by its argument or else
+// from standard input, in a session with the prelude loaded, and prints the
+// value of its last statement. With `--idioms` it also lists on standard
+// error the idioms that were recognised, to check that hot loops are.
+// `--path=dirs` adds directories, separated as in `PATH`, to the search
+// path for `:Import`, after the current directory.
 
-mod errors;
-mod ext;
-mod macro_tests;
-mod normalizer;
-mod tokenizer;
-mod typing;
+use std::io::Read;
 
-fn main() {
-    let program_to_compile = "nDCube ← {v←⍵ ⋄ ⍺{⍺=1u4:v/⍵ ⋄ v/[⍺-1] (⍺-1) ∇ ⍵} (⍺/⍵) ⍴ ⍳⍵*⍺}";
+use htb_apl::runtime::evaluator::Session;
 
-    let stream = tokenize(normalize_apl_code(program_to_compile.to_string())).unwrap();
+fn main() {
+    let (flags, files): (Vec<String>, Vec<String>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let idioms = flags.iter().any(|flag| flag == "--idioms");
+    let path: Vec<_> = flags
+        .iter()
+        .filter_map(|flag| flag.strip_prefix("--path="))
+        .flat_map(std::env::split_paths)
+        .collect();
+    if let Some(flag) = flags
+        .iter()
+        .find(|flag| *flag != "--idioms" && !flag.starts_with("--path="))
+    {
+        eprintln!("unknown flag {}", flag);
+        std::process::exit(2)
+    }
 
-    println!("{:?}", tokenize_to_partition(stream).unwrap());
+    let source = match files.first() {
+        Some(file) => std::fs::read_to_string(file).unwrap_or_else(|error| {
+            eprintln!("{}: {}", file, error);
+            std::process::exit(1)
+        }),
+        None => {
+            let mut source = String::new();
+            if let Err(error) = std::io::stdin().read_to_string(&mut source) {
+                eprintln!("{}", error);
+                std::process::exit(1)
+            }
+            source
+        }
+    };
+    let mut session = Session::with_prelude().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(1)
+    });
+    for directory in path {
+        session.search(directory);
+    }
+    let result = session.execute(&source);
+    if idioms {
+        for idiom in session.idioms() {
+            eprintln!("idiom: {}", idiom);
+        }
+    }
+    match result {
+        Ok(Some(result)) => println!("{}", result),
+        Ok(None) => {}
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1)
+        }
+    }
 }
@@ -0,0 +1,1233 @@
+// Builds the syntax tree of a program from the partitioner's statements.
+// Within a statement, strands bind first, then operators, then functions:
+// a dyadic operator takes everything bound so far on its left as its left
+// operand, and just the next item on its right, so `f∘g∘h` is `(f∘g)∘h`
+// and `+⍤1 0` has the rank `1 0`. Functions then apply right to left, and
+// a phrase that ends in a function is a train. Whether a name is an array
+// or a function is known from its latest assignment, as it has to be to
+// parse APL at all; names that have never been assigned are arrays, and
+// system functions such as `⎕INTO` are functions.
+//
+// A tradfn runs from a line starting with `∇` and its header to a line of
+// just `∇`. Its lines are grouped into control structures, each opened by
+// a keyword such as `:If` and closed by its own end keyword or `:End`.
+// `:Import name` parses a module when it is met, so that its members are
+// known as arrays or functions in `name.member`.
+
+pub mod idioms;
+pub mod modules;
+pub mod tree;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::errors::Errors;
+use crate::parser::idioms::Idiom;
+use crate::parser::modules::{Loader, Module};
+use crate::tokenizer::bracket_partitioner::{PartitionStream, Partitioner};
+use crate::tokenizer::numeric_literal::NumericLiteral;
+use crate::tokenizer::{Loc, Token, Valence};
+use crate::typing::numeric::{settle, strand_precision};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Number(NumericLiteral),
+    String(String),
+    Zilde,
+    // A name, `⍺` or `⍵`.
+    Name(String),
+    // Arrays side by side, `1 2 3` or `'ab' (1 2)`.
+    Strand(Vec<Expr>),
+    Monadic(Function, Box<Expr>),
+    Dyadic(Box<Expr>, Function, Box<Expr>),
+    Assign(String, Box<Expr>),
+    // `array[indices]`.
+    Index(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Function {
+    Primitive(Token),
+    // A name bound to a function, or `∇`.
+    Name(String),
+    Dfn(Rc<[Statement]>),
+    Tradfn(Rc<Tradfn>),
+    // An operator and its operands, the right one for dyadic operators only.
+    Derived(Token, Box<Operand>, Option<Box<Operand>>),
+    // `∘.f`.
+    Outer(Box<Function>),
+    // `(f g h)` or `(A g h)`.
+    Fork(Box<Operand>, Box<Function>, Box<Function>),
+    // `(g h)`.
+    Atop(Box<Function>, Box<Function>),
+    // A phrase with a fast implementation, see `idioms`.
+    Idiom(Idiom, Box<Function>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Operand {
+    Function(Function),
+    Array(Expr),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    Expr(Expr),
+    // `condition: result`, in a dfn.
+    Guard(Expr, Expr),
+    // `codes:: result`, in a dfn: the result if the rest of the dfn fails
+    // with one of the error numbers `codes`.
+    ErrorGuard(Expr, Expr),
+    // `name←function`.
+    Define(String, Function),
+    // `:Import name`.
+    Import(String, Rc<Module>),
+}
+
+/// A traditional function, from its header `[result←][left] name [right]`
+/// and the locals listed after it, each after a `;`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tradfn {
+    pub name: String,
+    pub result: Option<String>,
+    pub left: Option<String>,
+    pub right: Option<String>,
+    pub locals: Vec<String>,
+    pub body: Vec<Line>,
+}
+
+/// A line of a tradfn, or a control structure and the lines inside it.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Line {
+    Statement(Statement),
+    // `:If` and each `:ElseIf`, with their conditions, then `:Else`.
+    If(Vec<(Expr, Vec<Line>)>, Vec<Line>),
+    // `:For names :In array`.
+    For(Vec<String>, Expr, Vec<Line>),
+    // `:While`, ending in `:EndWhile` or in an `:Until` condition.
+    While(Expr, Vec<Line>, Option<Expr>),
+    // `:Repeat`, ending in `:EndRepeat` or in an `:Until` condition.
+    Repeat(Vec<Line>, Option<Expr>),
+    // `:Select`, its cases in order, then `:Else`.
+    Select(Expr, Vec<Case>, Vec<Line>),
+    // `:Trap` and the error numbers it catches, then `:Else`.
+    Trap(Expr, Vec<Line>, Vec<Line>),
+    Leave,
+    Continue,
+    Return,
+}
+
+/// A `:Case`, which matches its value, or a `:CaseList`, which matches any
+/// item of it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Case {
+    pub list: bool,
+    pub value: Expr,
+    pub body: Vec<Line>,
+}
+
+/// The system functions, which are functions without ever being assigned.
+pub const SYSTEM_FUNCTIONS: [&str; 3] = ["⎕INTO", "⎕OVERFLOW", "⎕SIGNAL"];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum NameClass {
+    Array,
+    Function,
+}
+
+// A token or a bracketed group, in the order they appear in a statement.
+#[derive(Clone, Copy)]
+enum Item<'a> {
+    Token(&'a Token, &'a Loc),
+    Round(&'a PartitionStream),
+    Square(&'a PartitionStream),
+    Curly(&'a PartitionStream),
+}
+
+// The statements of a partition, split at each `⋄`.
+fn statements(stream: &PartitionStream) -> Vec<Vec<Item<'_>>> {
+    let mut statements = vec![];
+    for partition in stream {
+        let parts = match partition {
+            Partitioner::Statement(parts) => parts,
+            _ => continue,
+        };
+        let mut items = vec![];
+        for part in parts {
+            match part {
+                Partitioner::Expression(tokens) => items.extend(
+                    tokens
+                        .iter()
+                        .filter(|(token, _)| *token != Token::NL)
+                        .map(|(token, loc)| Item::Token(token, loc)),
+                ),
+                Partitioner::RoundContainer(inner) => items.push(Item::Round(inner)),
+                Partitioner::SquareContainer(inner) => items.push(Item::Square(inner)),
+                Partitioner::CurlyContainer(inner) => items.push(Item::Curly(inner)),
+                Partitioner::ExpressionSeperator => statements.push(std::mem::take(&mut items)),
+                Partitioner::Statement(_) => {}
+            }
+        }
+        statements.push(items);
+    }
+    statements.retain(|items| !items.is_empty());
+    statements
+}
+
+pub(crate) fn spell(token: &Token) -> String {
+    match (token, token.as_char()) {
+        (_, Some(c)) => c.to_string(),
+        (Token::Keyword(word), None) => format!(":{}", word),
+        (token, None) => format!("{:?}", token),
+    }
+}
+
+// The keyword a line starts with, and the rest of the line.
+fn keyword<'a, 'b>(items: &'b [Item<'a>]) -> Option<(&'a str, &'b [Item<'a>])> {
+    match items.split_first() {
+        Some((Item::Token(Token::Keyword(word), _), rest)) => Some((word.as_str(), rest)),
+        _ => None,
+    }
+}
+
+fn names(items: &[Item]) -> Option<Vec<String>> {
+    items
+        .iter()
+        .map(|item| match item {
+            Item::Token(Token::Identifier(name), _) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// The parts of a tradfn header, split at each `;`: the result and the
+// arguments either side of the name, then the locals. Called with one
+// argument, a tradfn leaves its left argument unset.
+fn header(items: &[Item], loc: &Loc) -> anyhow::Result<Tradfn> {
+    let mut parts = items.split(|item| matches!(item, Item::Token(Token::Semicolon, _)));
+    let signature = parts.next().unwrap_or_default();
+    let (result, signature) = match signature {
+        [Item::Token(Token::Identifier(result), _), Item::Token(Token::LeftArrow, _), rest @ ..] => {
+            (Some(result.clone()), rest)
+        }
+        signature => (None, signature),
+    };
+    // The left argument may be in braces, as a reminder that it is optional
+    let braced = |inner| match statements(inner).as_slice() {
+        [items] => names(items).filter(|names| names.len() == 1),
+        _ => None,
+    };
+    let names = match signature {
+        [Item::Curly(left), rest @ ..] => braced(left)
+            .zip(names(rest).filter(|rest| rest.len() == 2))
+            .map(|(mut left, rest)| {
+                left.extend(rest);
+                left
+            }),
+        signature => names(signature),
+    };
+    let mut names = match names {
+        Some(names) if !names.is_empty() && names.len() <= 3 => names.into_iter(),
+        _ => anyhow::bail!(Errors::Syntax(format!(
+            "the header at {} should be [result←][left] name [right]",
+            loc
+        ))),
+    };
+    let (left, name, right) = match names.len() {
+        1 => (None, names.next(), None),
+        2 => (None, names.next(), names.next()),
+        _ => (names.next(), names.next(), names.next()),
+    };
+    let locals = parts
+        .map(|part| match part {
+            [Item::Token(Token::Identifier(local), _)] => Ok(local.clone()),
+            _ => anyhow::bail!(Errors::Syntax(format!(
+                "the locals at {} should be names",
+                loc
+            ))),
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(Tradfn {
+        name: name.unwrap(),
+        result,
+        left,
+        right,
+        locals,
+        body: vec![],
+    })
+}
+
+// A statement part way through parsing: operators not yet bound to their
+// operands, and assignments waiting for their value.
+enum Node {
+    Array(Expr),
+    Function(Function),
+    MonadicOperator(Token, Loc),
+    DyadicOperator(Token, Loc),
+    // `∘.`
+    Outer(Loc),
+    Assign(String),
+}
+
+enum Phrase {
+    Array(Expr),
+    Function(Function),
+}
+
+/// Parses programs statement by statement. It remembers which names were
+/// last assigned functions, so that a session can be parsed a piece at a
+/// time.
+pub struct Parser {
+    // The global scope, then one for each dfn being parsed.
+    scopes: Vec<HashMap<String, NameClass>>,
+    // What the members of each imported module hold.
+    modules: HashMap<String, HashMap<String, NameClass>>,
+    loader: Rc<RefCell<Loader>>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            scopes: vec![HashMap::new()],
+            modules: HashMap::new(),
+            loader: Rc::new(RefCell::new(Loader::default())),
+        }
+    }
+}
+
+impl Parser {
+    pub fn parse(&mut self, stream: &PartitionStream) -> anyhow::Result<Vec<Statement>> {
+        let lines = statements(stream);
+        let mut parsed = Vec::with_capacity(lines.len());
+        let mut at = 0;
+        while let Some(items) = lines.get(at) {
+            at += 1;
+            if let Some(("Import", names)) = keyword(items) {
+                parsed.extend(self.import(names)?);
+                continue;
+            }
+            parsed.push(match items.split_first() {
+                Some((Item::Token(Token::Del, loc), header)) => {
+                    self.tradfn(header, loc, &lines, &mut at)?
+                }
+                _ => self.statement(items, false)?,
+            });
+        }
+        Ok(parsed)
+    }
+
+    /// Adds a directory to the end of the path modules are looked for in.
+    pub fn search(&mut self, directory: impl Into<std::path::PathBuf>) {
+        self.loader.borrow_mut().search(directory);
+    }
+
+    /// Parses a phrase that should give a function, such as a train to draw
+    /// the tree of.
+    pub fn function(&mut self, stream: &PartitionStream) -> anyhow::Result<Function> {
+        match statements(stream).as_slice() {
+            [items] => {
+                let nodes = self.bind(items)?;
+                match self.build(nodes)? {
+                    Phrase::Function(function) => Ok(function),
+                    Phrase::Array(_) => {
+                        anyhow::bail!(Errors::Syntax(
+                            "expected a function, got an array".to_string()
+                        ))
+                    }
+                }
+            }
+            _ => anyhow::bail!(Errors::Syntax("expected a single function".to_string())),
+        }
+    }
+
+    /// Records what a name holds, for parsing code that uses names it has
+    /// not seen assigned, as `⍎` does.
+    pub fn declare(&mut self, name: &str, function: bool) {
+        let class = match function {
+            true => NameClass::Function,
+            false => NameClass::Array,
+        };
+        self.define(name, class);
+    }
+
+    /// Makes the names declared so far the prelude, which the modules the
+    /// parser imports can use too.
+    pub fn share_prelude(&mut self) {
+        self.loader.borrow_mut().prelude = self.scopes[0].clone();
+    }
+
+    /// Records what the members of a module hold, as `declare` does for
+    /// names.
+    pub fn declare_module(
+        &mut self,
+        name: &str,
+        members: impl IntoIterator<Item = (String, bool)>,
+    ) {
+        let members = members
+            .into_iter()
+            .map(|(member, function)| match function {
+                true => (member, NameClass::Function),
+                false => (member, NameClass::Array),
+            })
+            .collect();
+        self.modules.insert(name.to_string(), members);
+    }
+
+    fn import(&mut self, names: &[Item]) -> anyhow::Result<Vec<Statement>> {
+        let names = match self::names(names) {
+            Some(names) if !names.is_empty() => names,
+            _ => anyhow::bail!(Errors::Syntax(":Import expects module names".to_string())),
+        };
+        let mut imports = Vec::with_capacity(names.len());
+        for name in names {
+            let module = modules::load(&self.loader, &name)?;
+            self.define(&name, NameClass::Array);
+            self.modules.insert(name.clone(), module.exports.clone());
+            imports.push(Statement::Import(name, module));
+        }
+        Ok(imports)
+    }
+
+    fn class(&self, name: &str) -> NameClass {
+        if SYSTEM_FUNCTIONS.contains(&name) {
+            return NameClass::Function;
+        }
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+            .or_else(|| self.loader.borrow().prelude.get(name).copied())
+            .unwrap_or(NameClass::Array)
+    }
+
+    fn define(&mut self, name: &str, class: NameClass) {
+        if self.scopes.len() == 1 {
+            self.modules.remove(name);
+        }
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), class);
+    }
+
+    fn statement(&mut self, items: &[Item], in_dfn: bool) -> anyhow::Result<Statement> {
+        let colon = items
+            .iter()
+            .position(|item| matches!(item, Item::Token(Token::Colon, _)));
+        if let Some(colon) = colon {
+            if let (false, Item::Token(_, loc)) = (in_dfn, items[colon]) {
+                anyhow::bail!(Errors::Syntax(format!("a guard outside a dfn at {}", loc)));
+            }
+            if let Some(Item::Token(Token::Colon, _)) = items.get(colon + 1) {
+                return Ok(Statement::ErrorGuard(
+                    self.array(&items[..colon])?,
+                    self.array(&items[colon + 2..])?,
+                ));
+            }
+            return Ok(Statement::Guard(
+                self.array(&items[..colon])?,
+                self.array(&items[colon + 1..])?,
+            ));
+        }
+
+        let mut nodes = self.bind(items)?;
+        if let [Node::Assign(_), .., Node::Function(_)] = nodes.as_slice() {
+            let name = match nodes.remove(0) {
+                Node::Assign(name) => name,
+                _ => unreachable!(),
+            };
+            let function = match self.build(nodes)? {
+                Phrase::Function(function) => function,
+                Phrase::Array(_) => unreachable!("the phrase ends in a function"),
+            };
+            self.define(&name, NameClass::Function);
+            return Ok(Statement::Define(name, function));
+        }
+        match self.build(nodes)? {
+            Phrase::Array(expr) => Ok(Statement::Expr(expr)),
+            Phrase::Function(_) => {
+                anyhow::bail!(Errors::Syntax("a function with no argument".to_string()))
+            }
+        }
+    }
+
+    fn array(&mut self, items: &[Item]) -> anyhow::Result<Expr> {
+        let nodes = self.bind(items)?;
+        match self.build(nodes)? {
+            Phrase::Array(expr) => Ok(expr),
+            Phrase::Function(_) => anyhow::bail!(Errors::Syntax(
+                "expected an array, got a function".to_string()
+            )),
+        }
+    }
+
+    // A tradfn, from its header to the line of just `∇` that closes it. Its
+    // arguments, result and locals are arrays in its body, and its name is
+    // a function there and after, or an array if it is niladic.
+    fn tradfn(
+        &mut self,
+        header_items: &[Item],
+        loc: &Loc,
+        lines: &[Vec<Item>],
+        at: &mut usize,
+    ) -> anyhow::Result<Statement> {
+        let mut tradfn = header(header_items, loc)?;
+        let end = lines[*at..]
+            .iter()
+            .position(|items| matches!(items.as_slice(), [Item::Token(Token::Del, _)]));
+        let body = match end {
+            Some(end) => &lines[*at..*at + end],
+            None => anyhow::bail!(Errors::Syntax(format!("the ∇ at {} is never closed", loc))),
+        };
+        *at += body.len() + 1;
+
+        let class = match tradfn.right {
+            Some(_) => NameClass::Function,
+            None => NameClass::Array,
+        };
+        self.define(&tradfn.name, class);
+        self.scopes.push(HashMap::new());
+        let parameters = [&tradfn.result, &tradfn.left, &tradfn.right];
+        for name in parameters.iter().copied().flatten().chain(&tradfn.locals) {
+            self.scopes
+                .last_mut()
+                .unwrap()
+                .insert(name.clone(), NameClass::Array);
+        }
+        let mut line = 0;
+        let parsed = self.lines(body, &mut line);
+        self.scopes.pop();
+        tradfn.body = parsed?;
+        if let Some((word, _)) = body.get(line).and_then(|items| keyword(items)) {
+            anyhow::bail!(Errors::Syntax(format!(
+                ":{} outside the structure it belongs to",
+                word
+            )));
+        }
+
+        let name = tradfn.name.clone();
+        Ok(Statement::Define(name, Function::Tradfn(Rc::new(tradfn))))
+    }
+
+    // Lines of a tradfn from `*at`, up to the end or to a keyword that goes
+    // on or closes the structure they are in, which is left to the caller.
+    fn lines(&mut self, lines: &[Vec<Item>], at: &mut usize) -> anyhow::Result<Vec<Line>> {
+        let mut parsed = vec![];
+        while let Some(items) = lines.get(*at) {
+            let (word, rest) = match keyword(items) {
+                Some(keyword) => keyword,
+                None => {
+                    *at += 1;
+                    parsed.push(Line::Statement(self.statement(items, false)?));
+                    continue;
+                }
+            };
+            let line = match word {
+                "If" => {
+                    *at += 1;
+                    let mut arms = vec![(self.array(rest)?, self.lines(lines, at)?)];
+                    let mut otherwise = vec![];
+                    while let Some((word, rest)) = lines.get(*at).and_then(|items| keyword(items)) {
+                        match (word, otherwise.is_empty()) {
+                            ("ElseIf", true) => {
+                                *at += 1;
+                                arms.push((self.array(rest)?, self.lines(lines, at)?));
+                            }
+                            ("Else", true) => {
+                                *at += 1;
+                                otherwise = self.lines(lines, at)?;
+                            }
+                            _ => break,
+                        }
+                    }
+                    self.close(lines, at, "If", "EndIf")?;
+                    Line::If(arms, otherwise)
+                }
+                "For" => {
+                    let split = rest.iter().position(
+                        |item| matches!(item, Item::Token(Token::Keyword(word), _) if word == "In"),
+                    );
+                    let (variables, array) = match split.map(|split| rest.split_at(split)) {
+                        Some((variables, array)) => (names(variables), &array[1..]),
+                        None => (None, rest),
+                    };
+                    let variables = match variables {
+                        Some(variables) if !variables.is_empty() => variables,
+                        _ => {
+                            anyhow::bail!(Errors::Syntax(":For expects names then :In".to_string()))
+                        }
+                    };
+                    for name in &variables {
+                        self.define(name, NameClass::Array);
+                    }
+                    *at += 1;
+                    let array = self.array(array)?;
+                    let body = self.lines(lines, at)?;
+                    self.close(lines, at, "For", "EndFor")?;
+                    Line::For(variables, array, body)
+                }
+                "While" => {
+                    *at += 1;
+                    let condition = self.array(rest)?;
+                    let body = self.lines(lines, at)?;
+                    let until = self.close(lines, at, "While", "EndWhile")?;
+                    Line::While(condition, body, until)
+                }
+                "Repeat" if rest.is_empty() => {
+                    *at += 1;
+                    let body = self.lines(lines, at)?;
+                    let until = self.close(lines, at, "Repeat", "EndRepeat")?;
+                    Line::Repeat(body, until)
+                }
+                "Select" => {
+                    *at += 1;
+                    let value = self.array(rest)?;
+                    if !self.lines(lines, at)?.is_empty() {
+                        anyhow::bail!(Errors::Syntax(
+                            "lines in a :Select before its first :Case".to_string()
+                        ));
+                    }
+                    let mut cases = vec![];
+                    let mut otherwise = vec![];
+                    while let Some((word, rest)) = lines.get(*at).and_then(|items| keyword(items)) {
+                        match (word, otherwise.is_empty()) {
+                            ("Case" | "CaseList", true) => {
+                                *at += 1;
+                                cases.push(Case {
+                                    list: word == "CaseList",
+                                    value: self.array(rest)?,
+                                    body: self.lines(lines, at)?,
+                                });
+                            }
+                            ("Else", true) => {
+                                *at += 1;
+                                otherwise = self.lines(lines, at)?;
+                            }
+                            _ => break,
+                        }
+                    }
+                    self.close(lines, at, "Select", "EndSelect")?;
+                    Line::Select(value, cases, otherwise)
+                }
+                "Trap" => {
+                    *at += 1;
+                    let codes = self.array(rest)?;
+                    let body = self.lines(lines, at)?;
+                    let mut otherwise = vec![];
+                    if let Some(("Else", [])) = lines.get(*at).and_then(|items| keyword(items)) {
+                        *at += 1;
+                        otherwise = self.lines(lines, at)?;
+                    }
+                    self.close(lines, at, "Trap", "EndTrap")?;
+                    Line::Trap(codes, body, otherwise)
+                }
+                "Leave" | "Continue" | "Return" if rest.is_empty() => {
+                    *at += 1;
+                    match word {
+                        "Leave" => Line::Leave,
+                        "Continue" => Line::Continue,
+                        _ => Line::Return,
+                    }
+                }
+                "Import" => {
+                    anyhow::bail!(Errors::Syntax(
+                        ":Import is only allowed outside functions".to_string()
+                    ))
+                }
+                "Repeat" | "Leave" | "Continue" | "Return" => {
+                    anyhow::bail!(Errors::Syntax(format!(":{} takes nothing after it", word)))
+                }
+                _ => break,
+            };
+            parsed.push(line);
+        }
+        Ok(parsed)
+    }
+
+    // Takes the line closing a structure: its own end keyword, `:End`, or
+    // for loops an `:Until` and the condition after it.
+    fn close(
+        &mut self,
+        lines: &[Vec<Item>],
+        at: &mut usize,
+        opened: &str,
+        end: &str,
+    ) -> anyhow::Result<Option<Expr>> {
+        let until = matches!(opened, "While" | "Repeat");
+        let closed = match lines.get(*at).and_then(|items| keyword(items)) {
+            Some((word, [])) if word == end || word == "End" => None,
+            Some(("Until", condition)) if until => Some(self.array(condition)?),
+            _ => anyhow::bail!(Errors::Syntax(format!(":{} without :{}", opened, end))),
+        };
+        *at += 1;
+        Ok(closed)
+    }
+
+    fn dfn(&mut self, body: &PartitionStream) -> anyhow::Result<Rc<[Statement]>> {
+        self.scopes.push(HashMap::new());
+        let statements = statements(body)
+            .iter()
+            .map(|items| self.statement(items, true))
+            .collect::<anyhow::Result<Vec<_>>>();
+        self.scopes.pop();
+        Ok(statements?.into())
+    }
+
+    // The items as nodes, with strands and operators bound.
+    fn bind(&mut self, items: &[Item]) -> anyhow::Result<Vec<Node>> {
+        let nodes = self.nodes(items)?;
+        bind_operators(strands(nodes)?)
+    }
+
+    fn nodes(&mut self, items: &[Item]) -> anyhow::Result<Vec<Node>> {
+        let mut nodes = Vec::with_capacity(items.len());
+        let mut items = items.iter().peekable();
+
+        while let Some(item) = items.next() {
+            let assigned = matches!(items.peek(), Some(Item::Token(Token::LeftArrow, _)));
+            let node = match *item {
+                Item::Token(token, loc) => match token {
+                    Token::Identifier(name) if assigned => {
+                        items.next();
+                        Node::Assign(name.clone())
+                    }
+                    Token::Identifier(name)
+                        if self.modules.contains_key(name)
+                            && matches!(items.peek(), Some(Item::Token(Token::Dot, _))) =>
+                    {
+                        items.next();
+                        let member = match items.next() {
+                            Some(Item::Token(Token::Identifier(member), _)) => member,
+                            _ => anyhow::bail!(Errors::Syntax(format!(
+                                "expected a member of {} at {}",
+                                name, loc
+                            ))),
+                        };
+                        let qualified = format!("{}.{}", name, member);
+                        match self.modules[name].get(member) {
+                            Some(NameClass::Array) => Node::Array(Expr::Name(qualified)),
+                            Some(NameClass::Function) => Node::Function(Function::Name(qualified)),
+                            None => anyhow::bail!(Errors::Value(format!(
+                                "{} is not defined",
+                                qualified
+                            ))),
+                        }
+                    }
+                    Token::Alpha if assigned => {
+                        items.next();
+                        Node::Assign("⍺".to_string())
+                    }
+                    Token::Identifier(name) => match self.class(name) {
+                        NameClass::Array => Node::Array(Expr::Name(name.clone())),
+                        NameClass::Function => Node::Function(Function::Name(name.clone())),
+                    },
+                    Token::NumericLiteral(n) => Node::Array(Expr::Number(n.clone())),
+                    Token::StringLiteral(s) => Node::Array(Expr::String(s.clone())),
+                    Token::Zilde => Node::Array(Expr::Zilde),
+                    Token::Alpha | Token::Omega => Node::Array(Expr::Name(spell(token))),
+                    Token::Del => Node::Function(Function::Name(spell(token))),
+                    Token::Jot if matches!(items.peek(), Some(Item::Token(Token::Dot, _))) => {
+                        items.next();
+                        Node::Outer(loc.clone())
+                    }
+                    token => match token.valence() {
+                        Some(Valence::Monadic | Valence::Dyadic | Valence::Ambivalent) => {
+                            Node::Function(Function::Primitive(token.clone()))
+                        }
+                        Some(Valence::MonadicOperator) => {
+                            Node::MonadicOperator(token.clone(), loc.clone())
+                        }
+                        Some(Valence::DyadicOperator) => {
+                            Node::DyadicOperator(token.clone(), loc.clone())
+                        }
+                        _ => anyhow::bail!(Errors::Syntax(format!(
+                            "unexpected {} at {}",
+                            spell(token),
+                            loc
+                        ))),
+                    },
+                },
+                Item::Round(inner) => match statements(inner).as_slice() {
+                    [items] => {
+                        let nodes = self.bind(items)?;
+                        match self.build(nodes)? {
+                            Phrase::Array(expr) => Node::Array(expr),
+                            Phrase::Function(function) => Node::Function(function),
+                        }
+                    }
+                    _ => anyhow::bail!(Errors::Syntax(
+                        "expected one expression in parentheses".to_string()
+                    )),
+                },
+                Item::Curly(body) => Node::Function(Function::Dfn(self.dfn(body)?)),
+                Item::Square(inner) => {
+                    let array = match nodes.pop() {
+                        Some(Node::Array(array)) => array,
+                        _ => anyhow::bail!(Errors::Nonce("axes are not supported yet".to_string())),
+                    };
+                    Node::Array(Expr::Index(Box::new(array), Box::new(self.indices(inner)?)))
+                }
+            };
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
+    // What goes between the brackets of `array[indices]`.
+    fn indices(&mut self, inner: &PartitionStream) -> anyhow::Result<Expr> {
+        match statements(inner).as_slice() {
+            [items]
+                if items
+                    .iter()
+                    .any(|item| matches!(item, Item::Token(Token::Semicolon, _))) =>
+            {
+                anyhow::bail!(Errors::Nonce(
+                    "indexing along more than one axis is not supported yet".to_string()
+                ))
+            }
+            [items] => self.array(items),
+            _ => anyhow::bail!(Errors::Syntax(
+                "expected one expression in brackets".to_string()
+            )),
+        }
+    }
+
+    // Applies the functions right to left. A phrase that ends in a function
+    // is a function, alone or as a train.
+    fn build(&mut self, mut nodes: Vec<Node>) -> anyhow::Result<Phrase> {
+        let mut expr = match nodes.pop() {
+            Some(Node::Array(expr)) => expr,
+            Some(Node::Function(function)) => return Ok(Phrase::Function(train(nodes, function)?)),
+            Some(Node::Assign(name)) => {
+                anyhow::bail!(Errors::Syntax(format!("nothing to assign to {}", name)))
+            }
+            Some(_) => unreachable!("operators are bound"),
+            None => anyhow::bail!(Errors::Syntax("empty expression".to_string())),
+        };
+
+        while let Some(node) = nodes.pop() {
+            expr = match node {
+                Node::Function(function) => match nodes.last() {
+                    Some(Node::Array(_)) => match nodes.pop() {
+                        Some(Node::Array(left)) => {
+                            Expr::Dyadic(Box::new(left), function, Box::new(expr))
+                        }
+                        _ => unreachable!(),
+                    },
+                    _ => Expr::Monadic(function, Box::new(expr)),
+                },
+                Node::Assign(name) => {
+                    self.define(&name, NameClass::Array);
+                    Expr::Assign(name, Box::new(expr))
+                }
+                Node::Array(_) => {
+                    anyhow::bail!(Errors::Syntax(
+                        "two arrays with no function between".to_string()
+                    ))
+                }
+                _ => unreachable!("operators are bound"),
+            };
+        }
+        Ok(Phrase::Array(expr))
+    }
+}
+
+// Joins arrays side by side into strands. Numeric literals in a strand
+// share its precision, as in `1.5h 2 3`.
+fn strands(nodes: Vec<Node>) -> anyhow::Result<Vec<Node>> {
+    fn strand(mut items: Vec<Expr>) -> anyhow::Result<Node> {
+        if items.len() == 1 {
+            return Ok(Node::Array(items.pop().unwrap()));
+        }
+        let precision = strand_precision(items.iter().filter_map(|item| match item {
+            Expr::Number(n) => Some(n),
+            _ => None,
+        }))?;
+        Ok(Node::Array(Expr::Strand(
+            items
+                .into_iter()
+                .map(|item| match item {
+                    Expr::Number(n) => Expr::Number(settle(&n, precision)),
+                    item => item,
+                })
+                .collect(),
+        )))
+    }
+
+    let mut bound = Vec::with_capacity(nodes.len());
+    let mut run = vec![];
+    for node in nodes {
+        match node {
+            Node::Array(expr) => run.push(expr),
+            node => {
+                if !run.is_empty() {
+                    bound.push(strand(std::mem::take(&mut run))?);
+                }
+                bound.push(node);
+            }
+        }
+    }
+    if !run.is_empty() {
+        bound.push(strand(run)?);
+    }
+    Ok(bound)
+}
+
+// The functions before `right` and it as a train. From the right, each
+// three are a fork, whose left tine may be an array, and two left over at
+// the start are an atop: `(e f g h)` is `(e (f g h))`.
+fn train(mut nodes: Vec<Node>, mut right: Function) -> anyhow::Result<Function> {
+    loop {
+        let middle = match nodes.pop() {
+            None => return Ok(right),
+            Some(Node::Function(middle)) => middle,
+            Some(Node::Array(_)) => {
+                anyhow::bail!(Errors::Syntax(
+                    "only the left tine of a fork may be an array".to_string()
+                ))
+            }
+            Some(Node::Assign(name)) => {
+                anyhow::bail!(Errors::Syntax(format!("{} assigned a train", name)))
+            }
+            Some(_) => unreachable!("operators are bound"),
+        };
+        let left = match nodes.pop() {
+            None => return Ok(Function::Atop(Box::new(middle), Box::new(right))),
+            Some(Node::Function(left)) => Operand::Function(left),
+            Some(Node::Array(left)) => Operand::Array(left),
+            Some(Node::Assign(name)) => {
+                anyhow::bail!(Errors::Syntax(format!("{} assigned a train", name)))
+            }
+            Some(_) => unreachable!("operators are bound"),
+        };
+        right = Function::Fork(Box::new(left), Box::new(middle), Box::new(right));
+    }
+}
+
+fn operand(node: Option<Node>) -> Option<Operand> {
+    match node {
+        Some(Node::Function(function)) => Some(Operand::Function(function)),
+        Some(Node::Array(expr)) => Some(Operand::Array(expr)),
+        _ => None,
+    }
+}
+
+// Binds operators to their operands from left to right.
+fn bind_operators(nodes: Vec<Node>) -> anyhow::Result<Vec<Node>> {
+    let mut bound: Vec<Node> = Vec::with_capacity(nodes.len());
+    let mut nodes = nodes.into_iter();
+
+    while let Some(node) = nodes.next() {
+        let derived = match node {
+            Node::MonadicOperator(operator, loc) => match bound.pop() {
+                Some(Node::Function(function)) => {
+                    Function::Derived(operator, Box::new(Operand::Function(function)), None)
+                }
+                // With an array on its left, `/` is replicate, and so on
+                Some(Node::Array(array))
+                    if matches!(
+                        operator,
+                        Token::Slash | Token::SlashBar | Token::Backslash | Token::BackslashBar
+                    ) =>
+                {
+                    bound.push(Node::Array(array));
+                    Function::Primitive(operator)
+                }
+                _ => anyhow::bail!(Errors::Syntax(format!(
+                    "{} at {} needs a function on its left",
+                    spell(&operator),
+                    loc
+                ))),
+            },
+            Node::DyadicOperator(operator, loc) => {
+                match (operand(bound.pop()), operand(nodes.next())) {
+                    (Some(left), Some(right)) => {
+                        Function::Derived(operator, Box::new(left), Some(Box::new(right)))
+                    }
+                    _ => anyhow::bail!(Errors::Syntax(format!(
+                        "{} at {} needs an operand on each side",
+                        spell(&operator),
+                        loc
+                    ))),
+                }
+            }
+            Node::Outer(loc) => match nodes.next() {
+                Some(Node::Function(function)) => Function::Outer(Box::new(function)),
+                _ => anyhow::bail!(Errors::Syntax(format!(
+                    "∘. at {} needs a function on its right",
+                    loc
+                ))),
+            },
+            node => {
+                bound.push(node);
+                continue;
+            }
+        };
+        bound.push(Node::Function(derived));
+    }
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Expr, Function, Line, Operand, Parser, Statement};
+    use crate::normalizer::normalize_apl_code;
+    use crate::tokenizer::bracket_partitioner::tokenize_to_partition;
+    use crate::tokenizer::numeric_literal::NumericLiteral;
+    use crate::tokenizer::{tokenize, Token};
+
+    fn parse(source: &str) -> anyhow::Result<Vec<Statement>> {
+        let tokens = tokenize(normalize_apl_code(source.to_string()))?;
+        Parser::default().parse(&tokenize_to_partition(tokens)?)
+    }
+
+    fn expr(source: &str) -> Expr {
+        match parse(source).unwrap().pop() {
+            Some(Statement::Expr(expr)) => expr,
+            statement => panic!("{:?}", statement),
+        }
+    }
+
+    fn number(n: f64) -> Expr {
+        Expr::Number(NumericLiteral::Auto(n))
+    }
+
+    fn primitive(token: Token) -> Function {
+        Function::Primitive(token)
+    }
+
+    fn derived(operator: Token, left: Operand, right: Option<Operand>) -> Function {
+        Function::Derived(operator, Box::new(left), right.map(Box::new))
+    }
+
+    #[test]
+    fn it_applies_functions_right_to_left() {
+        // 1 2-3×⍵
+        assert_eq!(
+            expr("1 2-3×⍵"),
+            Expr::Dyadic(
+                Box::new(Expr::Strand(vec![number(1.0), number(2.0)])),
+                primitive(Token::Minus),
+                Box::new(Expr::Dyadic(
+                    Box::new(number(3.0)),
+                    primitive(Token::Times),
+                    Box::new(Expr::Name("⍵".to_string()))
+                ))
+            )
+        );
+        assert_eq!(
+            expr("x←-1"),
+            Expr::Assign(
+                "x".to_string(),
+                Box::new(Expr::Monadic(
+                    primitive(Token::Minus),
+                    Box::new(number(1.0))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn it_gives_operators_a_long_left_scope() {
+        // +∘×∘÷ ⍵ is (+∘×)∘÷ ⍵
+        let f = |token| Operand::Function(primitive(token));
+        assert_eq!(
+            expr("+∘×∘÷⍵"),
+            Expr::Monadic(
+                derived(
+                    Token::Jot,
+                    Operand::Function(derived(Token::Jot, f(Token::Plus), Some(f(Token::Times)))),
+                    Some(f(Token::Divide))
+                ),
+                Box::new(Expr::Name("⍵".to_string()))
+            )
+        );
+        // +/¨ ⍵ is (+/)¨ ⍵, and the rank of +⍤1 0 is the strand 1 0
+        assert_eq!(
+            expr("+/¨⍵"),
+            Expr::Monadic(
+                derived(
+                    Token::Diaeresis,
+                    Operand::Function(derived(Token::Slash, f(Token::Plus), None)),
+                    None
+                ),
+                Box::new(Expr::Name("⍵".to_string()))
+            )
+        );
+        assert_eq!(
+            expr("⍺+⍤1 0⊢⍵"),
+            Expr::Dyadic(
+                Box::new(Expr::Name("⍺".to_string())),
+                derived(
+                    Token::JotDiaeresis,
+                    f(Token::Plus),
+                    Some(Operand::Array(Expr::Strand(vec![number(1.0), number(0.0)])))
+                ),
+                Box::new(Expr::Monadic(
+                    primitive(Token::RightTack),
+                    Box::new(Expr::Name("⍵".to_string()))
+                ))
+            )
+        );
+        // ∘.× takes its operand on the right, and / after an array is replicate
+        assert_eq!(
+            expr("1 2∘.×3"),
+            Expr::Dyadic(
+                Box::new(Expr::Strand(vec![number(1.0), number(2.0)])),
+                Function::Outer(Box::new(primitive(Token::Times))),
+                Box::new(number(3.0))
+            )
+        );
+        assert_eq!(
+            expr("1 0/⍵"),
+            Expr::Dyadic(
+                Box::new(Expr::Strand(vec![number(1.0), number(0.0)])),
+                primitive(Token::Slash),
+                Box::new(Expr::Name("⍵".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn it_knows_functions_by_their_assignment() {
+        let statements = parse("f←{⍵+1}\nf 2\ng←f⍣2\nf←3\nf 2").unwrap();
+
+        assert!(matches!(&statements[0], Statement::Define(name, Function::Dfn(_)) if name == "f"));
+        assert_eq!(
+            statements[1],
+            Statement::Expr(Expr::Monadic(
+                Function::Name("f".to_string()),
+                Box::new(number(2.0))
+            ))
+        );
+        assert!(
+            matches!(&statements[2], Statement::Define(name, Function::Derived(..)) if name == "g")
+        );
+        // Reassigned an array, `f 2` is a strand
+        assert_eq!(
+            statements[4],
+            Statement::Expr(Expr::Strand(vec![Expr::Name("f".to_string()), number(2.0)]))
+        );
+    }
+
+    #[test]
+    fn it_parses_dfns_with_guards() {
+        let statements = parse("{⍺←0 ⋄ ⍵=0:⍺ ⋄ (⍺+1)∇⍵-1}5").unwrap();
+        let body = match &statements[..] {
+            [Statement::Expr(Expr::Monadic(Function::Dfn(body), _))] => body.clone(),
+            statements => panic!("{:?}", statements),
+        };
+        assert_eq!(body.len(), 3);
+        assert!(matches!(body[1], Statement::Guard(..)));
+    }
+
+    #[test]
+    fn it_parses_trains() {
+        let f = |token| Box::new(primitive(token));
+        // (+ - ×) is a fork and (+ -) an atop, (⊢ + - ×) an atop of a fork
+        assert_eq!(
+            expr("(+-×)1"),
+            Expr::Monadic(
+                Function::Fork(
+                    Box::new(Operand::Function(primitive(Token::Plus))),
+                    f(Token::Minus),
+                    f(Token::Times)
+                ),
+                Box::new(number(1.0))
+            )
+        );
+        assert_eq!(
+            expr("(⊢+-×)1"),
+            Expr::Monadic(
+                Function::Atop(
+                    f(Token::RightTack),
+                    Box::new(Function::Fork(
+                        Box::new(Operand::Function(primitive(Token::Plus))),
+                        f(Token::Minus),
+                        f(Token::Times)
+                    ))
+                ),
+                Box::new(number(1.0))
+            )
+        );
+        // An array may be the left tine, and a train may be assigned bare
+        let statements = parse("g←1+⊢\ng 2").unwrap();
+        assert_eq!(
+            statements[0],
+            Statement::Define(
+                "g".to_string(),
+                Function::Fork(
+                    Box::new(Operand::Array(number(1.0))),
+                    f(Token::Plus),
+                    f(Token::RightTack)
+                )
+            )
+        );
+        assert!(matches!(
+            &statements[1],
+            Statement::Expr(Expr::Monadic(Function::Name(name), _)) if name == "g"
+        ));
+    }
+
+    #[test]
+    fn it_parses_tradfns() {
+        let source = "∇r←a foo b;x;y\n:If a>b\nr←a\n:ElseIf a=b\nr←0\n:Else\n:For x :In ⍳b\nr←x\n:End\n:EndIf\n∇\n1 foo 2";
+        let statements = parse(source).unwrap();
+        let tradfn = match &statements[0] {
+            Statement::Define(name, Function::Tradfn(tradfn)) if name == "foo" => tradfn.clone(),
+            statement => panic!("{:?}", statement),
+        };
+        assert_eq!(tradfn.result.as_deref(), Some("r"));
+        assert_eq!(tradfn.left.as_deref(), Some("a"));
+        assert_eq!(tradfn.right.as_deref(), Some("b"));
+        assert_eq!(tradfn.locals, vec!["x".to_string(), "y".to_string()]);
+        match tradfn.body.as_slice() {
+            [Line::If(arms, otherwise)] => {
+                assert_eq!(arms.len(), 2);
+                assert!(
+                    matches!(otherwise.as_slice(), [Line::For(names, _, _)] if names == &["x"])
+                );
+            }
+            body => panic!("{:?}", body),
+        }
+        // After its definition, foo is a function
+        assert!(matches!(
+            &statements[1],
+            Statement::Expr(Expr::Dyadic(_, Function::Name(name), _)) if name == "foo"
+        ));
+    }
+
+    #[test]
+    fn it_reports_unbalanced_control_structures() {
+        for source in [
+            "∇foo\n1",
+            "∇foo\n:If 1\n∇",
+            "∇foo\n:EndIf\n∇",
+            "∇foo\n:While 1\n:EndIf\n∇",
+            "∇foo\n:For :In 1\n:EndFor\n∇",
+            "∇foo\n:Select 1\n2\n:EndSelect\n∇",
+            "∇a b c d\n∇",
+            "∇foo;1\n∇",
+        ] {
+            assert!(parse(source).is_err(), "{} should not parse", source);
+        }
+    }
+
+    #[test]
+    fn it_reports_syntax_errors() {
+        for source in [
+            "⍵=0:1",
+            "1 2 +",
+            "+",
+            "¨1",
+            "1∘",
+            "x[1;2]",
+            "+/[1]x",
+            "(+ 1 -) 1",
+        ] {
+            assert!(parse(source).is_err(), "{} should not parse", source);
+        }
+    }
+}
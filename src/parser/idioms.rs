@@ -0,0 +1,290 @@
+// Idiom recognition: a pass over the syntax tree that finds phrases with a
+// fast implementation of their own and marks them as `Function::Idiom`,
+// keeping the phrase they replace. The evaluator runs the fused kernel for
+// an idiom when its arguments suit it and the phrase otherwise, so a
+// recognised idiom gives what the phrase would. Idioms are found both as
+// trains, `(≢∪)`, and as expressions, `≢∪x`, but only ever made of
+// primitives: a name could be redefined.
+
+use std::rc::Rc;
+
+use crate::parser::{Case, Expr, Function, Line, Operand, Statement, Tradfn};
+use crate::tokenizer::Token;
+
+/// The idioms the pass knows.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Idiom {
+    // `+/÷≢`
+    Mean,
+    // `⊃⌽`
+    Last,
+    // `{⍵[⍋⍵]}`
+    SortUp,
+    // `{⍵[⍒⍵]}`
+    SortDown,
+    // `∨/∊`
+    AnyMember,
+    // `∧/∊`
+    AllMember,
+    // `≢∪`
+    CountUnique,
+}
+
+impl Idiom {
+    pub const ALL: [Idiom; 7] = [
+        Idiom::Mean,
+        Idiom::Last,
+        Idiom::SortUp,
+        Idiom::SortDown,
+        Idiom::AnyMember,
+        Idiom::AllMember,
+        Idiom::CountUnique,
+    ];
+
+    /// How the idiom is written.
+    pub fn phrase(self) -> &'static str {
+        match self {
+            Idiom::Mean => "+/÷≢",
+            Idiom::Last => "⊃⌽",
+            Idiom::SortUp => "{⍵[⍋⍵]}",
+            Idiom::SortDown => "{⍵[⍒⍵]}",
+            Idiom::AnyMember => "∨/∊",
+            Idiom::AllMember => "∧/∊",
+            Idiom::CountUnique => "≢∪",
+        }
+    }
+
+    /// What the idiom computes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Idiom::Mean => "mean",
+            Idiom::Last => "last",
+            Idiom::SortUp => "sort up",
+            Idiom::SortDown => "sort down",
+            Idiom::AnyMember => "any member",
+            Idiom::AllMember => "all members",
+            Idiom::CountUnique => "count unique",
+        }
+    }
+}
+
+impl std::fmt::Display for Idiom {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{} {}", self.phrase(), self.name())
+    }
+}
+
+fn primitive(function: &Function) -> Option<&Token> {
+    match function {
+        Function::Primitive(token) => Some(token),
+        _ => None,
+    }
+}
+
+// The function a reduction `f/` reduces with, if it is a primitive.
+fn reduction(function: &Function) -> Option<&Token> {
+    match function {
+        Function::Derived(Token::Slash, operand, None) => match &**operand {
+            Operand::Function(function) => primitive(function),
+            Operand::Array(_) => None,
+        },
+        _ => None,
+    }
+}
+
+// The idiom `f g`, applied monadically or dyadically, makes.
+fn composition(f: &Function, g: &Function) -> Option<Idiom> {
+    match (primitive(f), reduction(f), primitive(g)?) {
+        (Some(Token::RightShoe), _, Token::CircleStile) => Some(Idiom::Last),
+        (Some(Token::EqualUnderbarSlash), _, Token::DownShoe) => Some(Idiom::CountUnique),
+        (_, Some(Token::LogicalOR), Token::Epsilon) => Some(Idiom::AnyMember),
+        (_, Some(Token::LogicalAND), Token::Epsilon) => Some(Idiom::AllMember),
+        _ => None,
+    }
+}
+
+// The idiom a whole function is, as a train or a dfn.
+fn whole(function: &Function) -> Option<Idiom> {
+    match function {
+        Function::Fork(left, middle, right) => {
+            match (&**left, primitive(middle)?, primitive(right)?) {
+                (Operand::Function(left), Token::Divide, Token::EqualUnderbarSlash)
+                    if reduction(left) == Some(&Token::Plus) =>
+                {
+                    Some(Idiom::Mean)
+                }
+                _ => None,
+            }
+        }
+        Function::Atop(f, g) => composition(f, g),
+        Function::Dfn(body) => match &**body {
+            [Statement::Expr(Expr::Index(array, indices))] => match (&**array, &**indices) {
+                (Expr::Name(array), Expr::Monadic(grade, omega))
+                    if array == "⍵" && **omega == Expr::Name("⍵".to_string()) =>
+                {
+                    match primitive(grade)? {
+                        Token::GradeUp => Some(Idiom::SortUp),
+                        Token::GradeDown => Some(Idiom::SortDown),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The statements with the idioms in them replaced, noting each idiom in
+/// `fired` as it is found.
+pub fn recognise(statements: &[Statement], fired: &mut Vec<Idiom>) -> Vec<Statement> {
+    Recogniser { fired }.statements(statements)
+}
+
+struct Recogniser<'a> {
+    fired: &'a mut Vec<Idiom>,
+}
+
+impl Recogniser<'_> {
+    fn statements(&mut self, statements: &[Statement]) -> Vec<Statement> {
+        statements
+            .iter()
+            .map(|statement| self.statement(statement))
+            .collect()
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Statement {
+        match statement {
+            Statement::Expr(expr) => Statement::Expr(self.expr(expr)),
+            Statement::Guard(condition, result) => {
+                Statement::Guard(self.expr(condition), self.expr(result))
+            }
+            Statement::ErrorGuard(codes, result) => {
+                Statement::ErrorGuard(self.expr(codes), self.expr(result))
+            }
+            Statement::Define(name, function) => {
+                Statement::Define(name.clone(), self.function(function))
+            }
+            Statement::Import(..) => statement.clone(),
+        }
+    }
+
+    fn fire(&mut self, idiom: Idiom, phrase: Function) -> Function {
+        self.fired.push(idiom);
+        Function::Idiom(idiom, Box::new(phrase))
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Expr {
+        let boxed = |this: &mut Self, expr: &Expr| Box::new(this.expr(expr));
+        match expr {
+            // `f g x` and `f x g y`, for the idioms `f g`
+            Expr::Monadic(f, inner) => match &**inner {
+                Expr::Monadic(g, x) if composition(f, g).is_some() => {
+                    let idiom = composition(f, g).unwrap();
+                    let phrase = Function::Atop(Box::new(f.clone()), Box::new(g.clone()));
+                    Expr::Monadic(self.fire(idiom, phrase), boxed(self, x))
+                }
+                Expr::Dyadic(x, g, y) if composition(f, g).is_some() => {
+                    let idiom = composition(f, g).unwrap();
+                    let phrase = Function::Atop(Box::new(f.clone()), Box::new(g.clone()));
+                    let x = boxed(self, x);
+                    Expr::Dyadic(x, self.fire(idiom, phrase), boxed(self, y))
+                }
+                _ => Expr::Monadic(self.function(f), boxed(self, inner)),
+            },
+            Expr::Dyadic(x, f, y) => {
+                let x = boxed(self, x);
+                Expr::Dyadic(x, self.function(f), boxed(self, y))
+            }
+            Expr::Strand(items) => Expr::Strand(items.iter().map(|item| self.expr(item)).collect()),
+            Expr::Assign(name, value) => Expr::Assign(name.clone(), boxed(self, value)),
+            Expr::Index(array, indices) => {
+                let array = boxed(self, array);
+                Expr::Index(array, boxed(self, indices))
+            }
+            Expr::Number(_) | Expr::String(_) | Expr::Zilde | Expr::Name(_) => expr.clone(),
+        }
+    }
+
+    fn operand(&mut self, operand: &Operand) -> Operand {
+        match operand {
+            Operand::Function(function) => Operand::Function(self.function(function)),
+            Operand::Array(expr) => Operand::Array(self.expr(expr)),
+        }
+    }
+
+    fn function(&mut self, function: &Function) -> Function {
+        if let Some(idiom) = whole(function) {
+            return self.fire(idiom, function.clone());
+        }
+        match function {
+            Function::Dfn(body) => Function::Dfn(self.statements(body).into()),
+            Function::Tradfn(tradfn) => Function::Tradfn(Rc::new(Tradfn {
+                body: self.lines(&tradfn.body),
+                ..(**tradfn).clone()
+            })),
+            Function::Derived(operator, left, right) => {
+                let left = Box::new(self.operand(left));
+                let right = right.as_ref().map(|right| Box::new(self.operand(right)));
+                Function::Derived(operator.clone(), left, right)
+            }
+            Function::Outer(function) => Function::Outer(Box::new(self.function(function))),
+            Function::Fork(left, middle, right) => Function::Fork(
+                Box::new(self.operand(left)),
+                Box::new(self.function(middle)),
+                Box::new(self.function(right)),
+            ),
+            Function::Atop(left, right) => Function::Atop(
+                Box::new(self.function(left)),
+                Box::new(self.function(right)),
+            ),
+            Function::Primitive(_) | Function::Name(_) | Function::Idiom(..) => function.clone(),
+        }
+    }
+
+    fn lines(&mut self, lines: &[Line]) -> Vec<Line> {
+        lines.iter().map(|line| self.line(line)).collect()
+    }
+
+    fn line(&mut self, line: &Line) -> Line {
+        match line {
+            Line::Statement(statement) => Line::Statement(self.statement(statement)),
+            Line::If(branches, otherwise) => Line::If(
+                branches
+                    .iter()
+                    .map(|(condition, body)| (self.expr(condition), self.lines(body)))
+                    .collect(),
+                self.lines(otherwise),
+            ),
+            Line::For(names, array, body) => {
+                Line::For(names.clone(), self.expr(array), self.lines(body))
+            }
+            Line::While(condition, body, until) => Line::While(
+                self.expr(condition),
+                self.lines(body),
+                until.as_ref().map(|until| self.expr(until)),
+            ),
+            Line::Repeat(body, until) => Line::Repeat(
+                self.lines(body),
+                until.as_ref().map(|until| self.expr(until)),
+            ),
+            Line::Select(value, cases, otherwise) => Line::Select(
+                self.expr(value),
+                cases
+                    .iter()
+                    .map(|case| Case {
+                        list: case.list,
+                        value: self.expr(&case.value),
+                        body: self.lines(&case.body),
+                    })
+                    .collect(),
+                self.lines(otherwise),
+            ),
+            Line::Trap(codes, body, otherwise) => {
+                Line::Trap(self.expr(codes), self.lines(body), self.lines(otherwise))
+            }
+            Line::Leave | Line::Continue | Line::Return => line.clone(),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// Modules: code in files of their own, imported with `:Import name` and
+// used as `name.member`. A module is `name.apl`, or the `:g` sections of
+// `name.icl`, in the first directory of the search path that has one. It
+// can use the names of the prelude, and exports every name it assigns
+// outside its functions. Each file is read and parsed once however often
+// it is imported, and a module may not import itself, however indirectly.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::errors::Errors;
+use crate::normalizer::normalize_apl_code;
+use crate::parser::idioms::recognise;
+use crate::parser::{NameClass, Parser, Statement};
+use crate::tokenizer::bracket_partitioner::tokenize_to_partition;
+use crate::tokenizer::tokenize;
+
+/// A parsed module, ready to run into a namespace.
+#[derive(Debug, PartialEq)]
+pub struct Module {
+    pub name: String,
+    pub statements: Vec<Statement>,
+    pub(super) exports: HashMap<String, NameClass>,
+}
+
+/// Finds and parses modules for the parsers that share it.
+#[derive(Debug)]
+pub struct Loader {
+    path: Vec<PathBuf>,
+    cache: HashMap<PathBuf, Rc<Module>>,
+    // The modules being parsed, each imported by the one before.
+    loading: Vec<String>,
+    // What the names of the prelude hold, which every module can use.
+    pub(super) prelude: HashMap<String, NameClass>,
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Loader {
+            path: vec![PathBuf::from(".")],
+            cache: HashMap::new(),
+            loading: vec![],
+            prelude: HashMap::new(),
+        }
+    }
+}
+
+impl Loader {
+    /// Adds a directory to the end of the search path.
+    pub fn search(&mut self, directory: impl Into<PathBuf>) {
+        self.path.push(directory.into());
+    }
+
+    fn find(&self, name: &str) -> anyhow::Result<PathBuf> {
+        self.path
+            .iter()
+            .flat_map(|directory| {
+                ["apl", "icl"].map(|extension| directory.join(name).with_extension(extension))
+            })
+            .find(|file| file.is_file())
+            .ok_or_else(|| {
+                let path: Vec<_> = self
+                    .path
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                anyhow::anyhow!(Errors::Value(format!(
+                    "no module {} in the search path {}",
+                    name,
+                    path.join(" ")
+                )))
+            })
+    }
+}
+
+// The lines of the `:g` sections of an `.icl` file, each running from a
+// line starting `:g` to a line of just `:`. Other lines are left blank so
+// that places in errors are places in the file.
+pub(crate) fn sections(source: &str) -> String {
+    let mut inside = false;
+    let mut lines = vec![];
+    for line in source.lines() {
+        let kept = match (inside, line.trim()) {
+            (false, opening) if opening.starts_with(":g") => {
+                inside = true;
+                ""
+            }
+            (true, ":") => {
+                inside = false;
+                ""
+            }
+            (true, _) => line,
+            (false, _) => "",
+        };
+        lines.push(kept);
+    }
+    lines.join("\n")
+}
+
+fn read(file: &Path) -> anyhow::Result<String> {
+    let source = std::fs::read_to_string(file).map_err(|error| {
+        anyhow::anyhow!(Errors::Domain(format!("{}: {}", file.display(), error)))
+    })?;
+    Ok(
+        match file.extension().and_then(|extension| extension.to_str()) {
+            Some("icl") => sections(&source),
+            _ => source,
+        },
+    )
+}
+
+/// Parses the module `name`, or gives it from the cache if its file was
+/// parsed before.
+pub fn load(loader: &Rc<RefCell<Loader>>, name: &str) -> anyhow::Result<Rc<Module>> {
+    let file = loader.borrow().find(name)?;
+    if let Some(module) = loader.borrow().cache.get(&file) {
+        return Ok(module.clone());
+    }
+    if loader
+        .borrow()
+        .loading
+        .iter()
+        .any(|loading| loading == name)
+    {
+        let chain = loader.borrow().loading.join(" → ");
+        anyhow::bail!(Errors::Domain(format!(
+            "modules import each other: {} → {}",
+            chain, name
+        )));
+    }
+
+    let source = read(&file)?;
+    let mut parser = Parser {
+        loader: loader.clone(),
+        ..Parser::default()
+    };
+    loader.borrow_mut().loading.push(name.to_string());
+    let statements = tokenize(normalize_apl_code(source))
+        .and_then(tokenize_to_partition)
+        .and_then(|stream| parser.parse(&stream));
+    loader.borrow_mut().loading.pop();
+    let statements = match statements {
+        Ok(statements) => recognise(&statements, &mut vec![]),
+        Err(error) => {
+            let context = format!("{} in module {}", error, name);
+            return Err(error.context(context));
+        }
+    };
+
+    let module = Rc::new(Module {
+        name: name.to_string(),
+        statements,
+        exports: parser.scopes.swap_remove(0),
+    });
+    loader.borrow_mut().cache.insert(file, module.clone());
+    Ok(module)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{load, sections, Loader};
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    /// A fresh directory holding the given files, for a test to import from.
+    pub(crate) fn directory(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("htb_apl_{}_{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        for (name, source) in files {
+            std::fs::write(directory.join(name), source).unwrap();
+        }
+        directory
+    }
+
+    fn loader(directory: PathBuf) -> Rc<RefCell<Loader>> {
+        let mut loader = Loader::default();
+        loader.search(directory);
+        Rc::new(RefCell::new(loader))
+    }
+
+    #[test]
+    fn it_keeps_the_g_sections() {
+        let source = ":g // prelude\nx←1\n:\nclass\n:g\ny←2\n:\n";
+        assert_eq!(sections(source), "\nx←1\n\n\n\ny←2\n");
+    }
+
+    #[test]
+    fn it_parses_each_file_once() {
+        let directory = directory(
+            "parses_once",
+            &[
+                ("a.apl", ":Import b\nf←{b.g ⍵}"),
+                ("b.icl", ":g\ng←{⍵+1}\nv←2\n:\n"),
+            ],
+        );
+        let loader = loader(directory);
+        let a = load(&loader, "a").unwrap();
+        assert!(a.exports.contains_key("f"));
+
+        let b = load(&loader, "b").unwrap();
+        assert_eq!(b.exports.len(), 2);
+        assert_eq!(loader.borrow().cache.len(), 2);
+        assert!(Rc::ptr_eq(&b, &load(&loader, "b").unwrap()));
+    }
+
+    #[test]
+    fn it_finds_cycles_and_missing_modules() {
+        let directory = directory(
+            "cycles",
+            &[
+                ("a.apl", ":Import b"),
+                ("b.apl", ":Import c"),
+                ("c.apl", ":Import a"),
+            ],
+        );
+        let loader = loader(directory);
+        let error = load(&loader, "a").unwrap_err().to_string();
+        assert!(error.contains("a → b → c → a"), "{}", error);
+        assert!(load(&loader, "d").is_err());
+    }
+}
@@ -0,0 +1,189 @@
+// Draws how a function phrase parsed, as a tree in the manner of Dyalog's
+// `]box -trains=tree`: each operator sits above its operands and each train
+// above its tines, joined to them by box-drawing lines. A fork has no label
+// of its own; the line to its middle tine goes on up through the join.
+
+use crate::parser::{spell, Expr, Function, Operand};
+
+// A drawing and the column where the line from above meets its top.
+struct Drawing {
+    rows: Vec<Vec<char>>,
+    width: usize,
+    anchor: usize,
+}
+
+impl Drawing {
+    fn leaf(label: String) -> Drawing {
+        let row: Vec<char> = label.chars().collect();
+        Drawing {
+            width: row.len(),
+            rows: vec![row],
+            anchor: 0,
+        }
+    }
+}
+
+fn junction(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, false, _, _) => '─',
+        (false, true, false, true) => '┌',
+        (false, true, true, false) => '┐',
+        (false, true, true, true) => '┬',
+        (true, true, false, true) => '├',
+        (true, true, true, false) => '┤',
+        (true, true, true, true) => '┼',
+        (true, false, false, true) => '└',
+        (true, false, true, false) => '┘',
+        (true, false, true, true) => '┴',
+        (_, _, false, false) => '│',
+    }
+}
+
+// The children side by side, a space apart, under a label if there is one
+// and a line joining them. A lone operand hangs down to the left of its
+// operator.
+fn join(label: Option<String>, children: Vec<Drawing>) -> Drawing {
+    let mut offsets = Vec::with_capacity(children.len());
+    let mut width = 0;
+    for child in &children {
+        offsets.push(width);
+        width += child.width + 1;
+    }
+    let anchors: Vec<usize> = children
+        .iter()
+        .zip(&offsets)
+        .map(|(child, offset)| offset + child.anchor)
+        .collect();
+    let (first, last) = (anchors[0], anchors[anchors.len() - 1]);
+    let anchor = match (anchors.len(), &label) {
+        (1, _) => first + 2,
+        (3, None) => anchors[1],
+        _ => (first + last) / 2,
+    };
+    let label: Vec<char> = label.unwrap_or_default().chars().collect();
+    let width = (width - 1).max(anchor + label.len().max(1));
+
+    let mut rows = vec![];
+    if !label.is_empty() {
+        let mut row = vec![' '; anchor];
+        row.extend(&label);
+        rows.push(row);
+    }
+    let (start, end) = (first.min(anchor), last.max(anchor));
+    rows.push(
+        (0..=end)
+            .map(|column| match column < start {
+                true => ' ',
+                false => junction(
+                    column == anchor,
+                    anchors.contains(&column),
+                    column > start,
+                    column < end,
+                ),
+            })
+            .collect(),
+    );
+    let height = children.iter().map(|child| child.rows.len()).max();
+    for line in 0..height.unwrap_or(0) {
+        let mut row = vec![];
+        for (child, offset) in children.iter().zip(&offsets) {
+            row.resize(*offset, ' ');
+            row.extend(child.rows.get(line).into_iter().flatten());
+        }
+        rows.push(row);
+    }
+
+    Drawing {
+        rows,
+        width,
+        anchor,
+    }
+}
+
+// An array operand or tine as it would be written, if it is a literal or a
+// name.
+fn array(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => n.to_string(),
+        Expr::String(s) => format!("'{}'", s.replace('\'', "''")),
+        Expr::Zilde => "⍬".to_string(),
+        Expr::Name(name) => name.clone(),
+        Expr::Strand(items) => items
+            .iter()
+            .map(|item| match item {
+                Expr::Strand(_) => format!("({})", array(item)),
+                item => array(item),
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => "(…)".to_string(),
+    }
+}
+
+fn operand(operand: &Operand) -> Drawing {
+    match operand {
+        Operand::Function(function) => draw(function),
+        Operand::Array(expr) => Drawing::leaf(array(expr)),
+    }
+}
+
+fn draw(function: &Function) -> Drawing {
+    match function {
+        Function::Primitive(token) => Drawing::leaf(spell(token)),
+        Function::Name(name) => Drawing::leaf(name.clone()),
+        Function::Dfn(_) => Drawing::leaf("{…}".to_string()),
+        Function::Tradfn(tradfn) => Drawing::leaf(tradfn.name.clone()),
+        Function::Derived(operator, left, right) => {
+            let mut operands = vec![operand(left)];
+            operands.extend(right.iter().map(|right| operand(right)));
+            join(Some(spell(operator)), operands)
+        }
+        Function::Outer(function) => join(Some("∘.".to_string()), vec![draw(function)]),
+        Function::Fork(left, middle, right) => {
+            join(None, vec![operand(left), draw(middle), draw(right)])
+        }
+        Function::Atop(left, right) => join(None, vec![draw(left), draw(right)]),
+        Function::Idiom(_, phrase) => draw(phrase),
+    }
+}
+
+/// The lines of a drawing of `function` as a tree.
+pub fn tree(function: &Function) -> Vec<String> {
+    draw(function)
+        .rows
+        .iter()
+        .map(|row| row.iter().collect::<String>().trim_end().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tree;
+    use crate::normalizer::normalize_apl_code;
+    use crate::parser::Parser;
+    use crate::tokenizer::bracket_partitioner::tokenize_to_partition;
+    use crate::tokenizer::tokenize;
+
+    fn drawn(source: &str) -> Vec<String> {
+        let tokens = tokenize(normalize_apl_code(source.to_string())).unwrap();
+        let function = Parser::default()
+            .function(&tokenize_to_partition(tokens).unwrap())
+            .unwrap();
+        tree(&function)
+    }
+
+    #[test]
+    fn it_draws_trains() {
+        assert_eq!(drawn("+/÷≢"), ["  ┌─┼─┐", "  / ÷ ≢", "┌─┘", "+"]);
+        assert_eq!(drawn("-÷"), ["┌┴┐", "- ÷"]);
+        // (e f g h) is an atop of e and the fork (f g h)
+        assert_eq!(drawn("-1+⊢"), ["┌─┴─┐", "- ┌─┼─┐", "  1 + ⊢"]);
+    }
+
+    #[test]
+    fn it_draws_operators() {
+        assert_eq!(drawn("+.×"), [" .", "┌┴┐", "+ ×"]);
+        assert_eq!(drawn("∘.×"), ["  ∘.", "┌─┘", "×"]);
+        assert_eq!(drawn("+⍤1 0"), [" ⍤", "┌┴┐", "+ 1 0"]);
+    }
+}
@@ -0,0 +1,18 @@
+pub(crate) mod array;
+pub(crate) mod bits;
+pub mod evaluator;
+pub(crate) mod ir;
+pub(crate) mod lanes;
+pub(crate) mod number;
+pub(crate) mod operators;
+pub(crate) mod primitives;
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::runtime::array::{Array, Scalar};
+
+    /// A vector of the numbers `ns`.
+    pub(crate) fn numbers(ns: &[f64]) -> Array {
+        Array::vector(ns.iter().map(|n| Scalar::from(*n)).collect())
+    }
+}
@@ -0,0 +1,531 @@
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::convert::TryFrom;
+
+use num_rational::BigRational;
+
+use crate::errors::Errors;
+use crate::runtime::bits::Bits;
+use crate::runtime::lanes::Lanes;
+use crate::runtime::number::{Int, IntType, Number, Precision};
+use crate::tokenizer::numeric_literal::NumericLiteral;
+use crate::tokenizer::Token;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Scalar {
+    Number(Number),
+    // A single Unicode code point. Comparison and ordering are by code point;
+    // source text is NFC normalised before it reaches the tokenizer.
+    Char(char),
+    // An enclosed array. Never holds a simple scalar, enclosing one of those
+    // is a no-op (see `Array::enclose`).
+    Boxed(Box<Array>),
+}
+
+impl Scalar {
+    pub fn is_char(&self) -> bool {
+        matches!(self, Scalar::Char(_))
+    }
+
+    pub fn is_simple(&self) -> bool {
+        !matches!(self, Scalar::Boxed(_))
+    }
+
+    pub fn as_number(&self) -> anyhow::Result<Number> {
+        match self {
+            Scalar::Number(n) => Ok(n.clone()),
+            Scalar::Char(c) => {
+                anyhow::bail!(Errors::Domain(format!("expected a number, got {:?}", c)))
+            }
+            Scalar::Boxed(_) => {
+                anyhow::bail!(Errors::Domain("expected a number, got a box".to_string()))
+            }
+        }
+    }
+
+    pub fn as_real(&self) -> anyhow::Result<f64> {
+        match self.as_number()?.as_real() {
+            Some(n) => Ok(n),
+            None => anyhow::bail!(Errors::Domain(
+                "expected a real number, got a complex one".to_string()
+            )),
+        }
+    }
+
+    /// The fill element for this element's type: `0` for numbers, `' '` for
+    /// characters, and for a box the box of its array's fills.
+    pub fn fill(&self) -> Scalar {
+        match self {
+            Scalar::Number(n) => Scalar::Number(n.zero()),
+            Scalar::Char(_) => Scalar::Char(' '),
+            Scalar::Boxed(array) => Scalar::Boxed(Box::new(array.fills())),
+        }
+    }
+
+    /// Opens a box. Simple scalars disclose to themselves.
+    pub fn disclose(self) -> Array {
+        match self {
+            Scalar::Boxed(array) => *array,
+            scalar => Array::scalar(scalar),
+        }
+    }
+
+    pub fn depth(&self) -> isize {
+        match self {
+            Scalar::Boxed(array) => array.depth().abs(),
+            _ => 0,
+        }
+    }
+
+    /// Equality of value: numbers compare by value whatever their type (see
+    /// `Number::same`), and boxes by `Array::matches`.
+    pub fn matches(&self, other: &Scalar) -> bool {
+        match (self, other) {
+            (Scalar::Number(a), Scalar::Number(b)) => a.same(b),
+            (Scalar::Boxed(a), Scalar::Boxed(b)) => a.matches(b),
+            (a, b) => a == b,
+        }
+    }
+
+    fn as_boolean(&self) -> Option<bool> {
+        match self {
+            Scalar::Number(n) => n.as_boolean(),
+            _ => None,
+        }
+    }
+
+    fn is_u1(&self) -> bool {
+        matches!(self, Scalar::Number(Number::Int(i)) if i.ty() == IntType::U1)
+    }
+}
+
+impl From<Number> for Scalar {
+    fn from(n: Number) -> Self {
+        Scalar::Number(n)
+    }
+}
+
+impl From<f64> for Scalar {
+    fn from(n: f64) -> Self {
+        Scalar::Number(Number::Real(n))
+    }
+}
+
+impl From<char> for Scalar {
+    fn from(c: char) -> Self {
+        Scalar::Char(c)
+    }
+}
+
+impl From<bool> for Scalar {
+    fn from(b: bool) -> Self {
+        Scalar::Number(Number::boolean(b))
+    }
+}
+
+impl TryFrom<&NumericLiteral> for Scalar {
+    type Error = anyhow::Error;
+
+    fn try_from(literal: &NumericLiteral) -> anyhow::Result<Self> {
+        Ok(Scalar::Number(match *literal {
+            NumericLiteral::Float(volume, n) => Number::float(n, Precision::from_volume(volume)?),
+            NumericLiteral::Auto(n) => Number::Real(n),
+            NumericLiteral::SysUint(n) => Number::Int(Int::new(n as i128, IntType::U64)?),
+            NumericLiteral::SysInt(n) => Number::Int(Int::new(n as i128, IntType::I64)?),
+            NumericLiteral::Uint(bits, n) => {
+                Number::Int(Int::new(n as i128, IntType::new(false, bits)?)?)
+            }
+            NumericLiteral::Int(bits, n) => {
+                Number::Int(Int::new(n as i128, IntType::new(true, bits)?)?)
+            }
+            NumericLiteral::Boolean(b) => return Ok(b.into()),
+            NumericLiteral::Complex(re, im) => Number::complex(re, im),
+            NumericLiteral::Big(ref n) => Number::Rational(BigRational::from_integer(n.clone())),
+            NumericLiteral::Rational(ref q) => Number::Rational(q.clone()),
+        }))
+    }
+}
+
+// The elements of an array. Arrays of nothing but `u1`s are packed a bit
+// each, and those `⎕INTO` or a kernel gave a single fixed width are kept
+// unboxed; either is unpacked only if someone asks for the scalars.
+#[derive(Debug, Clone)]
+enum Data {
+    Scalars(Vec<Scalar>),
+    Bits(Bits, OnceCell<Vec<Scalar>>),
+    Lanes(Lanes, OnceCell<Vec<Scalar>>),
+}
+
+/// A rectangular array stored in row-major order.
+#[derive(Debug, Clone)]
+pub struct Array {
+    shape: Vec<usize>,
+    data: Data,
+    // An empty array has no first element to take its prototype from, so it
+    // carries one. Always `None` for non-empty arrays.
+    prototype: Option<Box<Scalar>>,
+}
+
+impl PartialEq for Array {
+    fn eq(&self, other: &Array) -> bool {
+        self.shape == other.shape
+            && match (&self.data, &other.data) {
+                (Data::Bits(a, _), Data::Bits(b, _)) => a == b,
+                (Data::Lanes(a, _), Data::Lanes(b, _)) => a == b,
+                _ => self.data() == other.data(),
+            }
+            && (!self.is_empty() || self.prototype() == other.prototype())
+    }
+}
+
+fn unpack(bits: &Bits) -> Vec<Scalar> {
+    bits.iter().map(Scalar::from).collect()
+}
+
+impl Array {
+    pub fn new(shape: Vec<usize>, data: Vec<Scalar>) -> Self {
+        assert_eq!(
+            shape.iter().product::<usize>(),
+            data.len(),
+            "shape {:?} does not match {} elements",
+            shape,
+            data.len()
+        );
+        let data = match !data.is_empty() && data.iter().all(Scalar::is_u1) {
+            true => Data::Bits(
+                data.iter().map(|b| b.as_boolean().unwrap()).collect(),
+                OnceCell::new(),
+            ),
+            false => Data::Scalars(data),
+        };
+        Array {
+            shape,
+            data,
+            prototype: None,
+        }
+    }
+
+    /// A boolean array from packed bits.
+    pub fn from_bits(shape: Vec<usize>, bits: Bits) -> Self {
+        assert_eq!(shape.iter().product::<usize>(), bits.len());
+        match bits.is_empty() {
+            true => Array::new(shape, vec![]),
+            false => Array {
+                shape,
+                data: Data::Bits(bits, OnceCell::new()),
+                prototype: None,
+            },
+        }
+    }
+
+    /// An array of a single fixed width from its unboxed elements.
+    pub fn from_lanes(shape: Vec<usize>, lanes: Lanes) -> Self {
+        assert_eq!(shape.iter().product::<usize>(), lanes.len());
+        match lanes.is_empty() {
+            true => Array::new(shape, vec![]),
+            false => Array {
+                shape,
+                data: Data::Lanes(lanes, OnceCell::new()),
+                prototype: None,
+            },
+        }
+    }
+
+    pub fn scalar(scalar: impl Into<Scalar>) -> Self {
+        Array::new(vec![], vec![scalar.into()])
+    }
+
+    pub fn vector(data: Vec<Scalar>) -> Self {
+        Array::new(vec![data.len()], data)
+    }
+
+    /// Sets the prototype of an empty array; non-empty arrays take theirs
+    /// from their first element and are returned unchanged.
+    pub fn with_prototype(mut self, prototype: Scalar) -> Self {
+        if self.is_empty() {
+            self.prototype = Some(Box::new(prototype.fill()));
+        }
+        self
+    }
+
+    /// The fill of the first element, or the stored prototype of an empty
+    /// array (`0` unless set with `with_prototype`).
+    pub fn prototype(&self) -> Scalar {
+        match &self.data {
+            Data::Bits(..) => return Scalar::from(false),
+            Data::Lanes(lanes, _) => return lanes.get(0).fill(),
+            Data::Scalars(_) => {}
+        }
+        match (self.data().first(), &self.prototype) {
+            (Some(first), _) => first.fill(),
+            (None, Some(prototype)) => (**prototype).clone(),
+            (None, None) => Scalar::from(0.0),
+        }
+    }
+
+    /// The array with every element replaced by its fill.
+    pub fn fills(&self) -> Array {
+        Array::new(
+            self.shape.clone(),
+            self.data().iter().map(Scalar::fill).collect(),
+        )
+        .with_prototype(self.prototype())
+    }
+
+    /// Encloses the array. A simple scalar encloses to itself.
+    pub fn enclose(self) -> Scalar {
+        if self.is_scalar() && self.data()[0].is_simple() {
+            self.into_data().pop().unwrap()
+        } else {
+            Scalar::Boxed(Box::new(self))
+        }
+    }
+
+    /// The nesting depth: `0` for a simple scalar, `1` for a simple array and
+    /// one more than the deepest element otherwise. Negative when the
+    /// elements are not all equally deep.
+    pub fn depth(&self) -> isize {
+        if self.is_scalar() && self.data()[0].is_simple() {
+            return 0;
+        }
+
+        let depths: Vec<isize> = match self.data().is_empty() {
+            true => vec![self.prototype().depth()],
+            false => self.data().iter().map(Scalar::depth).collect(),
+        };
+        let deepest = depths.iter().copied().max().unwrap();
+        let uneven = depths.iter().any(|depth| *depth != deepest)
+            || self.data().iter().any(|scalar| match scalar {
+                Scalar::Boxed(array) => array.depth() < 0,
+                _ => false,
+            });
+
+        if uneven {
+            -(deepest + 1)
+        } else {
+            deepest + 1
+        }
+    }
+
+    /// The value of a string literal: a single character is a scalar and
+    /// anything else a character vector.
+    pub fn from_string_literal(s: &str) -> Self {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Array::scalar(c),
+            _ => Array::vector(s.chars().map(Scalar::Char).collect())
+                .with_prototype(Scalar::Char(' ')),
+        }
+    }
+
+    /// The value of a literal token, if it is one.
+    pub fn from_literal(token: &Token) -> Option<anyhow::Result<Self>> {
+        match token {
+            Token::StringLiteral(s) => Some(Ok(Array::from_string_literal(s))),
+            Token::NumericLiteral(n) => Some(Scalar::try_from(n).map(Array::scalar)),
+            Token::Zilde => Some(Ok(Array::vector(vec![]))),
+            _ => None,
+        }
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn rank(&self) -> usize {
+        self.shape.len()
+    }
+
+    pub fn data(&self) -> &[Scalar] {
+        match &self.data {
+            Data::Scalars(data) => data,
+            Data::Bits(bits, unpacked) => unpacked.get_or_init(|| unpack(bits)),
+            Data::Lanes(lanes, unpacked) => unpacked.get_or_init(|| lanes.boxed()),
+        }
+    }
+
+    pub fn into_data(self) -> Vec<Scalar> {
+        match self.data {
+            Data::Scalars(data) => data,
+            Data::Bits(bits, mut unpacked) => unpacked.take().unwrap_or_else(|| unpack(&bits)),
+            Data::Lanes(lanes, mut unpacked) => unpacked.take().unwrap_or_else(|| lanes.boxed()),
+        }
+    }
+
+    /// The packed bits of an array of `u1`s.
+    pub fn bits(&self) -> Option<&Bits> {
+        match &self.data {
+            Data::Bits(bits, _) => Some(bits),
+            Data::Scalars(_) | Data::Lanes(..) => None,
+        }
+    }
+
+    /// The elements unboxed if they are all of one fixed width. Borrowed
+    /// when the array is kept that way.
+    pub fn lanes(&self) -> Option<Cow<'_, Lanes>> {
+        match &self.data {
+            Data::Lanes(lanes, _) => Some(Cow::Borrowed(lanes)),
+            Data::Scalars(data) => Lanes::unbox(data).map(Cow::Owned),
+            Data::Bits(..) => None,
+        }
+    }
+
+    /// The elements as packed bits if they are all 0 or 1, of any numeric
+    /// type. Borrowed when the array is already packed.
+    pub fn as_bits(&self) -> Option<Cow<'_, Bits>> {
+        match &self.data {
+            Data::Bits(bits, _) => Some(Cow::Borrowed(bits)),
+            Data::Scalars(_) | Data::Lanes(..) => self
+                .data()
+                .iter()
+                .map(Scalar::as_boolean)
+                .collect::<Option<Bits>>()
+                .map(Cow::Owned),
+        }
+    }
+
+    /// Whether the arrays have the same shape and elements of the same
+    /// value, as `≡` sees them.
+    pub fn matches(&self, other: &Array) -> bool {
+        self.shape == other.shape
+            && match self.data().is_empty() {
+                true => self.prototype().matches(&other.prototype()),
+                false => self
+                    .data()
+                    .iter()
+                    .zip(other.data())
+                    .all(|(a, b)| a.matches(b)),
+            }
+    }
+
+    // Packed arrays are never empty, see `from_bits` and `from_lanes`.
+    fn is_empty(&self) -> bool {
+        matches!(&self.data, Data::Scalars(data) if data.is_empty())
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        self.shape.is_empty()
+    }
+
+    /// Whether every element is a character. Empty arrays go by their
+    /// prototype.
+    pub fn is_char(&self) -> bool {
+        match self.data().is_empty() {
+            true => self.prototype().is_char(),
+            false => self.data().iter().all(Scalar::is_char),
+        }
+    }
+
+    pub fn is_simple(&self) -> bool {
+        matches!(self.data, Data::Bits(..) | Data::Lanes(..))
+            || self.data().iter().all(Scalar::is_simple)
+    }
+
+    /// The length of the leading axis, `1` for a scalar.
+    pub fn tally(&self) -> usize {
+        self.shape.first().copied().unwrap_or(1)
+    }
+
+    pub fn ravel(self) -> Self {
+        let prototype = self.prototype();
+        match self.data {
+            Data::Bits(bits, _) => Array::from_bits(vec![bits.len()], bits),
+            Data::Lanes(lanes, _) => Array::from_lanes(vec![lanes.len()], lanes),
+            Data::Scalars(data) => Array::vector(data).with_prototype(prototype),
+        }
+    }
+
+    /// Reads the array back as a string if it is a character scalar or
+    /// vector.
+    pub fn as_string(&self) -> Option<String> {
+        if self.rank() > 1 {
+            return None;
+        }
+        self.data()
+            .iter()
+            .map(|scalar| match scalar {
+                Scalar::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Reads a single `0` or `1`, as a guard or `⍣`'s right operand
+    /// expects.
+    pub fn as_condition(&self) -> anyhow::Result<bool> {
+        match self.data() {
+            [scalar] => match scalar.as_boolean() {
+                Some(b) => Ok(b),
+                None => anyhow::bail!(Errors::Domain(format!(
+                    "expected a boolean, got {:?}",
+                    scalar
+                ))),
+            },
+            _ => anyhow::bail!(Errors::Length("expected a single boolean".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Array, Scalar};
+
+    #[test]
+    fn it_makes_char_vectors_from_strings() {
+        assert_eq!(Array::from_string_literal("a"), Array::scalar('a'));
+        assert_eq!(Array::from_string_literal("ab").shape(), &[2]);
+        assert_eq!(Array::from_string_literal("").shape(), &[0]);
+
+        assert_eq!(
+            Array::from_string_literal("⍴a").data(),
+            &[Scalar::Char('⍴'), Scalar::Char('a')]
+        );
+        assert_eq!(
+            Array::from_string_literal("héllo").as_string(),
+            Some("héllo".to_string())
+        );
+        assert!(Array::from_string_literal("").is_char());
+    }
+
+    #[test]
+    fn it_tracks_prototypes() {
+        let nested = Array::vector(vec![
+            Array::from_string_literal("ab").enclose(),
+            Scalar::from(1.0),
+        ]);
+        assert_eq!(
+            nested.prototype(),
+            Array::vector(vec![' '.into(), ' '.into()]).enclose()
+        );
+
+        let empty = Array::vector(vec![]).with_prototype(nested.data()[0].clone());
+        assert_eq!(empty.prototype(), nested.prototype());
+        assert_eq!(Array::vector(vec![]).prototype(), Scalar::from(0.0));
+    }
+
+    #[test]
+    fn it_packs_booleans() {
+        let booleans = Array::vector((0..70).map(|i| Scalar::from(i % 2 == 0)).collect());
+        assert!(booleans.bits().is_some());
+        assert_eq!(booleans.data()[68], Scalar::from(true));
+        assert_eq!(booleans.prototype(), Scalar::from(false));
+        assert_eq!(booleans.clone().ravel(), booleans);
+
+        // Zeros and ones of other types read as bits but stay unpacked
+        let reals = Array::vector(
+            (0..70)
+                .map(|i| Scalar::from((i % 2 == 0) as u8 as f64))
+                .collect(),
+        );
+        assert!(reals.bits().is_none());
+        assert_eq!(
+            reals.as_bits().unwrap().into_owned(),
+            booleans.bits().unwrap().clone()
+        );
+        assert_ne!(reals, booleans);
+        assert!(reals.matches(&booleans));
+
+        let mixed = Array::vector(vec![true.into(), 'a'.into()]);
+        assert!(mixed.bits().is_none() && mixed.as_bits().is_none());
+    }
+}
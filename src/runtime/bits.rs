@@ -0,0 +1,245 @@
+/// A packed vector of booleans, 64 to a word. Bits past `len` in the last
+/// word are always zero, so whole words can be compared and counted.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Bits {
+    words: Vec<u64>,
+    len: usize,
+}
+
+const WORD: usize = 64;
+
+fn words_for(len: usize) -> usize {
+    len.div_ceil(WORD)
+}
+
+impl Bits {
+    pub fn zeros(len: usize) -> Self {
+        Bits {
+            words: vec![0; words_for(len)],
+            len,
+        }
+    }
+
+    pub fn ones(len: usize) -> Self {
+        let mut bits = Bits {
+            words: vec![u64::MAX; words_for(len)],
+            len,
+        };
+        bits.clear_tail();
+        bits
+    }
+
+    /// Bits from whole words, 64 to each, ignoring any past `len`.
+    pub fn from_words(words: Vec<u64>, len: usize) -> Self {
+        assert_eq!(words.len(), words_for(len));
+        let mut bits = Bits { words, len };
+        bits.clear_tail();
+        bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "bit {} of {}", i, self.len);
+        self.words[i / WORD] >> (i % WORD) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, bit: bool) {
+        assert!(i < self.len, "bit {} of {}", i, self.len);
+        let mask = 1 << (i % WORD);
+        match bit {
+            true => self.words[i / WORD] |= mask,
+            false => self.words[i / WORD] &= !mask,
+        }
+    }
+
+    pub fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(WORD) {
+            self.words.push(0);
+        }
+        self.len += 1;
+        self.set(self.len - 1, bit);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |i| self.get(i))
+    }
+
+    /// The positions of the set bits, in order, found a word at a time.
+    pub fn ones_positions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut remaining = word;
+            std::iter::from_fn(move || match remaining {
+                0 => None,
+                _ => {
+                    let bit = remaining.trailing_zeros() as usize;
+                    remaining &= remaining - 1;
+                    Some(w * WORD + bit)
+                }
+            })
+        })
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The number of set bits from `start` up to but not including `end`.
+    pub fn count_ones_in(&self, start: usize, end: usize) -> usize {
+        assert!(start <= end && end <= self.len);
+        if start == end {
+            return 0;
+        }
+        let (first, last) = (start / WORD, (end - 1) / WORD);
+        let low = u64::MAX << (start % WORD);
+        let high = u64::MAX >> (WORD - 1 - (end - 1) % WORD);
+
+        if first == last {
+            return (self.words[first] & low & high).count_ones() as usize;
+        }
+        let middle: usize = self.words[first + 1..last]
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+        (self.words[first] & low).count_ones() as usize
+            + middle
+            + (self.words[last] & high).count_ones() as usize
+    }
+
+    fn clear_tail(&mut self) {
+        if !self.len.is_multiple_of(WORD) {
+            let last = self.words.len() - 1;
+            self.words[last] &= (1 << (self.len % WORD)) - 1;
+        }
+    }
+
+    /// Applies `op` a word at a time.
+    pub fn map(&self, op: impl Fn(u64) -> u64) -> Self {
+        let mut bits = Bits {
+            words: self.words.iter().map(|&word| op(word)).collect(),
+            len: self.len,
+        };
+        bits.clear_tail();
+        bits
+    }
+
+    /// Combines two equally long vectors a word at a time.
+    pub fn zip(&self, other: &Bits, op: impl Fn(u64, u64) -> u64) -> Self {
+        assert_eq!(self.len, other.len);
+        let mut bits = Bits {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(&a, &b)| op(a, b))
+                .collect(),
+            len: self.len,
+        };
+        bits.clear_tail();
+        bits
+    }
+
+    /// The bits of `self` where `mask` is set.
+    pub fn compress(&self, mask: &Bits) -> Self {
+        assert_eq!(self.len, mask.len);
+        // An all-ones word copies straight across when aligned.
+        let mut bits = Bits::zeros(0);
+        for (w, (&word, &selected)) in self.words.iter().zip(&mask.words).enumerate() {
+            if selected == u64::MAX && bits.len.is_multiple_of(WORD) {
+                bits.words.push(word);
+                bits.len += WORD.min(self.len - w * WORD);
+                continue;
+            }
+            let mut remaining = selected;
+            while remaining != 0 {
+                let bit = remaining.trailing_zeros();
+                remaining &= remaining - 1;
+                bits.push(word >> bit & 1 == 1);
+            }
+        }
+        bits
+    }
+}
+
+impl std::iter::FromIterator<bool> for Bits {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = Bits::zeros(0);
+        for bit in iter {
+            bits.push(bit);
+        }
+        bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bits;
+
+    fn bits(pattern: &str) -> Bits {
+        pattern.chars().map(|c| c == '1').collect()
+    }
+
+    #[test]
+    fn it_packs_and_unpacks() {
+        let pattern: String = (0..150)
+            .map(|i| if i % 3 == 0 { '1' } else { '0' })
+            .collect();
+        let packed = bits(&pattern);
+
+        assert_eq!(packed.len(), 150);
+        assert_eq!(packed.count_ones(), 50);
+        assert_eq!(
+            packed
+                .iter()
+                .map(|b| if b { '1' } else { '0' })
+                .collect::<String>(),
+            pattern
+        );
+        assert_eq!(
+            packed.ones_positions().collect::<Vec<_>>(),
+            (0..150).step_by(3).collect::<Vec<_>>()
+        );
+        assert_eq!(Bits::ones(70).count_ones(), 70);
+
+        for (start, end) in [(0, 150), (1, 2), (5, 5), (3, 64), (63, 129), (60, 140)] {
+            assert_eq!(
+                packed.count_ones_in(start, end),
+                (start..end).filter(|i| i % 3 == 0).count(),
+                "{}..{}",
+                start,
+                end
+            );
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_tail_clear() {
+        assert_eq!(bits("101").map(|word| !word), bits("010"));
+        assert_eq!(Bits::ones(3).map(|word| !word).count_ones(), 0);
+    }
+
+    #[test]
+    fn it_compresses() {
+        assert_eq!(bits("1101").compress(&bits("0110")), bits("10"));
+
+        let long: String = (0..130)
+            .map(|i| if i % 2 == 0 { '1' } else { '0' })
+            .collect();
+        let everything = Bits::ones(130);
+        assert_eq!(bits(&long).compress(&everything), bits(&long));
+
+        let odd: String = (0..130)
+            .map(|i| if i % 2 == 1 { '1' } else { '0' })
+            .collect();
+        assert_eq!(bits(&long).compress(&bits(&odd)), Bits::zeros(65));
+    }
+}
@@ -0,0 +1,2177 @@
+// Runs programs: a session parses its input a statement at a time and
+// evaluates the trees the parser gives back. Names live in frames, one for
+// the session and one for each call of a dfn or tradfn, which sees the
+// frame the function was written in. A tradfn keeps only its arguments,
+// result and locals to itself; other names it assigns are assigned there.
+// An imported module runs in a frame of its own, which becomes the value
+// of its name: a namespace, whose members are found as `name.member`.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use crate::errors::Errors;
+use crate::normalizer::normalize_apl_code;
+use crate::parser::idioms::{recognise, Idiom};
+use crate::parser::modules::sections;
+use crate::parser::tree::tree;
+use crate::parser::{self, spell, Expr, Line, Parser, Statement};
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::ir;
+use crate::runtime::number::integer::{self, Overflow};
+use crate::runtime::number::{IntType, Number};
+use crate::runtime::operators::{
+    at, each, each_dyadic, inner_product, key, outer_product, power, power_until, rank,
+    rank_dyadic, rank_spec, reduce, reduce_first, stencil, stencil_spec, Replacement, Selection,
+};
+use crate::runtime::primitives::convert;
+use crate::runtime::primitives::format;
+use crate::runtime::primitives::fused;
+use crate::runtime::primitives::linear;
+use crate::runtime::primitives::logic::{self, Logic};
+use crate::runtime::primitives::order;
+use crate::runtime::primitives::random;
+use crate::runtime::primitives::scalar;
+use crate::runtime::primitives::search;
+use crate::runtime::primitives::signal;
+use crate::runtime::primitives::simd;
+use crate::runtime::primitives::structural;
+use crate::tokenizer::bracket_partitioner::tokenize_to_partition;
+use crate::tokenizer::{tokenize, Token};
+
+type Env = Rc<RefCell<Frame>>;
+
+#[derive(Default)]
+struct Frame {
+    names: HashMap<String, Value>,
+    parent: Option<Env>,
+    // For a tradfn call, the names it keeps to itself.
+    locals: Option<HashSet<String>>,
+    // Whether expressions in the frame and those below it are fused, if
+    // set here rather than above.
+    fusion: Option<bool>,
+}
+
+#[derive(Clone)]
+enum Value {
+    Array(Array),
+    Function(Function),
+    Namespace(Env),
+}
+
+// A function ready to apply: names looked up, array operands evaluated and
+// dfns closed over the frame they were written in.
+#[derive(Clone)]
+enum Function {
+    Primitive(Token),
+    Dfn(Rc<[Statement]>, Env),
+    Tradfn(Rc<parser::Tradfn>, Env),
+    // `⍎`, which runs code in the frame it was written in.
+    Execute(Env),
+    // One of `parser::SYSTEM_FUNCTIONS`.
+    System(String),
+    Derived(Token, Box<Operand>, Option<Box<Operand>>),
+    Outer(Box<Function>),
+    Fork(Box<Operand>, Box<Function>, Box<Function>),
+    Atop(Box<Function>, Box<Function>),
+    // An idiom's kernel, and the phrase it stands for.
+    Idiom(Idiom, Box<Function>),
+}
+
+#[derive(Clone)]
+enum Operand {
+    Function(Function),
+    Array(Array),
+}
+
+impl Operand {
+    fn function(&self, operator: &Token) -> anyhow::Result<&Function> {
+        match self {
+            Operand::Function(function) => Ok(function),
+            Operand::Array(_) => {
+                anyhow::bail!(Errors::Syntax(format!(
+                    "{} expects a function operand",
+                    spell(operator)
+                )))
+            }
+        }
+    }
+}
+
+// The settings `⍠` can change for the primitives in its left operand.
+#[derive(Clone, Copy)]
+struct Options {
+    // `⎕IO`, the index origin.
+    io: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { io: 1 }
+    }
+}
+
+// The prelude is built in, so that it is always the one that came with
+// the compiler.
+const PRELUDE: &str = include_str!("../../lib/prelude.icl");
+
+/// A workspace: the global names and what the parser knows of them.
+pub struct Session {
+    parser: Parser,
+    // Inside a frame of the prelude's names, which modules see too.
+    globals: Env,
+    // The idioms recognised in what the session ran, in order.
+    idioms: Vec<Idiom>,
+    system: System,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            parser: Parser::default(),
+            globals: Rc::new(RefCell::new(Frame {
+                parent: Some(Rc::new(RefCell::new(Frame::default()))),
+                ..Frame::default()
+            })),
+            idioms: vec![],
+            system: System::default(),
+        }
+    }
+}
+
+// The state of a session the primitives find in the thread running it,
+// rather than in a frame. A session puts its own in place to run code and
+// keeps what it was left as, so that sessions on one thread don't share
+// it.
+struct System {
+    // `⎕PP`.
+    precision: usize,
+    // `⎕RL`'s generator.
+    generator: random::Generator,
+    // The `⎕OVERFLOW` modes set.
+    overflows: HashMap<IntType, Overflow>,
+    // The error `⎕EN` and `⎕DM` tell of.
+    last: Option<Errors>,
+}
+
+impl Default for System {
+    fn default() -> Self {
+        System {
+            precision: format::DEFAULT_PRINT_PRECISION,
+            generator: random::Generator::default(),
+            overflows: HashMap::new(),
+            last: None,
+        }
+    }
+}
+
+impl System {
+    fn current() -> Self {
+        System {
+            precision: format::print_precision(),
+            generator: random::generator(),
+            overflows: integer::overflows(),
+            last: signal::last(),
+        }
+    }
+
+    fn restore(&self) {
+        format::set_print_precision(self.precision);
+        random::set_generator(self.generator.clone());
+        integer::set_overflows(self.overflows.clone());
+        signal::set_last(self.last.clone());
+    }
+}
+
+impl Session {
+    /// A session with the names of the prelude, the standard library in
+    /// `lib/prelude.icl`, already defined.
+    pub fn with_prelude() -> anyhow::Result<Self> {
+        let mut session = Session::default();
+        let prelude = root(&session.globals);
+        let outcome = session.running(|session| {
+            parse(&mut session.parser, &sections(PRELUDE), &mut vec![])
+                .and_then(|statements| run(&statements, &prelude))
+        });
+        match outcome {
+            Ok(_) => {
+                session.parser.share_prelude();
+                Ok(session)
+            }
+            Err(error) => {
+                let context = format!("{} in the prelude", error);
+                Err(error.context(context))
+            }
+        }
+    }
+
+    /// Adds a directory to the end of the path modules are looked for in.
+    pub fn search(&mut self, directory: impl Into<std::path::PathBuf>) {
+        self.parser.search(directory);
+    }
+
+    /// Runs `source`, giving the value of its last statement unless that
+    /// was an assignment. `]box f` gives instead a drawing of how the
+    /// function phrase `f` parsed, and `]ir x` the loops the expression `x`
+    /// fuses into, each as a character matrix. `]ir` evaluates the arrays
+    /// the loops would take, running any code in them.
+    pub fn execute(&mut self, source: &str) -> anyhow::Result<Option<Array>> {
+        self.running(|session| session.interpret(source))
+    }
+
+    /// The idioms recognised in the code the session has run, each time
+    /// one was found. Code run by `⍎`, imported from modules or in the
+    /// prelude has its idioms recognised too, but not listed.
+    pub fn idioms(&self) -> &[Idiom] {
+        &self.idioms
+    }
+
+    /// Turns loop fusion on or off; it is on unless turned off.
+    pub fn set_fusion(&mut self, on: bool) {
+        root(&self.globals).borrow_mut().fusion = Some(on);
+    }
+}
+
+impl Session {
+    // Runs `f` with the session's system state in place.
+    fn running<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.system.restore();
+        let outcome = f(self);
+        self.system = System::current();
+        outcome
+    }
+
+    fn interpret(&mut self, source: &str) -> anyhow::Result<Option<Array>> {
+        if let Some(phrase) = source.trim_start().strip_prefix("]box") {
+            return self.draw(phrase).map(Some);
+        }
+        if let Some(expr) = source.trim_start().strip_prefix("]ir") {
+            return self.ir(expr).map(Some);
+        }
+        let statements = parse(&mut self.parser, source, &mut self.idioms)?;
+        Ok(match run(&statements, &self.globals)? {
+            Outcome::Shown(array) => Some(array),
+            Outcome::Shy(_) | Outcome::Nothing => None,
+        })
+    }
+
+    fn draw(&mut self, phrase: &str) -> anyhow::Result<Array> {
+        let tokens = tokenize(normalize_apl_code(phrase.to_string()))?;
+        let function = self.parser.function(&tokenize_to_partition(tokens)?)?;
+        Ok(matrix(&tree(&function)))
+    }
+
+    fn ir(&mut self, source: &str) -> anyhow::Result<Array> {
+        let expr = match &parse(&mut self.parser, source, &mut vec![])?[..] {
+            [Statement::Expr(expr)] => expr.clone(),
+            _ => anyhow::bail!(Errors::Syntax("]ir takes an expression".to_string())),
+        };
+        let node = ir::lower(&expr, &mut |leaf| evaluate(leaf, &self.globals))?;
+        Ok(matrix(&ir::dump(&node)))
+    }
+}
+
+// Lines of text, as a character matrix padded on the right.
+fn matrix(lines: &[String]) -> Array {
+    let lines: Vec<Vec<char>> = lines.iter().map(|line| line.chars().collect()).collect();
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+    let data = lines
+        .iter()
+        .flat_map(|line| {
+            let padding = std::iter::repeat_n(' ', width - line.len());
+            line.iter().copied().chain(padding).map(Scalar::Char)
+        })
+        .collect();
+    Array::new(vec![lines.len(), width], data)
+}
+
+fn parse(
+    parser: &mut Parser,
+    source: &str,
+    idioms: &mut Vec<Idiom>,
+) -> anyhow::Result<Vec<Statement>> {
+    let tokens = tokenize(normalize_apl_code(source.to_string()))?;
+    let statements = parser.parse(&tokenize_to_partition(tokens)?)?;
+    Ok(recognise(&statements, idioms))
+}
+
+// What the last of a run of statements gave: nothing for a definition, and
+// for an assignment a shy value, which `⍎` passes on but a session doesn't
+// show.
+enum Outcome {
+    Nothing,
+    Shy(Array),
+    Shown(Array),
+}
+
+fn run(statements: &[Statement], env: &Env) -> anyhow::Result<Outcome> {
+    let mut outcome = Outcome::Nothing;
+    for statement in statements {
+        outcome = match statement {
+            Statement::Expr(expr @ Expr::Assign(..)) => Outcome::Shy(evaluate(expr, env)?),
+            Statement::Expr(expr) => match called(expr, env)? {
+                Some(array) => Outcome::Shown(array),
+                None => Outcome::Nothing,
+            },
+            Statement::Define(name, function) => {
+                let function = resolve(function, env)?;
+                assign(env, name, Value::Function(function));
+                Outcome::Nothing
+            }
+            Statement::Import(name, module) => {
+                let namespace = Rc::new(RefCell::new(Frame {
+                    parent: Some(root(env)),
+                    ..Frame::default()
+                }));
+                if let Err(error) = run(&module.statements, &namespace) {
+                    let context = format!("{} in module {}", error, module.name);
+                    return Err(error.context(context));
+                }
+                assign(env, name, Value::Namespace(namespace));
+                Outcome::Nothing
+            }
+            Statement::Guard(..) | Statement::ErrorGuard(..) => {
+                unreachable!("the parser keeps guards in dfns")
+            }
+        };
+    }
+    Ok(outcome)
+}
+
+// The value of a whole statement, which unlike any other expression may
+// be a call of a tradfn that gives no result.
+fn called(expr: &Expr, env: &Env) -> anyhow::Result<Option<Array>> {
+    match expr {
+        Expr::Name(name) => {
+            if let Some(Value::Function(Function::Tradfn(tradfn, defined))) = lookup(env, name) {
+                return call_tradfn(&tradfn, &defined, None, None);
+            }
+        }
+        // primitives only, which `evaluate` fuses
+        Expr::Monadic(..) | Expr::Dyadic(..) if fusible(expr, env) => {
+            return evaluate(expr, env).map(Some);
+        }
+        Expr::Monadic(function, right) => {
+            let right = evaluate(right, env)?;
+            return match resolve(function, env)? {
+                Function::Tradfn(tradfn, defined) => {
+                    call_tradfn(&tradfn, &defined, None, Some(right))
+                }
+                function => monadic(&function, right, Options::default()).map(Some),
+            };
+        }
+        Expr::Dyadic(left, function, right) => {
+            let right = evaluate(right, env)?;
+            let function = resolve(function, env)?;
+            let left = evaluate(left, env)?;
+            return match function {
+                Function::Tradfn(tradfn, defined) => {
+                    call_tradfn(&tradfn, &defined, Some(left), Some(right))
+                }
+                function => dyadic(&function, left, right, Options::default()).map(Some),
+            };
+        }
+        _ => {}
+    }
+    evaluate(expr, env).map(Some)
+}
+
+// `⍎`: runs a string in `env`, parsed knowing which of the names there are
+// functions. Errors say which string they came from; places in the error
+// are places in it.
+fn execute(right: &Array, env: &Env) -> anyhow::Result<Array> {
+    let source = match right.as_string() {
+        Some(source) => source,
+        None if right.data().is_empty() => String::new(),
+        None => anyhow::bail!(Errors::Domain("⍎ expects a character vector".to_string())),
+    };
+
+    let mut parser = Parser::default();
+    let mut frames = vec![env.clone()];
+    loop {
+        let parent = frames.last().unwrap().borrow().parent.clone();
+        match parent {
+            Some(parent) => frames.push(parent),
+            None => break,
+        }
+    }
+    // Outermost first, so that inner frames shadow outer ones
+    for frame in frames.iter().rev() {
+        for (name, value) in &frame.borrow().names {
+            match value {
+                Value::Namespace(namespace) => {
+                    let members = namespace
+                        .borrow()
+                        .names
+                        .iter()
+                        .map(|(member, value)| (member.clone(), is_function(value)))
+                        .collect::<Vec<_>>();
+                    parser.declare_module(name, members);
+                }
+                value => parser.declare(name, is_function(value)),
+            }
+        }
+    }
+
+    let outcome =
+        parse(&mut parser, &source, &mut vec![]).and_then(|statements| run(&statements, env));
+    match outcome {
+        Ok(Outcome::Shown(array) | Outcome::Shy(array)) => Ok(array),
+        Ok(Outcome::Nothing) => {
+            anyhow::bail!(Errors::Value(format!("⍎{} gave no result", quote(&source))))
+        }
+        Err(error) => {
+            let context = format!("{} in ⍎{}", error, quote(&source));
+            Err(error.context(context))
+        }
+    }
+}
+
+// Whether the parser should take a name holding `value` as a function.
+fn is_function(value: &Value) -> bool {
+    match value {
+        Value::Function(Function::Tradfn(tradfn, _)) => tradfn.right.is_some(),
+        Value::Function(_) => true,
+        Value::Array(_) | Value::Namespace(_) => false,
+    }
+}
+
+fn quote(source: &str) -> String {
+    format!("'{}'", source.replace('\'', "''"))
+}
+
+// `⍺` and `⍵` belong to a single call, every other name is also looked for
+// in the frames around it. A member of a namespace is looked for in it
+// alone.
+fn lookup(env: &Env, name: &str) -> Option<Value> {
+    if let Some((space, member)) = name.split_once('.') {
+        return match lookup(env, space) {
+            Some(Value::Namespace(namespace)) => lookup_member(&namespace, member),
+            _ => None,
+        };
+    }
+    let frame = env.borrow();
+    match (frame.names.get(name), &frame.parent) {
+        (Some(value), _) => Some(value.clone()),
+        (None, _) if name == "⍺" || name == "⍵" => None,
+        (None, Some(parent)) => lookup(parent, name),
+        (None, None) => None,
+    }
+}
+
+// A tradfn call assigns names it doesn't keep local where it was defined.
+// Whether `name` is bound to an array, without copying it as `lookup`
+// would.
+fn is_array(env: &Env, name: &str) -> bool {
+    if let Some((space, member)) = name.split_once('.') {
+        return match lookup(env, space) {
+            Some(Value::Namespace(namespace)) => {
+                matches!(namespace.borrow().names.get(member), Some(Value::Array(_)))
+            }
+            _ => false,
+        };
+    }
+    let frame = env.borrow();
+    match (frame.names.get(name), &frame.parent) {
+        (Some(value), _) => matches!(value, Value::Array(_)),
+        (None, _) if name == "⍺" || name == "⍵" => false,
+        (None, Some(parent)) => is_array(parent, name),
+        (None, None) => false,
+    }
+}
+
+// The outermost frame around `env`, holding the names of the prelude.
+fn root(env: &Env) -> Env {
+    match &env.borrow().parent {
+        Some(parent) => root(parent),
+        None => env.clone(),
+    }
+}
+
+// Whether fusion is on for `env`, as the nearest frame that says so has it.
+fn fusion(env: &Env) -> bool {
+    let frame = env.borrow();
+    match (frame.fusion, &frame.parent) {
+        (Some(on), _) => on,
+        (None, Some(parent)) => fusion(parent),
+        (None, None) => true,
+    }
+}
+
+// Whether `expr` is two or more primitives the IR fuses into one loop,
+// without changing what they mean.
+fn fusible(expr: &Expr, env: &Env) -> bool {
+    ir::operations(expr) > 1 && fusion(env) && ir::pure(expr, &|name| is_array(env, name))
+}
+
+fn lookup_member(namespace: &Env, member: &str) -> Option<Value> {
+    namespace.borrow().names.get(member).cloned()
+}
+
+fn assign(env: &Env, name: &str, value: Value) {
+    let shared = match &env.borrow().locals {
+        Some(locals) if !locals.contains(name) => env.borrow().parent.clone(),
+        _ => None,
+    };
+    match shared {
+        Some(parent) => assign(&parent, name, value),
+        None => {
+            env.borrow_mut().names.insert(name.to_string(), value);
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, env: &Env) -> anyhow::Result<Array> {
+    Ok(match expr {
+        Expr::Number(literal) => Array::scalar(Scalar::try_from(literal)?),
+        Expr::String(s) => Array::from_string_literal(s),
+        Expr::Zilde => Array::vector(vec![]),
+        Expr::Name(name) if name.starts_with('⎕') => system_variable(name)?,
+        Expr::Name(name) => match lookup(env, name) {
+            Some(Value::Array(array)) => array,
+            Some(Value::Function(Function::Tradfn(tradfn, defined))) if tradfn.right.is_none() => {
+                result(&tradfn, call_tradfn(&tradfn, &defined, None, None)?)?
+            }
+            Some(Value::Function(_)) => {
+                anyhow::bail!(Errors::Syntax(format!("{} is a function", name)))
+            }
+            Some(Value::Namespace(_)) => {
+                anyhow::bail!(Errors::Syntax(format!("{} is a namespace", name)))
+            }
+            None => anyhow::bail!(Errors::Value(format!("{} is not defined", name))),
+        },
+        Expr::Strand(items) => {
+            let mut values = items
+                .iter()
+                .rev()
+                .map(|item| evaluate(item, env).map(Array::enclose))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            values.reverse();
+            Array::vector(values)
+        }
+        // two or more primitives the IR fuses into one loop
+        Expr::Monadic(..) | Expr::Dyadic(..) if fusible(expr, env) => {
+            ir::run(&ir::lower(expr, &mut |leaf| evaluate(leaf, env))?)?
+        }
+        Expr::Monadic(function, right) => {
+            let right = evaluate(right, env)?;
+            monadic(&resolve(function, env)?, right, Options::default())?
+        }
+        Expr::Dyadic(left, function, right) => {
+            let right = evaluate(right, env)?;
+            let function = resolve(function, env)?;
+            dyadic(&function, evaluate(left, env)?, right, Options::default())?
+        }
+        Expr::Index(array, indices) => {
+            let indices = evaluate(indices, env)?;
+            structural::index(&evaluate(array, env)?, &indices)?
+        }
+        Expr::Assign(name, value) => {
+            // `⍺←` gives a default, used only when there is no left argument
+            let existing = env.borrow().names.get("⍺").cloned();
+            if let (true, Some(Value::Array(alpha))) = (name == "⍺", existing) {
+                return Ok(alpha);
+            }
+            let value = evaluate(value, env)?;
+            match name.starts_with('⎕') {
+                true => set_system_variable(name, &value)?,
+                false => assign(env, name, Value::Array(value.clone())),
+            }
+            value
+        }
+    })
+}
+
+// The system variables belong to the workspace as a whole, rather than to
+// any frame.
+fn system_variable(name: &str) -> anyhow::Result<Array> {
+    match name {
+        "⎕PP" => Ok(format::precision()),
+        "⎕RL" => Ok(random::link()),
+        "⎕EN" => Ok(signal::number()),
+        "⎕DM" => Ok(signal::message()),
+        _ => anyhow::bail!(Errors::Value(format!("{} is not defined", name))),
+    }
+}
+
+fn set_system_variable(name: &str, value: &Array) -> anyhow::Result<()> {
+    match name {
+        "⎕PP" => format::set_precision(value),
+        "⎕RL" => random::set_link(value),
+        _ => anyhow::bail!(Errors::Syntax(format!("{} cannot be assigned", name))),
+    }
+}
+
+fn system_monadic(name: &str, right: &Array) -> anyhow::Result<Array> {
+    match name {
+        "⎕SIGNAL" => signal::signal(None, right),
+        "⎕OVERFLOW" => convert::overflow(None, right),
+        "⎕INTO" => anyhow::bail!(Errors::Valence(format!("{} needs a left argument", name))),
+        _ => anyhow::bail!(Errors::Value(format!("{} is not defined", name))),
+    }
+}
+
+fn system_dyadic(name: &str, left: &Array, right: &Array) -> anyhow::Result<Array> {
+    match name {
+        "⎕INTO" => convert::into(left, right),
+        "⎕SIGNAL" => signal::signal(Some(left), right),
+        "⎕OVERFLOW" => convert::overflow(Some(left), right),
+        _ => anyhow::bail!(Errors::Value(format!("{} is not defined", name))),
+    }
+}
+
+fn resolve(function: &parser::Function, env: &Env) -> anyhow::Result<Function> {
+    Ok(match function {
+        parser::Function::Primitive(Token::Hydrant) => Function::Execute(env.clone()),
+        parser::Function::Primitive(token) => Function::Primitive(token.clone()),
+        parser::Function::Name(name) if parser::SYSTEM_FUNCTIONS.contains(&name.as_str()) => {
+            Function::System(name.clone())
+        }
+        parser::Function::Name(name) => match lookup(env, name) {
+            Some(Value::Function(function)) => function,
+            Some(Value::Array(_)) => anyhow::bail!(Errors::Syntax(format!("{} is an array", name))),
+            Some(Value::Namespace(_)) => {
+                anyhow::bail!(Errors::Syntax(format!("{} is a namespace", name)))
+            }
+            None => anyhow::bail!(Errors::Value(format!("{} is not defined", name))),
+        },
+        parser::Function::Dfn(body) => Function::Dfn(body.clone(), env.clone()),
+        parser::Function::Tradfn(tradfn) => Function::Tradfn(tradfn.clone(), env.clone()),
+        parser::Function::Derived(operator, left, right) => {
+            let right = match right {
+                Some(right) => Some(Box::new(operand(right, env)?)),
+                None => None,
+            };
+            Function::Derived(operator.clone(), Box::new(operand(left, env)?), right)
+        }
+        parser::Function::Outer(function) => Function::Outer(Box::new(resolve(function, env)?)),
+        parser::Function::Fork(left, middle, right) => {
+            let right = Box::new(resolve(right, env)?);
+            let middle = Box::new(resolve(middle, env)?);
+            Function::Fork(Box::new(operand(left, env)?), middle, right)
+        }
+        parser::Function::Atop(left, right) => {
+            let right = Box::new(resolve(right, env)?);
+            Function::Atop(Box::new(resolve(left, env)?), right)
+        }
+        parser::Function::Idiom(idiom, phrase) => {
+            Function::Idiom(*idiom, Box::new(resolve(phrase, env)?))
+        }
+    })
+}
+
+fn operand(operand: &parser::Operand, env: &Env) -> anyhow::Result<Operand> {
+    Ok(match operand {
+        parser::Operand::Function(function) => Operand::Function(resolve(function, env)?),
+        parser::Operand::Array(expr) => Operand::Array(evaluate(expr, env)?),
+    })
+}
+
+fn call(dfn: &Function, left: Option<Array>, right: Array) -> anyhow::Result<Array> {
+    let (body, env) = match dfn {
+        Function::Dfn(body, env) => (body, env),
+        _ => unreachable!(),
+    };
+    let frame = Rc::new(RefCell::new(Frame {
+        names: HashMap::new(),
+        parent: Some(env.clone()),
+        locals: None,
+        fusion: None,
+    }));
+    assign(&frame, "⍵", Value::Array(right));
+    if let Some(left) = left {
+        assign(&frame, "⍺", Value::Array(left));
+    }
+    assign(&frame, "∇", Value::Function(dfn.clone()));
+    dfn_body(body, &frame)
+}
+
+// Runs the statements of a dfn. The first expression that isn't an
+// assignment gives the result.
+fn dfn_body(body: &[Statement], frame: &Env) -> anyhow::Result<Array> {
+    for (i, statement) in body.iter().enumerate() {
+        match statement {
+            Statement::Expr(expr @ Expr::Assign(..)) => {
+                evaluate(expr, frame)?;
+            }
+            Statement::Expr(expr) => return evaluate(expr, frame),
+            Statement::Guard(condition, result) => {
+                if evaluate(condition, frame)?.as_condition()? {
+                    return evaluate(result, frame);
+                }
+            }
+            Statement::ErrorGuard(codes, result) => {
+                let codes = signal::codes(&evaluate(codes, frame)?)?;
+                return match dfn_body(&body[i + 1..], frame) {
+                    Err(error) if signal::caught(&error, &codes) => evaluate(result, frame),
+                    outcome => outcome,
+                };
+            }
+            Statement::Define(name, function) => {
+                let function = resolve(function, frame)?;
+                assign(frame, name, Value::Function(function));
+            }
+            Statement::Import(..) => unreachable!("the parser keeps imports out of dfns"),
+        }
+    }
+    anyhow::bail!(Errors::Value("the dfn ended without a result".to_string()))
+}
+
+// Calls a tradfn in a frame of its own, giving the value of its result
+// name if that was set. Localised system variables get their values back
+// when it returns.
+fn call_tradfn(
+    tradfn: &parser::Tradfn,
+    env: &Env,
+    left: Option<Array>,
+    right: Option<Array>,
+) -> anyhow::Result<Option<Array>> {
+    match (&tradfn.left, &left, &tradfn.right, &right) {
+        (None, Some(_), _, _) => {
+            anyhow::bail!(Errors::Valence(format!(
+                "{} takes no left argument",
+                tradfn.name
+            )))
+        }
+        (_, _, None, Some(_)) => anyhow::bail!(Errors::Valence(format!(
+            "{} takes no arguments",
+            tradfn.name
+        ))),
+        (_, _, Some(_), None) => {
+            anyhow::bail!(Errors::Valence(format!(
+                "{} needs a right argument",
+                tradfn.name
+            )))
+        }
+        _ => {}
+    }
+    let parameters = [&tradfn.result, &tradfn.left, &tradfn.right];
+    let locals = parameters
+        .iter()
+        .copied()
+        .flatten()
+        .chain(&tradfn.locals)
+        .cloned()
+        .collect();
+    let frame = Rc::new(RefCell::new(Frame {
+        names: HashMap::new(),
+        parent: Some(env.clone()),
+        locals: Some(locals),
+        fusion: None,
+    }));
+    for (name, value) in [(&tradfn.left, left), (&tradfn.right, right)] {
+        if let (Some(name), Some(value)) = (name, value) {
+            assign(&frame, name, Value::Array(value));
+        }
+    }
+
+    let saved = tradfn
+        .locals
+        .iter()
+        .filter(|name| name.starts_with('⎕'))
+        .map(|name| Ok((name, system_variable(name)?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let last = signal::last();
+    let flow = block(&tradfn.body, &frame);
+    for (name, value) in saved {
+        match name.as_str() {
+            // read-only, so given back the error they tell of
+            "⎕EN" | "⎕DM" => signal::set_last(last.clone()),
+            name => set_system_variable(name, &value)?,
+        }
+    }
+    if let Flow::Leave | Flow::Continue = flow? {
+        anyhow::bail!(Errors::Syntax(format!(
+            ":Leave or :Continue outside a loop in {}",
+            tradfn.name
+        )));
+    }
+
+    let result = match &tradfn.result {
+        Some(result) => frame.borrow().names.get(result).cloned(),
+        None => None,
+    };
+    Ok(match result {
+        Some(Value::Array(array)) => Some(array),
+        _ => None,
+    })
+}
+
+// A tradfn's result, where a value is needed.
+fn result(tradfn: &parser::Tradfn, result: Option<Array>) -> anyhow::Result<Array> {
+    match result {
+        Some(array) => Ok(array),
+        None => anyhow::bail!(Errors::Value(format!("{} gave no result", tradfn.name))),
+    }
+}
+
+// How a run of tradfn lines ended: by running out, or by a jump out of the
+// loop or the function around them.
+enum Flow {
+    Next,
+    Leave,
+    Continue,
+    Return,
+}
+
+fn block(lines: &[Line], env: &Env) -> anyhow::Result<Flow> {
+    for line in lines {
+        let flow = match line {
+            Line::Statement(statement) => {
+                run(std::slice::from_ref(statement), env)?;
+                Flow::Next
+            }
+            Line::If(arms, otherwise) => {
+                let mut chosen = otherwise;
+                for (condition, body) in arms {
+                    if evaluate(condition, env)?.as_condition()? {
+                        chosen = body;
+                        break;
+                    }
+                }
+                block(chosen, env)?
+            }
+            Line::For(names, array, body) => {
+                let array = evaluate(array, env)?;
+                let mut flow = Flow::Next;
+                for item in array.data() {
+                    bind_each(names, item.clone().disclose(), env)?;
+                    if let Some(end) = looping(body, env)? {
+                        flow = end;
+                        break;
+                    }
+                }
+                flow
+            }
+            Line::While(condition, body, until) => loop {
+                if !evaluate(condition, env)?.as_condition()? {
+                    break Flow::Next;
+                }
+                if let Some(end) = looping(body, env)? {
+                    break end;
+                }
+                if let Some(until) = until {
+                    if evaluate(until, env)?.as_condition()? {
+                        break Flow::Next;
+                    }
+                }
+            },
+            Line::Repeat(body, until) => loop {
+                if let Some(end) = looping(body, env)? {
+                    break end;
+                }
+                if let Some(until) = until {
+                    if evaluate(until, env)?.as_condition()? {
+                        break Flow::Next;
+                    }
+                }
+            },
+            Line::Select(value, cases, otherwise) => {
+                let value = evaluate(value, env)?;
+                let mut chosen = otherwise;
+                for case in cases {
+                    let candidate = evaluate(&case.value, env)?;
+                    let matched = match case.list {
+                        true => candidate
+                            .data()
+                            .iter()
+                            .any(|item| item.clone().disclose().matches(&value)),
+                        false => candidate.matches(&value),
+                    };
+                    if matched {
+                        chosen = &case.body;
+                        break;
+                    }
+                }
+                block(chosen, env)?
+            }
+            Line::Trap(codes, body, otherwise) => {
+                let codes = signal::codes(&evaluate(codes, env)?)?;
+                match block(body, env) {
+                    Ok(flow) => flow,
+                    Err(error) if signal::caught(&error, &codes) => block(otherwise, env)?,
+                    Err(error) => return Err(error),
+                }
+            }
+            Line::Leave => Flow::Leave,
+            Line::Continue => Flow::Continue,
+            Line::Return => Flow::Return,
+        };
+        if !matches!(flow, Flow::Next) {
+            return Ok(flow);
+        }
+    }
+    Ok(Flow::Next)
+}
+
+// Runs a loop body once, giving how the loop ends if it does.
+fn looping(body: &[Line], env: &Env) -> anyhow::Result<Option<Flow>> {
+    Ok(match block(body, env)? {
+        Flow::Next | Flow::Continue => None,
+        Flow::Leave => Some(Flow::Next),
+        Flow::Return => Some(Flow::Return),
+    })
+}
+
+// Sets the names of a `:For` to an item, or to its items if there are
+// several names.
+fn bind_each(names: &[String], item: Array, env: &Env) -> anyhow::Result<()> {
+    if let [name] = names {
+        assign(env, name, Value::Array(item));
+        return Ok(());
+    }
+    if item.data().len() != names.len() {
+        anyhow::bail!(Errors::Length(format!(
+            ":For {} needs {} items in each",
+            names.join(" "),
+            names.len()
+        )));
+    }
+    for (name, part) in names.iter().zip(item.data()) {
+        assign(env, name, Value::Array(part.clone().disclose()));
+    }
+    Ok(())
+}
+
+fn monadic(function: &Function, right: Array, options: Options) -> anyhow::Result<Array> {
+    match function {
+        Function::Primitive(token) => primitive_monadic(token, right, options),
+        Function::Dfn(..) => call(function, None, right),
+        Function::Tradfn(tradfn, env) => {
+            result(tradfn, call_tradfn(tradfn, env, None, Some(right))?)
+        }
+        Function::Execute(env) => execute(&right, env),
+        Function::System(name) => system_monadic(name, &right),
+        Function::Derived(operator, left, operand) => {
+            derived_monadic(operator, left, operand.as_deref(), right, options)
+        }
+        Function::Outer(_) => {
+            anyhow::bail!(Errors::Valence("∘. needs a left argument".to_string()))
+        }
+        // (f g h) ⍵ is (f ⍵) g (h ⍵), and (g h) ⍵ is g h ⍵
+        Function::Fork(left, middle, function) => {
+            let result = monadic(function, right.clone(), options)?;
+            let left = match &**left {
+                Operand::Array(array) => array.clone(),
+                Operand::Function(left) => monadic(left, right, options)?,
+            };
+            dyadic(middle, left, result, options)
+        }
+        Function::Atop(left, function) => {
+            monadic(left, monadic(function, right, options)?, options)
+        }
+        Function::Idiom(idiom, phrase) => match fused::monadic(*idiom, &right) {
+            Some(result) => Ok(result),
+            None => monadic(phrase, right, options),
+        },
+    }
+}
+
+fn dyadic(
+    function: &Function,
+    left: Array,
+    right: Array,
+    options: Options,
+) -> anyhow::Result<Array> {
+    match function {
+        Function::Primitive(token) => primitive_dyadic(token, left, right, options),
+        Function::Dfn(..) => call(function, Some(left), right),
+        Function::Tradfn(tradfn, env) => {
+            result(tradfn, call_tradfn(tradfn, env, Some(left), Some(right))?)
+        }
+        Function::Execute(_) => {
+            anyhow::bail!(Errors::Nonce("dyadic ⍎ is not supported yet".to_string()))
+        }
+        Function::System(name) => system_dyadic(name, &left, &right),
+        Function::Derived(operator, left_operand, operand) => derived_dyadic(
+            operator,
+            left_operand,
+            operand.as_deref(),
+            left,
+            right,
+            options,
+        ),
+        Function::Outer(function) => {
+            outer_product(&|a, b| dyadic(function, a, b, options), left, right)
+        }
+        // ⍺ (f g h) ⍵ is (⍺ f ⍵) g (⍺ h ⍵), and ⍺ (g h) ⍵ is g ⍺ h ⍵
+        Function::Fork(left_tine, middle, function) => {
+            let result = dyadic(function, left.clone(), right.clone(), options)?;
+            let left = match &**left_tine {
+                Operand::Array(array) => array.clone(),
+                Operand::Function(left_tine) => dyadic(left_tine, left, right, options)?,
+            };
+            dyadic(middle, left, result, options)
+        }
+        Function::Atop(function, right_tine) => {
+            monadic(function, dyadic(right_tine, left, right, options)?, options)
+        }
+        Function::Idiom(idiom, phrase) => match fused::dyadic(*idiom, &left, &right) {
+            Some(result) => Ok(result),
+            None => dyadic(phrase, left, right, options),
+        },
+    }
+}
+
+// Index results shifted to the origin.
+fn origin(indices: Array, options: Options) -> anyhow::Result<Array> {
+    match options.io {
+        1 => Ok(indices),
+        _ => scalar::dyadic(Number::subtract, &indices, &Array::scalar(1.0)),
+    }
+}
+
+fn primitive_monadic(token: &Token, right: Array, options: Options) -> anyhow::Result<Array> {
+    if *token == Token::Tilde {
+        return logic::not(&right);
+    }
+    if let Some(function) = scalar::monadic_function(token) {
+        return scalar::monadic(function, &right);
+    }
+    Ok(match token {
+        Token::EqualUnderbar => structural::depth(&right),
+        Token::EqualUnderbarSlash => structural::tally(&right),
+        Token::Rho => structural::shape(&right),
+        Token::Comma => right.ravel(),
+        Token::CircleStile => structural::reverse(&right),
+        Token::LeftShoe => structural::enclose(right),
+        Token::LeftShoeUnderbar => structural::nest(right),
+        Token::RightShoe => structural::first(right),
+        Token::UpArrow => structural::mix(right),
+        Token::Epsilon => search::enlist(right),
+        Token::Iota => origin(search::index_generator(&right)?, options)?,
+        Token::IotaUnderbar => origin(search::where_indices(&right)?, options)?,
+        Token::GradeUp => origin(order::grade(&right, false)?, options)?,
+        Token::GradeDown => origin(order::grade(&right, true)?, options)?,
+        Token::DownShoe => search::unique(&right),
+        Token::Domino => linear::inverse(&right)?,
+        Token::QuestionMark => random::roll(right, options.io)?,
+        Token::Thorn => format::format(&right),
+        Token::LeftTack | Token::RightTack => right,
+        token => anyhow::bail!(Errors::Nonce(format!(
+            "monadic {} is not supported yet",
+            spell(token)
+        ))),
+    })
+}
+
+fn primitive_dyadic(
+    token: &Token,
+    left: Array,
+    right: Array,
+    options: Options,
+) -> anyhow::Result<Array> {
+    if let Some(logic) = Logic::from_token(token) {
+        return logic::logical(logic, &left, &right);
+    }
+    if let Some(result) = simd::dyadic(token, &left, &right) {
+        return result;
+    }
+    match token {
+        Token::Equal => return scalar::equal(&left, &right, true),
+        Token::NotEqual => return scalar::equal(&left, &right, false),
+        _ => {}
+    }
+    if let Some(function) = scalar::dyadic_function(token) {
+        return scalar::dyadic(function, &left, &right);
+    }
+    Ok(match token {
+        Token::EqualUnderbar => Array::scalar(left.matches(&right)),
+        Token::EqualUnderbarSlash => Array::scalar(!left.matches(&right)),
+        Token::Rho => structural::reshape(&left, &right)?,
+        Token::Comma => structural::catenate(&left, &right)?,
+        Token::LeftShoe => structural::partitioned_enclose(&left, &right)?,
+        Token::LeftShoeUnderbar => structural::partition(&left, &right)?,
+        Token::Epsilon => search::membership(&left, &right),
+        Token::Iota => origin(search::index_of(&left, &right)?, options)?,
+        Token::IotaUnderbar => origin(order::interval_index(&left, &right)?, options)?,
+        Token::GradeUp => origin(order::grade_collated(&left, &right, false)?, options)?,
+        Token::GradeDown => origin(order::grade_collated(&left, &right, true)?, options)?,
+        Token::DownShoe => search::union(&left, &right)?,
+        Token::UpShoe => search::intersection(&left, &right)?,
+        Token::EpsilonUnderbar => search::find(&left, &right),
+        Token::Domino => linear::divide(&left, &right)?,
+        Token::Thorn => format::format_fixed(&left, &right)?,
+        Token::QuestionMark => random::deal(&left, &right, options.io)?,
+        Token::UpTack => linear::decode(&left, &right)?,
+        Token::DownTack => linear::encode(&left, &right)?,
+        Token::Slash => structural::replicate(&left, &right)?,
+        Token::SlashBar if right.rank() <= 1 => structural::replicate(&left, &right)?,
+        Token::LeftTack => left,
+        Token::RightTack => right,
+        token => anyhow::bail!(Errors::Nonce(format!(
+            "dyadic {} is not supported yet",
+            spell(token)
+        ))),
+    })
+}
+
+// `f/`, or `f⌿` when `first`. A primitive brings its identity for empty
+// rows, and `+/` sums packed booleans by counting them.
+fn reduction(
+    function: &Function,
+    right: Array,
+    first: bool,
+    options: Options,
+) -> anyhow::Result<Array> {
+    let identity = match function {
+        Function::Primitive(token) => scalar::identity(token),
+        _ => None,
+    };
+    let f = |a, b| dyadic(function, a, b, options);
+    match (function, first && right.rank() > 1) {
+        (_, true) => reduce_first(&f, right, identity),
+        (Function::Primitive(Token::Plus), false) => scalar::sum(&right),
+        (_, false) => reduce(&f, right, identity),
+    }
+}
+
+// A single integer, as `⍣` counts with.
+fn integer(array: &Array) -> anyhow::Result<i64> {
+    match array.data() {
+        [n] if array.rank() <= 1 => {
+            let n = n.as_real()?;
+            match n.fract() == 0.0 {
+                true => Ok(n as i64),
+                false => anyhow::bail!(Errors::Domain(format!(
+                    "expected an integer, got {}",
+                    format::format_number(n)
+                ))),
+            }
+        }
+        _ => anyhow::bail!(Errors::Length("expected a single integer".to_string())),
+    }
+}
+
+// The options set by the right operand of `⍠`: a name and a value, or a
+// list of them. Only `'IO'` is known so far.
+fn variant(mut options: Options, spec: &Array) -> anyhow::Result<Options> {
+    let single = match spec.data() {
+        [name, _] => name.clone().disclose().as_string().is_some(),
+        _ => false,
+    };
+    let pairs = match single {
+        true => vec![spec.clone()],
+        false => spec.data().iter().cloned().map(Scalar::disclose).collect(),
+    };
+
+    for pair in pairs {
+        let (name, value) = match pair.data() {
+            [name, value] => (name.clone().disclose().as_string(), value.clone()),
+            _ => anyhow::bail!(Errors::Domain(
+                "⍠ expects names paired with values".to_string()
+            )),
+        };
+        match name.as_deref() {
+            Some("IO") => {
+                options.io = match value.as_real()? {
+                    n if n == 0.0 || n == 1.0 => n as usize,
+                    n => anyhow::bail!(Errors::Domain(format!(
+                        "the index origin is 0 or 1, not {}",
+                        format::format_number(n)
+                    ))),
+                }
+            }
+            Some(name) => anyhow::bail!(Errors::Domain(format!("⍠ has no option {}", name))),
+            None => anyhow::bail!(Errors::Domain("⍠ expects option names".to_string())),
+        }
+    }
+    Ok(options)
+}
+
+// `f@s` and `A@s`; a left argument is passed on to `f`.
+fn at_operator(
+    replacement: &Operand,
+    selection: &Operand,
+    left: Option<Array>,
+    right: Array,
+    options: Options,
+) -> anyhow::Result<Array> {
+    let replace = |old| {
+        let function = replacement.function(&Token::At)?;
+        match &left {
+            Some(left) => dyadic(function, left.clone(), old, options),
+            None => monadic(function, old, options),
+        }
+    };
+    let select = |array| monadic(selection.function(&Token::At)?, array, options);
+
+    let replacement = match replacement {
+        Operand::Array(values) => Replacement::Values(values.clone()),
+        Operand::Function(_) => Replacement::Function(&replace),
+    };
+    let selection = match selection {
+        Operand::Array(indices) => Selection::Indices(indices.clone()),
+        Operand::Function(_) => Selection::Mask(&select),
+    };
+    at(replacement, selection, right, options.io)
+}
+
+fn derived_monadic(
+    operator: &Token,
+    left: &Operand,
+    operand: Option<&Operand>,
+    right: Array,
+    options: Options,
+) -> anyhow::Result<Array> {
+    let f = || left.function(operator);
+
+    match (operator, operand) {
+        (Token::QuadEqual, None) => {
+            let f = f()?;
+            key(&|a, b| dyadic(f, a, b, options), &right, None, options.io)
+        }
+        (Token::Diaeresis, None) => {
+            let f = f()?;
+            each(&|item| monadic(f, item, options), right)
+        }
+        (Token::Slash | Token::SlashBar, None) => {
+            reduction(f()?, right, *operator == Token::SlashBar, options)
+        }
+        (Token::TildeDiaeresis, None) => match left {
+            Operand::Array(array) => Ok(array.clone()),
+            Operand::Function(f) => dyadic(f, right.clone(), right, options),
+        },
+        // f∘g ⍵ is f g ⍵, A∘g ⍵ is A g ⍵ and f∘B ⍵ is ⍵ f B
+        (Token::Jot, Some(g)) => match (left, g) {
+            (Operand::Function(f), Operand::Function(g)) => {
+                monadic(f, monadic(g, right, options)?, options)
+            }
+            (Operand::Array(a), Operand::Function(g)) => dyadic(g, a.clone(), right, options),
+            (Operand::Function(f), Operand::Array(b)) => dyadic(f, right, b.clone(), options),
+            (Operand::Array(_), Operand::Array(_)) => {
+                anyhow::bail!(Errors::Syntax("∘ expects a function operand".to_string()))
+            }
+        },
+        // Monadic f⍥g and f⍤g are both f g ⍵
+        (Token::CircleDieresis | Token::JotDiaeresis, Some(Operand::Function(g))) => {
+            monadic(f()?, monadic(g, right, options)?, options)
+        }
+        (Token::JotDiaeresis, Some(Operand::Array(spec))) => {
+            let f = f()?;
+            let [k, _, _] = rank_spec(spec)?;
+            rank(&|cell| monadic(f, cell, options), k, right)
+        }
+        (Token::StarDiaeresis, Some(Operand::Array(count))) => {
+            let f = f()?;
+            power(&|array| monadic(f, array, options), integer(count)?, right)
+        }
+        (Token::StarDiaeresis, Some(Operand::Function(g))) => {
+            let f = f()?;
+            power_until(
+                &|array| monadic(f, array, options),
+                &|a, b| dyadic(g, a, b, options),
+                right,
+            )
+        }
+        (Token::At, Some(selection)) => at_operator(left, selection, None, right, options),
+        (Token::QuadDiamond, Some(Operand::Array(spec))) => {
+            let f = f()?;
+            let (sizes, steps) = stencil_spec(spec)?;
+            stencil(&|a, b| dyadic(f, a, b, options), &sizes, &steps, right)
+        }
+        (Token::QuadColon, Some(Operand::Array(spec))) => {
+            monadic(f()?, right, variant(options, spec)?)
+        }
+        (Token::Dot, Some(_)) => {
+            anyhow::bail!(Errors::Valence(". needs a left argument".to_string()))
+        }
+        (operator, _) => {
+            anyhow::bail!(Errors::Nonce(format!(
+                "monadic {} is not supported yet",
+                spell(operator)
+            )))
+        }
+    }
+}
+
+fn derived_dyadic(
+    operator: &Token,
+    left_operand: &Operand,
+    operand: Option<&Operand>,
+    left: Array,
+    right: Array,
+    options: Options,
+) -> anyhow::Result<Array> {
+    let f = || left_operand.function(operator);
+
+    match (operator, operand) {
+        (Token::QuadEqual, None) => {
+            let f = f()?;
+            key(
+                &|a, b| dyadic(f, a, b, options),
+                &left,
+                Some(&right),
+                options.io,
+            )
+        }
+        (Token::Diaeresis, None) => {
+            let f = f()?;
+            each_dyadic(&|a, b| dyadic(f, a, b, options), left, right)
+        }
+        (Token::TildeDiaeresis, None) => match left_operand {
+            Operand::Array(array) => Ok(array.clone()),
+            Operand::Function(f) => dyadic(f, right, left, options),
+        },
+        (Token::Jot, Some(Operand::Function(g))) => {
+            dyadic(f()?, left, monadic(g, right, options)?, options)
+        }
+        (Token::Jot, Some(_)) => anyhow::bail!(Errors::Valence(
+            "∘ with an array operand is monadic".to_string()
+        )),
+        (Token::CircleDieresis, Some(Operand::Function(g))) => {
+            let left = monadic(g, left, options)?;
+            dyadic(f()?, left, monadic(g, right, options)?, options)
+        }
+        (Token::JotDiaeresis, Some(Operand::Function(g))) => {
+            monadic(f()?, dyadic(g, left, right, options)?, options)
+        }
+        (Token::JotDiaeresis, Some(Operand::Array(spec))) => {
+            let f = f()?;
+            let [_, l, r] = rank_spec(spec)?;
+            rank_dyadic(&|a, b| dyadic(f, a, b, options), l, r, left, right)
+        }
+        // ⍺ f⍣n ⍵ is (⍺∘f)⍣n ⍵
+        (Token::StarDiaeresis, Some(Operand::Array(count))) => {
+            let f = f()?;
+            let count = integer(count)?;
+            power(
+                &|array| dyadic(f, left.clone(), array, options),
+                count,
+                right,
+            )
+        }
+        (Token::StarDiaeresis, Some(Operand::Function(g))) => {
+            let f = f()?;
+            power_until(
+                &|array| dyadic(f, left.clone(), array, options),
+                &|a, b| dyadic(g, a, b, options),
+                right,
+            )
+        }
+        (Token::At, Some(selection)) => {
+            at_operator(left_operand, selection, Some(left), right, options)
+        }
+        (Token::Dot, Some(Operand::Function(g))) => {
+            let f = f()?;
+            if let (Function::Primitive(Token::Plus), Function::Primitive(Token::Times)) = (f, g) {
+                if let Some(product) = linear::matrix_product(&left, &right) {
+                    return Ok(product);
+                }
+            }
+            inner_product(
+                &|array| reduction(f, array, false, options),
+                &|a, b| dyadic(g, a, b, options),
+                left,
+                right,
+            )
+        }
+        (Token::QuadColon, Some(Operand::Array(spec))) => {
+            dyadic(f()?, left, right, variant(options, spec)?)
+        }
+        (Token::QuadDiamond, Some(_)) => anyhow::bail!(Errors::Valence("⌺ is monadic".to_string())),
+        (operator, _) => {
+            anyhow::bail!(Errors::Nonce(format!(
+                "dyadic {} is not supported yet",
+                spell(operator)
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Idiom, Session};
+    use crate::runtime::array::Array;
+
+    fn run(source: &str) -> Array {
+        match Session::default().execute(source) {
+            Ok(Some(array)) => array,
+            result => panic!("{} gave {:?}", source, result.map(|_| ())),
+        }
+    }
+
+    // Checks that two expressions give matching arrays.
+    fn check(source: &str, expected: &str) {
+        let (result, expected) = (run(source), run(expected));
+        assert!(
+            result.matches(&expected),
+            "{} gave {:?}, not {:?}",
+            source,
+            result,
+            expected
+        );
+    }
+
+    fn fails(source: &str) {
+        assert!(
+            Session::default().execute(source).is_err(),
+            "{} should fail",
+            source
+        );
+    }
+
+    #[test]
+    fn it_runs_dfns() {
+        check("{⍵≤1:⍵ ⋄ (∇⍵-1)+∇⍵-2}10", "55");
+        check("{⍺←10 ⋄ ⍺+⍵}1", "11");
+        check("2{⍺←10 ⋄ ⍺+⍵}1", "3");
+        // An inner dfn has its own ⍺
+        check("1{{⍺←0 ⋄ ⍺}⍵}2", "0");
+        fails("{⍵:1}2");
+        // A guard before a name that spells a control word
+        check("in←5 ⋄ {⍵>0:in ⋄ 0}1", "5");
+        check("if←1 ⋄ for←2 ⋄ {⍵:if+for ⋄ 0}1", "3");
+        fails("{x←⍵}1");
+
+        let mut session = Session::default();
+        assert!(session.execute("f←{⍵+1} ⋄ x←f 2").unwrap().is_none());
+        assert!(session.execute("x").unwrap().unwrap().matches(&run("3")));
+    }
+
+    #[test]
+    fn it_composes() {
+        check("-∘÷4", "¯0.25");
+        check("2∘*3", "8");
+        check("*∘2⊢3", "9");
+        check("1 2+∘÷4", "1.25 2.25");
+        // +/∘⍳ 4 is (+/)∘⍳ 4
+        check("+/∘⍳4", "10");
+        check("3×⍥|¯4", "12");
+        check("-⍤÷4", "¯0.25");
+        check("3-⍨10", "7");
+    }
+
+    #[test]
+    fn it_takes_products() {
+        check("1 2∘.×1 2 3", "2 3⍴1 2 3 2 4 6");
+        check("(2 2⍴1 2 3 4)+.×2 2⍴5 6 7 8", "2 2⍴19 22 43 50");
+        check("1 2 3+.×4 5 6", "32");
+        check("'abc'∧.='abc'", "1");
+        check("⍬+.×⍬", "0");
+        check("⍬∧.=⍬", "1");
+        check("⍬∨.≠⍬", "0");
+        check("⍬⌊.+⍬", "⌊/⍬");
+        fails("⍬{⍺+⍵}.×⍬");
+        fails("1 2+.×1 2 3");
+    }
+
+    #[test]
+    fn it_reduces_empty_rows_to_identities() {
+        for (function, identity) in [
+            ("+", "0"),
+            ("-", "0"),
+            ("×", "1"),
+            ("÷", "1"),
+            ("|", "0"),
+            ("*", "1"),
+            ("!", "1"),
+            ("⌊", "1.7976931348623157E308"),
+            ("⌈", "¯1.7976931348623157E308"),
+            ("∧", "1"),
+            ("∨", "0"),
+            ("=", "1"),
+            ("≠", "0"),
+            ("<", "0"),
+            ("≤", "1"),
+            (">", "0"),
+            ("≥", "1"),
+        ] {
+            check(&format!("{}/⍬", function), identity);
+            check(&format!("{}⌿⍬", function), identity);
+            check(&format!("{}/2 0⍴0", function), &format!("2⍴{}", identity));
+            check(&format!("{}⌿0 2⍴0", function), &format!("2⍴{}", identity));
+        }
+        for function in ["⍲", "⍱", "○", "⍟", "{⍺+⍵}"] {
+            fails(&format!("{}/⍬", function));
+        }
+        check("{⍺+⍵}/2 0 3⍴0", "2 0⍴0");
+    }
+
+    #[test]
+    fn it_reduces_along_the_first_axis() {
+        check("+⌿2 3⍴⍳6", "5 7 9");
+        check("-⌿3 2⍴⍳6", "3 4");
+        check("{⍺,⍵}⌿2 2⍴⍳4", "(1 3)(2 4)");
+        check("+⌿2 2 2⍴⍳8", "2 2⍴6 8 10 12");
+        check("+⌿1 0 1", "2");
+        check("×⌿5", "5");
+    }
+
+    #[test]
+    fn it_applies_to_cells() {
+        check("+/⍤1⊢2 3⍴⍳6", "6 15");
+        check("1 2+⍤0 1⊢2 2⍴10", "2 2⍴11 11 12 12");
+        check("{⊂⍵}⍤¯1⊢2 2⍴⍳4", "(1 2)(3 4)");
+        fails("1 2 3+⍤1⊢2 2⍴10");
+    }
+
+    #[test]
+    fn it_applies_powers() {
+        check("{⍵×2}⍣3⊢1", "8");
+        check("{⌊⍵÷2}⍣≡100", "0");
+        check("1E¯9>|(1+∘÷⍣≡1)-0.5×1+5*0.5", "1");
+        check("2×⍣3⊢1", "8");
+    }
+
+    #[test]
+    fn it_replaces_at() {
+        check("0@2 4⊢⍳5", "1 0 3 0 5");
+        check("{10×⍵}@{2|⍵}⍳5", "10 2 30 4 50");
+        check("'*'@(⊂2 2)⊢2 3⍴'abcdef'", "2 3⍴'abcd*f'");
+        check("0@1⊢2 2⍴⍳4", "2 2⍴0 0 3 4");
+        fails("0@6⊢⍳5");
+        fails("1 2@1 2 3⊢⍳5");
+    }
+
+    #[test]
+    fn it_applies_stencils() {
+        check("{+/,⍵}⌺3⊢⍳5", "3 6 9 12 9");
+        check("{⍺}⌺3⊢⍳4", "4 1⍴1 0 0 ¯1");
+        check("{+/,⍵}⌺3 3⊢3 3⍴1", "3 3⍴4 6 4 6 9 6 4 6 4");
+        check("{⊂⍵}⌺(2 1⍴2 2)⊢⍳4", "(1 2)(3 4)");
+    }
+
+    #[test]
+    fn it_sets_options_with_variant() {
+        check("⍳⍠'IO' 0⊢3", "0 1 2");
+        check("'abc'⍳⍠('IO' 0)⊢'c'", "2");
+        check("⍳⍠'IO' 1⊢3", "1 2 3");
+        fails("⍳⍠'XX' 0⊢3");
+        fails("⍳⍠'IO' 2⊢3");
+    }
+
+    #[test]
+    fn it_groups_by_key() {
+        check("{⍺,≢⍵}⌸'abcab'", "3 2⍴'a' 2 'b' 2 'c' 1");
+        check("{⊂⍵}⌸'abab'", "(1 3)(2 4)");
+        check("'abab'{⍺,+/⍵}⌸1 2 3 4", "2 2⍴'a' 4 'b' 6");
+        check("{⍺}⌸⍠'IO' 0⊢1 1", ",1");
+        check("{⊂⍵}⌸⍠'IO' 0⊢1 1", ",⊂0 1");
+        fails("'ab'{⍺}⌸1 2 3");
+    }
+
+    #[test]
+    fn it_sorts_and_searches() {
+        check("⍋3 1 2", "2 3 1");
+        check("⍒⍠'IO' 0⊢3 1 2", "0 2 1");
+        check("'cab'⍋'abc'", "3 1 2");
+        check("∪3 1 3 2 1", "3 1 2");
+        check("1 2∪2 3 3", "1 2 3 3");
+        check("1 2 3 2∩2 4", "2 2");
+        check("'ab'⍷'cabab'", "0 1 0 1 0");
+        check("10 20⍸5 15 25", "0 1 2");
+    }
+
+    #[test]
+    fn it_does_linear_algebra() {
+        check("2⊥1 0 1", "5");
+        check("24 60 60⊤10000", "2 46 40");
+        check("(2 3⍴⍳6)+.×3 2⍴⍳6", "2 2⍴22 28 49 64");
+        // Booleans take the general way and give the same
+        check("(2 2⍴1 0 0 1=1)+.×2 2⍴⍳4", "2 2⍴⍳4");
+        // Regression: fit y = 1 + 2x to points on that line
+        check("1E¯12>⌈/|1 2-(3 5 7 9)⌹4 2⍴1 1 1 2 1 3 1 4", "1");
+        check("1E¯12>⌈/,|(2 2⍴1 0 0 1)-M+.×⌹M←2 2⍴4 7 2 6", "1");
+        fails("⌹2 2⍴1 2 2 4");
+        fails("1 2⌹3 2⍴⍳6");
+    }
+
+    #[test]
+    fn it_executes_strings() {
+        check("⍎'42'", "42");
+        check("⍎'1 ¯2 3'", "1 ¯2 3");
+        check("⍎'''héllo'''", "'héllo'");
+        check("⍎'''it''''s'''", "'it''s'");
+        check("⍎'''a'' ''b'''", "'ab'");
+        check("⍎'''ab'' 1'", "'ab' 1");
+        check("⍕⍎'0.1h 0.2 0.3'", "'0.1 0.2 0.3'");
+        check("(⍎'0.1h 0.2')≡0.1h 0.2h", "1");
+        check(
+            "⍕⍎'¯1r3 2r4 123456789012345678901234567890n'",
+            "'¯1r3 1r2 123456789012345678901234567890'",
+        );
+        check("⍎'1+2'", "3");
+        check("⍎'x←2 ⋄ x×3'", "6");
+        check("⍎¨'1+1' '2×3'", "2 6");
+        // In the frame it is written in, seeing its functions and arguments
+        check("{⍎'⍵+1'}41", "42");
+        check("f←{⍵×2} ⋄ ⍎'f 4'", "8");
+        check("{y←⍵ ⋄ ⍎'y←y+1' ⋄ y}1", "2");
+        check("⍎'⍎''1+1'''", "2");
+        fails("⍎''");
+        fails("⍎1");
+        fails("1⍎'1'");
+
+        let error = Session::default().execute("⍎'1 2+1 2 3'").unwrap_err();
+        assert!(error.to_string().ends_with("in ⍎'1 2+1 2 3'"), "{}", error);
+        let error = Session::default().execute("⍎'1+)'").unwrap_err();
+        assert!(error.to_string().contains("1:"), "{}", error);
+    }
+
+    #[test]
+    fn it_applies_trains() {
+        check("(+/÷≢)1 2 3 4", "2.5");
+        check("mean←+/÷≢ ⋄ mean¨(1 2)(3 4 5)", "1.5 4");
+        check("3(+,-)1", "4 2");
+        check("(1+⊢)5", "6");
+        check("2(⊣,⊢)3", "2 3");
+        check("(-÷)4", "¯0.25");
+        check("1(-×)2", "¯2");
+        // (e f g h) is e (f g h), and (d e f g h) is d e (f g h)
+        check("(-+,-)3", "¯3 3");
+        check("(⊢+-,×)2", "0 3");
+    }
+
+    #[test]
+    fn it_draws_trains() {
+        let mut session = Session::default();
+        session.execute("mean←+/÷≢").unwrap();
+        let drawing = session.execute("]box mean÷2∘×").unwrap().unwrap();
+        let expected = ["┌────┼──┐ ", "mean ÷  ∘ ", "       ┌┴┐", "       2 ×"];
+        assert!(drawing.matches(&run(&format!("4 10⍴'{}'", expected.concat()))));
+        assert!(Session::default().execute("]box 1 2").is_err());
+    }
+
+    #[test]
+    fn it_imports_modules() {
+        let directory = crate::parser::modules::tests::directory(
+            "imports",
+            &[
+                (
+                    "stats.apl",
+                    ":Import util\nmean←{(+/⍵)÷util.count ⍵}\nscale←10",
+                ),
+                ("util.icl", ":g // helpers\ncount←≢\n:\n:m\nnot apl\n"),
+            ],
+        );
+        let mut session = Session::default();
+        session.search(&directory);
+        assert!(session.execute(":Import stats").unwrap().is_none());
+        let mean = session.execute("stats.scale×stats.mean 1 2 3").unwrap();
+        assert!(mean.unwrap().matches(&run("20")));
+        // Members are in the namespace, not the workspace
+        assert!(session.execute("mean 1").is_err());
+        assert!(session.execute("stats.util.count 1").is_err());
+        assert!(session.execute("stats.nothing").is_err());
+        let executed = session.execute("⍎'stats.mean 4 6'").unwrap();
+        assert!(executed.unwrap().matches(&run("5")));
+        assert!(session.execute(":Import missing").is_err());
+    }
+
+    #[test]
+    fn it_gives_modules_the_prelude() {
+        let directory = crate::parser::modules::tests::directory(
+            "prelude",
+            &[(
+                "text.apl",
+                "t←trim\nwords←{' ' split ⍵}\naverage←mean\nleak←{secret}",
+            )],
+        );
+        let mut session = Session::with_prelude().unwrap();
+        session.search(&directory);
+        session.execute("secret←1").unwrap();
+        session.execute(":Import text").unwrap();
+        let trimmed = session.execute("text.t '  ab  '").unwrap();
+        assert!(trimmed.unwrap().matches(&run("'ab'")));
+        let words = session.execute("≢text.words 'a bc d'").unwrap();
+        assert!(words.unwrap().matches(&run("3")));
+        let average = session.execute("text.average 1 2 3").unwrap();
+        assert!(average.unwrap().matches(&run("2")));
+        // The prelude isn't exported, and the workspace isn't seen
+        assert!(session.execute("text.trim 'a'").is_err());
+        assert!(session.execute("text.leak 0").is_err());
+    }
+
+    // Checks an expression run after the prelude against one run without,
+    // by value and, if `typed`, by type.
+    fn library_typed(source: &str, expected: &str, typed: bool) {
+        let result = Session::with_prelude().unwrap().execute(source).unwrap();
+        let (result, expected) = (result.unwrap(), run(expected));
+        assert!(
+            result.matches(&expected) && (!typed || result.data() == expected.data()),
+            "{} gave {:?}, not {:?}",
+            source,
+            result,
+            expected
+        );
+    }
+
+    fn library(source: &str, expected: &str) {
+        library_typed(source, expected, false);
+    }
+
+    #[test]
+    fn it_loads_the_prelude() {
+        let version = format!("'{}'", env!("CARGO_PKG_VERSION"));
+        library("version", &version);
+        // Its names can be assigned over like any other
+        library("mean←2 ⋄ mean", "2");
+    }
+
+    #[test]
+    fn it_runs_the_prelude_idioms() {
+        library("mean 1 2 3 4", "2.5");
+        library("variance 1 2 3 4", "1.25");
+        library("' ' split 'ab cd  e'", "'ab' 'cd' (,'e')");
+        library("', ' join 'ab' 'cd' (,'e')", "'ab, cd, e'");
+        library("trim '  ab c  '", "'ab c'");
+        library("trim '   '", "''");
+    }
+
+    #[test]
+    fn it_runs_the_prelude_matrix_functions() {
+        library("identity 2", "2 2⍴1 0 0 1");
+        library("diagonal 3 3⍴⍳9", "1 5 9");
+        library("trace 3 3⍴⍳9", "15");
+        library("(2 2⍴1 2 3 4) matmul 2 2⍴5 6 7 8", "2 2⍴19 22 43 50");
+        library("inverse 2 2⍴4 0 0 2", "2 2⍴0.25 0 0 0.5");
+        library("(2 2⍴2 0 0 4) solve 3 5", "1.5 1.25");
+        library("norm 3 4", "5");
+    }
+
+    #[test]
+    fn it_runs_the_prelude_string_functions() {
+        library("upper 'Hello, World'", "'HELLO, WORLD'");
+        library("lower 'Hello, World'", "'hello, world'");
+        library("'ab' starts 'abc'", "1");
+        library("'bc' starts 'abc'", "0");
+        library("'bc' ends 'abc'", "1");
+        library("'abcd' ends 'abc'", "0");
+        library("'bc' contains 'abcd'", "1");
+        library("'db' contains 'abcd'", "0");
+    }
+
+    #[test]
+    fn it_runs_the_prelude_conversions() {
+        library_typed("to_b 1 0", "1b 0b", true);
+        library_typed("to_u8 0 255", "0u8 255u8", true);
+        library_typed("to_u16 7", "7u16", true);
+        library_typed("to_u32 7", "7u32", true);
+        library_typed("to_u64 7", "7u64", true);
+        library_typed("to_i8 ¯128", "¯128i8", true);
+        library_typed("to_i16 7", "7i16", true);
+        library_typed("to_i32 7", "7i32", true);
+        library_typed("to_i64 7", "7i64", true);
+        library_typed("to_h 0.1", "0.1h", true);
+        library_typed("to_f 0.1", "0.1f", true);
+        library_typed("to_d 0.5f", "0.5", true);
+        library_typed("to_n 0.5", "1r2", true);
+        library_typed("'u3' into 7", "7u3", true);
+        fails("'u8' ⎕INTO 256");
+        fails("'u8' ⎕INTO 0.5");
+        fails("'q' ⎕INTO 1");
+        fails("⎕INTO 1");
+    }
+
+    #[test]
+    fn it_runs_tradfns() {
+        check("∇r←a plus b\nr←a+b\n∇\n2 plus 3", "5");
+        check("∇r←double x\nr←2×x\n∇\ndouble¨1 2", "2 4");
+        check("∇r←five\nr←5\n∇\nfive+1", "6");
+        // Recursive, and with locals that leave the workspace alone
+        check(
+            "∇r←fact n\n:If n≤1 ⋄ r←1 ⋄ :Else ⋄ r←n×fact n-1 ⋄ :EndIf\n∇\nfact 5",
+            "120",
+        );
+        check("x←1\n∇f;x\nx←2\n∇\nf ⋄ x", "1");
+        check("x←1\n∇f\nx←2\n∇\nf ⋄ x", "2");
+        check("⎕PP←10\n∇f;⎕PP\n⎕PP←3\n∇\nf ⋄ ⎕PP", "10");
+        check(
+            "∇r←f;⎕EN;⎕DM\nr←{500::⎕EN ⋄ ⎕SIGNAL 500}0\n∇\nx←{11::0 ⋄ ÷0}0 ⋄ (f),⎕EN,⍴⎕DM",
+            "500 11 2",
+        );
+        // An ambivalent header leaves the left argument unset
+        check("∇r←{a}f b\nr←b\n∇\nf 1", "1");
+        fails("∇f\n∇\n1+f");
+        fails("∇r←f b\nr←b\n∇\n1 f 2");
+
+        let mut session = Session::default();
+        assert!(session.execute("∇f\n1\n∇").unwrap().is_none());
+        assert!(session.execute("f").unwrap().is_none());
+    }
+
+    #[test]
+    fn it_runs_control_structures() {
+        let sum = "∇r←sum n;i\nr←0\n:For i :In ⍳n\n:If i=3 ⋄ :Continue ⋄ :EndIf\n:If i>5 ⋄ :Leave ⋄ :EndIf\nr←r+i\n:EndFor\n∇\n";
+        check(&format!("{}sum 10", sum), "12");
+        check(
+            "∇r←pairs;a;b\nr←0\n:For a b :In (1 2)(3 4)\nr←r+a×b\n:EndFor\n∇\npairs",
+            "14",
+        );
+        check(
+            "∇r←halve n\nr←0\n:While n>1\nn←⌊n÷2 ⋄ r←r+1\n:EndWhile\n∇\nhalve 100",
+            "6",
+        );
+        check(
+            "∇r←count;i\ni←0\n:Repeat\ni←i+1\n:Until i≥4\nr←i\n∇\ncount",
+            "4",
+        );
+        check(
+            "∇r←first v\n:For r :In v\n:If r>2 ⋄ :Return ⋄ :EndIf\n:End\nr←0\n∇\nfirst 1 5 3",
+            "5",
+        );
+        let kind = "∇r←kind x\n:Select x\n:Case 1 ⋄ r←'one'\n:CaseList 2 3 ⋄ r←'few'\n:Else ⋄ r←'many'\n:EndSelect\n∇\n";
+        check(&format!("{}kind 1", kind), "'one'");
+        check(&format!("{}kind 3", kind), "'few'");
+        check(&format!("{}kind 9", kind), "'many'");
+        fails("∇f\n:Leave\n∇\nf");
+        fails("∇f\n:If 2\n:EndIf\n∇\nf");
+    }
+
+    #[test]
+    fn it_traps_errors() {
+        let safe = "∇r←safe x\n:Trap 5\nr←1 2+x\n:Else\nr←'length'\n:EndTrap\n∇\n";
+        check(&format!("{}safe 3", safe), "4 5");
+        check(&format!("{}safe 1 2 3", safe), "'length'");
+        // Other errors get through
+        fails(&format!("{}safe 'a'", safe));
+        check(
+            "∇r←any x\n:Trap 0 ⋄ r←÷x ⋄ :Else ⋄ r←¯1 ⋄ :EndTrap\n∇\nany 0",
+            "¯1",
+        );
+        check("∇r←f\nr←1\n:Trap 6 ⋄ r←nothing+1 ⋄ :EndTrap\n∇\nf", "1");
+    }
+
+    #[test]
+    fn it_signals_errors() {
+        check("{11:: 'caught' ⋄ ⎕SIGNAL 11}0", "'caught'");
+        check("{0:: ⎕EN ⋄ 'no' ⎕SIGNAL 500}0", "500");
+        check("{0:: ⎕DM ⋄ 'no' ⎕SIGNAL 500}0", "'no' ''");
+        check("{5:: ⊃⎕DM ⋄ 1 2+⍵}1 2 3", "'LENGTH ERROR'");
+        check("{4 5:: ⎕EN ⋄ 1 2+⍵}1 2 3", "5");
+        // A guard covers only what comes after it
+        check("{x←⍵ ⋄ 11:: x ⋄ x÷0}2", "2");
+        fails("{x←÷⍵ ⋄ 11:: 0 ⋄ x}0");
+        fails("{3:: 0 ⋄ ÷⍵}0");
+        fails("{⎕SIGNAL 11}0");
+        fails("'oops' ⎕SIGNAL 500");
+        fails("⎕SIGNAL 0");
+        fails("11:: 0");
+        check("⎕SIGNAL ⍬ ⋄ 1", "1");
+        // An error keeps its number through ⍎
+        check("{500::⎕EN ⋄ ⍎'''oops'' ⎕SIGNAL 500'}0", "500");
+        check("{5::⎕EN ⋄ ⍎'1 2+1 2 3'}0", "5");
+        check(
+            "∇r←f x\n:Trap 500\n'mine' ⎕SIGNAL 500\n:Else\nr←⎕EN\n:EndTrap\n∇\nf 0",
+            "500",
+        );
+        check(
+            "∇r←f\n:Trap 11\n⎕SIGNAL 11\n:Else\nr←⊃⎕DM\n:EndTrap\n∇\nf",
+            "'DOMAIN ERROR'",
+        );
+
+        // Each session has its own last error
+        let mut session = Session::default();
+        session.execute("x←{500::0 ⋄ ⎕SIGNAL 500}0").unwrap();
+        check("⎕EN", "0");
+        check("⍴⎕DM", ",0");
+        let number = session.execute("⎕EN").unwrap().unwrap();
+        assert!(number.matches(&run("500")));
+    }
+
+    #[test]
+    fn it_sets_overflow_modes() {
+        fails("200u8+100u8");
+        check("⎕OVERFLOW 'u8'", "'checked'");
+        check("'wrapping' ⎕OVERFLOW 'u8'", "'checked'");
+        let wrapping = "m←'wrapping' ⎕OVERFLOW 'u8' ⋄ ";
+        check(&format!("{}(200u8+100u8),2u8-3u8", wrapping), "44 255");
+        check(
+            &format!("{}'saturating' ⎕OVERFLOW 'u8'", wrapping),
+            "'wrapping'",
+        );
+        let saturating = "m←'saturating' ⎕OVERFLOW 'u8' ⋄ ";
+        check(&format!("{}(200u8+100u8),2u8-3u8", saturating), "255 0");
+        // Other widths keep their own modes
+        fails(&format!("{}100i8+100i8", saturating));
+        check(
+            &format!("{}'checked' ⎕OVERFLOW 'u8'", saturating),
+            "'saturating'",
+        );
+        fails(&format!(
+            "{}m←'checked' ⎕OVERFLOW 'u8' ⋄ 200u8+100u8",
+            saturating
+        ));
+        fails("'sometimes' ⎕OVERFLOW 'u8'");
+        fails("'wrapping' ⎕OVERFLOW 'f'");
+
+        // and each session its own
+        let mut session = Session::default();
+        session.execute(wrapping).unwrap();
+        fails("200u8+100u8");
+        let sum = session.execute("200u8+100u8").unwrap().unwrap();
+        assert!(sum.matches(&run("44")));
+    }
+
+    #[test]
+    fn it_indexes_and_reverses() {
+        check("x←'abc' ⋄ x[3 1]", "'ca'");
+        check("(⍳5)[2 2⍴1 5]", "2 2⍴1 5");
+        check("⌽2 3⍴⍳6", "2 3⍴3 2 1 6 5 4");
+        check("⌽5", "5");
+        fails("(⍳3)[4]");
+        fails("(⍳3)[1.5]");
+        fails("(2 2⍴⍳4)[1]");
+    }
+
+    // The idioms recognised in running `source`.
+    fn fired(source: &str) -> Vec<Idiom> {
+        let mut session = Session::default();
+        session.execute(source).unwrap();
+        session.idioms().to_vec()
+    }
+
+    #[test]
+    fn it_recognises_idioms() {
+        use Idiom::*;
+        assert_eq!(fired("(+/÷≢)1 2 3 4"), [Mean]);
+        assert_eq!(fired("mean←+/÷≢ ⋄ mean 1 2"), [Mean]);
+        assert_eq!(fired("⊃⌽1 2 3"), [Last]);
+        assert_eq!(fired("{⍵[⍋⍵]}3 1 2 ⋄ {⍵[⍒⍵]}3 1 2"), [SortUp, SortDown]);
+        assert_eq!(
+            fired("∨/1 2∊3 ⋄ ∧/1 2∊3 ⋄ 1 2 (∨/∊) 3"),
+            [AnyMember, AllMember, AnyMember]
+        );
+        assert_eq!(fired("≢∪1 1 2"), [CountUnique]);
+        // Inside dfns, trains and tradfns
+        assert_eq!(fired("{≢∪⍵}1 ⋄ (⊢,(⊃⌽))1 2"), [CountUnique, Last]);
+        assert_eq!(fired("∇r←f x\n:If 1\nr←⊃⌽x\n:EndIf\n∇\nf 1 2"), [Last]);
+        // Only ever for primitives
+        assert!(fired("first←⊃ ⋄ first⌽1 2 ⋄ {⍺[⍋⍵]}⍨3 1 2 ⋄ +/÷⍴ 1 2").is_empty());
+    }
+
+    #[test]
+    fn it_gives_what_the_phrase_would() {
+        let numbers = ["3 1 4 1 5", "0.5 ¯2 1E300 1E300", "1 0 1b", "1", "⍬"];
+        let any = ["3 1 4 1 5", "'banana'", "(1 2)(3)(1 2)", "1 0 1b", "⍬"];
+        for (idiom, phrase, arguments) in [
+            ("(+/÷≢)", "{(+/⍵)÷≢⍵}", &numbers[..]),
+            (
+                "⊃⌽",
+                "{⊃⍵[≢⍵]}",
+                &["3 1 4 1 5", "'banana'", "(1 2)(3)(1 2)", "1 0 1b"],
+            ),
+            ("{⍵[⍋⍵]}", "{i←⍋⍵ ⋄ ⍵[i]}", &any),
+            ("{⍵[⍒⍵]}", "{i←⍒⍵ ⋄ ⍵[i]}", &any),
+            ("≢∪", "{u←∪⍵ ⋄ ≢u}", &any),
+        ] {
+            for argument in arguments {
+                let source = format!("{} {}", idiom, argument);
+                check(&source, &format!("{} {}", phrase, argument));
+            }
+        }
+        check("2 3 (∨/∊) 3 4", "1");
+        check("'ab' (∧/∊) 'abc'", "1");
+        check("'ab' (∧/∊) 'bcd'", "0");
+        check("(2 2⍴1 2 3 4) (∨/∊) 4", "0 1");
+        check("(+/÷≢) 2 2⍴1 2 3 4", "1.5 3.5");
+        check("⊃⌽2 2⍴1 2 3 4", "2");
+        check("1 (≢∪) 2 1 3", "3");
+    }
+
+    #[test]
+    fn it_gives_what_the_phrase_would_for_every_idiom() {
+        let monadic = [
+            "3 1 4 1 5",
+            "0.5 ¯2 1E300",
+            "'banana'",
+            "(1 2)(3)(1 2)",
+            "1 0 1b",
+            "1",
+            "⍬",
+            "''",
+        ];
+        let dyadic = [
+            ("2 3", "3 4"),
+            ("'ab'", "'abc'"),
+            ("1 2", "⍬"),
+            ("⍬", "1 2"),
+            ("⍬", "⍬"),
+            ("''", "'abc'"),
+            ("(2 2⍴1 2 3 4)", "4"),
+        ];
+        // Each the long way round, where no idiom is recognised
+        let phrase = |idiom| match idiom {
+            Idiom::Mean => "{s←+/⍵ ⋄ s÷≢⍵}",
+            Idiom::Last => "{r←⌽⍵ ⋄ ⊃r}",
+            Idiom::SortUp => "{i←⍋⍵ ⋄ ⍵[i]}",
+            Idiom::SortDown => "{i←⍒⍵ ⋄ ⍵[i]}",
+            Idiom::AnyMember => "{m←⍺∊⍵ ⋄ ∨/m}",
+            Idiom::AllMember => "{m←⍺∊⍵ ⋄ ∧/m}",
+            Idiom::CountUnique => "{u←∪⍵ ⋄ ≢u}",
+        };
+        for idiom in Idiom::ALL {
+            let sources: Vec<_> = match idiom {
+                Idiom::AnyMember | Idiom::AllMember => dyadic
+                    .iter()
+                    .map(|(left, right)| {
+                        (
+                            format!("{} ({}) {}", left, idiom.phrase(), right),
+                            format!("{} {} {}", left, phrase(idiom), right),
+                        )
+                    })
+                    .collect(),
+                _ => monadic
+                    .iter()
+                    .map(|argument| {
+                        (
+                            format!("({}) {}", idiom.phrase(), argument),
+                            format!("{} {}", phrase(idiom), argument),
+                        )
+                    })
+                    .collect(),
+            };
+            for (source, phrase) in sources {
+                let mut session = Session::default();
+                let result = session.execute(&source);
+                assert_eq!(session.idioms(), [idiom], "{}", source);
+                match (result, Session::default().execute(&phrase)) {
+                    (Ok(Some(result)), Ok(Some(expected))) => assert!(
+                        result.matches(&expected),
+                        "{} gave {:?}, not {:?}",
+                        source,
+                        result,
+                        expected
+                    ),
+                    (Err(_), Err(_)) => {}
+                    (result, expected) => panic!(
+                        "{} gave {:?}, but {} gave {:?}",
+                        source,
+                        result.map(|_| ()),
+                        phrase,
+                        expected.map(|_| ())
+                    ),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_fuses_what_the_primitives_give() {
+        let unfused = |source: &str| {
+            let mut session = Session::default();
+            session.set_fusion(false);
+            session.execute(source).map(Option::unwrap)
+        };
+        for source in [
+            "+/2×⍳10",
+            "(3/4)⍴⍳4*3",
+            "+/(2 3⍴¯1×⍳3)+1",
+            "⌈/|(⍳5)-3",
+            "-/÷⍳4",
+            "(⍳3)+1 2 3b",
+            "+/1+0×1 0 1b",
+            "1 2×'ab'+1",
+            "+/0⍴1+⍳3",
+            "(⍳3)+⍳4",
+            "×/(1 0)⍴⍳2+1",
+            "2⍴⍳2×2 2",
+            "1÷0×⍳3",
+            "x←3 ⋄ x×⍳x+1",
+            // errors, the first the primitives would raise
+            "(÷0)+1 2+1 2 3",
+            "(÷0×⍳3)+1 2 3 4",
+            "x←0 ⋄ {0::x ⋄ (x←5)+÷0}0",
+            "x←0 ⋄ {0::x ⋄ (x←5)+÷0×⍳3}0",
+            "y←0 ⋄ (y←1)+÷0×⍳3",
+        ] {
+            let fused = Session::default().execute(source).map(Option::unwrap);
+            match (fused, unfused(source)) {
+                (Ok(fused), Ok(unfused)) => {
+                    assert_eq!(fused.data(), unfused.data(), "{}", source);
+                    assert_eq!(fused.shape(), unfused.shape(), "{}", source);
+                }
+                (Err(fused), Err(unfused)) => {
+                    assert_eq!(fused.to_string(), unfused.to_string(), "{}", source)
+                }
+                (fused, unfused) => panic!("{} gave {:?} fused, {:?} not", source, fused, unfused),
+            }
+        }
+        check("x←0 ⋄ {0::x ⋄ (x←5)+÷0}0", "0");
+    }
+
+    #[test]
+    fn it_dumps_the_ir() {
+        let mut session = Session::default();
+        session.execute("n←4").unwrap();
+        let dump = session.execute("]ir +/2×⍳n").unwrap().unwrap();
+        assert_eq!(
+            dump.to_string(),
+            [
+                "loop 1",
+                "  %0 ← ⍳4    ⍝ 4",
+                "  %1 ← 2×%0  ⍝ 4",
+                "  %2 ← +/%1  ⍝ scalar",
+                "fused:   1 array of 1 element, 9 element operations",
+                "unfused: 3 arrays of 9 elements, 9 element operations",
+            ]
+            .join("\n")
+        );
+        let dump = session.execute("]ir 'ab',1").unwrap().unwrap();
+        assert_eq!(
+            dump.to_string(),
+            "not fused: an array that is not all numbers"
+        );
+        assert!(session.execute("]ir x←1").is_ok());
+        assert!(session.execute("]ir 1 ⋄ 2").is_err());
+    }
+
+    #[test]
+    fn it_formats() {
+        check("⍕1 ¯2.5", "'1 ¯2.5'");
+        check("⍴⍕1 (2 3)", "3 7");
+        check("⎕PP", "10");
+        check("⎕PP←3 ⋄ ⍕÷3", "'0.333'");
+        check("2⍕1 2", "' 1.00 2.00'");
+        check("4 1⍕2 2⍴⍳4", "2 8⍴' 1.0 2.0 3.0 4.0'");
+        fails("⎕PP←0");
+        fails("2⍕'ab'");
+
+        // ⎕PP is the session's, and its results print with it
+        let mut session = Session::default();
+        session.execute("⎕PP←3").unwrap();
+        check("⍕÷3", "'0.3333333333'");
+        let third = session.execute("÷3").unwrap().unwrap();
+        assert_eq!(third.to_string(), "0.333");
+
+        // Messages write numbers as APL does
+        let message = |source| Session::default().execute(source).unwrap_err().to_string();
+        assert_eq!(
+            message("3 4 5⍕1.5"),
+            "LENGTH ERROR: ⍕ expects 1 or 2 numbers on the left, or 2 per column, got 3"
+        );
+        assert_eq!(
+            message("⍳¯1"),
+            "DOMAIN ERROR: ⍳ expects a non-negative integer, got ¯1"
+        );
+        assert_eq!(
+            message("¯1.5⍕2"),
+            "DOMAIN ERROR: ⍕ expects whole numbers on the left, got ¯1.5"
+        );
+    }
+
+    #[test]
+    fn it_draws_reproducibly() {
+        check("⎕RL←42 0 ⋄ a←?10⍴6 ⋄ ⎕RL←42 0 ⋄ a≡?10⍴6", "1");
+        check("⎕RL←42 ⋄ a←5?100 ⋄ ⎕RL←42 ⋄ a≡5?100", "1");
+        check("⎕RL←7 1 ⋄ ⎕RL", "7 1");
+        check("∧/(⍳5)∊5?5", "1");
+        check("∧/(?⍠'IO' 0⊢100⍴3)∊0 1 2", "1");
+        fails("⎕RL←0.5");
+        fails("⎕XX");
+        fails("4?3");
+
+        // Each session draws from a generator of its own
+        let draws = |session: &mut Session| session.execute("?10⍴1000").unwrap().unwrap();
+        let fresh = draws(&mut Session::default());
+        assert_eq!(draws(&mut Session::default()), fresh);
+        let (mut first, mut second) = (Session::default(), Session::default());
+        first.execute("⎕RL←42").unwrap();
+        second.execute("⎕RL←7").unwrap();
+        let mut seeded = Session::default();
+        seeded.execute("⎕RL←42").unwrap();
+        assert_eq!(draws(&mut first), draws(&mut seeded));
+    }
+}
@@ -0,0 +1,571 @@
+// The array IR, between the syntax tree and the primitives. An expression
+// made of scalar functions, `⍳`, `⍴` and reductions is lowered to a tree of
+// lazy arrays, each knowing how to compute any one of its elements, and run
+// as a single loop that builds only the result. Everything else in the
+// expression is evaluated as usual, once, and enters the tree as a leaf.
+//
+// The cost model in `cost` counts how often the loop asks for each element.
+// A node asked for more elements than it has, such as one cycled by `⍴` or
+// extended from a scalar, is computed once into an array of its own, so
+// fusion never does more work than the primitives would. Where the loop
+// can't give what the primitives would, for characters, nested or empty
+// arrays or arguments the primitives reject, the tree is run a node at a
+// time by the primitives themselves instead.
+//
+// The leaves are all evaluated before the loop runs, so an expression is
+// fused only when they are literals and names of arrays, which can't fail
+// or change anything. The loop computes its elements in another order than
+// the primitives, so if it fails the tree is run again a node at a time, to
+// raise the error the primitives would have raised first.
+
+use crate::parser::{spell, Expr, Function, Operand};
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::number::Number;
+use crate::runtime::operators::reduce;
+use crate::runtime::primitives::logic::Logic;
+use crate::runtime::primitives::scalar::{self, DyadicScalar, MonadicScalar};
+use crate::runtime::primitives::{search, structural};
+use crate::tokenizer::Token;
+
+pub(crate) mod cost;
+
+/// A lazy array: the primitive that computes it from its arguments.
+#[derive(Debug, Clone)]
+pub enum Node {
+    // An array the evaluator computed.
+    Array(Array),
+    // `⍳n`
+    Iota(Array),
+    // `shape⍴node`
+    Reshape(Array, Box<Node>),
+    // A monadic scalar function.
+    Monadic(Token, Box<Node>),
+    // A dyadic scalar function.
+    Dyadic(Token, Box<Node>, Box<Node>),
+    // `f/node`, with a dyadic scalar `f`.
+    Reduce(Token, Box<Node>),
+}
+
+// What a function in the syntax tree is to the IR.
+enum Op {
+    Iota,
+    Reshape,
+    Scalar(Token),
+    Reduce(Token),
+}
+
+fn monadic_scalar(token: &Token) -> Option<MonadicScalar> {
+    match token {
+        // `~` takes only booleans, which `logic::not` checks for
+        Token::Tilde => None,
+        token => scalar::monadic_function(token),
+    }
+}
+
+// The dyadic scalar functions the IR runs. The logical functions and `=`
+// and `≠` have primitives of their own, for packed booleans and characters.
+fn dyadic_scalar(token: &Token) -> Option<DyadicScalar> {
+    match token {
+        Token::Equal | Token::NotEqual => None,
+        token if Logic::from_token(token).is_some() => None,
+        token => scalar::dyadic_function(token),
+    }
+}
+
+fn primitive(function: &Function) -> Option<&Token> {
+    match function {
+        Function::Primitive(token) => Some(token),
+        _ => None,
+    }
+}
+
+fn monadic_op(function: &Function) -> Option<Op> {
+    match function {
+        Function::Primitive(Token::Iota) => Some(Op::Iota),
+        Function::Primitive(token) => monadic_scalar(token).map(|_| Op::Scalar(token.clone())),
+        Function::Derived(Token::Slash, operand, None) => match &**operand {
+            Operand::Function(function) => {
+                let token = primitive(function)?;
+                dyadic_scalar(token).map(|_| Op::Reduce(token.clone()))
+            }
+            Operand::Array(_) => None,
+        },
+        _ => None,
+    }
+}
+
+fn dyadic_op(function: &Function) -> Option<Op> {
+    match primitive(function)? {
+        Token::Rho => Some(Op::Reshape),
+        token => dyadic_scalar(token).map(|_| Op::Scalar(token.clone())),
+    }
+}
+
+/// How many primitives the IR would fuse in lowering `expr`: the evaluator
+/// lowers an expression only when there are at least two.
+pub fn operations(expr: &Expr) -> usize {
+    match expr {
+        Expr::Monadic(function, right) => match monadic_op(function) {
+            Some(Op::Iota) => 1,
+            Some(_) => 1 + operations(right),
+            None => 0,
+        },
+        Expr::Dyadic(left, function, right) => match dyadic_op(function) {
+            Some(Op::Reshape) => 1 + operations(right),
+            Some(_) => 1 + operations(left) + operations(right),
+            None => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Whether what `lower` would evaluate as leaves of `expr` are all
+/// literals, or names that `array` says are bound to arrays.
+pub fn pure(expr: &Expr, array: &dyn Fn(&str) -> bool) -> bool {
+    match expr {
+        Expr::Monadic(function, right) => match monadic_op(function) {
+            Some(Op::Iota) => constant(right, array),
+            Some(_) => pure(right, array),
+            None => constant(expr, array),
+        },
+        Expr::Dyadic(left, function, right) => match dyadic_op(function) {
+            Some(Op::Reshape) => constant(left, array) && pure(right, array),
+            Some(Op::Scalar(_)) => pure(left, array) && pure(right, array),
+            Some(Op::Iota) | Some(Op::Reduce(_)) | None => constant(expr, array),
+        },
+        expr => constant(expr, array),
+    }
+}
+
+// Whether evaluating `expr` can neither fail nor change anything.
+fn constant(expr: &Expr, array: &dyn Fn(&str) -> bool) -> bool {
+    match expr {
+        Expr::Number(_) | Expr::String(_) | Expr::Zilde => true,
+        Expr::Name(name) => array(name),
+        Expr::Strand(items) => items.iter().all(|item| constant(item, array)),
+        _ => false,
+    }
+}
+
+/// Lowers `expr` to the IR, with `leaf` evaluating what the IR doesn't
+/// run. Leaves are evaluated right to left, as the evaluator would.
+pub fn lower(
+    expr: &Expr,
+    leaf: &mut dyn FnMut(&Expr) -> anyhow::Result<Array>,
+) -> anyhow::Result<Node> {
+    Ok(match expr {
+        Expr::Monadic(function, right) => match monadic_op(function) {
+            Some(Op::Iota) => Node::Iota(leaf(right)?),
+            Some(Op::Scalar(token)) => Node::Monadic(token, Box::new(lower(right, leaf)?)),
+            Some(Op::Reduce(token)) => Node::Reduce(token, Box::new(lower(right, leaf)?)),
+            Some(Op::Reshape) | None => Node::Array(leaf(expr)?),
+        },
+        Expr::Dyadic(left, function, right) => match dyadic_op(function) {
+            Some(Op::Reshape) => {
+                let right = Box::new(lower(right, leaf)?);
+                Node::Reshape(leaf(left)?, right)
+            }
+            Some(Op::Scalar(token)) => {
+                let right = Box::new(lower(right, leaf)?);
+                Node::Dyadic(token, Box::new(lower(left, leaf)?), right)
+            }
+            Some(Op::Iota) | Some(Op::Reduce(_)) | None => Node::Array(leaf(expr)?),
+        },
+        expr => Node::Array(leaf(expr)?),
+    })
+}
+
+/// Runs the tree: as one loop if it can be, or else a node at a time.
+pub fn run(node: &Node) -> anyhow::Result<Array> {
+    match Lazy::plan(node) {
+        Ok(mut plan) => {
+            plan.settle(plan.size);
+            match plan.store().and_then(|()| plan.materialise()) {
+                Ok(array) => Ok(array),
+                Err(_) => interpret(node),
+            }
+        }
+        Err(_) => interpret(node),
+    }
+}
+
+/// Runs the tree a primitive at a time, each node an array of its own.
+pub fn interpret(node: &Node) -> anyhow::Result<Array> {
+    match node {
+        Node::Array(array) => Ok(array.clone()),
+        Node::Iota(n) => search::index_generator(n),
+        Node::Reshape(shape, right) => structural::reshape(shape, &interpret(right)?),
+        Node::Monadic(token, right) => {
+            scalar::monadic(monadic_scalar(token).unwrap(), &interpret(right)?)
+        }
+        Node::Dyadic(token, left, right) => {
+            let right = interpret(right)?;
+            scalar::dyadic(dyadic_scalar(token).unwrap(), &interpret(left)?, &right)
+        }
+        Node::Reduce(Token::Plus, right) => scalar::sum(&interpret(right)?),
+        Node::Reduce(token, right) => {
+            let function = dyadic_scalar(token).unwrap();
+            let f = |a, b| scalar::dyadic(function, &a, &b);
+            reduce(&f, interpret(right)?, scalar::identity(token))
+        }
+    }
+}
+
+/// The tree as text, a line for each node and the nodes grouped into the
+/// loops that compute them, followed by what the cost model makes of it.
+pub fn dump(node: &Node) -> Vec<String> {
+    let mut plan = match Lazy::plan(node) {
+        Ok(plan) => plan,
+        Err(reason) => return vec![format!("not fused: {}", reason)],
+    };
+    plan.settle(plan.size);
+
+    let mut dump = Dump::default();
+    let mut lines = vec![];
+    dump.body(&plan, &mut lines);
+    dump.loops.push(lines);
+    let width = dump
+        .loops
+        .iter()
+        .flatten()
+        .map(|(text, _)| text.chars().count())
+        .max();
+
+    let mut out = vec![];
+    for (i, lines) in dump.loops.iter().enumerate() {
+        out.push(format!("loop {}", i + 1));
+        for (text, shape) in lines {
+            let padding = width.unwrap_or(0) - text.chars().count();
+            out.push(format!("  {}{}  ⍝ {}", text, " ".repeat(padding), shape));
+        }
+    }
+    let (fused, unfused) = (cost::fused(&plan), cost::unfused(&plan));
+    out.push(format!("fused:   {}", fused));
+    out.push(format!("unfused: {}", unfused));
+    out
+}
+
+// A node of the loop: its shape, and how to compute its elements.
+struct Lazy<'a> {
+    shape: Vec<usize>,
+    size: usize,
+    kind: Kind<'a>,
+    // How many elements the loop asks this node for.
+    pulls: usize,
+    // Whether the node is computed once, before the loop, into `stored`.
+    store: bool,
+    stored: Option<Array>,
+}
+
+enum Kind<'a> {
+    Array(&'a Array),
+    Iota(&'a Array),
+    Reshape(&'a Array, Box<Lazy<'a>>),
+    Monadic(&'a Token, MonadicScalar, Box<Lazy<'a>>),
+    Dyadic(&'a Token, DyadicScalar, Box<Lazy<'a>>, Box<Lazy<'a>>),
+    // With the length of the rows it reduces.
+    Reduce(&'a Token, DyadicScalar, usize, Box<Lazy<'a>>),
+}
+
+// A count, such as `⍳` and `⍴` take: whole and not negative.
+fn count(scalar: &Scalar) -> Option<usize> {
+    match scalar.as_number().ok()?.as_real()? {
+        n if n >= 0.0 && n.fract() == 0.0 => Some(n as usize),
+        _ => None,
+    }
+}
+
+impl<'a> Lazy<'a> {
+    fn new(shape: Vec<usize>, kind: Kind<'a>) -> Result<Self, &'static str> {
+        let size = shape.iter().product();
+        if size == 0 {
+            return Err("an empty array");
+        }
+        Ok(Lazy {
+            shape,
+            size,
+            kind,
+            pulls: 0,
+            store: false,
+            stored: None,
+        })
+    }
+
+    // The loop for `node`, if it gives what the primitives would.
+    fn plan(node: &'a Node) -> Result<Self, &'static str> {
+        let boxed = |node| Lazy::plan(node).map(Box::new);
+        match node {
+            Node::Array(array) => match array.data().iter().all(|s| matches!(s, Scalar::Number(_)))
+            {
+                true => Lazy::new(array.shape().to_vec(), Kind::Array(array)),
+                false => Err("an array that is not all numbers"),
+            },
+            Node::Iota(n) => match (n.rank(), n.data()) {
+                (0 | 1, [scalar]) => match count(scalar) {
+                    Some(count) => Lazy::new(vec![count], Kind::Iota(n)),
+                    None => Err("⍳ of what is not a count"),
+                },
+                _ => Err("⍳ of what is not a count"),
+            },
+            Node::Reshape(shape, right) => {
+                let counts = match shape.rank() {
+                    0 | 1 => shape.data().iter().map(count).collect::<Option<Vec<_>>>(),
+                    _ => None,
+                };
+                match counts {
+                    Some(counts) => Lazy::new(counts, Kind::Reshape(shape, boxed(right)?)),
+                    None => Err("⍴ to what is not a shape"),
+                }
+            }
+            Node::Monadic(token, right) => {
+                let right = boxed(right)?;
+                let function = monadic_scalar(token).unwrap();
+                Lazy::new(right.shape.clone(), Kind::Monadic(token, function, right))
+            }
+            Node::Dyadic(token, left, right) => {
+                let (left, right) = (boxed(left)?, boxed(right)?);
+                let shape = match (left.shape.is_empty(), right.shape.is_empty()) {
+                    (true, _) => right.shape.clone(),
+                    (false, true) => left.shape.clone(),
+                    (false, false) if left.shape == right.shape => left.shape.clone(),
+                    (false, false) => return Err("arguments of different shapes"),
+                };
+                let function = dyadic_scalar(token).unwrap();
+                Lazy::new(shape, Kind::Dyadic(token, function, left, right))
+            }
+            Node::Reduce(token, right) => {
+                let right = boxed(right)?;
+                let (&length, shape) = match right.shape.split_last() {
+                    Some(split) => split,
+                    None => return Err("a reduction of a scalar"),
+                };
+                let function = dyadic_scalar(token).unwrap();
+                Lazy::new(shape.to_vec(), Kind::Reduce(token, function, length, right))
+            }
+        }
+    }
+
+    // Whether the node computes its elements, rather than having them.
+    fn computes(&self) -> bool {
+        !matches!(self.kind, Kind::Array(_) | Kind::Iota(_))
+    }
+
+    fn children(&self) -> Vec<&Lazy<'a>> {
+        match &self.kind {
+            Kind::Array(_) | Kind::Iota(_) => vec![],
+            Kind::Reshape(_, right) | Kind::Monadic(_, _, right) | Kind::Reduce(_, _, _, right) => {
+                vec![right]
+            }
+            Kind::Dyadic(_, _, left, right) => vec![left, right],
+        }
+    }
+
+    // Works out, from the elements asked of each node, which to store: a
+    // node asked for more elements than it has is computed once instead.
+    fn settle(&mut self, pulls: usize) {
+        self.store = self.computes() && pulls > self.size;
+        self.pulls = match self.store {
+            true => self.size,
+            false => pulls,
+        };
+        let pulls = self.pulls;
+        match &mut self.kind {
+            Kind::Array(_) | Kind::Iota(_) => {}
+            Kind::Reshape(_, right) | Kind::Monadic(_, _, right) => right.settle(pulls),
+            Kind::Dyadic(_, _, left, right) => {
+                left.settle(pulls);
+                right.settle(pulls);
+            }
+            Kind::Reduce(_, _, length, right) => right.settle(pulls * *length),
+        }
+    }
+
+    // Computes the stored nodes, innermost first.
+    fn store(&mut self) -> anyhow::Result<()> {
+        match &mut self.kind {
+            Kind::Array(_) | Kind::Iota(_) => {}
+            Kind::Reshape(_, right) | Kind::Monadic(_, _, right) | Kind::Reduce(_, _, _, right) => {
+                right.store()?
+            }
+            Kind::Dyadic(_, _, left, right) => {
+                right.store()?;
+                left.store()?;
+            }
+        }
+        if self.store {
+            self.stored = Some(self.materialise()?);
+        }
+        Ok(())
+    }
+
+    // The node's elements, in an array of its own.
+    fn materialise(&self) -> anyhow::Result<Array> {
+        let mut data = Vec::with_capacity(self.size);
+        for i in 0..self.size {
+            data.push(Scalar::Number(self.compute(i)?));
+        }
+        Ok(Array::new(self.shape.clone(), data))
+    }
+
+    // Element `i` of the node, in row-major order.
+    fn element(&self, i: usize) -> anyhow::Result<Number> {
+        match &self.stored {
+            Some(array) => array.data()[i].as_number(),
+            None => self.compute(i),
+        }
+    }
+
+    fn compute(&self, i: usize) -> anyhow::Result<Number> {
+        match &self.kind {
+            Kind::Array(array) => array.data()[i].as_number(),
+            Kind::Iota(_) => Ok(Number::Real((i + 1) as f64)),
+            Kind::Reshape(_, right) => right.element(i % right.size),
+            Kind::Monadic(_, function, right) => function(right.element(i)?),
+            Kind::Dyadic(_, function, left, right) => {
+                let b = right.element(if right.shape.is_empty() { 0 } else { i })?;
+                let a = left.element(if left.shape.is_empty() { 0 } else { i })?;
+                function(a, b)
+            }
+            // folded in from the right, as `f/` does
+            Kind::Reduce(_, function, length, right) => {
+                let row = i * length;
+                let mut acc = right.element(row + length - 1)?;
+                for j in (row..row + length - 1).rev() {
+                    acc = function(right.element(j)?, acc)?;
+                }
+                Ok(acc)
+            }
+        }
+    }
+}
+
+// A leaf, as it is written in the dump: small vectors in full.
+fn leaf(array: &Array) -> String {
+    match (array.rank(), array.data().len()) {
+        (0 | 1, 1..=8) => array.to_string(),
+        _ => {
+            let shape: Vec<String> = array.shape().iter().map(usize::to_string).collect();
+            format!("({}⍴…)", shape.join(" "))
+        }
+    }
+}
+
+#[derive(Default)]
+struct Dump {
+    // The lines of each loop, as text and shape, in the order they run.
+    loops: Vec<Vec<(String, String)>>,
+    next: usize,
+}
+
+impl Dump {
+    // The name a node goes by in the node using it.
+    fn node(&mut self, lazy: &Lazy, lines: &mut Vec<(String, String)>) -> String {
+        match (&lazy.kind, lazy.store) {
+            (Kind::Array(array), _) => leaf(array),
+            (_, true) => {
+                let mut own = vec![];
+                let name = self.body(lazy, &mut own);
+                self.loops.push(own);
+                name
+            }
+            (_, false) => self.body(lazy, lines),
+        }
+    }
+
+    fn body(&mut self, lazy: &Lazy, lines: &mut Vec<(String, String)>) -> String {
+        let text = match &lazy.kind {
+            Kind::Array(array) => leaf(array),
+            Kind::Iota(n) => format!("⍳{}", leaf(n)),
+            Kind::Reshape(shape, right) => format!("{}⍴{}", leaf(shape), self.node(right, lines)),
+            Kind::Monadic(token, _, right) => {
+                format!("{}{}", spell(token), self.node(right, lines))
+            }
+            Kind::Dyadic(token, _, left, right) => {
+                let right = self.node(right, lines);
+                format!("{}{}{}", self.node(left, lines), spell(token), right)
+            }
+            Kind::Reduce(token, _, _, right) => {
+                format!("{}/{}", spell(token), self.node(right, lines))
+            }
+        };
+        let name = format!("%{}", self.next);
+        self.next += 1;
+        let shape = match lazy.shape.is_empty() {
+            true => "scalar".to_string(),
+            false => {
+                let shape: Vec<String> = lazy.shape.iter().map(usize::to_string).collect();
+                shape.join(" ")
+            }
+        };
+        lines.push((format!("{} ← {}", name, text), shape));
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dump, interpret, run, Node};
+    use crate::runtime::array::Array;
+    use crate::runtime::tests::numbers;
+    use crate::tokenizer::Token;
+
+    fn leaf(array: Array) -> Box<Node> {
+        Box::new(Node::Array(array))
+    }
+
+    // `+/(2 3⍴¯1×⍳3)+1`
+    fn cycled() -> Node {
+        let negated = Node::Dyadic(
+            Token::Times,
+            leaf(Array::scalar(-1.0)),
+            Box::new(Node::Iota(Array::scalar(3.0))),
+        );
+        let reshaped = Node::Reshape(numbers(&[2.0, 3.0]), Box::new(negated));
+        let added = Node::Dyadic(Token::Plus, Box::new(reshaped), leaf(Array::scalar(1.0)));
+        Node::Reduce(Token::Plus, Box::new(added))
+    }
+
+    #[test]
+    fn it_gives_what_the_primitives_would() {
+        let node = cycled();
+        let fused = run(&node).unwrap();
+        assert!(fused.matches(&numbers(&[-3.0, -3.0])));
+        assert!(fused.matches(&interpret(&node).unwrap()));
+
+        let mixed = Node::Dyadic(
+            Token::Plus,
+            leaf(numbers(&[1.0, 2.0])),
+            leaf(Array::from_string_literal("ab")),
+        );
+        assert!(run(&mixed).is_err());
+        let short = Node::Dyadic(
+            Token::Plus,
+            leaf(numbers(&[1.0, 2.0])),
+            leaf(numbers(&[1.0])),
+        );
+        assert!(run(&short)
+            .unwrap_err()
+            .to_string()
+            .starts_with("LENGTH ERROR"));
+    }
+
+    #[test]
+    fn it_stores_what_it_would_compute_twice() {
+        assert_eq!(
+            dump(&cycled()),
+            [
+                "loop 1",
+                "  %0 ← ⍳3      ⍝ 3",
+                "  %1 ← ¯1×%0   ⍝ 3",
+                "loop 2",
+                "  %2 ← 2 3⍴%1  ⍝ 2 3",
+                "  %3 ← %2+1    ⍝ 2 3",
+                "  %4 ← +/%3    ⍝ 2",
+                "fused:   2 arrays of 5 elements, 20 element operations",
+                "unfused: 5 arrays of 20 elements, 20 element operations",
+            ]
+        );
+        let empty = Node::Iota(Array::scalar(0.0));
+        assert_eq!(dump(&empty), ["not fused: an empty array"]);
+    }
+}
@@ -0,0 +1,73 @@
+// The cost model: what running a tree costs, as the arrays it allocates and
+// the elements it computes. Run a node at a time, every node is an array of
+// its own and each element is computed once. Fused, only the result and the
+// stored nodes are arrays, and each element is computed as often as the
+// loop asks for it, which `Lazy::settle` keeps to at most once.
+
+use super::Lazy;
+
+/// What running a tree costs.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Cost {
+    pub arrays: usize,
+    // The elements of those arrays.
+    pub elements: usize,
+    // Elements computed, by a scalar function, `⍳`, `⍴` or a reduction.
+    pub operations: usize,
+}
+
+impl std::fmt::Display for Cost {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{} of {}, {} element operations",
+            plural(self.arrays, "array"),
+            plural(self.elements, "element"),
+            self.operations
+        )
+    }
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    match n {
+        1 => format!("1 {}", noun),
+        n => format!("{} {}s", n, noun),
+    }
+}
+
+fn nodes<'a, 'b>(lazy: &'b Lazy<'a>, all: &mut Vec<&'b Lazy<'a>>) {
+    all.push(lazy);
+    for child in lazy.children() {
+        nodes(child, all);
+    }
+}
+
+// The nodes that are operations rather than arrays the evaluator gave.
+fn operations<'a, 'b>(plan: &'b Lazy<'a>) -> Vec<&'b Lazy<'a>> {
+    let mut all = vec![];
+    nodes(plan, &mut all);
+    all.retain(|lazy| !matches!(lazy.kind, super::Kind::Array(_)));
+    all
+}
+
+/// The cost of running a settled plan as a loop.
+pub(super) fn fused(plan: &Lazy) -> Cost {
+    let operations = operations(plan);
+    let stored = operations.iter().filter(|lazy| lazy.store);
+    Cost {
+        arrays: 1 + stored.clone().count(),
+        elements: plan.size + stored.map(|lazy| lazy.size).sum::<usize>(),
+        operations: operations.iter().map(|lazy| lazy.pulls).sum(),
+    }
+}
+
+/// The cost of running the same tree a node at a time.
+pub(super) fn unfused(plan: &Lazy) -> Cost {
+    let operations = operations(plan);
+    let elements = operations.iter().map(|lazy| lazy.size).sum();
+    Cost {
+        arrays: operations.len(),
+        elements,
+        operations: elements,
+    }
+}
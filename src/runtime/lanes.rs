@@ -0,0 +1,170 @@
+// The elements of an array of a single fixed-width type, unboxed: the
+// native integer widths, `i8` to `u64`, singles and doubles. `⎕INTO` makes
+// arrays like this and the kernels in `primitives::simd` compute on them,
+// so that a chain of those never boxes an element.
+
+use std::convert::TryFrom;
+
+use crate::runtime::array::Scalar;
+use crate::runtime::number::{Float, Int, IntType, Number};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Lanes {
+    I8(Vec<i8>),
+    I16(Vec<i16>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+}
+
+// `$body` with `$lanes` of whichever width they are, giving lanes of the
+// same width.
+macro_rules! same {
+    ($lanes:expr, $v:ident => $body:expr) => {
+        match $lanes {
+            Lanes::I8($v) => Lanes::I8($body),
+            Lanes::I16($v) => Lanes::I16($body),
+            Lanes::I32($v) => Lanes::I32($body),
+            Lanes::I64($v) => Lanes::I64($body),
+            Lanes::U8($v) => Lanes::U8($body),
+            Lanes::U16($v) => Lanes::U16($body),
+            Lanes::U32($v) => Lanes::U32($body),
+            Lanes::U64($v) => Lanes::U64($body),
+            Lanes::F32($v) => Lanes::F32($body),
+            Lanes::F64($v) => Lanes::F64($body),
+        }
+    };
+}
+
+fn int(value: i128, ty: IntType) -> Scalar {
+    Scalar::Number(Number::Int(
+        Int::new(value, ty).expect("a lane is in range for its type"),
+    ))
+}
+
+// The elements, if every one is an integer of type `ty`.
+fn integers<T: TryFrom<i128>>(data: &[Scalar], ty: IntType) -> Option<Vec<T>> {
+    data.iter()
+        .map(|scalar| match scalar {
+            Scalar::Number(Number::Int(i)) if i.ty() == ty => T::try_from(i.value()).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Lanes {
+    /// The elements of `data` unboxed, if they are all numbers of one of
+    /// the widths.
+    pub fn unbox(data: &[Scalar]) -> Option<Lanes> {
+        Some(match data.first()? {
+            Scalar::Number(Number::Int(i)) => match i.ty() {
+                IntType::I8 => Lanes::I8(integers(data, IntType::I8)?),
+                IntType::I16 => Lanes::I16(integers(data, IntType::I16)?),
+                IntType::I32 => Lanes::I32(integers(data, IntType::I32)?),
+                IntType::I64 => Lanes::I64(integers(data, IntType::I64)?),
+                IntType::U8 => Lanes::U8(integers(data, IntType::U8)?),
+                IntType::U16 => Lanes::U16(integers(data, IntType::U16)?),
+                IntType::U32 => Lanes::U32(integers(data, IntType::U32)?),
+                IntType::U64 => Lanes::U64(integers(data, IntType::U64)?),
+                _ => return None,
+            },
+            Scalar::Number(Number::Float(Float::Single(_))) => Lanes::F32(
+                data.iter()
+                    .map(|scalar| match scalar {
+                        Scalar::Number(Number::Float(Float::Single(n))) => Some(*n),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            Scalar::Number(Number::Real(_)) => Lanes::F64(
+                data.iter()
+                    .map(|scalar| match scalar {
+                        Scalar::Number(Number::Real(n)) => Some(*n),
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            _ => return None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Lanes::I8(v) => v.len(),
+            Lanes::I16(v) => v.len(),
+            Lanes::I32(v) => v.len(),
+            Lanes::I64(v) => v.len(),
+            Lanes::U8(v) => v.len(),
+            Lanes::U16(v) => v.len(),
+            Lanes::U32(v) => v.len(),
+            Lanes::U64(v) => v.len(),
+            Lanes::F32(v) => v.len(),
+            Lanes::F64(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The element at `i`, boxed.
+    pub fn get(&self, i: usize) -> Scalar {
+        match self {
+            Lanes::I8(v) => int(v[i] as i128, IntType::I8),
+            Lanes::I16(v) => int(v[i] as i128, IntType::I16),
+            Lanes::I32(v) => int(v[i] as i128, IntType::I32),
+            Lanes::I64(v) => int(v[i] as i128, IntType::I64),
+            Lanes::U8(v) => int(v[i] as i128, IntType::U8),
+            Lanes::U16(v) => int(v[i] as i128, IntType::U16),
+            Lanes::U32(v) => int(v[i] as i128, IntType::U32),
+            Lanes::U64(v) => int(v[i] as i128, IntType::U64),
+            Lanes::F32(v) => Scalar::Number(Number::Float(Float::Single(v[i]))),
+            Lanes::F64(v) => Scalar::from(v[i]),
+        }
+    }
+
+    /// Every element, boxed.
+    pub fn boxed(&self) -> Vec<Scalar> {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+
+    /// The first element `count` times, as a scalar extends.
+    pub fn repeat(&self, count: usize) -> Lanes {
+        same!(self, v => vec![v[0]; count])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lanes;
+    use crate::runtime::array::Scalar;
+    use crate::runtime::number::{Int, IntType, Number};
+    use crate::runtime::tests::numbers;
+
+    #[test]
+    fn it_unboxes_one_width_at_a_time() {
+        let u16s: Vec<Scalar> = [1, 65535]
+            .iter()
+            .map(|n| Scalar::Number(Number::Int(Int::new(*n, IntType::U16).unwrap())))
+            .collect();
+        let lanes = Lanes::unbox(&u16s).unwrap();
+        assert_eq!(lanes, Lanes::U16(vec![1, 65535]));
+        assert_eq!(lanes.boxed(), u16s);
+        assert_eq!(lanes.repeat(3), Lanes::U16(vec![1, 1, 1]));
+
+        let doubles = numbers(&[1.5, -2.0]);
+        assert_eq!(
+            Lanes::unbox(doubles.data()),
+            Some(Lanes::F64(vec![1.5, -2.0]))
+        );
+        let mixed = [u16s[0].clone(), Scalar::from(1.0)];
+        assert_eq!(Lanes::unbox(&mixed), None);
+        assert_eq!(Lanes::unbox(&[Scalar::from(true)]), None);
+        assert_eq!(Lanes::unbox(&[]), None);
+    }
+}
@@ -0,0 +1,1046 @@
+// The numeric element type and the scalar functions defined on it. Reals
+// are promoted to complex whenever a result leaves the real line, and a
+// complex result with no imaginary part is demoted back to a real.
+// Fixed-width integers stay integers under `+ - × |` and whole powers, in
+// the type given by `IntType::promote`, and become reals otherwise.
+// Halves and singles are computed on as doubles and rounded back to the
+// narrowest precision common to the arguments; for `+ - × ÷` that gives
+// the correctly rounded result, as a double has more than twice the bits.
+// Booleans are `u1` integers; the logical functions take any number that
+// is 0 or 1 and give a boolean.
+// Rationals, from the `n` and `r` literals, are exact. They stay exact under
+// `+ - × ÷ | !` with any whole number, and under `*` to a whole power;
+// anything else computes on their nearest double.
+
+pub(crate) mod complex;
+pub(crate) mod float;
+pub(crate) mod integer;
+pub(crate) mod rational;
+
+use std::f64::consts::PI;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::errors::Errors;
+
+pub use complex::Complex;
+pub use float::{Float, Precision, F16};
+pub use integer::{Int, IntType};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Number {
+    Int(Int),
+    Float(Float),
+    // A double.
+    Real(f64),
+    // Never has a zero imaginary part, see `Number::complex`.
+    Complex(Complex),
+    Rational(BigRational),
+}
+
+impl Number {
+    /// A complex number, or a real one if `im` is zero.
+    pub fn complex(re: f64, im: f64) -> Self {
+        match im == 0.0 {
+            true => Number::Real(re),
+            false => Number::Complex(Complex::new(re, im)),
+        }
+    }
+
+    /// A float of the given precision, rounded to it.
+    pub fn float(n: f64, precision: Precision) -> Self {
+        match precision {
+            Precision::Half => Number::Float(Float::Half(F16::from_f64(n))),
+            Precision::Single => Number::Float(Float::Single(n as f32)),
+            Precision::Double => Number::Real(n),
+        }
+    }
+
+    pub fn as_real(&self) -> Option<f64> {
+        match *self {
+            Number::Int(i) => Some(i.to_f64()),
+            Number::Float(f) => Some(f.to_f64()),
+            Number::Real(n) => Some(n),
+            Number::Complex(_) => None,
+            Number::Rational(ref q) => Some(rational::to_f64(q)),
+        }
+    }
+
+    pub fn to_complex(&self) -> Complex {
+        match *self {
+            Number::Int(i) => Complex::real(i.to_f64()),
+            Number::Float(f) => Complex::real(f.to_f64()),
+            Number::Real(n) => Complex::real(n),
+            Number::Complex(z) => z,
+            Number::Rational(ref q) => Complex::real(rational::to_f64(q)),
+        }
+    }
+
+    // The number as an exact rational: any rational or integer, and whole
+    // floats.
+    fn exact(&self) -> Option<BigRational> {
+        match *self {
+            Number::Int(i) => Some(BigRational::from_integer(BigInt::from(i.value()))),
+            Number::Rational(ref q) => Some(q.clone()),
+            Number::Complex(_) => None,
+            _ => rational::from_integral(self.as_real()?),
+        }
+    }
+
+    // Both as exact rationals, when the computation between them should be
+    // exact: one of them is a rational, and the other is a whole number if
+    // it isn't.
+    fn exact_pair(&self, other: &Self) -> Option<(BigRational, BigRational)> {
+        match (self, other) {
+            (Number::Rational(_), _) | (_, Number::Rational(_)) => {
+                Some((self.exact()?, other.exact()?))
+            }
+            _ => None,
+        }
+    }
+
+    // Integers and narrow floats as doubles, to compute on.
+    fn floating(&self) -> Self {
+        match *self {
+            Number::Int(i) => Number::Real(i.to_f64()),
+            Number::Float(f) => Number::Real(f.to_f64()),
+            Number::Rational(ref q) => Number::Real(rational::to_f64(q)),
+            _ => self.clone(),
+        }
+    }
+
+    // The precision a result computed from `self` is rounded to. Integers
+    // and rationals have none and take on the precision of whatever they
+    // meet.
+    fn precision(&self) -> Option<Precision> {
+        match *self {
+            Number::Int(_) | Number::Rational(_) => None,
+            Number::Float(f) => Some(f.precision()),
+            Number::Real(_) | Number::Complex(_) => Some(Precision::Double),
+        }
+    }
+
+    fn common_precision(&self, other: &Self) -> Option<Precision> {
+        match (self.precision(), other.precision()) {
+            (Some(a), Some(b)) => Some(a.common(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    // Rounds a real result to `precision`. Complex numbers are always
+    // doubles.
+    fn narrowed(self, precision: Option<Precision>) -> Self {
+        match (&self, precision) {
+            (Number::Real(n), Some(precision)) => Number::float(*n, precision),
+            _ => self,
+        }
+    }
+
+    /// The zero of the same type.
+    pub fn zero(&self) -> Self {
+        match *self {
+            Number::Int(i) => Number::Int(i.zero()),
+            Number::Rational(_) => Number::Rational(BigRational::zero()),
+            _ => Number::Real(0.0),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.floating() == Number::Real(0.0)
+    }
+
+    /// A `u1`.
+    pub fn boolean(b: bool) -> Self {
+        Number::Int(Int::new(b as i128, IntType::U1).unwrap())
+    }
+
+    /// Whether the number is 0 or 1 and which, whatever its type.
+    pub fn as_boolean(&self) -> Option<bool> {
+        match *self {
+            Number::Int(i) if i.value() == 0 || i.value() == 1 => Some(i.value() == 1),
+            Number::Int(_) | Number::Complex(_) => None,
+            _ => match self.as_real() {
+                Some(0.0) => Some(false),
+                Some(1.0) => Some(true),
+                _ => None,
+            },
+        }
+    }
+
+    /// Equality of value across types, as `=` and the search functions see
+    /// it: `1b`, `1u8` and `1.0` are all the same number.
+    pub fn same(&self, other: &Self) -> bool {
+        if let Some((a, b)) = self.exact_pair(other) {
+            return a == b;
+        }
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => a.value() == b.value(),
+            _ => self.to_complex() == other.to_complex(),
+        }
+    }
+
+    fn booleans(&self, other: &Self) -> anyhow::Result<(bool, bool)> {
+        match (self.as_boolean(), other.as_boolean()) {
+            (Some(a), Some(b)) => Ok((a, b)),
+            _ => anyhow::bail!(Errors::Domain("expected booleans".to_string())),
+        }
+    }
+
+    /// Monadic `~`.
+    pub fn not(self) -> anyhow::Result<Self> {
+        match self.as_boolean() {
+            Some(b) => Ok(Number::boolean(!b)),
+            None => anyhow::bail!(Errors::Domain("expected a boolean".to_string())),
+        }
+    }
+
+    /// Dyadic `∧`.
+    pub fn and(self, other: Self) -> anyhow::Result<Self> {
+        let (a, b) = self.booleans(&other)?;
+        Ok(Number::boolean(a && b))
+    }
+
+    /// Dyadic `∨`.
+    pub fn or(self, other: Self) -> anyhow::Result<Self> {
+        let (a, b) = self.booleans(&other)?;
+        Ok(Number::boolean(a || b))
+    }
+
+    /// Dyadic `⍲`.
+    pub fn nand(self, other: Self) -> anyhow::Result<Self> {
+        let (a, b) = self.booleans(&other)?;
+        Ok(Number::boolean(!(a && b)))
+    }
+
+    /// Dyadic `⍱`.
+    pub fn nor(self, other: Self) -> anyhow::Result<Self> {
+        let (a, b) = self.booleans(&other)?;
+        Ok(Number::boolean(!(a || b)))
+    }
+
+    // The order of two real numbers, exactly when either is a rational.
+    fn compare(&self, other: &Self) -> anyhow::Result<std::cmp::Ordering> {
+        if let Some((a, b)) = self.exact_pair(other) {
+            return Ok(a.cmp(&b));
+        }
+        if let (Number::Int(a), Number::Int(b)) = (self, other) {
+            return Ok(a.value().cmp(&b.value()));
+        }
+        match (self.as_real(), other.as_real()) {
+            (Some(a), Some(b)) => match a.partial_cmp(&b) {
+                Some(ordering) => Ok(ordering),
+                None => anyhow::bail!(Errors::Domain("NaN has no order".to_string())),
+            },
+            _ => anyhow::bail!(Errors::Domain("complex numbers have no order".to_string())),
+        }
+    }
+
+    /// The order `⍋` sorts numbers in: by value, exactly when either is a
+    /// rational, and complex numbers by their real then imaginary parts.
+    /// NaN is equal to everything.
+    pub fn order(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering::Equal;
+
+        match self.compare(other) {
+            Ok(ordering) => ordering,
+            Err(_) => {
+                let (a, b) = (self.to_complex(), other.to_complex());
+                let re = a.re.partial_cmp(&b.re).unwrap_or(Equal);
+                re.then(a.im.partial_cmp(&b.im).unwrap_or(Equal))
+            }
+        }
+    }
+
+    /// Dyadic `=`.
+    pub fn equal(self, other: Self) -> anyhow::Result<Self> {
+        Ok(Number::boolean(self.same(&other)))
+    }
+
+    /// Dyadic `≠`.
+    pub fn not_equal(self, other: Self) -> anyhow::Result<Self> {
+        Ok(Number::boolean(!self.same(&other)))
+    }
+
+    /// Dyadic `<`.
+    pub fn less(self, other: Self) -> anyhow::Result<Self> {
+        Ok(Number::boolean(self.compare(&other)?.is_lt()))
+    }
+
+    /// Dyadic `≤`.
+    pub fn less_or_equal(self, other: Self) -> anyhow::Result<Self> {
+        Ok(Number::boolean(self.compare(&other)?.is_le()))
+    }
+
+    /// Dyadic `>`.
+    pub fn greater(self, other: Self) -> anyhow::Result<Self> {
+        Ok(Number::boolean(self.compare(&other)?.is_gt()))
+    }
+
+    /// Dyadic `≥`.
+    pub fn greater_or_equal(self, other: Self) -> anyhow::Result<Self> {
+        Ok(Number::boolean(self.compare(&other)?.is_ge()))
+    }
+
+    /// Dyadic `⌈`, whichever of the two is larger, as it is.
+    pub fn maximum(self, other: Self) -> anyhow::Result<Self> {
+        Ok(match self.compare(&other)?.is_lt() {
+            true => other,
+            false => self,
+        })
+    }
+
+    /// Dyadic `⌊`.
+    pub fn minimum(self, other: Self) -> anyhow::Result<Self> {
+        Ok(match self.compare(&other)?.is_gt() {
+            true => other,
+            false => self,
+        })
+    }
+
+    /// Monadic `⌊`, the complex floor for complex numbers.
+    pub fn floor(self) -> anyhow::Result<Self> {
+        Ok(match self {
+            Number::Int(_) => self,
+            Number::Float(f) => Number::float(f.to_f64().floor(), f.precision()),
+            Number::Real(n) => Number::Real(n.floor()),
+            Number::Complex(z) => z.floor().into(),
+            Number::Rational(q) => Number::Rational(q.floor()),
+        })
+    }
+
+    /// Monadic `⌈`, `-⌊-⍵`.
+    pub fn ceiling(self) -> anyhow::Result<Self> {
+        self.negate()?.floor()?.negate()
+    }
+
+    /// Monadic `+`.
+    pub fn conjugate(self) -> anyhow::Result<Self> {
+        Ok(match self {
+            Number::Complex(z) => z.conj().into(),
+            _ => self,
+        })
+    }
+
+    /// Monadic `-`.
+    pub fn negate(self) -> anyhow::Result<Self> {
+        Ok(match self {
+            Number::Int(i) => Number::Int(i.negate()?),
+            Number::Float(f) => Number::float(-f.to_f64(), f.precision()),
+            Number::Real(n) => Number::Real(-n),
+            Number::Complex(z) => z.neg().into(),
+            Number::Rational(q) => Number::Rational(-q),
+        })
+    }
+
+    /// Monadic `×`: the number of magnitude 1 in the direction of `self`,
+    /// or 0.
+    pub fn direction(self) -> anyhow::Result<Self> {
+        Ok(match self {
+            _ if self.is_zero() => self,
+            Number::Int(i) => Number::Int(i.signum()?),
+            Number::Complex(z) => z.signum().into(),
+            Number::Rational(q) => Number::Rational(q.signum()),
+            n => Number::Real(n.to_complex().re.signum()).narrowed(n.precision()),
+        })
+    }
+
+    /// Monadic `÷`.
+    pub fn reciprocal(self) -> anyhow::Result<Self> {
+        if let Number::Rational(q) = &self {
+            if q.is_zero() {
+                anyhow::bail!(Errors::Domain("division by zero".to_string()));
+            }
+            return Ok(Number::Rational(q.recip()));
+        }
+        Ok(Number::Real(1.0)
+            .divide(self.floating())?
+            .narrowed(self.precision()))
+    }
+
+    /// Monadic `*`.
+    pub fn exp(self) -> anyhow::Result<Self> {
+        Ok(match self.floating() {
+            Number::Real(n) => Number::Real(n.exp()),
+            n => n.to_complex().exp().into(),
+        }
+        .narrowed(self.precision()))
+    }
+
+    /// Monadic `⍟`. Negative numbers have a complex logarithm.
+    pub fn ln(self) -> anyhow::Result<Self> {
+        if self.is_zero() {
+            anyhow::bail!(Errors::Domain("⍟0 is undefined".to_string()));
+        }
+        Ok(match self.floating() {
+            Number::Real(n) if n > 0.0 => Number::Real(n.ln()),
+            _ => self.to_complex().ln().into(),
+        }
+        .narrowed(self.precision()))
+    }
+
+    /// Monadic `|`.
+    pub fn magnitude(self) -> anyhow::Result<Self> {
+        Ok(match self {
+            Number::Int(i) => Number::Int(i.magnitude()?),
+            Number::Float(f) => Number::float(f.to_f64().abs(), f.precision()),
+            Number::Real(n) => Number::Real(n.abs()),
+            Number::Complex(z) => Number::Real(z.abs()),
+            Number::Rational(q) => Number::Rational(q.abs()),
+        })
+    }
+
+    /// Monadic `○`.
+    pub fn pi_times(self) -> anyhow::Result<Self> {
+        Ok(Number::Real(PI)
+            .multiply(self.floating())?
+            .narrowed(self.precision()))
+    }
+
+    /// Monadic `!`: factorial, and `Γ(n+1)` for reals that aren't whole.
+    pub fn factorial(self) -> anyhow::Result<Self> {
+        match &self {
+            Number::Rational(q) if q.is_integer() => {
+                return Ok(Number::Rational(BigRational::from_integer(
+                    rational::factorial(q.numer())?,
+                )))
+            }
+            Number::Int(i) => {
+                let n = rational::factorial(&BigInt::from(i.value()))?;
+                return Ok(Number::Int(Int::from_big(&n, i.ty())?));
+            }
+            Number::Complex(_) => {
+                anyhow::bail!(Errors::Domain("! of a complex number".to_string()))
+            }
+            _ => {}
+        }
+        let n = self.as_real().unwrap();
+        if n < 0.0 && n.fract() == 0.0 {
+            anyhow::bail!(Errors::Domain("! of a negative integer".to_string()));
+        }
+        Ok(Number::Real(gamma(n + 1.0)).narrowed(self.precision()))
+    }
+
+    /// Dyadic `+`.
+    pub fn add(self, other: Self) -> anyhow::Result<Self> {
+        if let Some((a, b)) = self.exact_pair(&other) {
+            return Ok(Number::Rational(a + b));
+        }
+        if let (Number::Int(a), Number::Int(b)) = (&self, &other) {
+            return Ok(Number::Int(a.add(*b)?));
+        }
+        Ok(match (self.floating(), other.floating()) {
+            (Number::Real(a), Number::Real(b)) => Number::Real(a + b),
+            _ => self.to_complex().add(other.to_complex()).into(),
+        }
+        .narrowed(self.common_precision(&other)))
+    }
+
+    /// Dyadic `-`.
+    pub fn subtract(self, other: Self) -> anyhow::Result<Self> {
+        if let Some((a, b)) = self.exact_pair(&other) {
+            return Ok(Number::Rational(a - b));
+        }
+        if let (Number::Int(a), Number::Int(b)) = (&self, &other) {
+            return Ok(Number::Int(a.subtract(*b)?));
+        }
+        Ok(match (self.floating(), other.floating()) {
+            (Number::Real(a), Number::Real(b)) => Number::Real(a - b),
+            _ => self.to_complex().sub(other.to_complex()).into(),
+        }
+        .narrowed(self.common_precision(&other)))
+    }
+
+    /// Dyadic `×`.
+    pub fn multiply(self, other: Self) -> anyhow::Result<Self> {
+        if let Some((a, b)) = self.exact_pair(&other) {
+            return Ok(Number::Rational(a * b));
+        }
+        if let (Number::Int(a), Number::Int(b)) = (&self, &other) {
+            return Ok(Number::Int(a.multiply(*b)?));
+        }
+        Ok(match (self.floating(), other.floating()) {
+            (Number::Real(a), Number::Real(b)) => Number::Real(a * b),
+            _ => self.to_complex().mul(other.to_complex()).into(),
+        }
+        .narrowed(self.common_precision(&other)))
+    }
+
+    /// Dyadic `÷`. `0÷0` is 1, any other division by zero is an error.
+    pub fn divide(self, other: Self) -> anyhow::Result<Self> {
+        if other.is_zero() {
+            if self.is_zero() {
+                return Ok(match self.exact_pair(&other) {
+                    Some(_) => Number::Rational(BigRational::one()),
+                    None => Number::Real(1.0).narrowed(self.common_precision(&other)),
+                });
+            }
+            anyhow::bail!(Errors::Domain("division by zero".to_string()));
+        }
+        if let Some((a, b)) = self.exact_pair(&other) {
+            return Ok(Number::Rational(a / b));
+        }
+        Ok(match (self.floating(), other.floating()) {
+            (Number::Real(a), Number::Real(b)) => Number::Real(a / b),
+            _ => self.to_complex().div(other.to_complex()).into(),
+        }
+        .narrowed(self.common_precision(&other)))
+    }
+
+    /// Dyadic `*`, `self` raised to `other`. A negative base with a
+    /// fractional exponent gives the principal complex root.
+    pub fn power(self, other: Self) -> anyhow::Result<Self> {
+        match (&self, &other) {
+            (Number::Int(a), Number::Int(b)) if b.value() >= 0 => {
+                return Ok(Number::Int(a.power(*b)?))
+            }
+            _ => {}
+        }
+        if let Some((a, b)) = self.exact_pair(&other) {
+            if b.is_integer() {
+                if a.is_zero() && b.is_negative() {
+                    anyhow::bail!(Errors::Domain("0 raised to a negative power".to_string()));
+                }
+                return match b.to_integer().to_i32() {
+                    Some(b) => Ok(Number::Rational(a.pow(b))),
+                    None => {
+                        anyhow::bail!(Errors::Domain(format!("the exponent {} is too large", b)))
+                    }
+                };
+            }
+        }
+        if self.is_zero() {
+            let result = match other.to_complex().re {
+                _ if other.is_zero() => 1.0,
+                re if re > 0.0 => 0.0,
+                _ => anyhow::bail!(Errors::Domain(
+                    "0 raised to a non-positive power".to_string()
+                )),
+            };
+            return Ok(Number::Real(result).narrowed(self.common_precision(&other)));
+        }
+        Ok(match (self.floating(), other.floating()) {
+            (Number::Real(a), Number::Real(b)) if a > 0.0 || b.fract() == 0.0 => {
+                Number::Real(a.powf(b))
+            }
+            // A negative base lies at angle π, so the result lies at angle πb.
+            // Half-integer powers land on the imaginary axis exactly, rather
+            // than a rounding error away from it.
+            (Number::Real(a), Number::Real(b)) => {
+                let magnitude = (-a).powf(b);
+                let re = match (b * 2.0).fract() == 0.0 {
+                    true => 0.0,
+                    false => magnitude * (PI * b).cos(),
+                };
+                Number::complex(re, magnitude * (PI * b).sin())
+            }
+            (a, b) => a.to_complex().powc(b.to_complex()).into(),
+        }
+        .narrowed(self.common_precision(&other)))
+    }
+
+    /// Dyadic `⍟`, the base `self` logarithm of `other`.
+    pub fn log(self, other: Self) -> anyhow::Result<Self> {
+        if self.floating() == Number::Real(1.0) {
+            return match other.floating() == Number::Real(1.0) {
+                true => Ok(Number::Real(1.0)),
+                false => anyhow::bail!(Errors::Domain("logarithm to base 1".to_string())),
+            };
+        }
+        Ok(other
+            .floating()
+            .ln()?
+            .divide(self.floating().ln()?)?
+            .narrowed(self.common_precision(&other)))
+    }
+
+    /// Dyadic `|`, `other` modulo `self`. Complex residues use the complex
+    /// floor, so the result is always nearer zero than `self`.
+    pub fn residue(self, other: Self) -> anyhow::Result<Self> {
+        if let (Number::Int(a), Number::Int(b)) = (&self, &other) {
+            return Ok(Number::Int(a.residue(*b)?));
+        }
+        if let Some((a, b)) = self.exact_pair(&other) {
+            return Ok(Number::Rational(match a.is_zero() {
+                true => b,
+                false => &b - &a * (&b / &a).floor(),
+            }));
+        }
+        if self.is_zero() {
+            return Ok(other);
+        }
+        Ok(match (self.floating(), other.floating()) {
+            (Number::Real(a), Number::Real(b)) => Number::Real(b - a * (b / a).floor()),
+            _ => {
+                let (a, b) = (self.to_complex(), other.to_complex());
+                b.sub(a.mul(b.div(a).floor())).into()
+            }
+        }
+        .narrowed(self.common_precision(&other)))
+    }
+
+    /// Dyadic `!`, the binomial coefficient `self` of `other`. Whole numbers
+    /// extend to negatives by the limit of the gamma function form; see
+    /// `rational::binomial`.
+    pub fn binomial(self, other: Self) -> anyhow::Result<Self> {
+        if let (Number::Complex(_), _) | (_, Number::Complex(_)) = (&self, &other) {
+            anyhow::bail!(Errors::Domain("! of a complex number".to_string()));
+        }
+        if let (Number::Int(k), Number::Int(n)) = (&self, &other) {
+            let binomial = rational::binomial(&BigInt::from(k.value()), &BigInt::from(n.value()))?;
+            return Ok(Number::Int(Int::from_big(
+                &binomial,
+                k.ty().promote(n.ty()),
+            )?));
+        }
+        match (self.exact(), other.exact()) {
+            (Some(k), Some(n)) if k.is_integer() && n.is_integer() => {
+                let binomial = BigRational::from_integer(rational::binomial(k.numer(), n.numer())?);
+                return Ok(match self.exact_pair(&other) {
+                    Some(_) => Number::Rational(binomial),
+                    None => Number::Real(rational::to_f64(&binomial))
+                        .narrowed(self.common_precision(&other)),
+                });
+            }
+            _ => {}
+        }
+
+        // Γ(n+1) ÷ Γ(k+1) × Γ(n-k+1), where a pole below the line makes it 0
+        let (k, n) = (self.as_real().unwrap(), other.as_real().unwrap());
+        let pole = |x: f64| x <= 0.0 && x.fract() == 0.0;
+        if pole(n + 1.0) {
+            anyhow::bail!(Errors::Domain(format!("{}!{} is infinite", k, n)));
+        }
+        let result = match pole(k + 1.0) || pole(n - k + 1.0) {
+            true => 0.0,
+            false => gamma(n + 1.0) / (gamma(k + 1.0) * gamma(n - k + 1.0)),
+        };
+        Ok(Number::Real(result).narrowed(self.common_precision(&other)))
+    }
+
+    /// Dyadic `○`, the circular function family selected by `self`.
+    pub fn circular(self, other: Self) -> anyhow::Result<Self> {
+        let precision = other.precision();
+        let other = other.floating();
+        let selector = match self.floating() {
+            Number::Real(n) if n.fract() == 0.0 && (-12.0..=12.0).contains(&n) => n as i8,
+            _ => anyhow::bail!(Errors::Domain(
+                "○ expects a left argument in ¯12 to 12".to_string()
+            )),
+        };
+        let z = other.to_complex();
+        let one = Complex::real(1.0);
+
+        // Takes the real function when `other` is real and inside its real
+        // domain, so that real arguments don't pick up rounding noise in the
+        // imaginary part on their way through the complex one.
+        let real_or =
+            |real: fn(f64) -> f64, domain: fn(f64) -> bool, complex: fn(Complex) -> Complex| {
+                match other {
+                    Number::Real(x) if domain(x) => Number::Real(real(x)),
+                    _ => complex(z).into(),
+                }
+            };
+        let anywhere = |_: f64| true;
+        let unit = |x: f64| (-1.0..=1.0).contains(&x);
+
+        Ok(match selector {
+            0 => real_or(
+                |x| (1.0 - x * x).sqrt(),
+                unit,
+                |z| Complex::real(1.0).sub(z.mul(z)).sqrt(),
+            ),
+            1 => real_or(f64::sin, anywhere, Complex::sin),
+            2 => real_or(f64::cos, anywhere, Complex::cos),
+            3 => real_or(f64::tan, anywhere, Complex::tan),
+            4 => real_or(
+                |x| (1.0 + x * x).sqrt(),
+                anywhere,
+                |z| Complex::real(1.0).add(z.mul(z)).sqrt(),
+            ),
+            5 => real_or(f64::sinh, anywhere, Complex::sinh),
+            6 => real_or(f64::cosh, anywhere, Complex::cosh),
+            7 => real_or(f64::tanh, anywhere, Complex::tanh),
+            8 => Complex::real(-1.0).sub(z.mul(z)).sqrt().into(),
+            9 => Number::Real(z.re),
+            10 => Number::Real(z.abs()),
+            11 => Number::Real(z.im),
+            12 => Number::Real(z.arg()),
+            -1 => real_or(f64::asin, unit, Complex::asin),
+            -2 => real_or(f64::acos, unit, Complex::acos),
+            -3 => real_or(f64::atan, anywhere, Complex::atan),
+            // (z + 1) × √((z - 1) ÷ (z + 1)), which unlike √(z² - 1) is odd
+            -4 => match other {
+                Number::Real(x) if x.abs() >= 1.0 => {
+                    Number::Real(x.signum() * (x * x - 1.0).sqrt())
+                }
+                _ if z == Complex::real(-1.0) => Number::Real(0.0),
+                _ => z.add(one).mul(z.sub(one).div(z.add(one)).sqrt()).into(),
+            },
+            -5 => real_or(f64::asinh, anywhere, Complex::asinh),
+            -6 => real_or(f64::acosh, |x| x >= 1.0, Complex::acosh),
+            -7 => real_or(f64::atanh, |x| -1.0 < x && x < 1.0, Complex::atanh),
+            -8 => Complex::real(-1.0).sub(z.mul(z)).sqrt().neg().into(),
+            -9 => other,
+            -10 => z.conj().into(),
+            -11 => Complex::new(0.0, 1.0).mul(z).into(),
+            -12 => Complex::new(0.0, 1.0).mul(z).exp().into(),
+            _ => unreachable!(),
+        }
+        .narrowed(precision))
+    }
+}
+
+// The gamma function, by the Lanczos approximation (g = 7, n = 9) and the
+// reflection formula below ½. Whole arguments up to 171 multiply out
+// exactly instead, so that `!` of an integer is an integer.
+fn gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x.fract() == 0.0 && (1.0..=171.0).contains(&x) {
+        return (2..x as u32).map(f64::from).product();
+    }
+    if x < 0.5 {
+        return PI / ((PI * x).sin() * gamma(1.0 - x));
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    (2.0 * PI).sqrt() * t.powf(x + 0.5) * (-t).exp() * sum
+}
+
+impl From<Complex> for Number {
+    fn from(z: Complex) -> Self {
+        Number::complex(z.re, z.im)
+    }
+}
+
+impl From<f64> for Number {
+    fn from(n: f64) -> Self {
+        Number::Real(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Float, Int, IntType, Number, Precision, F16};
+    use num_rational::BigRational;
+
+    fn close(a: Number, b: Number) -> bool {
+        a.subtract(b)
+            .unwrap()
+            .magnitude()
+            .unwrap()
+            .as_real()
+            .unwrap()
+            < 1e-12
+    }
+
+    #[test]
+    fn it_promotes_and_demotes() {
+        assert!(close(
+            Number::Real(-1.0).power(Number::Real(0.5)).unwrap(),
+            Number::complex(0.0, 1.0)
+        ));
+        assert!(close(
+            Number::Real(-1.0).ln().unwrap(),
+            Number::complex(0.0, std::f64::consts::PI)
+        ));
+        assert_eq!(
+            Number::complex(0.0, 1.0)
+                .multiply(Number::complex(0.0, 1.0))
+                .unwrap(),
+            Number::Real(-1.0)
+        );
+        assert_eq!(
+            Number::complex(1.0, 2.0)
+                .add(Number::complex(1.0, -2.0))
+                .unwrap(),
+            Number::Real(2.0)
+        );
+        assert_eq!(
+            Number::Real(-8.0).power(Number::Real(2.0)).unwrap(),
+            Number::Real(64.0)
+        );
+    }
+
+    #[test]
+    fn it_keeps_integers_integral() {
+        let int = |value, signed, bits| {
+            Number::Int(Int::new(value, IntType::new(signed, bits).unwrap()).unwrap())
+        };
+
+        assert_eq!(
+            int(15, false, 4).add(int(-1, true, 4)).unwrap(),
+            int(14, true, 5)
+        );
+        assert_eq!(
+            int(2, true, 8).power(int(6, true, 8)).unwrap(),
+            int(64, true, 8)
+        );
+        assert!(int(2, true, 8).power(int(7, true, 8)).is_err());
+        assert_eq!(
+            int(7, true, 8).residue(int(-1, true, 8)).unwrap(),
+            int(6, true, 8)
+        );
+
+        // Anything else leaves the integers
+        assert_eq!(
+            int(1, false, 4).divide(int(2, false, 4)).unwrap(),
+            Number::Real(0.5)
+        );
+        assert_eq!(
+            int(3, true, 8).add(Number::Real(0.5)).unwrap(),
+            Number::Real(3.5)
+        );
+        assert_eq!(
+            int(2, true, 8).power(int(-1, true, 8)).unwrap(),
+            Number::Real(0.5)
+        );
+        assert_eq!(
+            int(1, true, 8).add(Number::complex(0.0, 1.0)).unwrap(),
+            Number::complex(1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn it_computes_in_the_declared_precision() {
+        let half = |n| Number::float(n, Precision::Half);
+        let single = |n| Number::float(n, Precision::Single);
+        let u8 = Number::Int(Int::new(1, IntType::new(false, 8).unwrap()).unwrap());
+
+        // 2049 is not a half, it rounds to even
+        assert_eq!(half(2048.0).add(half(1.0)).unwrap(), half(2048.0));
+        assert_eq!(single(2048.0).add(single(1.0)).unwrap(), single(2049.0));
+        assert_eq!(
+            half(1.0).divide(half(3.0)).unwrap(),
+            Number::Float(Float::Half(F16::from_bits(0x3555)))
+        );
+
+        assert_eq!(half(1.0).add(single(0.1)).unwrap(), single(1.1));
+        assert_eq!(half(1.0).add(u8).unwrap(), half(2.0));
+        assert_eq!(half(1.0).add(Number::Real(0.1)).unwrap(), Number::Real(1.1));
+        assert_eq!(half(-2.0).negate().unwrap(), half(2.0));
+        assert_eq!(half(-2.0).direction().unwrap(), half(-1.0));
+        assert_eq!(half(4.0).reciprocal().unwrap(), half(0.25));
+        assert!(matches!(half(-1.0).ln().unwrap(), Number::Complex(_)));
+    }
+
+    #[test]
+    fn it_divides_like_apl() {
+        assert_eq!(
+            Number::Real(0.0).divide(Number::Real(0.0)).unwrap(),
+            Number::Real(1.0)
+        );
+        assert!(Number::Real(1.0).divide(Number::Real(0.0)).is_err());
+        assert!(Number::Real(0.0).reciprocal().is_err());
+        assert_eq!(
+            Number::complex(3.0, 4.0).magnitude().unwrap(),
+            Number::Real(5.0)
+        );
+    }
+
+    #[test]
+    fn it_takes_residues() {
+        assert_eq!(
+            Number::Real(3.0).residue(Number::Real(-7.0)).unwrap(),
+            Number::Real(2.0)
+        );
+        assert_eq!(
+            Number::Real(0.0).residue(Number::Real(5.0)).unwrap(),
+            Number::Real(5.0)
+        );
+        // 1J2|4J4 ←→ 0J1
+        assert!(close(
+            Number::complex(1.0, 2.0)
+                .residue(Number::complex(4.0, 4.0))
+                .unwrap(),
+            Number::complex(0.0, 1.0)
+        ));
+    }
+
+    #[test]
+    fn it_computes_circular_functions() {
+        let circular = |selector: f64, n: Number| Number::Real(selector).circular(n).unwrap();
+
+        assert!(close(circular(0.0, Number::Real(0.6)), Number::Real(0.8)));
+        assert!(close(
+            circular(0.0, Number::Real(2.0)),
+            Number::complex(0.0, 3f64.sqrt())
+        ));
+        assert!(close(
+            circular(1.0, circular(-1.0, Number::Real(2.0))),
+            Number::Real(2.0)
+        ));
+        assert_eq!(circular(9.0, Number::complex(3.0, 4.0)), Number::Real(3.0));
+        assert_eq!(circular(10.0, Number::complex(3.0, 4.0)), Number::Real(5.0));
+        assert_eq!(circular(11.0, Number::complex(3.0, 4.0)), Number::Real(4.0));
+        assert_eq!(circular(-4.0, Number::Real(-1.0)), Number::Real(0.0));
+        assert!(close(
+            circular(-4.0, Number::Real(-2.0)),
+            Number::Real(-(3f64.sqrt()))
+        ));
+        assert!(close(
+            circular(-12.0, Number::Real(std::f64::consts::PI)),
+            Number::Real(-1.0)
+        ));
+        assert!(Number::Real(13.0).circular(Number::Real(1.0)).is_err());
+        assert!(Number::Real(1.5).circular(Number::Real(1.0)).is_err());
+    }
+
+    #[test]
+    fn it_treats_booleans_as_one_bit_integers() {
+        let (yes, no) = (|| Number::boolean(true), || Number::boolean(false));
+
+        assert_eq!(yes().and(Number::Real(1.0)).unwrap(), yes());
+        assert_eq!(no().or(no()).unwrap(), no());
+        assert_eq!(yes().nand(yes()).unwrap(), no());
+        assert_eq!(no().nor(no()).unwrap(), yes());
+        assert_eq!(Number::Real(0.0).not().unwrap(), yes());
+        assert!(Number::Real(2.0).not().is_err());
+        assert!(yes().and(Number::Real(0.5)).is_err());
+
+        // 1b + 1b ←→ 2, not an overflow
+        assert_eq!(
+            yes().add(yes()).unwrap(),
+            Number::Int(Int::new(2, IntType::I64).unwrap())
+        );
+        assert!(yes().same(&Number::Real(1.0)));
+        assert!(!yes().same(&no()));
+    }
+
+    #[test]
+    fn it_keeps_rationals_exact() {
+        let q = |n: i64, d: i64| Number::Rational(BigRational::new(n.into(), d.into()));
+        let big = |s: &str| Number::Rational(BigRational::from_integer(s.parse().unwrap()));
+
+        // 1r3 + 1r6 ←→ 1r2, 1r3 × 3 ←→ 1, 1r2 ÷ 0 is an error, 1|¯7r2 ←→ 1r2
+        assert_eq!(q(1, 3).add(q(1, 6)).unwrap(), q(1, 2));
+        assert_eq!(q(1, 3).multiply(Number::Real(3.0)).unwrap(), q(1, 1));
+        assert!(q(1, 2).divide(Number::Real(0.0)).is_err());
+        assert_eq!(q(0, 1).divide(Number::Real(0.0)).unwrap(), q(1, 1));
+        assert_eq!(Number::Real(1.0).divide(q(3, 1)).unwrap(), q(1, 3));
+        assert_eq!(q(1, 1).residue(q(-7, 2)).unwrap(), q(1, 2));
+
+        // 2n * 100 ←→ 1267650600228229401496703205376
+        assert_eq!(
+            big("2").power(Number::Real(100.0)).unwrap(),
+            big("1267650600228229401496703205376")
+        );
+        assert_eq!(q(2, 3).power(Number::Real(-2.0)).unwrap(), q(9, 4));
+        assert!(matches!(
+            q(4, 1).power(Number::Real(0.5)).unwrap(),
+            Number::Real(_)
+        ));
+
+        // !30n ←→ 265252859812191058636308480000000, 50!100n exactly
+        assert_eq!(
+            big("30").factorial().unwrap(),
+            big("265252859812191058636308480000000")
+        );
+        assert_eq!(
+            Number::Real(50.0).binomial(big("100")).unwrap(),
+            big("100891344545564193334812497256")
+        );
+
+        // Anything not whole leaves the rationals
+        assert_eq!(q(1, 2).add(Number::Real(0.25)).unwrap(), Number::Real(0.75));
+        assert!(q(1, 1).same(&Number::Real(1.0)));
+        assert!(!q(1, 3).same(&q(1, 2)));
+        // Against a fraction that isn't exact they compare as doubles
+        assert!(q(1, 3).same(&Number::Real(1.0 / 3.0)));
+    }
+
+    #[test]
+    fn it_computes_factorials_and_binomials() {
+        assert_eq!(Number::Real(5.0).factorial().unwrap(), Number::Real(120.0));
+        assert!(close(
+            Number::Real(0.5).factorial().unwrap(),
+            Number::Real(std::f64::consts::PI.sqrt() / 2.0)
+        ));
+        assert!(close(
+            Number::Real(-0.5).factorial().unwrap(),
+            Number::Real(std::f64::consts::PI.sqrt())
+        ));
+        assert!(Number::Real(-1.0).factorial().is_err());
+
+        // 2!5 ←→ 10, 0.5!1 ←→ 4÷π, 3!¯3 ←→ ¯10
+        assert_eq!(
+            Number::Real(2.0).binomial(Number::Real(5.0)).unwrap(),
+            Number::Real(10.0)
+        );
+        assert!(close(
+            Number::Real(0.5).binomial(Number::Real(1.0)).unwrap(),
+            Number::Real(4.0 / std::f64::consts::PI)
+        ));
+        assert_eq!(
+            Number::Real(3.0).binomial(Number::Real(-3.0)).unwrap(),
+            Number::Real(-10.0)
+        );
+
+        // !5u8 overflows a u8 at 120 < 255, but !6u8 = 720 doesn't fit
+        let u8 = |n| Number::Int(Int::new(n, IntType::new(false, 8).unwrap()).unwrap());
+        assert_eq!(u8(5).factorial().unwrap(), u8(120));
+        assert!(u8(6).factorial().is_err());
+    }
+
+    #[test]
+    fn it_compares_and_rounds() {
+        let yes = || Number::boolean(true);
+        let no = || Number::boolean(false);
+        let third = || Number::Rational(BigRational::new(1.into(), 3.into()));
+
+        assert_eq!(
+            Number::Real(1.0).equal(Number::boolean(true)).unwrap(),
+            yes()
+        );
+        assert_eq!(Number::Real(2.0).less(Number::Real(3.0)).unwrap(), yes());
+        assert_eq!(
+            Number::Real(2.0)
+                .greater_or_equal(Number::Real(3.0))
+                .unwrap(),
+            no()
+        );
+        // Exactly: 1r3 < 0.3333333333333333 is false in doubles
+        assert_eq!(third().less(Number::Real(1.0)).unwrap(), yes());
+        assert!(Number::complex(0.0, 1.0).less(Number::Real(1.0)).is_err());
+        assert_eq!(
+            Number::complex(0.0, 1.0)
+                .not_equal(Number::Real(1.0))
+                .unwrap(),
+            yes()
+        );
+
+        // 3⌈2.5 ←→ 3, keeping the type of the larger
+        let three = Number::Int(Int::new(3, IntType::new(true, 8).unwrap()).unwrap());
+        assert_eq!(three.clone().maximum(Number::Real(2.5)).unwrap(), three);
+        assert_eq!(three.minimum(Number::Real(2.5)).unwrap(), Number::Real(2.5));
+
+        assert_eq!(Number::Real(-2.5).floor().unwrap(), Number::Real(-3.0));
+        assert_eq!(Number::Real(-2.5).ceiling().unwrap(), Number::Real(-2.0));
+        assert_eq!(
+            third().ceiling().unwrap(),
+            Number::Rational(BigRational::from_integer(1.into()))
+        );
+        assert_eq!(
+            Number::float(1.5, Precision::Half).floor().unwrap(),
+            Number::float(1.0, Precision::Half)
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use std::f64::consts::FRAC_PI_2;
+
+/// A complex number. All functions take the principal branch.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+const I: Complex = Complex { re: 0.0, im: 1.0 };
+const ONE: Complex = Complex { re: 1.0, im: 0.0 };
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    pub fn real(re: f64) -> Self {
+        Complex { re, im: 0.0 }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.re == 0.0 && self.im == 0.0
+    }
+
+    pub fn conj(self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    pub fn neg(self) -> Self {
+        Complex::new(-self.re, -self.im)
+    }
+
+    pub fn add(self, other: Self) -> Self {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+
+    pub fn sub(self, other: Self) -> Self {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+
+    pub fn mul(self, other: Self) -> Self {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+
+    pub fn div(self, other: Self) -> Self {
+        let denominator = other.re * other.re + other.im * other.im;
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator,
+        )
+    }
+
+    pub fn scale(self, factor: f64) -> Self {
+        Complex::new(self.re * factor, self.im * factor)
+    }
+
+    pub fn abs(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn signum(self) -> Self {
+        match self.is_zero() {
+            true => self,
+            false => self.scale(1.0 / self.abs()),
+        }
+    }
+
+    pub fn exp(self) -> Self {
+        let magnitude = self.re.exp();
+        Complex::new(magnitude * self.im.cos(), magnitude * self.im.sin())
+    }
+
+    pub fn ln(self) -> Self {
+        Complex::new(self.abs().ln(), self.arg())
+    }
+
+    // Computed algebraically rather than through the polar form so that the
+    // root of a negative real comes out purely imaginary.
+    pub fn sqrt(self) -> Self {
+        if self.is_zero() {
+            return self;
+        }
+        let t = ((self.re.abs() + self.abs()) / 2.0).sqrt();
+        if self.re >= 0.0 {
+            Complex::new(t, self.im / (2.0 * t))
+        } else {
+            Complex::new(self.im.abs() / (2.0 * t), t.copysign(self.im))
+        }
+    }
+
+    // Integer powers are taken by repeated squaring, so that `0J1*2` is
+    // exactly ¯1 rather than a rounding error off the real axis.
+    pub fn powc(self, exponent: Self) -> Self {
+        match exponent.im == 0.0 && exponent.re.fract() == 0.0 && exponent.re.abs() <= 1e9 {
+            true => self.powi(exponent.re as i64),
+            false => exponent.mul(self.ln()).exp(),
+        }
+    }
+
+    pub fn powi(self, exponent: i64) -> Self {
+        let (mut result, mut base) = (ONE, self);
+        let mut n = exponent.unsigned_abs();
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.mul(base);
+            }
+            base = base.mul(base);
+            n >>= 1;
+        }
+        match exponent < 0 {
+            true => ONE.div(result),
+            false => result,
+        }
+    }
+
+    /// McDonnell's complex floor: the nearest Gaussian integer below and to
+    /// the left of `self` that is within a distance of 1.
+    pub fn floor(self) -> Self {
+        let (re, im) = (self.re.floor(), self.im.floor());
+        let (fraction_re, fraction_im) = (self.re - re, self.im - im);
+
+        if fraction_re + fraction_im < 1.0 {
+            Complex::new(re, im)
+        } else if fraction_re >= fraction_im {
+            Complex::new(re + 1.0, im)
+        } else {
+            Complex::new(re, im + 1.0)
+        }
+    }
+
+    pub fn sin(self) -> Self {
+        Complex::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    pub fn cos(self) -> Self {
+        Complex::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    pub fn tan(self) -> Self {
+        self.sin().div(self.cos())
+    }
+
+    pub fn sinh(self) -> Self {
+        Complex::new(
+            self.re.sinh() * self.im.cos(),
+            self.re.cosh() * self.im.sin(),
+        )
+    }
+
+    pub fn cosh(self) -> Self {
+        Complex::new(
+            self.re.cosh() * self.im.cos(),
+            self.re.sinh() * self.im.sin(),
+        )
+    }
+
+    pub fn tanh(self) -> Self {
+        self.sinh().div(self.cosh())
+    }
+
+    // asin z = -i ln(iz + √(1 - z²))
+    pub fn asin(self) -> Self {
+        I.mul(self)
+            .add(ONE.sub(self.mul(self)).sqrt())
+            .ln()
+            .mul(I.neg())
+    }
+
+    // acos z = π/2 - asin z
+    pub fn acos(self) -> Self {
+        Complex::real(FRAC_PI_2).sub(self.asin())
+    }
+
+    // atan z = i/2 (ln(1 - iz) - ln(1 + iz))
+    pub fn atan(self) -> Self {
+        let iz = I.mul(self);
+        ONE.sub(iz).ln().sub(ONE.add(iz).ln()).mul(I.scale(0.5))
+    }
+
+    // asinh z = ln(z + √(z² + 1))
+    pub fn asinh(self) -> Self {
+        self.add(self.mul(self).add(ONE).sqrt()).ln()
+    }
+
+    // acosh z = ln(z + √(z + 1) √(z - 1))
+    pub fn acosh(self) -> Self {
+        self.add(self.add(ONE).sqrt().mul(self.sub(ONE).sqrt()))
+            .ln()
+    }
+
+    // atanh z = (ln(1 + z) - ln(1 - z)) / 2
+    pub fn atanh(self) -> Self {
+        ONE.add(self).ln().sub(ONE.sub(self).ln()).scale(0.5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Complex, I};
+
+    fn close(a: Complex, b: Complex) -> bool {
+        a.sub(b).abs() < 1e-12
+    }
+
+    #[test]
+    fn it_computes_principal_values() {
+        let z = Complex::new(0.5, 0.3);
+
+        assert!(close(z.sqrt().mul(z.sqrt()), z));
+        assert!(close(z.ln().exp(), z));
+        assert!(close(z.sin().asin(), z));
+        assert!(close(z.cos().acos(), z));
+        assert!(close(z.tan().atan(), z));
+        assert!(close(z.sinh().asinh(), z));
+        assert!(close(z.cosh().acosh(), z));
+        assert!(close(z.tanh().atanh(), z));
+
+        assert!(close(Complex::real(-1.0).sqrt(), Complex::new(0.0, 1.0)));
+    }
+
+    #[test]
+    fn it_takes_integer_powers_exactly() {
+        assert_eq!(I.powc(Complex::real(2.0)), Complex::real(-1.0));
+        assert_eq!(I.powc(Complex::real(3.0)), I.neg());
+        assert_eq!(Complex::new(1.0, 1.0).powi(4), Complex::real(-4.0));
+        assert_eq!(Complex::new(0.0, 2.0).powi(-2), Complex::real(-0.25));
+        assert!(close(
+            Complex::new(0.5, 0.3).powc(Complex::real(2.5)),
+            Complex::real(2.5).mul(Complex::new(0.5, 0.3).ln()).exp()
+        ));
+    }
+
+    #[test]
+    fn it_floors_in_the_complex_plane() {
+        assert_eq!(Complex::new(1.2, 3.4).floor(), Complex::new(1.0, 3.0));
+        assert_eq!(Complex::new(1.7, 3.4).floor(), Complex::new(2.0, 3.0));
+        assert_eq!(Complex::new(1.4, 3.7).floor(), Complex::new(1.0, 4.0));
+        assert_eq!(Complex::new(-0.5, -0.5).floor(), Complex::new(0.0, -1.0));
+    }
+}
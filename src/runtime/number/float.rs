@@ -0,0 +1,190 @@
+use crate::errors::Errors;
+
+/// The precision of a float, from the volume in its literal: `h` (4) is
+/// half, `f` (5) single and `d` (6) double, each the log₂ of the width in
+/// bits.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum Precision {
+    Half,
+    Single,
+    Double,
+}
+
+impl Precision {
+    pub fn from_volume(volume: u8) -> anyhow::Result<Self> {
+        Ok(match volume {
+            4 => Precision::Half,
+            5 => Precision::Single,
+            6 => Precision::Double,
+            _ => anyhow::bail!(Errors::Domain(format!(
+                "floats are 16, 32 or 64 bits, got f{}",
+                volume
+            ))),
+        })
+    }
+
+    pub fn volume(self) -> u8 {
+        match self {
+            Precision::Half => 4,
+            Precision::Single => 5,
+            Precision::Double => 6,
+        }
+    }
+
+    /// Rounds `n` to the nearest value this precision can hold, ties to
+    /// even.
+    pub fn round(self, n: f64) -> f64 {
+        match self {
+            Precision::Half => F16::from_f64(n).to_f64(),
+            Precision::Single => n as f32 as f64,
+            Precision::Double => n,
+        }
+    }
+
+    /// The narrowest precision that holds every value of both.
+    pub fn common(self, other: Precision) -> Precision {
+        self.max(other)
+    }
+}
+
+/// A half precision float, IEEE 754 binary16. Converted in software, there
+/// is no `f16` on stable Rust.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct F16(u16);
+
+// Shifts `bits` right by `shift`, rounding to nearest, ties to even.
+fn shift_rounding(bits: u64, shift: u32) -> u64 {
+    let (quotient, remainder) = (bits >> shift, bits & ((1 << shift) - 1));
+    let half = 1 << (shift - 1);
+    if remainder > half || (remainder == half && quotient & 1 == 1) {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
+
+impl F16 {
+    pub fn from_bits(bits: u16) -> Self {
+        F16(bits)
+    }
+
+    pub fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    // Rounds straight from the f64, going through f32 first would round
+    // twice.
+    pub fn from_f64(n: f64) -> Self {
+        let bits = n.to_bits();
+        let sign = ((bits >> 48) & 0x8000) as u16;
+        let exponent = ((bits >> 52) & 0x7ff) as i32;
+        let mantissa = bits & 0xf_ffff_ffff_ffff;
+
+        if exponent == 0x7ff {
+            let nan = if mantissa != 0 { 0x200 } else { 0 };
+            return F16(sign | 0x7c00 | nan);
+        }
+
+        // The exponent rebiased for a half. At 1 and above the result is
+        // normal, and a mantissa rounding up carries into the exponent, up
+        // to infinity at 0x7c00 if need be.
+        let exponent = exponent - 1023 + 15;
+        if exponent >= 0x1f {
+            return F16(sign | 0x7c00);
+        }
+        let magnitude = if exponent >= 1 {
+            shift_rounding(((exponent as u64) << 52) | mantissa, 42)
+        } else {
+            // Subnormal: a count of 2⁻²⁴s, with the implicit bit made
+            // explicit.
+            let shift = (43 - exponent) as u32;
+            match shift {
+                0..=63 => shift_rounding(mantissa | (1 << 52), shift),
+                _ => 0,
+            }
+        };
+
+        F16(sign | magnitude.min(0x7c00) as u16)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        let exponent = ((self.0 >> 10) & 0x1f) as i32;
+        let mantissa = (self.0 & 0x3ff) as f64;
+
+        let magnitude = match exponent {
+            0 => mantissa * 2f64.powi(-24),
+            0x1f if mantissa == 0.0 => f64::INFINITY,
+            0x1f => f64::NAN,
+            _ => (1024.0 + mantissa) * 2f64.powi(exponent - 25),
+        };
+
+        match self.0 & 0x8000 {
+            0 => magnitude,
+            _ => -magnitude,
+        }
+    }
+}
+
+/// A float narrower than a double. Doubles are `Number::Real`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Float {
+    Half(F16),
+    Single(f32),
+}
+
+impl Float {
+    pub fn precision(self) -> Precision {
+        match self {
+            Float::Half(_) => Precision::Half,
+            Float::Single(_) => Precision::Single,
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Float::Half(n) => n.to_f64(),
+            Float::Single(n) => n as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Precision, F16};
+
+    #[test]
+    fn it_converts_halves() {
+        for (n, bits) in [
+            (1.0, 0x3c00),
+            (-2.0, 0xc000),
+            (0.5, 0x3800),
+            (65504.0, 0x7bff),
+            (6.103515625e-5, 0x0400),
+            (5.960464477539063e-8, 0x0001),
+            (0.0, 0x0000),
+            (f64::INFINITY, 0x7c00),
+        ] {
+            assert_eq!(F16::from_f64(n).to_bits(), bits, "{}", n);
+            assert_eq!(F16::from_bits(bits).to_f64(), n);
+        }
+        assert!(F16::from_f64(f64::NAN).to_f64().is_nan());
+    }
+
+    #[test]
+    fn it_rounds_to_nearest_even() {
+        let half = |n| Precision::Half.round(n);
+
+        // Halves have 11 significant bits, so above 2048 only evens remain
+        assert_eq!(half(2049.0), 2048.0);
+        assert_eq!(half(2051.0), 2052.0);
+        assert_eq!(half(2050.5), 2050.0);
+        assert_eq!(half(0.1), 0.0999755859375);
+        assert_eq!(half(65519.0), 65504.0);
+        assert_eq!(half(65520.0), f64::INFINITY);
+        assert_eq!(half(1e-8), 0.0);
+        assert_eq!(half(-1e-8), 0.0);
+
+        assert_eq!(Precision::Single.round(0.1), 0.1f32 as f64);
+        assert_eq!(Precision::Double.round(0.1), 0.1);
+    }
+}
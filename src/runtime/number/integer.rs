@@ -0,0 +1,474 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::errors::Errors;
+
+/// What an integer operation does with a result that doesn't fit its type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Overflow {
+    /// Keeps the low bits, two's complement style.
+    Wrapping,
+    /// Clamps to the nearest representable value.
+    Saturating,
+    /// Raises a DOMAIN ERROR.
+    Checked,
+}
+
+impl Overflow {
+    pub const MODES: [Overflow; 3] = [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating];
+
+    /// The name `⎕OVERFLOW` gives and takes for the mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            Overflow::Wrapping => "wrapping",
+            Overflow::Saturating => "saturating",
+            Overflow::Checked => "checked",
+        }
+    }
+}
+
+thread_local! {
+    // The modes of the session running in this thread. Widths without an
+    // entry are checked.
+    static OVERFLOW: RefCell<HashMap<IntType, Overflow>> = RefCell::new(HashMap::new());
+}
+
+/// Sets how operations producing `ty` handle overflow.
+pub fn set_overflow(ty: IntType, mode: Overflow) {
+    OVERFLOW.with(|modes| modes.borrow_mut().insert(ty, mode));
+}
+
+/// The modes set now, for a session to keep between runs.
+pub fn overflows() -> HashMap<IntType, Overflow> {
+    OVERFLOW.with(|modes| modes.borrow().clone())
+}
+
+/// Puts a session's modes in place, for the operations until the next.
+pub fn set_overflows(modes: HashMap<IntType, Overflow>) {
+    OVERFLOW.with(|current| *current.borrow_mut() = modes);
+}
+
+/// A signed or unsigned integer type of 1 to 64 bits, as written in a
+/// literal's volume (`i4`, `u8`).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct IntType {
+    signed: bool,
+    bits: u8,
+}
+
+impl IntType {
+    pub const I8: IntType = IntType {
+        signed: true,
+        bits: 8,
+    };
+    pub const I16: IntType = IntType {
+        signed: true,
+        bits: 16,
+    };
+    pub const I32: IntType = IntType {
+        signed: true,
+        bits: 32,
+    };
+    pub const I64: IntType = IntType {
+        signed: true,
+        bits: 64,
+    };
+    pub const U8: IntType = IntType {
+        signed: false,
+        bits: 8,
+    };
+    pub const U16: IntType = IntType {
+        signed: false,
+        bits: 16,
+    };
+    pub const U32: IntType = IntType {
+        signed: false,
+        bits: 32,
+    };
+    pub const U64: IntType = IntType {
+        signed: false,
+        bits: 64,
+    };
+    /// Booleans, as written with the `b` suffix.
+    pub const U1: IntType = IntType {
+        signed: false,
+        bits: 1,
+    };
+
+    pub fn new(signed: bool, bits: u8) -> anyhow::Result<Self> {
+        if !(1..=64).contains(&bits) {
+            anyhow::bail!(Errors::Domain(format!(
+                "integer widths run from 1 to 64, got {}",
+                bits
+            )));
+        }
+        Ok(IntType { signed, bits })
+    }
+
+    pub fn is_signed(self) -> bool {
+        self.signed
+    }
+
+    pub fn bits(self) -> u8 {
+        self.bits
+    }
+
+    pub fn min(self) -> i128 {
+        match self.signed {
+            true => -(1 << (self.bits - 1)),
+            false => 0,
+        }
+    }
+
+    pub fn max(self) -> i128 {
+        match self.signed {
+            true => (1 << (self.bits - 1)) - 1,
+            false => (1 << self.bits) - 1,
+        }
+    }
+
+    pub fn contains(self, value: i128) -> bool {
+        self.min() <= value && value <= self.max()
+    }
+
+    pub fn overflow(self) -> Overflow {
+        OVERFLOW.with(|modes| {
+            modes
+                .borrow()
+                .get(&self)
+                .copied()
+                .unwrap_or(Overflow::Checked)
+        })
+    }
+
+    /// The type a dyadic function between `self` and `other` computes in:
+    /// the narrowest one that holds every value of both. Signed wins over
+    /// unsigned, taking one more bit than the unsigned width (up to 64) so
+    /// that its largest value still fits. Booleans meeting booleans count
+    /// as `i64`s, so that `1b+1b` is `2` rather than an overflow.
+    pub fn promote(self, other: IntType) -> IntType {
+        match (self.signed, other.signed) {
+            _ if self == IntType::U1 && other == IntType::U1 => IntType::I64,
+            (true, true) | (false, false) => IntType {
+                signed: self.signed,
+                bits: self.bits.max(other.bits),
+            },
+            (true, false) => IntType {
+                signed: true,
+                bits: self.bits.max(other.bits + 1).min(64),
+            },
+            (false, true) => other.promote(self),
+        }
+    }
+
+    // Keeps the low `bits` bits of `value`, sign extending if signed.
+    fn wrap(self, value: i128) -> i128 {
+        let low = value as u128 & ((1u128 << self.bits) - 1);
+        if self.signed && low >> (self.bits - 1) == 1 {
+            low as i128 - (1i128 << self.bits)
+        } else {
+            low as i128
+        }
+    }
+
+    fn fit(self, wide: Wide) -> anyhow::Result<Int> {
+        let (wrapped, negative) = match wide {
+            Wide::Exact(value) if self.contains(value) => return Ok(Int { value, ty: self }),
+            Wide::Exact(value) => (value, value < 0),
+            Wide::Overflowed { wrapped, negative } => (wrapped, negative),
+        };
+
+        let value = match self.overflow() {
+            Overflow::Wrapping => self.wrap(wrapped),
+            Overflow::Saturating if negative => self.min(),
+            Overflow::Saturating => self.max(),
+            Overflow::Checked => anyhow::bail!(Errors::Domain(format!(
+                "the result does not fit in {}",
+                self
+            ))),
+        };
+        Ok(Int { value, ty: self })
+    }
+}
+
+impl std::fmt::Display for IntType {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let signature = if self.signed { 'i' } else { 'u' };
+        write!(formatter, "{}{}", signature, self.bits)
+    }
+}
+
+// The mathematical result of an integer operation. Operands are at most 64
+// bits wide, so only products and powers can leave an i128, and then just
+// the low bits and the sign are kept.
+#[derive(Clone, Copy)]
+enum Wide {
+    Exact(i128),
+    Overflowed { wrapped: i128, negative: bool },
+}
+
+fn multiply(a: i128, b: i128) -> Wide {
+    match a.checked_mul(b) {
+        Some(product) => Wide::Exact(product),
+        None => Wide::Overflowed {
+            wrapped: a.wrapping_mul(b),
+            negative: (a < 0) != (b < 0),
+        },
+    }
+}
+
+/// An integer of a fixed-width type. Always in range for its type.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Int {
+    value: i128,
+    ty: IntType,
+}
+
+impl Int {
+    /// Fits `value` into `ty` under that type's overflow mode.
+    pub fn new(value: i128, ty: IntType) -> anyhow::Result<Self> {
+        ty.fit(Wide::Exact(value))
+    }
+
+    /// Fits an integer of any size into `ty`, like `new`.
+    pub fn from_big(value: &BigInt, ty: IntType) -> anyhow::Result<Self> {
+        match value.to_i128() {
+            Some(value) => ty.fit(Wide::Exact(value)),
+            None => {
+                // The low 128 bits, two's complement
+                let low = value
+                    .magnitude()
+                    .iter_u64_digits()
+                    .take(2)
+                    .enumerate()
+                    .fold(0u128, |low, (i, digit)| low | (digit as u128) << (64 * i));
+                let wrapped = match value.is_negative() {
+                    true => low.wrapping_neg() as i128,
+                    false => low as i128,
+                };
+                ty.fit(Wide::Overflowed {
+                    wrapped,
+                    negative: value.is_negative(),
+                })
+            }
+        }
+    }
+
+    pub fn value(self) -> i128 {
+        self.value
+    }
+
+    pub fn ty(self) -> IntType {
+        self.ty
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.value as f64
+    }
+
+    /// The zero of this type.
+    pub fn zero(self) -> Self {
+        Int {
+            value: 0,
+            ty: self.ty,
+        }
+    }
+
+    pub fn negate(self) -> anyhow::Result<Self> {
+        self.ty.fit(Wide::Exact(-self.value))
+    }
+
+    pub fn magnitude(self) -> anyhow::Result<Self> {
+        self.ty.fit(Wide::Exact(self.value.abs()))
+    }
+
+    pub fn signum(self) -> anyhow::Result<Self> {
+        self.ty.fit(Wide::Exact(self.value.signum()))
+    }
+
+    pub fn add(self, other: Self) -> anyhow::Result<Self> {
+        let ty = self.ty.promote(other.ty);
+        ty.fit(Wide::Exact(self.value + other.value))
+    }
+
+    pub fn subtract(self, other: Self) -> anyhow::Result<Self> {
+        let ty = self.ty.promote(other.ty);
+        ty.fit(Wide::Exact(self.value - other.value))
+    }
+
+    pub fn multiply(self, other: Self) -> anyhow::Result<Self> {
+        let ty = self.ty.promote(other.ty);
+        ty.fit(multiply(self.value, other.value))
+    }
+
+    /// `other` modulo `self`, taking the sign of `self`.
+    pub fn residue(self, other: Self) -> anyhow::Result<Self> {
+        let ty = self.ty.promote(other.ty);
+        if self.value == 0 {
+            return ty.fit(Wide::Exact(other.value));
+        }
+        let remainder = other.value.rem_euclid(self.value);
+        ty.fit(Wide::Exact(match self.value < 0 && remainder != 0 {
+            true => remainder + self.value,
+            false => remainder,
+        }))
+    }
+
+    /// `self` raised to `exponent`, which must not be negative.
+    pub fn power(self, exponent: Self) -> anyhow::Result<Self> {
+        let ty = self.ty.promote(exponent.ty);
+        let negative = self.value < 0 && exponent.value % 2 == 1;
+
+        let (mut base, mut remaining) = (Wide::Exact(self.value), exponent.value);
+        let mut result = Wide::Exact(1);
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                result = multiply_wide(result, base);
+            }
+            remaining /= 2;
+            if remaining > 0 {
+                base = multiply_wide(base, base);
+            }
+        }
+
+        ty.fit(match result {
+            Wide::Exact(value) => Wide::Exact(value),
+            Wide::Overflowed { wrapped, .. } => Wide::Overflowed { wrapped, negative },
+        })
+    }
+}
+
+// Multiplies two possibly overflowed values. Once overflowed the sign is
+// worked out by the caller, so only the low bits are tracked.
+fn multiply_wide(a: Wide, b: Wide) -> Wide {
+    match (a, b) {
+        (Wide::Exact(a), Wide::Exact(b)) => multiply(a, b),
+        (Wide::Exact(a), Wide::Overflowed { wrapped: b, .. })
+        | (Wide::Overflowed { wrapped: a, .. }, Wide::Exact(b))
+        | (Wide::Overflowed { wrapped: a, .. }, Wide::Overflowed { wrapped: b, .. }) => {
+            Wide::Overflowed {
+                wrapped: a.wrapping_mul(b),
+                negative: false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{set_overflow, Int, IntType, Overflow};
+
+    fn int(value: i128, signed: bool, bits: u8) -> Int {
+        Int::new(value, IntType::new(signed, bits).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn it_knows_the_range_of_a_width() {
+        let u4 = IntType::new(false, 4).unwrap();
+        let i4 = IntType::new(true, 4).unwrap();
+
+        assert_eq!((u4.min(), u4.max()), (0, 15));
+        assert_eq!((i4.min(), i4.max()), (-8, 7));
+        assert_eq!(
+            (IntType::U64.max(), IntType::I64.min()),
+            (u64::MAX as i128, i64::MIN as i128)
+        );
+        assert!(IntType::new(false, 0).is_err());
+        assert!(IntType::new(false, 65).is_err());
+    }
+
+    #[test]
+    fn it_promotes_mixed_widths() {
+        let ty = |signed, bits| IntType::new(signed, bits).unwrap();
+
+        assert_eq!(ty(false, 4).promote(ty(false, 8)), ty(false, 8));
+        assert_eq!(ty(true, 4).promote(ty(true, 2)), ty(true, 4));
+        assert_eq!(ty(false, 8).promote(ty(true, 4)), ty(true, 9));
+        assert_eq!(ty(true, 16).promote(ty(false, 8)), ty(true, 16));
+        assert_eq!(IntType::U64.promote(IntType::I64), IntType::I64);
+        assert_eq!(IntType::U1.promote(IntType::U1), IntType::I64);
+        assert_eq!(IntType::U1.promote(ty(false, 8)), ty(false, 8));
+
+        // 15u4 + ¯1i4 ←→ 14i5
+        assert_eq!(
+            int(15, false, 4).add(int(-1, true, 4)).unwrap(),
+            int(14, true, 5)
+        );
+    }
+
+    #[test]
+    fn it_handles_overflow_per_width() {
+        let i3 = IntType::new(true, 3).unwrap();
+        let u5 = IntType::new(false, 5).unwrap();
+        set_overflow(i3, Overflow::Wrapping);
+        set_overflow(u5, Overflow::Saturating);
+
+        // 3i3 + 1i3 wraps to ¯4, 3i3 × 3i3 to 1
+        assert_eq!(int(3, true, 3).add(int(1, true, 3)).unwrap().value(), -4);
+        assert_eq!(
+            int(3, true, 3).multiply(int(3, true, 3)).unwrap().value(),
+            1
+        );
+        assert_eq!(int(-4, true, 3).negate().unwrap().value(), -4);
+
+        assert_eq!(
+            int(30, false, 5).add(int(30, false, 5)).unwrap().value(),
+            31
+        );
+        assert_eq!(
+            int(3, false, 5).subtract(int(4, false, 5)).unwrap().value(),
+            0
+        );
+        assert_eq!(
+            int(2, false, 5).power(int(10, false, 5)).unwrap().value(),
+            31
+        );
+
+        let u6 = |n| int(n, false, 6);
+        assert!(u6(60).add(u6(4)).is_err());
+        assert!(u6(1).negate().is_err());
+        assert_eq!(u6(7).multiply(u6(9)).unwrap().value(), 63);
+    }
+
+    #[test]
+    fn it_keeps_the_low_bits_of_huge_results() {
+        set_overflow(IntType::U64, Overflow::Wrapping);
+        let max = Int::new(IntType::U64.max(), IntType::U64).unwrap();
+
+        // (2⁶⁴ - 1)² = 2¹²⁸ - 2⁶⁵ + 1, past even an i128
+        assert_eq!(max.multiply(max).unwrap().value(), 1);
+
+        let three = Int::new(3, IntType::U64).unwrap();
+        let hundred = Int::new(100, IntType::U64).unwrap();
+        assert_eq!(
+            three.power(hundred).unwrap().value(),
+            3u128.wrapping_pow(100) as u64 as i128
+        );
+    }
+
+    #[test]
+    fn it_fits_big_integers() {
+        let i16 = IntType::new(true, 16).unwrap();
+        set_overflow(i16, Overflow::Wrapping);
+        let big = |s: &str| s.parse::<num_bigint::BigInt>().unwrap();
+
+        // 2¹²⁸ + 5 keeps its low bits, 5
+        let huge = big("340282366920938463463374607431768211461");
+        assert_eq!(Int::from_big(&huge, i16).unwrap().value(), 5);
+        assert_eq!(Int::from_big(&-huge, i16).unwrap().value(), -5);
+        assert_eq!(Int::from_big(&big("-7"), i16).unwrap().value(), -7);
+        assert!(Int::from_big(&big("300"), IntType::new(false, 8).unwrap()).is_err());
+    }
+
+    #[test]
+    fn it_takes_residues_with_the_sign_of_the_divisor() {
+        let i8 = |n| int(n, true, 8);
+
+        assert_eq!(i8(3).residue(i8(-7)).unwrap().value(), 2);
+        assert_eq!(i8(-3).residue(i8(7)).unwrap().value(), -2);
+        assert_eq!(i8(0).residue(i8(7)).unwrap().value(), 7);
+    }
+}
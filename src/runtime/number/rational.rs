@@ -0,0 +1,116 @@
+// Exact arithmetic on big integers and rationals, for the `n` and `r`
+// literals. The rationals themselves are `num_rational::BigRational`; this
+// is what they lack.
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+
+use crate::errors::Errors;
+
+/// The nearest double, infinite if out of range.
+pub fn to_f64(q: &BigRational) -> f64 {
+    match q.to_f64() {
+        Some(n) => n,
+        None if q.is_negative() => f64::NEG_INFINITY,
+        None => f64::INFINITY,
+    }
+}
+
+/// `n` exactly, if it is a whole number.
+pub fn from_integral(n: f64) -> Option<BigRational> {
+    match n.fract() == 0.0 {
+        true => BigInt::from_f64(n).map(BigRational::from_integer),
+        false => None,
+    }
+}
+
+// Loop bounds have to be machine integers; anything bigger would not finish.
+fn count(n: &BigInt) -> anyhow::Result<u64> {
+    match n.to_u64() {
+        Some(n) => Ok(n),
+        None => anyhow::bail!(Errors::Domain(format!("{} is too large", n))),
+    }
+}
+
+/// `!n`, for `n` not negative.
+pub fn factorial(n: &BigInt) -> anyhow::Result<BigInt> {
+    if n.is_negative() {
+        anyhow::bail!(Errors::Domain("! of a negative integer".to_string()));
+    }
+    Ok((2..=count(n)?).fold(BigInt::one(), |product, i| product * i))
+}
+
+// The number of ways to choose `k` of `n`, for `0 ≤ k ≤ n`.
+fn choose(k: &BigInt, n: &BigInt) -> anyhow::Result<BigInt> {
+    let k = count(k.min(&(n - k)))?;
+    // Each partial product is itself a binomial, so the division is exact
+    Ok((0..k).fold(BigInt::one(), |product, i| product * (n - i) / (i + 1)))
+}
+
+/// `k!n`, extended to negative integers as the limit of
+/// `Γ(n+1) ÷ Γ(k+1) × Γ(n-k+1)`.
+pub fn binomial(k: &BigInt, n: &BigInt) -> anyhow::Result<BigInt> {
+    let sign = |exponent: &BigInt| match exponent.is_odd() {
+        true => -BigInt::one(),
+        false => BigInt::one(),
+    };
+    let (zero, one) = (BigInt::zero(), BigInt::one());
+
+    Ok(match (n.is_negative(), k.is_negative()) {
+        (false, _) if k.is_negative() || k > n => zero,
+        (false, _) => choose(k, n)?,
+        (true, false) => sign(k) * choose(k, &(k - n - &one))?,
+        (true, true) if k > n => zero,
+        (true, true) => sign(&(n - k)) * choose(&(n - k), &(-k - &one))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{binomial, factorial, to_f64};
+    use num_bigint::BigInt;
+    use num_rational::BigRational;
+
+    fn big(n: i64) -> BigInt {
+        BigInt::from(n)
+    }
+
+    #[test]
+    fn it_computes_factorials_exactly() {
+        assert_eq!(factorial(&big(0)).unwrap(), big(1));
+        assert_eq!(
+            factorial(&big(25)).unwrap().to_string(),
+            "15511210043330985984000000"
+        );
+        assert!(factorial(&big(-1)).is_err());
+    }
+
+    #[test]
+    fn it_computes_binomials_everywhere() {
+        // 2!5 ←→ 10, 6!5 ←→ 0, ¯1!5 ←→ 0
+        assert_eq!(binomial(&big(2), &big(5)).unwrap(), big(10));
+        assert_eq!(binomial(&big(6), &big(5)).unwrap(), big(0));
+        assert_eq!(binomial(&big(-1), &big(5)).unwrap(), big(0));
+        // 2!¯3 ←→ 6, 3!¯3 ←→ ¯10
+        assert_eq!(binomial(&big(2), &big(-3)).unwrap(), big(6));
+        assert_eq!(binomial(&big(3), &big(-3)).unwrap(), big(-10));
+        // ¯3!¯2 ←→ ¯2, ¯3!¯5 ←→ 0
+        assert_eq!(binomial(&big(-3), &big(-2)).unwrap(), big(-2));
+        assert_eq!(binomial(&big(-3), &big(-5)).unwrap(), big(0));
+        assert_eq!(
+            binomial(&big(50), &big(100)).unwrap().to_string(),
+            "100891344545564193334812497256"
+        );
+    }
+
+    #[test]
+    fn it_converts_to_doubles() {
+        let third = BigRational::new(big(1), big(3));
+        assert_eq!(to_f64(&third), 1.0 / 3.0);
+        let huge = BigRational::from_integer(BigInt::from(10).pow(400));
+        assert_eq!(to_f64(&huge), f64::INFINITY);
+        assert_eq!(to_f64(&-huge), f64::NEG_INFINITY);
+    }
+}
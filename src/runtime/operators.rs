@@ -0,0 +1,785 @@
+// The primitive operators. Operands are passed as plain closures over
+// arrays, so the same code serves primitive and user-defined functions.
+
+use std::collections::HashMap;
+
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::primitives::format::format_number;
+use crate::runtime::primitives::search::{from_major_cells, major_cells, Key};
+use crate::runtime::primitives::structural::{indices, mix};
+
+pub type Monadic<'a> = &'a dyn Fn(Array) -> anyhow::Result<Array>;
+pub type Dyadic<'a> = &'a dyn Fn(Array, Array) -> anyhow::Result<Array>;
+
+/// Monadic `f¨`: applies `f` to every disclosed element of `right` and
+/// encloses the results. On an empty array `f` is applied to the prototype
+/// to find the prototype of the result.
+pub fn each(function: Monadic, right: Array) -> anyhow::Result<Array> {
+    let shape = right.shape().to_vec();
+    let prototype = right.prototype();
+
+    let data = right
+        .into_data()
+        .into_iter()
+        .map(|scalar| function(scalar.disclose()).map(Array::enclose))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(with_each_prototype(Array::new(shape, data), || {
+        function(prototype.disclose())
+    }))
+}
+
+/// Dyadic `f¨`: applies `f` between corresponding disclosed elements of
+/// `left` and `right`, extending a scalar on either side.
+pub fn each_dyadic(function: Dyadic, left: Array, right: Array) -> anyhow::Result<Array> {
+    let shape = match (left.is_scalar(), right.is_scalar()) {
+        (true, _) => right.shape().to_vec(),
+        (false, true) => left.shape().to_vec(),
+        (false, false) if left.rank() != right.rank() => {
+            anyhow::bail!(Errors::Rank(
+                "¨ expects arguments of the same rank".to_string()
+            ))
+        }
+        (false, false) if left.shape() != right.shape() => {
+            anyhow::bail!(Errors::Length(
+                "¨ expects arguments of the same shape".to_string()
+            ))
+        }
+        (false, false) => left.shape().to_vec(),
+    };
+    let count: usize = shape.iter().product();
+    let (left_prototype, right_prototype) = (left.prototype(), right.prototype());
+
+    let extend = |array: Array| -> Box<dyn Iterator<Item = Scalar>> {
+        match array.is_scalar() {
+            true => Box::new(std::iter::repeat_n(array.into_data().pop().unwrap(), count)),
+            false => Box::new(array.into_data().into_iter()),
+        }
+    };
+
+    let data = extend(left)
+        .zip(extend(right))
+        .map(|(left, right)| function(left.disclose(), right.disclose()).map(Array::enclose))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(with_each_prototype(Array::new(shape, data), || {
+        function(left_prototype.disclose(), right_prototype.disclose())
+    }))
+}
+
+/// Monadic `f/`: reduces each row along the last axis of `right`, folding
+/// `f` in from the right. A scalar reduces to itself, and an empty row to
+/// `identity`, the identity of `f`; without one it is a DOMAIN ERROR.
+pub fn reduce(function: Dyadic, right: Array, identity: Option<Scalar>) -> anyhow::Result<Array> {
+    match right.rank() {
+        0 => Ok(right),
+        rank => reduce_axis(function, right, identity, rank - 1),
+    }
+}
+
+/// Monadic `f⌿`: reduces along the first axis of `right` as `f/` does
+/// along the last.
+pub fn reduce_first(
+    function: Dyadic,
+    right: Array,
+    identity: Option<Scalar>,
+) -> anyhow::Result<Array> {
+    match right.rank() {
+        0 => Ok(right),
+        _ => reduce_axis(function, right, identity, 0),
+    }
+}
+
+fn reduce_axis(
+    function: Dyadic,
+    right: Array,
+    identity: Option<Scalar>,
+    axis: usize,
+) -> anyhow::Result<Array> {
+    let length = right.shape()[axis];
+    let stride: usize = right.shape()[axis + 1..].iter().product();
+    let mut frame = right.shape().to_vec();
+    frame.remove(axis);
+    let count = frame.iter().product();
+
+    if length == 0 {
+        return match (count, identity) {
+            (0, _) => Ok(Array::new(frame, vec![])),
+            (count, Some(identity)) => Ok(Array::new(frame, vec![identity; count])),
+            (_, None) => {
+                anyhow::bail!(Errors::Domain(
+                    "this reduction of an empty row has no identity".to_string()
+                ))
+            }
+        };
+    }
+
+    let data = right.data();
+    let data = (0..count)
+        .map(|cell| {
+            let (outer, inner) = (cell / stride, cell % stride);
+            let item = |i: usize| {
+                data[(outer * length + i) * stride + inner]
+                    .clone()
+                    .disclose()
+            };
+            (0..length - 1)
+                .rev()
+                .try_fold(item(length - 1), |acc, i| function(item(i), acc))
+                .map(Array::enclose)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Array::new(frame, data))
+}
+
+/// Dyadic `∘.f`: outer product, `f` between every element of `left` and
+/// every element of `right`, in a result of both their shapes.
+pub fn outer_product(function: Dyadic, left: Array, right: Array) -> anyhow::Result<Array> {
+    let mut shape = left.shape().to_vec();
+    shape.extend_from_slice(right.shape());
+    let (left_prototype, right_prototype) = (left.prototype(), right.prototype());
+
+    let mut data = Vec::with_capacity(left.data().len() * right.data().len());
+    for a in left.data() {
+        for b in right.data() {
+            data.push(function(a.clone().disclose(), b.clone().disclose())?.enclose());
+        }
+    }
+
+    Ok(with_each_prototype(Array::new(shape, data), || {
+        function(left_prototype.disclose(), right_prototype.disclose())
+    }))
+}
+
+/// Dyadic `f.g`: inner product. Every row along the last axis of `left` is
+/// paired with every column along the first axis of `right`, `g` applied
+/// between them with `¨` and the result reduced. The reduction `f/` is
+/// passed in whole so that `+/` can bring its identity for empty rows.
+pub fn inner_product(
+    reduce: Monadic,
+    function: Dyadic,
+    left: Array,
+    right: Array,
+) -> anyhow::Result<Array> {
+    let left_length = left.shape().last().copied();
+    let right_length = right.shape().first().copied();
+    let length = match (left_length, right_length) {
+        (None, None) => 1,
+        (Some(length), None) | (None, Some(length)) => length,
+        (Some(a), Some(b)) if a == b => a,
+        (Some(a), Some(b)) => anyhow::bail!(Errors::Length(format!(
+            ". expects rows of {} to meet columns of {}",
+            a, b
+        ))),
+    };
+    let extend = |array: Array| match array.is_scalar() {
+        true => Array::new(vec![length], vec![array.data()[0].clone(); length]),
+        false => array,
+    };
+    let (left, right) = (extend(left), extend(right));
+
+    let rows: usize = left.shape()[..left.rank() - 1].iter().product();
+    let columns: usize = right.shape()[1..].iter().product();
+    let mut data = Vec::with_capacity(rows * columns);
+    for row in 0..rows {
+        let row = Array::vector(left.data()[row * length..(row + 1) * length].to_vec())
+            .with_prototype(left.prototype());
+        for column in 0..columns {
+            let column = (0..length)
+                .map(|i| right.data()[i * columns + column].clone())
+                .collect();
+            let column = Array::vector(column).with_prototype(right.prototype());
+            data.push(reduce(each_dyadic(function, row.clone(), column)?)?.enclose());
+        }
+    }
+
+    let mut shape = left.shape()[..left.rank() - 1].to_vec();
+    shape.extend_from_slice(&right.shape()[1..]);
+    Ok(Array::new(shape, data))
+}
+
+// Splits `array` into its cells of rank `rank`, or for a negative `rank`
+// of that many axes fewer than the array has, along with the frame they
+// are laid out in.
+fn cells(array: &Array, rank: i64) -> (Vec<usize>, Vec<Array>) {
+    let full = array.rank() as i64;
+    let rank = match rank < 0 {
+        true => (full + rank).max(0),
+        false => rank.min(full),
+    } as usize;
+    let (frame, shape) = array.shape().split_at(array.rank() - rank);
+
+    let size: usize = shape.iter().product();
+    let count: usize = frame.iter().product();
+    let prototype = array.prototype();
+    let cells = (0..count)
+        .map(|i| {
+            Array::new(
+                shape.to_vec(),
+                array.data()[i * size..(i + 1) * size].to_vec(),
+            )
+            .with_prototype(prototype.clone())
+        })
+        .collect();
+    (frame.to_vec(), cells)
+}
+
+// Lays the results of applying a function to cells out in their frame,
+// padding them to a common shape as `↑` does.
+fn assemble(frame: Vec<usize>, results: Vec<Array>) -> Array {
+    mix(Array::new(
+        frame,
+        results.into_iter().map(Array::enclose).collect(),
+    ))
+}
+
+/// Reads the right operand of `⍤` as the ranks for the monadic case and
+/// for the left and right arguments: `c` is all three, `b c` gives `c b c`.
+pub fn rank_spec(spec: &Array) -> anyhow::Result<[i64; 3]> {
+    if spec.rank() > 1 {
+        anyhow::bail!(Errors::Rank("⍤ expects a vector of ranks".to_string()));
+    }
+    let ranks = spec
+        .data()
+        .iter()
+        .map(|scalar| {
+            let n = scalar.as_real()?;
+            if n.fract() != 0.0 {
+                anyhow::bail!(Errors::Domain(format!(
+                    "⍤ expects integer ranks, got {}",
+                    format_number(n)
+                )));
+            }
+            Ok(n as i64)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    match ranks.as_slice() {
+        [c] => Ok([*c, *c, *c]),
+        [b, c] => Ok([*c, *b, *c]),
+        [a, b, c] => Ok([*a, *b, *c]),
+        _ => anyhow::bail!(Errors::Length("⍤ expects one to three ranks".to_string())),
+    }
+}
+
+/// Monadic `f⍤k`: applies `f` to each cell of rank `k`.
+pub fn rank(function: Monadic, rank: i64, right: Array) -> anyhow::Result<Array> {
+    let (frame, cells) = cells(&right, rank);
+    let results = cells
+        .into_iter()
+        .map(function)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(assemble(frame, results))
+}
+
+/// Dyadic `f⍤k`: applies `f` between corresponding cells of `left` and
+/// `right`. The frames must agree, unless one of them is empty, in which
+/// case its single cell pairs with every cell of the other.
+pub fn rank_dyadic(
+    function: Dyadic,
+    left_rank: i64,
+    right_rank: i64,
+    left: Array,
+    right: Array,
+) -> anyhow::Result<Array> {
+    let (left_frame, left_cells) = cells(&left, left_rank);
+    let (right_frame, right_cells) = cells(&right, right_rank);
+
+    let (frame, pairs): (_, Vec<_>) = if left_frame.is_empty() {
+        let cell = &left_cells[0];
+        (
+            right_frame,
+            right_cells
+                .into_iter()
+                .map(|right| (cell.clone(), right))
+                .collect(),
+        )
+    } else if right_frame.is_empty() {
+        let cell = &right_cells[0];
+        (
+            left_frame,
+            left_cells
+                .into_iter()
+                .map(|left| (left, cell.clone()))
+                .collect(),
+        )
+    } else if left_frame.len() != right_frame.len() {
+        anyhow::bail!(Errors::Rank(
+            "⍤ expects frames of the same rank".to_string()
+        ));
+    } else if left_frame != right_frame {
+        anyhow::bail!(Errors::Length(
+            "⍤ expects frames of the same shape".to_string()
+        ));
+    } else {
+        (
+            left_frame,
+            left_cells.into_iter().zip(right_cells).collect(),
+        )
+    };
+
+    let results = pairs
+        .into_iter()
+        .map(|(left, right)| function(left, right))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(assemble(frame, results))
+}
+
+/// `f⍣n`: applies `f` `n` times.
+pub fn power(function: Monadic, count: i64, right: Array) -> anyhow::Result<Array> {
+    if count < 0 {
+        anyhow::bail!(Errors::Nonce(
+            "⍣ with a negative count needs inverses, which are not supported yet".to_string()
+        ));
+    }
+    (0..count).try_fold(right, |array, _| function(array))
+}
+
+/// `f⍣g`: applies `f` until `g` holds between the latest result, on its
+/// left, and the one before it, so `f⍣≡` finds a fixpoint.
+pub fn power_until(function: Monadic, until: Dyadic, right: Array) -> anyhow::Result<Array> {
+    let mut current = right;
+    loop {
+        let next = function(current.clone())?;
+        if until(next.clone(), current)?.as_condition()? {
+            return Ok(next);
+        }
+        current = next;
+    }
+}
+
+/// The left operand of `@`: the new values, or a function of the old ones.
+pub enum Replacement<'a> {
+    Values(Array),
+    Function(Monadic<'a>),
+}
+
+/// The right operand of `@`: indices, or a function giving a boolean mask.
+pub enum Selection<'a> {
+    Indices(Array),
+    Mask(Monadic<'a>),
+}
+
+// The positions in the ravel of an array of shape `shape` picked out by
+// `indices`, in the order picked, and the shape they make. Simple indices
+// pick major cells, nested ones pick elements with one index per axis.
+fn selected(
+    indices: &Array,
+    shape: &[usize],
+    origin: usize,
+) -> anyhow::Result<(Vec<usize>, Vec<usize>)> {
+    if shape.is_empty() {
+        anyhow::bail!(Errors::Rank(
+            "@ expects indices into a non-scalar".to_string()
+        ));
+    }
+    let index = |scalar: &Scalar, length: usize| -> anyhow::Result<usize> {
+        let n = scalar.as_real()?;
+        let i = n - origin as f64;
+        if n.fract() != 0.0 || i < 0.0 || i >= length as f64 {
+            anyhow::bail!(Errors::Index(format!("{} is out of range", n)));
+        }
+        Ok(i as usize)
+    };
+
+    let mut positions = vec![];
+    let mut result = indices.shape().to_vec();
+    if indices.is_simple() {
+        let size: usize = shape[1..].iter().product();
+        for scalar in indices.data() {
+            let i = index(scalar, shape[0])?;
+            positions.extend(i * size..(i + 1) * size);
+        }
+        result.extend_from_slice(&shape[1..]);
+    } else {
+        for scalar in indices.data() {
+            let item = scalar.clone().disclose();
+            if item.data().len() != shape.len() {
+                anyhow::bail!(Errors::Rank("@ expects one index per axis".to_string()));
+            }
+            let mut offset = 0;
+            for (scalar, length) in item.data().iter().zip(shape) {
+                offset = offset * length + index(scalar, *length)?;
+            }
+            positions.push(offset);
+        }
+    }
+    Ok((positions, result))
+}
+
+/// `A@s` and `f@s`: at, the array with the selected elements replaced by
+/// `A` or by `f` applied to them.
+pub fn at(
+    replacement: Replacement,
+    selection: Selection,
+    right: Array,
+    origin: usize,
+) -> anyhow::Result<Array> {
+    let (positions, shape) = match selection {
+        Selection::Indices(indices) => selected(&indices, right.shape(), origin)?,
+        Selection::Mask(mask) => {
+            let mask = mask(right.clone())?;
+            if mask.shape() != right.shape() {
+                anyhow::bail!(Errors::Length(
+                    "@ expects a mask of its argument's shape".to_string()
+                ));
+            }
+            let positions: Vec<usize> = match mask.as_bits() {
+                Some(bits) => bits.ones_positions().collect(),
+                None => anyhow::bail!(Errors::Domain("@ expects a boolean mask".to_string())),
+            };
+            let shape = vec![positions.len()];
+            (positions, shape)
+        }
+    };
+
+    let prototype = right.prototype();
+    let old = positions.iter().map(|p| right.data()[*p].clone()).collect();
+    let old = Array::new(shape.clone(), old).with_prototype(prototype.clone());
+    let new = match replacement {
+        Replacement::Values(values) => values,
+        Replacement::Function(function) => function(old)?,
+    };
+    let new = match (new.is_scalar(), new.shape() == shape.as_slice()) {
+        (true, _) => vec![new.data()[0].clone(); positions.len()],
+        (false, true) => new.into_data(),
+        (false, false) => anyhow::bail!(Errors::Length(format!(
+            "@ selected {:?} but the new values are {:?}",
+            shape,
+            new.shape()
+        ))),
+    };
+
+    let shape = right.shape().to_vec();
+    let mut data = right.into_data();
+    for (position, scalar) in positions.into_iter().zip(new) {
+        data[position] = scalar;
+    }
+    Ok(Array::new(shape, data).with_prototype(prototype))
+}
+
+/// Reads the right operand of `⌺`: a vector of window sizes, or a matrix
+/// whose rows are the sizes and the steps between windows.
+pub fn stencil_spec(spec: &Array) -> anyhow::Result<(Vec<usize>, Vec<usize>)> {
+    let numbers = spec
+        .data()
+        .iter()
+        .map(|scalar| {
+            let n = scalar.as_real()?;
+            if n < 1.0 || n.fract() != 0.0 {
+                anyhow::bail!(Errors::Domain(format!(
+                    "⌺ expects positive integers, got {}",
+                    format_number(n)
+                )));
+            }
+            Ok(n as usize)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    match spec.shape() {
+        [] | [_] => Ok((numbers.clone(), vec![1; numbers.len()])),
+        [2, n] => Ok((numbers[..*n].to_vec(), numbers[*n..].to_vec())),
+        _ => anyhow::bail!(Errors::Rank(
+            "⌺ expects sizes, or sizes above steps".to_string()
+        )),
+    }
+}
+
+/// `f⌺s`: stencil. Slides windows of the given sizes along the leading axes
+/// of `right`, `⌈n÷step⌉` of them on an axis of length `n`, each centred on
+/// an item (just after the middle for an even size). Parts of a window off
+/// the ends of the array are filled. `f` is called with the padding on each
+/// axis on its left, positive if it is before the start and negative if
+/// past the end, and the window on its right.
+pub fn stencil(
+    function: Dyadic,
+    sizes: &[usize],
+    steps: &[usize],
+    right: Array,
+) -> anyhow::Result<Array> {
+    if sizes.len() > right.rank() {
+        anyhow::bail!(Errors::Rank(
+            "⌺ has more sizes than its argument has axes".to_string()
+        ));
+    }
+    let (leading, trailing) = right.shape().split_at(sizes.len());
+    let cell: usize = trailing.iter().product();
+    let fill = right.prototype();
+    let counts: Vec<usize> = leading
+        .iter()
+        .zip(steps)
+        .map(|(length, step)| length.div_ceil(*step))
+        .collect();
+
+    let mut shape = sizes.to_vec();
+    shape.extend_from_slice(trailing);
+
+    let mut results = Vec::with_capacity(counts.iter().product());
+    for window in indices(&counts) {
+        // Where the window starts on each axis, negative if before the start
+        let starts: Vec<i64> = window
+            .iter()
+            .zip(sizes.iter().zip(steps))
+            .map(|(i, (size, step))| (i * step) as i64 - ((size - 1) / 2) as i64)
+            .collect();
+        let padding = starts
+            .iter()
+            .zip(sizes.iter().zip(leading))
+            .map(|(start, (size, length))| {
+                let after = start + *size as i64 - *length as i64;
+                match (*start < 0, after > 0) {
+                    (true, _) => Scalar::from(-start as f64),
+                    (false, true) => Scalar::from(-after as f64),
+                    (false, false) => Scalar::from(0.0),
+                }
+            })
+            .collect();
+
+        let mut data = Vec::with_capacity(shape.iter().product());
+        for offset in indices(sizes) {
+            let position = offset.iter().zip(starts.iter().zip(leading)).try_fold(
+                0,
+                |position, (offset, (start, length))| {
+                    let i = start + *offset as i64;
+                    match i >= 0 && i < *length as i64 {
+                        true => Some(position * length + i as usize),
+                        false => None,
+                    }
+                },
+            );
+            match position {
+                Some(p) => data.extend_from_slice(&right.data()[p * cell..(p + 1) * cell]),
+                None => data.extend(std::iter::repeat_n(fill.clone(), cell)),
+            }
+        }
+
+        let window = Array::new(shape.clone(), data).with_prototype(fill.clone());
+        results.push(function(Array::vector(padding), window)?);
+    }
+    Ok(assemble(counts, results))
+}
+
+/// `f⌸`: key. Groups the major cells of `keys` by value, in the order each
+/// first occurs, and calls `f` once a group with the key on its left. On
+/// its right are the major cells of `values` under the group's keys, or
+/// without `values` the indices of the keys, counting from `origin`.
+pub fn key(
+    function: Dyadic,
+    keys: &Array,
+    values: Option<&Array>,
+    origin: usize,
+) -> anyhow::Result<Array> {
+    if keys.is_scalar() {
+        anyhow::bail!(Errors::Rank("⌸ expects keys along an axis".to_string()));
+    }
+    let cells = major_cells(keys);
+    if let Some(values) = values {
+        if values.is_scalar() || values.tally() != cells.len() {
+            anyhow::bail!(Errors::Length(format!(
+                "⌸ has {} keys for {} values",
+                cells.len(),
+                values.tally()
+            )));
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut group_of = HashMap::with_capacity(cells.len());
+    for (i, cell) in cells.iter().enumerate() {
+        let group = *group_of.entry(Key(cell)).or_insert_with(|| {
+            groups.push(vec![]);
+            groups.len() - 1
+        });
+        groups[group].push(i);
+    }
+
+    let cell_shape = keys.shape()[1..].to_vec();
+    let value_cells = values.map(major_cells);
+    let results = groups
+        .iter()
+        .map(|members| {
+            let key = Array::new(cell_shape.clone(), cells[members[0]].to_vec());
+            let right = match (values, &value_cells) {
+                (Some(values), Some(value_cells)) => {
+                    let selected: Vec<&[Scalar]> =
+                        members.iter().map(|i| value_cells[*i]).collect();
+                    from_major_cells(values, &selected)
+                }
+                _ => Array::vector(
+                    members
+                        .iter()
+                        .map(|i| Scalar::from((i + origin) as f64))
+                        .collect(),
+                ),
+            };
+            function(key, right)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(assemble(vec![groups.len()], results))
+}
+
+// Gives an empty result of `¨` the prototype found by applying the operand
+// to the prototypes of the arguments, falling back to `0` if that fails.
+fn with_each_prototype(result: Array, prototype: impl FnOnce() -> anyhow::Result<Array>) -> Array {
+    if !result.data().is_empty() {
+        return result;
+    }
+    match prototype() {
+        Ok(prototype) => result.with_prototype(prototype.enclose()),
+        Err(_) => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{each, each_dyadic, reduce, reduce_first};
+    use crate::runtime::array::Array;
+    use crate::runtime::primitives::structural::{first, tally};
+    use crate::runtime::tests::numbers;
+
+    fn nested(items: Vec<Array>) -> Array {
+        Array::vector(items.into_iter().map(Array::enclose).collect())
+    }
+
+    fn add(left: Array, right: Array) -> anyhow::Result<Array> {
+        Ok(Array::scalar(
+            left.data()[0].as_real()? + right.data()[0].as_real()?,
+        ))
+    }
+
+    #[test]
+    fn it_applies_each() {
+        // ≢¨(1 2)(3 4 5) ←→ 2 3
+        assert_eq!(
+            each(
+                &|array| Ok(tally(&array)),
+                nested(vec![numbers(&[1.0, 2.0]), numbers(&[3.0, 4.0, 5.0])])
+            )
+            .unwrap(),
+            numbers(&[2.0, 3.0])
+        );
+        // ⊂¨1 2 ←→ 1 2
+        assert_eq!(
+            each(
+                &|array| Ok(Array::scalar(array.enclose())),
+                numbers(&[1.0, 2.0])
+            )
+            .unwrap(),
+            numbers(&[1.0, 2.0])
+        );
+        // {⍵ ⍵}¨'ab' ←→ ('aa')('bb')
+        let pair = |array: Array| {
+            let c = array.data()[0].clone();
+            Ok(Array::vector(vec![c.clone(), c]))
+        };
+        assert_eq!(
+            each(&pair, Array::from_string_literal("ab")).unwrap(),
+            nested(vec![
+                Array::from_string_literal("aa"),
+                Array::from_string_literal("bb")
+            ])
+        );
+    }
+
+    #[test]
+    fn it_gives_empty_results_a_prototype() {
+        // ⊃{⍵ ⍵}¨'' ←→ '  '
+        let pair = |array: Array| {
+            let c = array.data()[0].clone();
+            Ok(Array::vector(vec![c.clone(), c]))
+        };
+        let result = each(&pair, Array::from_string_literal("")).unwrap();
+        assert_eq!(result.shape(), &[0]);
+        assert_eq!(first(result), Array::from_string_literal("  "));
+    }
+
+    #[test]
+    fn it_applies_each_dyadically() {
+        assert_eq!(
+            each_dyadic(&add, numbers(&[1.0, 2.0]), numbers(&[3.0, 4.0])).unwrap(),
+            numbers(&[4.0, 6.0])
+        );
+        assert_eq!(
+            each_dyadic(&add, Array::scalar(10.0), numbers(&[3.0, 4.0])).unwrap(),
+            numbers(&[13.0, 14.0])
+        );
+        assert!(each_dyadic(&add, numbers(&[1.0, 2.0]), numbers(&[1.0])).is_err());
+        assert!(each_dyadic(
+            &add,
+            numbers(&[1.0]),
+            Array::new(vec![1, 1], vec![1.0.into()])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn it_reduces_from_the_right() {
+        let subtract = |left: Array, right: Array| {
+            Ok(Array::scalar(
+                left.data()[0].as_real()? - right.data()[0].as_real()?,
+            ))
+        };
+
+        // -/1 2 3 ←→ 2
+        assert_eq!(
+            reduce(&subtract, numbers(&[1.0, 2.0, 3.0]), None).unwrap(),
+            Array::scalar(2.0)
+        );
+        // +/2 2⍴1 2 3 4 ←→ 3 7
+        let matrix = Array::new(vec![2, 2], numbers(&[1.0, 2.0, 3.0, 4.0]).into_data());
+        assert_eq!(reduce(&add, matrix, None).unwrap(), numbers(&[3.0, 7.0]));
+        assert_eq!(
+            reduce(&add, Array::scalar(5.0), None).unwrap(),
+            Array::scalar(5.0)
+        );
+        assert!(reduce(&add, numbers(&[]), None).is_err());
+        assert_eq!(
+            reduce(&add, numbers(&[]), Some(0.0.into())).unwrap(),
+            Array::scalar(0.0)
+        );
+        assert_eq!(
+            reduce(&add, Array::new(vec![0, 3], vec![]), None).unwrap(),
+            numbers(&[])
+        );
+        // +/2 0⍴0 ←→ 0 0
+        assert_eq!(
+            reduce(&add, Array::new(vec![2, 0], vec![]), Some(0.0.into())).unwrap(),
+            numbers(&[0.0, 0.0])
+        );
+    }
+
+    #[test]
+    fn it_reduces_along_the_first_axis() {
+        let subtract = |left: Array, right: Array| {
+            Ok(Array::scalar(
+                left.data()[0].as_real()? - right.data()[0].as_real()?,
+            ))
+        };
+        let matrix = Array::new(
+            vec![3, 2],
+            numbers(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).into_data(),
+        );
+
+        // -⌿3 2⍴⍳6 ←→ 3 4
+        assert_eq!(
+            reduce_first(&subtract, matrix, None).unwrap(),
+            numbers(&[3.0, 4.0])
+        );
+        // +⌿2 2 2⍴⍳8 ←→ 2 2⍴6 8 10 12
+        let cube = Array::new(
+            vec![2, 2, 2],
+            numbers(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).into_data(),
+        );
+        assert_eq!(
+            reduce_first(&add, cube, None).unwrap(),
+            Array::new(vec![2, 2], numbers(&[6.0, 8.0, 10.0, 12.0]).into_data())
+        );
+        // +⌿0 3⍴0 ←→ 0 0 0
+        assert_eq!(
+            reduce_first(&add, Array::new(vec![0, 3], vec![]), Some(0.0.into())).unwrap(),
+            numbers(&[0.0, 0.0, 0.0])
+        );
+        assert!(reduce_first(&add, Array::new(vec![0, 3], vec![]), None).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+// The primitive functions, one module per family, each taking and returning
+// whole arrays. `⎕IO` is fixed at 1; the evaluator shifts the indices they
+// give for other origins.
+
+pub(crate) mod convert;
+pub(crate) mod format;
+pub(crate) mod fused;
+pub(crate) mod linear;
+pub(crate) mod logic;
+pub(crate) mod order;
+pub(crate) mod random;
+pub(crate) mod scalar;
+pub(crate) mod search;
+pub(crate) mod signal;
+pub(crate) mod simd;
+pub(crate) mod structural;
@@ -0,0 +1,221 @@
+// `⎕INTO`: converts numbers to the type named by a literal suffix, so that
+// `'u8' ⎕INTO x` holds what `xu8` would if `x` were written as a literal.
+// Integer types take whole numbers, fitted under the type's overflow mode;
+// floats round to their precision; `'n'` makes numbers exact. `⎕OVERFLOW`
+// names integer types the same way to set their overflow modes.
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::FromPrimitive;
+
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::lanes::Lanes;
+use crate::runtime::number::integer::{set_overflow, Overflow};
+use crate::runtime::number::{Int, IntType, Number, Precision};
+use crate::tokenizer::numeric_literal::NumericLiteral;
+
+#[derive(Clone, Copy)]
+enum Target {
+    Int(IntType),
+    Float(Precision),
+    Exact,
+}
+
+// The type a suffix such as `u8`, `i`, `h` or `n` gives a literal.
+fn target(suffix: &str) -> anyhow::Result<Target> {
+    let literal = format!("0{}", suffix)
+        .parse::<NumericLiteral>()
+        .map_err(|error| {
+            anyhow::anyhow!(Errors::Domain(format!(
+                "{} is not a type: {}",
+                suffix, error
+            )))
+        })?;
+    Ok(match literal {
+        NumericLiteral::Boolean(_) => Target::Int(IntType::U1),
+        NumericLiteral::Uint(bits, _) => Target::Int(IntType::new(false, bits)?),
+        NumericLiteral::Int(bits, _) => Target::Int(IntType::new(true, bits)?),
+        NumericLiteral::SysUint(_) => Target::Int(IntType::U64),
+        NumericLiteral::SysInt(_) => Target::Int(IntType::I64),
+        NumericLiteral::Float(volume, _) => Target::Float(Precision::from_volume(volume)?),
+        NumericLiteral::Big(_) => Target::Exact,
+        _ => anyhow::bail!(Errors::Domain(format!("{} is not a type", suffix))),
+    })
+}
+
+// The whole number `number` is, exactly.
+fn whole(number: &Number) -> anyhow::Result<BigInt> {
+    let whole = match number {
+        Number::Int(i) => Some(BigInt::from(i.value())),
+        Number::Rational(q) if q.is_integer() => Some(q.to_integer()),
+        number => number
+            .as_real()
+            .filter(|n| n.fract() == 0.0)
+            .and_then(BigInt::from_f64),
+    };
+    match whole {
+        Some(whole) => Ok(whole),
+        None => anyhow::bail!(Errors::Domain(
+            "⎕INTO an integer type expects whole numbers".to_string()
+        )),
+    }
+}
+
+fn convert(number: Number, target: Target) -> anyhow::Result<Number> {
+    Ok(match (target, number) {
+        (Target::Int(ty), number) => Number::Int(Int::from_big(&whole(&number)?, ty)?),
+        (_, Number::Complex(_)) => {
+            anyhow::bail!(Errors::Domain(
+                "⎕INTO a real type expects real numbers".to_string()
+            ))
+        }
+        (Target::Float(precision), number) => Number::float(number.as_real().unwrap(), precision),
+        (Target::Exact, Number::Rational(q)) => Number::Rational(q),
+        (Target::Exact, Number::Int(i)) => {
+            Number::Rational(BigRational::from_integer(i.value().into()))
+        }
+        (Target::Exact, number) => match BigRational::from_float(number.as_real().unwrap()) {
+            Some(q) => Number::Rational(q),
+            None => anyhow::bail!(Errors::Domain(
+                "⎕INTO 'n' expects finite numbers".to_string()
+            )),
+        },
+    })
+}
+
+fn convert_scalar(scalar: &Scalar, target: Target) -> anyhow::Result<Scalar> {
+    Ok(match scalar {
+        Scalar::Number(number) => Scalar::Number(convert(number.clone(), target)?),
+        Scalar::Boxed(array) => convert_array(array, target)?.enclose(),
+        Scalar::Char(_) => anyhow::bail!(Errors::Domain("⎕INTO expects numbers".to_string())),
+    })
+}
+
+fn convert_array(array: &Array, target: Target) -> anyhow::Result<Array> {
+    let data: Vec<Scalar> = array
+        .data()
+        .iter()
+        .map(|scalar| convert_scalar(scalar, target))
+        .collect::<anyhow::Result<_>>()?;
+    // Kept unboxed if it is all of one fixed width, for the kernels
+    Ok(match Lanes::unbox(&data) {
+        Some(lanes) => Array::from_lanes(array.shape().to_vec(), lanes),
+        None => Array::new(array.shape().to_vec(), data),
+    })
+}
+
+/// Dyadic `⎕INTO`: `right` as the type named by the suffix `left`.
+pub fn into(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    let suffix = match left.as_string() {
+        Some(suffix) => suffix,
+        None => anyhow::bail!(Errors::Domain(
+            "⎕INTO expects a type suffix such as 'u8' on the left".to_string()
+        )),
+    };
+    convert_array(right, target(&suffix)?)
+}
+
+/// `⎕OVERFLOW`: `⎕OVERFLOW 'u8'` names what operations giving `u8` do with
+/// a result too wide for it, one of `'checked'`, `'wrapping'` and
+/// `'saturating'`, and `'wrapping' ⎕OVERFLOW 'u8'` sets it, giving the mode
+/// it replaces.
+pub fn overflow(left: Option<&Array>, right: &Array) -> anyhow::Result<Array> {
+    let ty = match right.as_string().map(|suffix| target(&suffix)) {
+        Some(Ok(Target::Int(ty))) => ty,
+        Some(Err(error)) => return Err(error),
+        _ => anyhow::bail!(Errors::Domain(
+            "⎕OVERFLOW expects an integer type such as 'u8'".to_string()
+        )),
+    };
+    let previous = ty.overflow();
+    if let Some(left) = left {
+        let name = left.as_string().unwrap_or_default();
+        match Overflow::MODES.iter().find(|mode| mode.name() == name) {
+            Some(mode) => set_overflow(ty, *mode),
+            None => anyhow::bail!(Errors::Domain(
+                "⎕OVERFLOW expects 'checked', 'wrapping' or 'saturating' on the left".to_string()
+            )),
+        }
+    }
+    Ok(Array::from_string_literal(previous.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::into;
+    use crate::runtime::array::Array;
+    use crate::runtime::lanes::Lanes;
+    use crate::runtime::number::{Int, IntType, Number, Precision};
+    use crate::runtime::tests::numbers;
+
+    fn converted(suffix: &str, ns: &[f64]) -> anyhow::Result<Vec<Number>> {
+        let array = into(&Array::from_string_literal(suffix), &numbers(ns))?;
+        Ok(array
+            .data()
+            .iter()
+            .map(|scalar| scalar.as_number().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn it_converts_to_every_width() {
+        for bits in 1..=64 {
+            for signed in [false, true] {
+                let ty = IntType::new(signed, bits).unwrap();
+                let suffix = format!("{}{}", if signed { 'i' } else { 'u' }, bits);
+                assert_eq!(
+                    converted(&suffix, &[0.0]).unwrap(),
+                    vec![Number::Int(Int::new(0, ty).unwrap())],
+                    "{}",
+                    suffix
+                );
+            }
+        }
+        let byte = IntType::new(false, 8).unwrap();
+        assert_eq!(
+            converted("u8", &[255.0]).unwrap(),
+            vec![Number::Int(Int::new(255, byte).unwrap())]
+        );
+        assert_eq!(
+            converted("h", &[0.1]).unwrap(),
+            vec![Number::float(0.1, Precision::Half)]
+        );
+        assert_eq!(
+            converted("b", &[1.0]).unwrap(),
+            vec![Number::Int(Int::new(1, IntType::U1).unwrap())]
+        );
+        assert!(matches!(
+            converted("n", &[0.5]).unwrap()[0],
+            Number::Rational(_)
+        ));
+    }
+
+    #[test]
+    fn it_keeps_fixed_widths_unboxed() {
+        let typed = |suffix: &str| into(&Array::from_string_literal(suffix), &numbers(&[1.0, 2.0]));
+        assert_eq!(
+            typed("i16").unwrap().lanes(),
+            Some(Cow::Borrowed(&Lanes::I16(vec![1, 2])))
+        );
+        assert_eq!(
+            typed("f").unwrap().lanes(),
+            Some(Cow::Borrowed(&Lanes::F32(vec![1.0, 2.0])))
+        );
+        assert!(typed("i4").unwrap().lanes().is_none());
+    }
+
+    #[test]
+    fn it_rejects_what_does_not_fit() {
+        assert!(converted("u8", &[1.5]).is_err());
+        assert!(converted("u65", &[1.0]).is_err());
+        assert!(converted("x", &[1.0]).is_err());
+        assert!(into(
+            &Array::from_string_literal("u8"),
+            &Array::from_string_literal("a")
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,714 @@
+// `⍕` and the display of arrays. Simple arrays lay out as aligned columns,
+// and nested ones as a grid of boxes, one for each item, drawn around the
+// display of the item.
+
+use std::cell::Cell;
+
+use num_rational::BigRational;
+
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::number::{Float, Number};
+
+/// `⎕PP` in a new session.
+pub const DEFAULT_PRINT_PRECISION: usize = 10;
+
+thread_local! {
+    // `⎕PP`, the significant digits a double is shown with, in the session
+    // running in this thread or which ran last.
+    static PRINT_PRECISION: Cell<usize> = const { Cell::new(DEFAULT_PRINT_PRECISION) };
+}
+
+// A double has no more digits than this to show.
+const MAX_PRINT_PRECISION: usize = 17;
+
+/// The `⎕PP` in place now, for a session to keep between runs.
+pub fn print_precision() -> usize {
+    PRINT_PRECISION.with(Cell::get)
+}
+
+/// Puts a session's `⎕PP` in place, for what is formatted until the next.
+pub fn set_print_precision(digits: usize) {
+    PRINT_PRECISION.with(|precision| precision.set(digits));
+}
+
+/// The value of `⎕PP`.
+pub fn precision() -> Array {
+    Array::scalar(print_precision() as f64)
+}
+
+/// Assigns `⎕PP`, from 1 to 17.
+pub fn set_precision(value: &Array) -> anyhow::Result<()> {
+    let digits = match value.data() {
+        [digits] if value.rank() <= 1 => digits.as_real()?,
+        _ => anyhow::bail!(Errors::Length("⎕PP is a single number".to_string())),
+    };
+    if digits.fract() != 0.0 || !(1.0..=MAX_PRINT_PRECISION as f64).contains(&digits) {
+        anyhow::bail!(Errors::Domain(format!(
+            "⎕PP runs from 1 to {}, got {}",
+            MAX_PRINT_PRECISION,
+            format_number(digits)
+        )));
+    }
+    set_print_precision(digits as usize);
+    Ok(())
+}
+
+/// Formats a number the way APL prints it: `¯` for negatives, at most
+/// `⎕PP` significant digits and `E` notation for very large or small
+/// magnitudes.
+pub fn format_number(n: f64) -> String {
+    format_significant(n, print_precision())
+}
+
+/// Formats a half or single with the fewest significant digits that read
+/// back as the same value, so `0.1h` prints as `0.1` rather than the
+/// double nearest to it.
+pub fn format_float(f: Float) -> String {
+    let n = f.to_f64();
+    (1..print_precision())
+        .map(|digits| format_significant(n, digits))
+        .find(|s| match s.replace('¯', "-").parse::<f64>() {
+            Ok(parsed) => f.precision().round(parsed) == n,
+            Err(_) => false,
+        })
+        .unwrap_or_else(|| format_number(n))
+}
+
+fn format_significant(n: f64, significant: usize) -> String {
+    if n.is_nan() {
+        return "NaN".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "∞" } else { "¯∞" }.to_string();
+    }
+    if n == 0.0 {
+        return "0".to_string();
+    }
+
+    // `{:e}` rounds to the requested precision and leaves us with a mantissa
+    // and exponent to lay out ourselves.
+    let scientific = format!("{:.*e}", significant - 1, n.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    let digits = digits.trim_end_matches('0');
+
+    let magnitude = if exponent < -5 || exponent >= print_precision() as i32 {
+        let (head, tail) = digits.split_at(1);
+        let mantissa = if tail.is_empty() {
+            head.to_string()
+        } else {
+            format!("{}.{}", head, tail)
+        };
+        format!("{}E{}", mantissa, exponent.to_string().replace('-', "¯"))
+    } else if exponent < 0 {
+        format!("0.{}{}", "0".repeat((-exponent - 1) as usize), digits)
+    } else {
+        let point = exponent as usize + 1;
+        if digits.len() <= point {
+            format!("{}{}", digits, "0".repeat(point - digits.len()))
+        } else {
+            format!("{}.{}", &digits[..point], &digits[point..])
+        }
+    };
+
+    if n < 0.0 {
+        format!("¯{}", magnitude)
+    } else {
+        magnitude
+    }
+}
+
+/// Formats an integer in full, whatever its magnitude.
+pub fn format_integer(n: i128) -> String {
+    high_minus(n)
+}
+
+/// Formats a rational exactly, whole ones as integers and the rest as
+/// `NrD`, the form they are written in.
+pub fn format_rational(q: &BigRational) -> String {
+    match q.is_integer() {
+        true => high_minus(q.numer()),
+        false => format!("{}r{}", high_minus(q.numer()), high_minus(q.denom())),
+    }
+}
+
+fn high_minus(n: impl std::fmt::Display) -> String {
+    n.to_string().replace('-', "¯")
+}
+
+// A simple scalar's display; boxes are drawn by `grid`.
+fn format_scalar(scalar: &Scalar) -> String {
+    match scalar {
+        Scalar::Number(Number::Int(i)) => format_integer(i.value()),
+        Scalar::Number(Number::Float(f)) => format_float(*f),
+        Scalar::Number(Number::Real(n)) => format_number(*n),
+        Scalar::Number(Number::Rational(q)) => format_rational(q),
+        // `aJb`, the same form `NumericLiteral::Complex` is written in.
+        Scalar::Number(Number::Complex(z)) => {
+            format!("{}J{}", format_number(z.re), format_number(z.im))
+        }
+        Scalar::Char(c) => c.to_string(),
+        Scalar::Boxed(_) => unreachable!("boxes are drawn as a grid"),
+    }
+}
+
+// How many characters of a formatted number follow its decimal point,
+// counting the point, for lining up a column of numbers on their points.
+// Numbers in `E` or `J` form line up on their right.
+fn fraction(cell: &str) -> usize {
+    match (cell.find('.'), cell.contains(['E', 'J'])) {
+        (Some(point), false) => cell[point..].chars().count(),
+        _ => 0,
+    }
+}
+
+// Lays out one row: characters run together, numbers are set apart by a
+// space from whatever is next to them. In a matrix every row shares the
+// column widths and the decision of which columns to separate, so that the
+// rows line up: characters to the left of their column and numbers on
+// their decimal points.
+struct Columns {
+    widths: Vec<usize>,
+    fractions: Vec<usize>,
+    chars: Vec<bool>,
+}
+
+fn format_row(row: &[Scalar], columns: Option<&Columns>) -> String {
+    let mut out = String::new();
+
+    for (i, scalar) in row.iter().enumerate() {
+        let run_together = match columns {
+            Some(columns) => i > 0 && columns.chars[i - 1] && columns.chars[i],
+            None => i > 0 && scalar.is_char() && row[i - 1].is_char(),
+        };
+        if i > 0 && !run_together {
+            out.push(' ');
+        }
+
+        let cell = format_scalar(scalar);
+        let (before, after) = match columns {
+            Some(columns) => {
+                let after = match scalar {
+                    Scalar::Number(_) => columns.fractions[i] - fraction(&cell),
+                    _ => 0,
+                };
+                (columns.widths[i] - cell.chars().count() - after, after)
+            }
+            None => (0, 0),
+        };
+        match scalar {
+            Scalar::Number(_) => {
+                out.push_str(&" ".repeat(before));
+                out.push_str(&cell);
+                out.push_str(&" ".repeat(after));
+            }
+            _ => {
+                out.push_str(&cell);
+                out.push_str(&" ".repeat(before));
+            }
+        }
+    }
+
+    out
+}
+
+fn from_lines(lines: &[Vec<char>], width: usize) -> Vec<Scalar> {
+    lines
+        .iter()
+        .flat_map(|line| {
+            let padding = width - line.len();
+            line.iter()
+                .copied()
+                .chain(std::iter::repeat_n(' ', padding))
+                .map(Scalar::Char)
+        })
+        .collect()
+}
+
+/// Monadic `⍕`: the character representation of an array. Simple scalars
+/// and vectors become character vectors, and simple arrays of higher rank
+/// keep their leading axes and lay out the last one as aligned columns.
+/// Nested arrays are drawn as a character matrix of boxes.
+pub fn format(array: &Array) -> Array {
+    if !array.is_simple() {
+        let lines = grid(array);
+        let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+        return Array::new(vec![lines.len(), width], from_lines(&lines, width));
+    }
+    if array.rank() <= 1 {
+        return Array::vector(
+            format_row(array.data(), None)
+                .chars()
+                .map(Scalar::Char)
+                .collect(),
+        );
+    }
+
+    let count = *array.shape().last().unwrap();
+    let mut columns = Columns {
+        widths: vec![0; count],
+        fractions: vec![0; count],
+        chars: vec![true; count],
+    };
+    let mut integers = vec![0; count];
+    for (i, scalar) in array.data().iter().enumerate() {
+        let (column, cell) = (i % count, format_scalar(scalar));
+        let width = cell.chars().count();
+        match scalar.is_char() {
+            true => columns.widths[column] = columns.widths[column].max(width),
+            false => {
+                let fraction = fraction(&cell);
+                integers[column] = integers[column].max(width - fraction);
+                columns.fractions[column] = columns.fractions[column].max(fraction);
+            }
+        }
+        columns.chars[column] &= scalar.is_char();
+    }
+    for ((width, integer), fraction) in columns
+        .widths
+        .iter_mut()
+        .zip(&integers)
+        .zip(&columns.fractions)
+    {
+        *width = (*width).max(integer + fraction);
+    }
+
+    let leading: usize = array.shape()[..array.rank() - 1].iter().product();
+    let rows: Vec<Vec<char>> = if count == 0 {
+        vec![Vec::new(); leading]
+    } else {
+        array
+            .data()
+            .chunks(count)
+            .map(|row| format_row(row, Some(&columns)).chars().collect())
+            .collect()
+    };
+
+    let width = rows.first().map_or(0, Vec::len);
+    let mut shape = array.shape()[..array.rank() - 1].to_vec();
+    shape.push(width);
+    Array::new(shape, from_lines(&rows, width))
+}
+
+// How many blank lines come before row `row` when an array of this shape
+// is shown a row at a time: one for each axis before the last two that the
+// row starts a new item of.
+fn gap(shape: &[usize], row: usize) -> usize {
+    if row == 0 || shape.len() < 3 {
+        return 0;
+    }
+    (0..shape.len() - 2)
+        .filter(|&axis| row.is_multiple_of(shape[axis + 1..shape.len() - 1].iter().product()))
+        .count()
+}
+
+// The display of an array as lines of text.
+fn picture(array: &Array) -> Vec<Vec<char>> {
+    if !array.is_simple() {
+        return grid(array);
+    }
+    let formatted = format(array);
+    let width = formatted.shape().last().copied().unwrap_or(0);
+    if formatted.rank() <= 1 {
+        return vec![formatted.data().iter().map(char_of).collect()];
+    }
+
+    let mut lines = Vec::new();
+    for (row, chars) in formatted.data().chunks(width.max(1)).enumerate() {
+        lines.extend(std::iter::repeat_n(Vec::new(), gap(formatted.shape(), row)));
+        lines.push(match width {
+            0 => Vec::new(),
+            _ => chars.iter().map(char_of).collect(),
+        });
+    }
+    lines
+}
+
+fn char_of(scalar: &Scalar) -> char {
+    match scalar {
+        Scalar::Char(c) => *c,
+        _ => unreachable!("⍕ gives characters"),
+    }
+}
+
+// A nested array as a grid of boxes, the rows and columns of its last two
+// axes, each box as wide as the widest item in its column and as tall as
+// the tallest in its row. Higher axes give separate grids with blank lines
+// between them, as they do in the display of a simple array.
+fn grid(array: &Array) -> Vec<Vec<char>> {
+    let columns = array.shape().last().copied().unwrap_or(1);
+    let shape = match array.rank() {
+        0 => vec![1, 1],
+        1 => vec![1, columns],
+        _ => array.shape().to_vec(),
+    };
+    let rows: usize = shape[..shape.len() - 1].iter().product();
+    if rows == 0 || columns == 0 {
+        return vec![Vec::new(); rows];
+    }
+    let plane = shape[shape.len() - 2];
+
+    let cells: Vec<Vec<Vec<char>>> = array
+        .data()
+        .iter()
+        .map(|scalar| match scalar {
+            Scalar::Boxed(item) => picture(item),
+            simple => vec![format_scalar(simple).chars().collect()],
+        })
+        .collect();
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            cells
+                .iter()
+                .skip(column)
+                .step_by(columns)
+                .flat_map(|cell| cell.iter().map(Vec::len))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let border = |left: char, middle: char, right: char| {
+        let mut line = vec![left];
+        for (column, width) in widths.iter().enumerate() {
+            if column > 0 {
+                line.push(middle);
+            }
+            line.extend(std::iter::repeat_n('─', *width));
+        }
+        line.push(right);
+        line
+    };
+
+    let mut lines = Vec::new();
+    for (row, cells) in cells.chunks(columns).enumerate() {
+        if row % plane == 0 {
+            if row > 0 {
+                lines.push(border('└', '┴', '┘'));
+            }
+            lines.extend(std::iter::repeat_n(Vec::new(), gap(&shape, row)));
+            lines.push(border('┌', '┬', '┐'));
+        } else {
+            lines.push(border('├', '┼', '┤'));
+        }
+        let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+        for i in 0..height {
+            let mut line = vec!['│'];
+            for (cell, width) in cells.iter().zip(&widths) {
+                let text = cell.get(i).map_or(&[][..], Vec::as_slice);
+                line.extend_from_slice(text);
+                line.extend(std::iter::repeat_n(' ', width - text.len()));
+                line.push('│');
+            }
+            lines.push(line);
+        }
+    }
+    lines.push(border('└', '┴', '┘'));
+    lines
+}
+
+// A whole number of `⍺` for dyadic `⍕`.
+fn whole(scalar: &Scalar) -> anyhow::Result<i64> {
+    match scalar.as_real()? {
+        n if n.fract() == 0.0 && n.abs() < 1e9 => Ok(n as i64),
+        n => anyhow::bail!(Errors::Domain(format!(
+            "⍕ expects whole numbers on the left, got {}",
+            format_number(n)
+        ))),
+    }
+}
+
+// A number to `decimals` places, or for negative `decimals` in `E` form to
+// that many significant digits.
+fn format_fixed_number(n: f64, decimals: i64) -> String {
+    let formatted = match decimals {
+        decimals if decimals >= 0 => format!("{:.*}", decimals as usize, n),
+        decimals => format!("{:.*e}", (-decimals - 1) as usize, n).replace('e', "E"),
+    };
+    // No ¯ in front of a number that rounds to 0
+    let formatted = match formatted
+        .split('E')
+        .next()
+        .unwrap()
+        .trim_matches(['-', '0', '.'])
+    {
+        "" => formatted.trim_start_matches('-').to_string(),
+        _ => formatted,
+    };
+    formatted.replace('-', "¯")
+}
+
+/// Dyadic `⍕`: fixed formatting of a simple numeric array. `left` gives a
+/// width and a number of decimals for each column of `right`, or one pair
+/// for every column, or just the decimals. Negative decimals give `E` form
+/// with that many significant digits. A width of 0 fits the column with a
+/// space before it; a number too wide for its column shows as `*`s.
+pub fn format_fixed(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    if left.rank() > 1 {
+        anyhow::bail!(Errors::Rank("⍕ expects a vector on the left".to_string()));
+    }
+    let spec = left
+        .data()
+        .iter()
+        .map(whole)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let count = right.shape().last().copied().unwrap_or(1);
+    let pairs: Vec<(i64, i64)> = match *spec.as_slice() {
+        [decimals] => vec![(0, decimals); count],
+        [width, decimals] => vec![(width, decimals); count],
+        _ if spec.len() == 2 * count => spec.chunks(2).map(|pair| (pair[0], pair[1])).collect(),
+        _ => anyhow::bail!(Errors::Length(format!(
+            "⍕ expects 1 or 2 numbers on the left, or 2 per column, got {}",
+            spec.len()
+        ))),
+    };
+    if pairs.iter().any(|&(width, _)| width < 0) {
+        anyhow::bail!(Errors::Domain("⍕ expects widths of 0 or more".to_string()));
+    }
+
+    let cells = right
+        .data()
+        .iter()
+        .enumerate()
+        .map(|(i, scalar)| match scalar {
+            Scalar::Number(_) => Ok(format_fixed_number(scalar.as_real()?, pairs[i % count].1)),
+            _ => anyhow::bail!(Errors::Domain(
+                "⍕ with a left argument expects numbers".to_string()
+            )),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let widths: Vec<usize> = (0..count)
+        .map(|column| match pairs[column].0 {
+            0 => {
+                let cells = cells.iter().skip(column).step_by(count.max(1));
+                cells
+                    .map(|cell| cell.chars().count() + 1)
+                    .max()
+                    .unwrap_or(1)
+            }
+            width => width as usize,
+        })
+        .collect();
+
+    let mut data = Vec::with_capacity(cells.len() + cells.len() / count.max(1));
+    for (i, cell) in cells.iter().enumerate() {
+        let width = widths[i % count];
+        let length = cell.chars().count();
+        let field = match length <= width {
+            true => format!("{}{}", " ".repeat(width - length), cell),
+            false => "*".repeat(width),
+        };
+        data.extend(field.chars().map(Scalar::Char));
+    }
+
+    let mut shape = match right.rank() {
+        0 => vec![],
+        rank => right.shape()[..rank - 1].to_vec(),
+    };
+    shape.push(widths.iter().sum());
+    Ok(Array::new(shape, data))
+}
+
+/// Writes the array as a session shows it: its format, a line per row.
+impl std::fmt::Display for Array {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let formatted = format(self);
+        let width = formatted.shape().last().copied().unwrap_or(0).max(1);
+        let lines: Vec<String> = formatted
+            .data()
+            .chunks(width)
+            .map(|row| {
+                row.iter()
+                    .map(|scalar| match scalar {
+                        Scalar::Char(c) => *c,
+                        _ => ' ',
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        write!(formatter, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, format_fixed, format_float, format_number, precision, set_precision};
+    use crate::runtime::array::Array;
+
+    fn string(array: &Array) -> String {
+        array.as_string().unwrap()
+    }
+
+    fn rows(matrix: &Array) -> Vec<String> {
+        let chars = string(&matrix.clone().ravel());
+        let chars: Vec<char> = chars.chars().collect();
+        chars
+            .chunks(matrix.shape()[1])
+            .map(|row| row.iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn it_formats_numbers() {
+        assert_eq!(format_number(1.0), "1");
+        assert_eq!(format_number(-2.0), "¯2");
+        assert_eq!(format_number(3.5), "3.5");
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(1234567890.0), "1234567890");
+        assert_eq!(format_number(1e20), "1E20");
+        assert_eq!(format_number(-1.5e-7), "¯1.5E¯7");
+        assert_eq!(format_number(0.00012), "0.00012");
+    }
+
+    #[test]
+    fn it_formats_narrow_floats_in_their_own_digits() {
+        use crate::runtime::number::{Float, Precision, F16};
+
+        assert_eq!(format_float(Float::Half(F16::from_f64(0.1))), "0.1");
+        assert_eq!(format_float(Float::Single(0.1)), "0.1");
+        // 65500 is nearer to 65504 than to any other half
+        assert_eq!(format_float(Float::Half(F16::from_f64(-65504.0))), "¯65500");
+        assert_eq!(
+            format_float(Float::Single(Precision::Single.round(1.0 / 3.0) as f32)),
+            "0.33333334"
+        );
+        assert_eq!(format_number(Precision::Half.round(0.1)), "0.09997558594");
+    }
+
+    #[test]
+    fn it_formats_vectors() {
+        let chars = Array::from_string_literal("⍴héllo");
+        assert_eq!(format(&chars), chars);
+
+        let numbers = Array::vector(vec![1.0.into(), (-2.0).into(), 3.5.into()]);
+        assert_eq!(string(&format(&numbers)), "1 ¯2 3.5");
+
+        let mixed = Array::vector(vec!['a'.into(), 'b'.into(), 1.0.into(), 'c'.into()]);
+        assert_eq!(string(&format(&mixed)), "ab 1 c");
+
+        assert_eq!(format(&Array::scalar('x')).shape(), &[1]);
+        assert_eq!(format(&Array::vector(vec![])).shape(), &[0]);
+    }
+
+    #[test]
+    fn it_formats_matrices() {
+        let matrix = Array::new(
+            vec![2, 2],
+            vec![1.0.into(), 20.0.into(), (-300.0).into(), 4.0.into()],
+        );
+        let formatted = format(&matrix);
+
+        assert_eq!(formatted.shape(), &[2, 7]);
+        assert_eq!(string(&formatted.ravel()), "   1 20¯300  4");
+    }
+
+    #[test]
+    fn it_lines_up_decimal_points() {
+        // ⍕3 1⍴1.5 10 ¯0.25
+        let column = Array::new(vec![3, 1], vec![1.5.into(), 10.0.into(), (-0.25).into()]);
+        let formatted = format(&column);
+        assert_eq!(formatted.shape(), &[3, 5]);
+        assert_eq!(string(&formatted.ravel()), " 1.5 10   ¯0.25");
+    }
+
+    #[test]
+    fn it_formats_to_the_print_precision() {
+        set_precision(&Array::scalar(3.0)).unwrap();
+        assert_eq!(format_number(1.0 / 3.0), "0.333");
+        assert_eq!(format_number(1234.0), "1.23E3");
+        assert_eq!(precision(), Array::scalar(3.0));
+        set_precision(&Array::scalar(17.0)).unwrap();
+        assert_eq!(format_number(0.1), "0.10000000000000001");
+
+        assert!(set_precision(&Array::scalar(0.0)).is_err());
+        assert!(set_precision(&Array::scalar(18.0)).is_err());
+    }
+
+    #[test]
+    fn it_draws_nested_arrays_in_boxes() {
+        // 1 (2 3) 'ab'
+        let nested = Array::vector(vec![
+            1.0.into(),
+            Array::vector(vec![2.0.into(), 3.0.into()]).enclose(),
+            Array::from_string_literal("ab").enclose(),
+        ]);
+        assert_eq!(
+            rows(&format(&nested)),
+            ["┌─┬───┬──┐", "│1│2 3│ab│", "└─┴───┴──┘"]
+        );
+
+        // 2 2⍴'a' (⊂1 2) 'bcd' 4, with a box in a box
+        let inner = Array::vector(vec![1.0.into(), 2.0.into()]).enclose();
+        let matrix = Array::new(
+            vec![2, 2],
+            vec![
+                'a'.into(),
+                Array::scalar(inner).enclose(),
+                Array::from_string_literal("bcd").enclose(),
+                4.0.into(),
+            ],
+        );
+        assert_eq!(
+            rows(&format(&matrix)),
+            [
+                "┌───┬─────┐",
+                "│a  │┌───┐│",
+                "│   ││1 2││",
+                "│   │└───┘│",
+                "├───┼─────┤",
+                "│bcd│4    │",
+                "└───┴─────┘",
+            ]
+        );
+
+        // Planes of a simple array in a box have a blank line between them
+        let cube = Array::new(vec![2, 1, 1], vec![1.0.into(), 2.0.into()]);
+        assert_eq!(
+            rows(&format(&Array::vector(vec![cube.enclose()]))),
+            ["┌─┐", "│1│", "│ │", "│2│", "└─┘"]
+        );
+    }
+
+    #[test]
+    fn it_formats_to_fixed_widths() {
+        let ns = Array::vector(vec![4.56789.into(), (-2.0).into(), 1000.0.into()]);
+        // 2⍕4.56789 ¯2 1000 ←→ ' 4.57 ¯2.00 1000.00'
+        assert_eq!(
+            string(&format_fixed(&Array::scalar(2.0), &ns).unwrap()),
+            " 4.57 ¯2.00 1000.00"
+        );
+        // 6 1⍕4.56789 ¯2 1000 ←→ '   4.6  ¯2.01000.0'
+        let spec = Array::vector(vec![6.0.into(), 1.0.into()]);
+        assert_eq!(
+            string(&format_fixed(&spec, &ns).unwrap()),
+            "   4.6  ¯2.01000.0"
+        );
+        // 1000.0 is too wide for 5 1⍕
+        let narrow = Array::vector(vec![5.0.into(), 1.0.into()]);
+        assert_eq!(
+            string(&format_fixed(&narrow, &ns).unwrap()),
+            "  4.6 ¯2.0*****"
+        );
+        // 0 ¯3⍕1234 ←→ ' 1.23E3'
+        let exponent = Array::vector(vec![0.0.into(), (-3.0).into()]);
+        assert_eq!(
+            string(&format_fixed(&exponent, &Array::scalar(1234.0)).unwrap()),
+            " 1.23E3"
+        );
+        // A pair for each column of a matrix
+        let matrix = Array::new(
+            vec![2, 2],
+            vec![1.0.into(), 2.5.into(), 10.0.into(), (-0.004).into()],
+        );
+        let spec = Array::vector(vec![3.0.into(), 0.0.into(), 6.0.into(), 2.0.into()]);
+        let formatted = format_fixed(&spec, &matrix).unwrap();
+        assert_eq!(formatted.shape(), &[2, 9]);
+        assert_eq!(string(&formatted.ravel()), "  1  2.50 10  0.00");
+
+        assert!(format_fixed(&Array::scalar(2.0), &Array::from_string_literal("ab")).is_err());
+        assert!(format_fixed(&Array::vector(vec![1.0.into(); 3]), &ns).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+// The kernels for the idioms that `parser::idioms` recognises. Each works
+// in one pass over a vector, without the arrays the phrase would build on
+// the way, and gives nothing when its arguments are not what it is for,
+// leaving the evaluator to run the phrase instead. Where a kernel does
+// give a result it is the one the phrase would.
+
+use std::collections::HashSet;
+
+use crate::parser::idioms::Idiom;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::number::Number;
+use crate::runtime::primitives::order::compare;
+use crate::runtime::primitives::search::Key;
+
+/// The idiom applied to `right`, if it has a kernel for it.
+pub fn monadic(idiom: Idiom, right: &Array) -> Option<Array> {
+    if right.rank() != 1 {
+        return None;
+    }
+    match idiom {
+        Idiom::Mean => mean(right),
+        Idiom::Last => right.data().last().map(|last| last.clone().disclose()),
+        Idiom::SortUp => Some(sort(right, false)),
+        Idiom::SortDown => Some(sort(right, true)),
+        Idiom::CountUnique => {
+            let unique: HashSet<Key> = right.data().chunks(1).map(Key).collect();
+            Some(Array::scalar(unique.len() as f64))
+        }
+        Idiom::AnyMember | Idiom::AllMember => None,
+    }
+}
+
+/// The idiom between `left` and `right`, if it has a kernel for it.
+pub fn dyadic(idiom: Idiom, left: &Array, right: &Array) -> Option<Array> {
+    if left.rank() != 1 || !matches!(idiom, Idiom::AnyMember | Idiom::AllMember) {
+        return None;
+    }
+    let items: HashSet<Key> = right.data().chunks(1).map(Key).collect();
+    let mut elements = left.data().chunks(1);
+    match idiom {
+        Idiom::AnyMember => Some(Array::scalar(elements.any(|e| items.contains(&Key(e))))),
+        Idiom::AllMember => Some(Array::scalar(elements.all(|e| items.contains(&Key(e))))),
+        _ => None,
+    }
+}
+
+// `+/÷≢` of doubles, summed from the right as `+/` does.
+fn mean(right: &Array) -> Option<Array> {
+    let mut doubles = right.data().iter().rev().map(|scalar| match scalar {
+        Scalar::Number(Number::Real(n)) => Some(*n),
+        _ => None,
+    });
+    let mut sum = doubles.next()??;
+    for n in doubles {
+        sum += n?;
+    }
+    let mean = sum / right.tally() as f64;
+    match mean.is_finite() {
+        true => Some(Array::scalar(mean)),
+        false => None,
+    }
+}
+
+// `{⍵[⍋⍵]}` and `{⍵[⍒⍵]}`: a stable sort, so equal elements keep their
+// order as they do under a grade.
+fn sort(right: &Array, descending: bool) -> Array {
+    let mut data = right.data().to_vec();
+    match descending {
+        true => data.sort_by(|a, b| compare(b, a)),
+        false => data.sort_by(compare),
+    }
+    Array::new(right.shape().to_vec(), data).with_prototype(right.prototype())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dyadic, monadic};
+    use crate::parser::idioms::Idiom;
+    use crate::runtime::array::Array;
+    use crate::runtime::tests::numbers;
+
+    #[test]
+    fn it_runs_the_kernels_on_vectors() {
+        let v = numbers(&[3.0, 1.0, 2.0, 1.0]);
+        let run = |idiom| monadic(idiom, &v).unwrap();
+        assert!(run(Idiom::Mean).matches(&Array::scalar(1.75)));
+        assert!(run(Idiom::Last).matches(&Array::scalar(1.0)));
+        assert!(run(Idiom::SortUp).matches(&numbers(&[1.0, 1.0, 2.0, 3.0])));
+        assert!(run(Idiom::SortDown).matches(&numbers(&[3.0, 2.0, 1.0, 1.0])));
+        assert!(run(Idiom::CountUnique).matches(&Array::scalar(3.0)));
+
+        let w = numbers(&[2.0, 5.0]);
+        assert!(dyadic(Idiom::AnyMember, &w, &v)
+            .unwrap()
+            .matches(&Array::scalar(1.0)));
+        assert!(dyadic(Idiom::AllMember, &w, &v)
+            .unwrap()
+            .matches(&Array::scalar(0.0)));
+    }
+
+    #[test]
+    fn it_leaves_other_arguments_to_the_phrase() {
+        assert!(monadic(Idiom::Mean, &Array::scalar(1.0)).is_none());
+        assert!(monadic(Idiom::Mean, &numbers(&[])).is_none());
+        assert!(monadic(Idiom::Mean, &Array::from_string_literal("ab")).is_none());
+        assert!(monadic(Idiom::Last, &numbers(&[])).is_none());
+        assert!(monadic(Idiom::AnyMember, &numbers(&[1.0])).is_none());
+        assert!(dyadic(Idiom::Mean, &numbers(&[1.0]), &numbers(&[1.0])).is_none());
+    }
+}
@@ -0,0 +1,464 @@
+// Linear algebra: `⌹` matrix inverse and divide, `⊥` decode, `⊤` encode,
+// and the product `+.×` of real matrices. `⌹` solves by Householder QR
+// decomposition in doubles, so an overdetermined system gives its least
+// squares solution; decode and encode keep to `Number` arithmetic, so that
+// exact integers stay exact.
+
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::number::Number;
+
+// A real matrix in row-major order.
+struct Matrix {
+    rows: usize,
+    columns: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    // A scalar is a 1 by 1 matrix and a vector a single column.
+    fn from_array(array: &Array) -> anyhow::Result<Self> {
+        let (rows, columns) = match *array.shape() {
+            [] => (1, 1),
+            [rows] => (rows, 1),
+            [rows, columns] => (rows, columns),
+            _ => anyhow::bail!(Errors::Rank(format!(
+                "⌹ expects a matrix, got rank {}",
+                array.rank()
+            ))),
+        };
+        let data = array
+            .data()
+            .iter()
+            .map(|scalar| match scalar.as_number()?.as_real() {
+                Some(n) => Ok(n),
+                None => anyhow::bail!(Errors::Nonce(
+                    "⌹ of complex numbers is not supported yet".to_string()
+                )),
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Matrix {
+            rows,
+            columns,
+            data,
+        })
+    }
+
+    fn identity(size: usize) -> Self {
+        let mut data = vec![0.0; size * size];
+        for i in 0..size {
+            data[i * size + i] = 1.0;
+        }
+        Matrix {
+            rows: size,
+            columns: size,
+            data,
+        }
+    }
+
+    fn at(&self, row: usize, column: usize) -> f64 {
+        self.data[row * self.columns + column]
+    }
+}
+
+// The Householder reflectors that take a matrix with at least as many rows
+// as columns to upper triangular R, applied to the matrix in place. Each
+// reflector `v` acts on the rows from its own index down.
+fn decompose(matrix: &mut Matrix) -> Vec<Vec<f64>> {
+    let (rows, columns) = (matrix.rows, matrix.columns);
+    let mut reflectors = Vec::with_capacity(columns);
+    for j in 0..columns {
+        let mut v: Vec<f64> = (j..rows).map(|i| matrix.at(i, j)).collect();
+        let norm = v.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm != 0.0 {
+            // Reflect away from the sign of the pivot, so as not to cancel
+            v[0] += norm.copysign(v[0]);
+        }
+        reflect(&v, j, matrix);
+        reflectors.push(v);
+    }
+    reflectors
+}
+
+// Applies I - 2vvᵀ/vᵀv to the rows of `matrix` from `offset` down.
+fn reflect(v: &[f64], offset: usize, matrix: &mut Matrix) {
+    let length: f64 = v.iter().map(|x| x * x).sum();
+    if length == 0.0 {
+        return;
+    }
+    for column in 0..matrix.columns {
+        let dot: f64 = v
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x * matrix.at(offset + i, column))
+            .sum();
+        let factor = 2.0 * dot / length;
+        for (i, x) in v.iter().enumerate() {
+            matrix.data[(offset + i) * matrix.columns + column] -= factor * x;
+        }
+    }
+}
+
+// Solves `coefficients` x = `values` in the least squares sense, giving x
+// as a matrix of as many columns as `values` has.
+fn solve(mut coefficients: Matrix, mut values: Matrix) -> anyhow::Result<Matrix> {
+    if coefficients.rows != values.rows {
+        anyhow::bail!(Errors::Length(format!(
+            "⌹ expects {} rows on the left, got {}",
+            coefficients.rows, values.rows
+        )));
+    }
+    if coefficients.rows < coefficients.columns {
+        anyhow::bail!(Errors::Length(format!(
+            "⌹ expects at least as many rows as columns, got {} by {}",
+            coefficients.rows, coefficients.columns
+        )));
+    }
+
+    let reflectors = decompose(&mut coefficients);
+    for (j, v) in reflectors.iter().enumerate() {
+        reflect(v, j, &mut values);
+    }
+
+    // R is singular if a pivot is negligible beside the largest
+    let n = coefficients.columns;
+    let largest = (0..n)
+        .map(|i| coefficients.at(i, i).abs())
+        .fold(0.0, f64::max);
+    let tolerance = largest * f64::EPSILON * coefficients.rows as f64;
+    if (0..n).any(|i| coefficients.at(i, i).abs() <= tolerance) {
+        anyhow::bail!(Errors::Domain("⌹ of a singular matrix".to_string()));
+    }
+
+    let k = values.columns;
+    let mut solution = vec![0.0; n * k];
+    for column in 0..k {
+        for i in (0..n).rev() {
+            let known: f64 = (i + 1..n)
+                .map(|j| coefficients.at(i, j) * solution[j * k + column])
+                .sum();
+            solution[i * k + column] = (values.at(i, column) - known) / coefficients.at(i, i);
+        }
+    }
+    Ok(Matrix {
+        rows: n,
+        columns: k,
+        data: solution,
+    })
+}
+
+fn reals(shape: Vec<usize>, data: Vec<f64>) -> Array {
+    Array::new(shape, data.into_iter().map(Scalar::from).collect())
+}
+
+/// Monadic `⌹`: the inverse of a square matrix, or for one with more rows
+/// than columns its left inverse, which gives least squares solutions.
+pub fn inverse(right: &Array) -> anyhow::Result<Array> {
+    let matrix = Matrix::from_array(right)?;
+    let identity = Matrix::identity(matrix.rows);
+    let shape = right.shape().iter().rev().copied().collect();
+    Ok(reals(shape, solve(matrix, identity)?.data))
+}
+
+/// Dyadic `⌹`: solves `right` x = `left` for x, in the least squares sense
+/// when there are more equations than unknowns. The result has the shape
+/// `(1↓⍴⍵),1↓⍴⍺`.
+pub fn divide(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    let solution = solve(Matrix::from_array(right)?, Matrix::from_array(left)?)?;
+    let mut shape = right.shape().get(1..).unwrap_or_default().to_vec();
+    shape.extend_from_slice(left.shape().get(1..).unwrap_or_default());
+    Ok(reals(shape, solution.data))
+}
+
+// The length of the last axis of `left` and the first of `right`, which
+// `⊥` pairs up, extending a length of 1 to the other.
+fn paired_length(left: &Array, right: &Array) -> anyhow::Result<usize> {
+    let left_length = left.shape().last().copied().unwrap_or(1);
+    let right_length = right.shape().first().copied().unwrap_or(1);
+    match (left_length, right_length) {
+        (a, b) if a == b => Ok(a),
+        (1, length) | (length, 1) => Ok(length),
+        (a, b) => anyhow::bail!(Errors::Length(format!(
+            "⊥ expects rows of {} to meet columns of {}",
+            a, b
+        ))),
+    }
+}
+
+// The leading axes of `left` and the trailing axes of `right`, which the
+// result of `⊥` and `+.×` is laid out in.
+fn frames<'a>(left: &'a Array, right: &'a Array) -> (&'a [usize], &'a [usize]) {
+    (
+        &left.shape()[..left.rank().saturating_sub(1)],
+        right.shape().get(1..).unwrap_or_default(),
+    )
+}
+
+fn product_shape(left: &Array, right: &Array) -> Vec<usize> {
+    let (rows, columns) = frames(left, right);
+    [rows, columns].concat()
+}
+
+/// Dyadic `⊥`: decode. Each column of `right` holds digits in the radices
+/// of a row of `left`, most significant first; the result is their value.
+pub fn decode(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    let length = paired_length(left, right)?;
+    let (left_length, right_length) = (
+        left.shape().last().copied().unwrap_or(1),
+        right.shape().first().copied().unwrap_or(1),
+    );
+    let (rows, columns) = frames(left, right);
+    let (rows, columns): (usize, usize) = (rows.iter().product(), columns.iter().product());
+    let radix =
+        |row: usize, i: usize| left.data()[row * left_length + i.min(left_length - 1)].as_number();
+    let digit = |i: usize, column: usize| {
+        right.data()[i.min(right_length - 1) * columns + column].as_number()
+    };
+
+    let mut data = Vec::with_capacity(rows * columns);
+    for row in 0..rows {
+        for column in 0..columns {
+            // By Horner's rule, starting from the leading digit so that its
+            // radix, which carries no weight, never comes into it
+            let mut value = match length {
+                0 => Number::Real(0.0),
+                _ => digit(0, column)?,
+            };
+            for i in 1..length {
+                value = value.multiply(radix(row, i)?)?.add(digit(i, column)?)?;
+            }
+            data.push(Scalar::Number(value));
+        }
+    }
+    Ok(Array::new(product_shape(left, right), data))
+}
+
+/// Dyadic `⊤`: encode. Each element of `right` is written in the radices of
+/// each column of `left`, giving digits along a new leading axis. A radix
+/// of 0 takes whatever is left of the value.
+pub fn encode(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    let length = left.shape().first().copied().unwrap_or(1);
+    let columns = left.data().len() / length.max(1);
+    let count = right.data().len();
+
+    let mut data = vec![Scalar::from(0.0); left.data().len() * count];
+    for column in 0..columns {
+        for (k, element) in right.data().iter().enumerate() {
+            let mut value = element.as_number()?;
+            for i in (0..length).rev() {
+                let radix = left.data()[i * columns + column].as_number()?;
+                let digit = match radix.is_zero() {
+                    true => std::mem::replace(&mut value, Number::Real(0.0)),
+                    false => {
+                        let digit = radix.clone().residue(value.clone())?;
+                        value = value.subtract(digit.clone())?.divide(radix)?;
+                        digit
+                    }
+                };
+                data[(i * columns + column) * count + k] = Scalar::Number(digit);
+            }
+        }
+    }
+
+    let mut shape = left.shape().to_vec();
+    shape.extend_from_slice(right.shape());
+    Ok(Array::new(shape, data))
+}
+
+// The elements of a non-scalar array of doubles, or none if any element is
+// another kind of number, which `+.×` leaves to the general inner product
+// so as to keep its type.
+fn doubles(array: &Array) -> Option<Vec<f64>> {
+    if array.is_scalar() {
+        return None;
+    }
+    array
+        .data()
+        .iter()
+        .map(|scalar| match scalar {
+            Scalar::Number(Number::Real(n)) => Some(*n),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `+.×` of two arrays of doubles, multiplied directly rather than through
+/// the general inner product. Gives `None` for any other arguments.
+pub fn matrix_product(left: &Array, right: &Array) -> Option<Array> {
+    let (a, b) = (doubles(left)?, doubles(right)?);
+    let length = match (left.shape().last(), right.shape().first()) {
+        (Some(a), Some(b)) if a == b => *a,
+        _ => return None,
+    };
+    let (rows, columns) = frames(left, right);
+    let (rows, columns): (usize, usize) = (rows.iter().product(), columns.iter().product());
+
+    // Row by row, adding multiples of the rows of `right`, so that the
+    // innermost loop runs along contiguous memory
+    let mut product = vec![0.0; rows * columns];
+    for (row, sums) in product.chunks_mut(columns.max(1)).enumerate().take(rows) {
+        for k in 0..length {
+            let factor = a[row * length + k];
+            for (sum, x) in sums.iter_mut().zip(&b[k * columns..(k + 1) * columns]) {
+                *sum += factor * x;
+            }
+        }
+    }
+    Some(reals(product_shape(left, right), product))
+}
+
+// Expected values are what Dyalog APL 18.2 gives.
+#[cfg(test)]
+mod tests {
+    use super::{decode, divide, encode, inverse, matrix_product};
+    use crate::runtime::array::{Array, Scalar};
+    use crate::runtime::tests::numbers;
+
+    fn matrix(rows: usize, ns: &[f64]) -> Array {
+        Array::new(
+            vec![rows, ns.len() / rows],
+            ns.iter().map(|n| Scalar::from(*n)).collect(),
+        )
+    }
+
+    fn assert_close(result: &Array, expected: &Array) {
+        assert_eq!(result.shape(), expected.shape());
+        for (a, b) in result.data().iter().zip(expected.data()) {
+            let (a, b) = (a.as_real().unwrap(), b.as_real().unwrap());
+            assert!((a - b).abs() < 1e-12, "{:?} is not {:?}", result, expected);
+        }
+    }
+
+    #[test]
+    fn it_inverts_matrices() {
+        // ⌹2 2⍴4 7 2 6 ←→ 2 2⍴0.6 ¯0.7 ¯0.2 0.4
+        assert_close(
+            &inverse(&matrix(2, &[4.0, 7.0, 2.0, 6.0])).unwrap(),
+            &matrix(2, &[0.6, -0.7, -0.2, 0.4]),
+        );
+        // ⌹4 ←→ 0.25, ⌹2 2 ←→ 0.25 0.25
+        assert_close(&inverse(&Array::scalar(4.0)).unwrap(), &Array::scalar(0.25));
+        assert_close(
+            &inverse(&numbers(&[2.0, 2.0])).unwrap(),
+            &numbers(&[0.25, 0.25]),
+        );
+        // The left inverse of a tall matrix: (⌹M)+.×M ←→ identity
+        let tall = matrix(3, &[1.0, 1.0, 1.0, 2.0, 1.0, 4.0]);
+        let left = inverse(&tall).unwrap();
+        assert_eq!(left.shape(), &[2, 3]);
+        assert_close(
+            &matrix_product(&left, &tall).unwrap(),
+            &matrix(2, &[1.0, 0.0, 0.0, 1.0]),
+        );
+
+        assert!(inverse(&matrix(2, &[1.0, 2.0, 2.0, 4.0])).is_err());
+        assert!(inverse(&matrix(2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0])).is_err());
+        assert!(inverse(&Array::scalar(0.0)).is_err());
+    }
+
+    #[test]
+    fn it_solves_by_least_squares() {
+        // 5 6⌹2 2⍴1 2 3 4 ←→ ¯4 4.5
+        assert_close(
+            &divide(&numbers(&[5.0, 6.0]), &matrix(2, &[1.0, 2.0, 3.0, 4.0])).unwrap(),
+            &numbers(&[-4.0, 4.5]),
+        );
+        // The line through (1 1) (2 3) (3 2) fits best as 1+0.5×x
+        let design = matrix(3, &[1.0, 1.0, 1.0, 2.0, 1.0, 3.0]);
+        assert_close(
+            &divide(&numbers(&[1.0, 3.0, 2.0]), &design).unwrap(),
+            &numbers(&[1.0, 0.5]),
+        );
+        // A matrix on the left solves for each of its columns
+        let both = matrix(3, &[1.0, 2.0, 3.0, 4.0, 2.0, 6.0]);
+        assert_close(
+            &divide(&both, &design).unwrap(),
+            &matrix(2, &[1.0, 0.0, 0.5, 2.0]),
+        );
+        // 6⌹3 ←→ 2
+        assert_close(
+            &divide(&Array::scalar(6.0), &Array::scalar(3.0)).unwrap(),
+            &Array::scalar(2.0),
+        );
+        assert!(divide(&numbers(&[1.0, 2.0]), &design).is_err());
+    }
+
+    #[test]
+    fn it_decodes() {
+        // 2⊥1 0 1 ←→ 5, 24 60 60⊥2 46 40 ←→ 10000
+        assert_eq!(
+            decode(&Array::scalar(2.0), &numbers(&[1.0, 0.0, 1.0])).unwrap(),
+            Array::scalar(5.0)
+        );
+        assert_eq!(
+            decode(&numbers(&[24.0, 60.0, 60.0]), &numbers(&[2.0, 46.0, 40.0])).unwrap(),
+            Array::scalar(10000.0)
+        );
+        // 10⊥3 2⍴1 2 3 4 5 6 ←→ 135 246
+        assert_eq!(
+            decode(
+                &Array::scalar(10.0),
+                &matrix(3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
+            )
+            .unwrap(),
+            numbers(&[135.0, 246.0])
+        );
+        // 2⊥⍬ ←→ 0
+        assert_eq!(
+            decode(&Array::scalar(2.0), &numbers(&[])).unwrap(),
+            Array::scalar(0.0)
+        );
+        assert!(decode(&numbers(&[2.0, 2.0]), &numbers(&[1.0, 0.0, 1.0])).is_err());
+    }
+
+    #[test]
+    fn it_encodes() {
+        // 24 60 60⊤10000 ←→ 2 46 40
+        assert_eq!(
+            encode(&numbers(&[24.0, 60.0, 60.0]), &Array::scalar(10000.0)).unwrap(),
+            numbers(&[2.0, 46.0, 40.0])
+        );
+        // 2 2 2⊤5 6 ←→ 3 2⍴1 1 0 1 1 0
+        assert_eq!(
+            encode(&numbers(&[2.0, 2.0, 2.0]), &numbers(&[5.0, 6.0])).unwrap(),
+            matrix(3, &[1.0, 1.0, 0.0, 1.0, 1.0, 0.0])
+        );
+        // 0 10⊤125 ←→ 12 5, 2 2⊤¯1 ←→ 1 1
+        assert_eq!(
+            encode(&numbers(&[0.0, 10.0]), &Array::scalar(125.0)).unwrap(),
+            numbers(&[12.0, 5.0])
+        );
+        assert_eq!(
+            encode(&numbers(&[2.0, 2.0]), &Array::scalar(-1.0)).unwrap(),
+            numbers(&[1.0, 1.0])
+        );
+        // Each column of the left is a radix vector: (2 2⍴10 2 10 2)⊤7 ←→ 2 2⍴0 1 7 1
+        assert_eq!(
+            encode(&matrix(2, &[10.0, 2.0, 10.0, 2.0]), &Array::scalar(7.0)).unwrap(),
+            matrix(2, &[0.0, 1.0, 7.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn it_multiplies_matrices() {
+        // (2 3⍴⍳6)+.×3 2⍴⍳6 ←→ 2 2⍴22 28 49 64
+        let a = matrix(2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let b = matrix(3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(
+            matrix_product(&a, &b).unwrap(),
+            matrix(2, &[22.0, 28.0, 49.0, 64.0])
+        );
+        assert_eq!(
+            matrix_product(&numbers(&[1.0, 2.0, 3.0]), &b).unwrap(),
+            numbers(&[22.0, 28.0])
+        );
+        assert_eq!(
+            matrix_product(&numbers(&[]), &numbers(&[])).unwrap(),
+            Array::scalar(0.0)
+        );
+        // Anything else goes the general way
+        assert!(matrix_product(&Array::scalar(1.0), &b).is_none());
+        assert!(matrix_product(&numbers(&[1.0, 2.0]), &b).is_none());
+        assert!(matrix_product(&Array::from_string_literal("ab"), &numbers(&[1.0, 2.0])).is_none());
+    }
+}
@@ -0,0 +1,164 @@
+// The logical functions `~ ∧ ∨ ⍲ ⍱`. They are scalar functions, but on
+// simple boolean arrays they work a word of packed bits at a time rather
+// than pervading element by element.
+
+use crate::runtime::array::Array;
+use crate::runtime::bits::Bits;
+use crate::runtime::number::Number;
+use crate::runtime::primitives::scalar::{self, DyadicScalar};
+use crate::tokenizer::Token;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Logic {
+    And,
+    Or,
+    Nand,
+    Nor,
+}
+
+impl Logic {
+    pub fn from_token(token: &Token) -> Option<Self> {
+        Some(match token {
+            Token::LogicalAND => Logic::And,
+            Token::LogicalOR => Logic::Or,
+            Token::LogicalNAND => Logic::Nand,
+            Token::LogicalNOR => Logic::Nor,
+            _ => return None,
+        })
+    }
+
+    fn word(self, a: u64, b: u64) -> u64 {
+        match self {
+            Logic::And => a & b,
+            Logic::Or => a | b,
+            Logic::Nand => !(a & b),
+            Logic::Nor => !(a | b),
+        }
+    }
+
+    fn scalar(self) -> DyadicScalar {
+        match self {
+            Logic::And => Number::and,
+            Logic::Or => Number::or,
+            Logic::Nand => Number::nand,
+            Logic::Nor => Number::nor,
+        }
+    }
+}
+
+fn packable(array: &Array) -> bool {
+    array.is_simple() && !array.data().is_empty()
+}
+
+/// Monadic `~`: not.
+pub fn not(right: &Array) -> anyhow::Result<Array> {
+    if packable(right) {
+        if let Some(bits) = right.as_bits() {
+            return Ok(Array::from_bits(
+                right.shape().to_vec(),
+                bits.map(|word| !word),
+            ));
+        }
+    }
+    scalar::monadic(Number::not, right)
+}
+
+/// Dyadic `∧ ∨ ⍲ ⍱`, extending a scalar on either side.
+pub fn logical(logic: Logic, left: &Array, right: &Array) -> anyhow::Result<Array> {
+    let shape = match (left.is_scalar(), right.is_scalar()) {
+        (true, _) => right.shape(),
+        (false, true) => left.shape(),
+        (false, false) if left.shape() == right.shape() => left.shape(),
+        // Let the pervasion report the mismatch
+        (false, false) => return scalar::dyadic(logic.scalar(), left, right),
+    };
+    let length = shape.iter().product();
+
+    if packable(left) && packable(right) {
+        if let (Some(a), Some(b)) = (left.as_bits(), right.as_bits()) {
+            return Ok(Array::from_bits(
+                shape.to_vec(),
+                extend(&a, length).zip(&extend(&b, length), |a, b| logic.word(a, b)),
+            ));
+        }
+    }
+    scalar::dyadic(logic.scalar(), left, right)
+}
+
+// A scalar's bit repeated `length` times, or the bits as they are.
+fn extend(bits: &Bits, length: usize) -> Bits {
+    match (bits.len() == length, bits.get(0)) {
+        (true, _) => bits.clone(),
+        (false, true) => Bits::ones(length),
+        (false, false) => Bits::zeros(length),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{logical, not, Logic};
+    use crate::runtime::array::{Array, Scalar};
+    use crate::runtime::tests::numbers;
+
+    fn booleans(bs: &[bool]) -> Array {
+        Array::vector(bs.iter().map(|b| Scalar::from(*b)).collect())
+    }
+
+    #[test]
+    fn it_computes_a_word_at_a_time() {
+        let a: Vec<bool> = (0..100).map(|i| i % 2 == 0).collect();
+        let b: Vec<bool> = (0..100).map(|i| i % 3 == 0).collect();
+        let expect = |f: fn(bool, bool) -> bool| {
+            booleans(&a.iter().zip(&b).map(|(a, b)| f(*a, *b)).collect::<Vec<_>>())
+        };
+        let (a_array, b_array) = (booleans(&a), booleans(&b));
+
+        for (logic, f) in [
+            (Logic::And, (|a, b| a && b) as fn(bool, bool) -> bool),
+            (Logic::Or, |a, b| a || b),
+            (Logic::Nand, |a, b| !(a && b)),
+            (Logic::Nor, |a, b| !(a || b)),
+        ] {
+            assert_eq!(logical(logic, &a_array, &b_array).unwrap(), expect(f));
+        }
+        assert_eq!(
+            not(&a_array).unwrap(),
+            booleans(&a.iter().map(|a| !a).collect::<Vec<_>>())
+        );
+    }
+
+    #[test]
+    fn it_extends_scalars_and_takes_any_zeros_and_ones() {
+        // 1 ∧ 0 1 1b ←→ 0 1 1
+        assert_eq!(
+            logical(
+                Logic::And,
+                &Array::scalar(1.0),
+                &booleans(&[false, true, true])
+            )
+            .unwrap(),
+            booleans(&[false, true, true])
+        );
+        // 0 1 0 ∨ 0 ←→ 0 1 0
+        assert_eq!(
+            logical(Logic::Or, &numbers(&[0.0, 1.0, 0.0]), &Array::scalar(0.0)).unwrap(),
+            booleans(&[false, true, false])
+        );
+        assert_eq!(
+            logical(Logic::Nor, &Array::scalar(true), &Array::scalar(false)).unwrap(),
+            Array::scalar(false)
+        );
+        assert_eq!(not(&Array::scalar(0.0)).unwrap(), Array::scalar(true));
+
+        assert!(logical(Logic::And, &numbers(&[2.0]), &numbers(&[1.0])).is_err());
+        assert!(logical(Logic::And, &booleans(&[true]), &booleans(&[true, false])).is_err());
+        assert!(not(&Array::scalar('a')).is_err());
+
+        // Nested arrays pervade: ~(1 0)(1) ←→ (0 1)(0)
+        let nested = Array::vector(vec![booleans(&[true, false]).enclose(), true.into()]);
+        assert_eq!(
+            not(&nested).unwrap(),
+            Array::vector(vec![booleans(&[false, true]).enclose(), false.into()])
+        );
+    }
+}
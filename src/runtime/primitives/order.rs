@@ -0,0 +1,309 @@
+// Total array ordering, which `⍋` and `⍒` sort by and `⍸` searches by.
+// Any two arrays are ordered: numbers come before characters, and simple
+// scalars before boxes. Numbers go by value (see `Number::order`) and
+// characters by code point. Arrays compare item by item in ravel order;
+// if one runs out first, or they are equal throughout, the one with fewer
+// items, then lower rank, then shorter leading axes comes first.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::number::Number;
+use crate::runtime::primitives::search::major_cells;
+
+/// Compares two scalars in total array ordering.
+pub fn compare(a: &Scalar, b: &Scalar) -> Ordering {
+    fn kind(scalar: &Scalar) -> u8 {
+        match scalar {
+            Scalar::Number(_) => 0,
+            Scalar::Char(_) => 1,
+            Scalar::Boxed(_) => 2,
+        }
+    }
+
+    match (a, b) {
+        (Scalar::Number(a), Scalar::Number(b)) => a.order(b),
+        (Scalar::Char(a), Scalar::Char(b)) => a.cmp(b),
+        (Scalar::Boxed(a), Scalar::Boxed(b)) => compare_items(a.data(), b.data())
+            .then_with(|| a.rank().cmp(&b.rank()))
+            .then_with(|| a.shape().cmp(b.shape())),
+        _ => kind(a).cmp(&kind(b)),
+    }
+}
+
+// Compares two runs of items in order, the shorter first if one is the
+// start of the other.
+fn compare_items(a: &[Scalar], b: &[Scalar]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| compare(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+// The elements of a vector of real numbers that doubles hold exactly, to
+// sort without going through `Number`.
+fn doubles(array: &Array) -> Option<Vec<f64>> {
+    const EXACT: i128 = 1 << 53;
+
+    if array.rank() != 1 {
+        return None;
+    }
+    array
+        .data()
+        .iter()
+        .map(|scalar| match scalar {
+            Scalar::Number(Number::Real(n)) => Some(*n),
+            Scalar::Number(Number::Float(f)) => Some(f.to_f64()),
+            Scalar::Number(Number::Int(i)) if i.value().abs() <= EXACT => Some(i.to_f64()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn indices(order: Vec<usize>) -> Array {
+    Array::vector(
+        order
+            .into_iter()
+            .map(|i| Scalar::from((i + 1) as f64))
+            .collect(),
+    )
+}
+
+/// Monadic `⍋` and `⍒`: the indices that sort the major cells of `right`
+/// into ascending or descending order. Equal cells keep their order.
+pub fn grade(right: &Array, descending: bool) -> anyhow::Result<Array> {
+    if right.is_scalar() {
+        anyhow::bail!(Errors::Rank("grade expects a non-scalar".to_string()));
+    }
+    let directed = |ordering: Ordering| match descending {
+        true => ordering.reverse(),
+        false => ordering,
+    };
+
+    let mut order: Vec<usize> = (0..right.tally()).collect();
+    match doubles(right) {
+        Some(keys) => order
+            .sort_by(|&i, &j| directed(keys[i].partial_cmp(&keys[j]).unwrap_or(Ordering::Equal))),
+        None => {
+            let cells = major_cells(right);
+            order.sort_by(|&i, &j| directed(compare_items(cells[i], cells[j])));
+        }
+    }
+    Ok(indices(order))
+}
+
+/// Dyadic `⍋` and `⍒`: grades the major cells of a character array by the
+/// collating sequence `left`. Each character ranks by its first position
+/// in `left`, and those not in it come after all the rest.
+pub fn grade_collated(left: &Array, right: &Array, descending: bool) -> anyhow::Result<Array> {
+    if left.rank() > 1 {
+        anyhow::bail!(Errors::Nonce(
+            "collating sequences of more than one level are not supported yet".to_string()
+        ));
+    }
+    if right.is_scalar() {
+        anyhow::bail!(Errors::Rank("grade expects a non-scalar".to_string()));
+    }
+    if !left.is_char() || !right.is_char() {
+        anyhow::bail!(Errors::Domain(
+            "grading by a collating sequence expects characters".to_string()
+        ));
+    }
+
+    let mut positions = HashMap::with_capacity(left.data().len());
+    for (i, scalar) in left.data().iter().enumerate() {
+        if let Scalar::Char(c) = scalar {
+            positions.entry(*c).or_insert(i);
+        }
+    }
+    let missing = left.data().len();
+    let ranks: Vec<usize> = right
+        .data()
+        .iter()
+        .map(|scalar| match scalar {
+            Scalar::Char(c) => positions.get(c).copied().unwrap_or(missing),
+            _ => missing,
+        })
+        .collect();
+
+    let count = right.tally();
+    let width = match count {
+        0 => 0,
+        count => ranks.len() / count,
+    };
+    let row = |i: usize| &ranks[i * width..(i + 1) * width];
+    let mut order: Vec<usize> = (0..count).collect();
+    order.sort_by(|&i, &j| match descending {
+        true => row(j).cmp(row(i)),
+        false => row(i).cmp(row(j)),
+    });
+    Ok(indices(order))
+}
+
+/// Dyadic `⍸`: interval index. For each element of `right`, how many items
+/// of the ascending vector `left` are at most it, found by binary search.
+pub fn interval_index(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    if left.rank() != 1 {
+        anyhow::bail!(Errors::Rank("⍸ expects a vector on the left".to_string()));
+    }
+    let items = left.data();
+    if items
+        .windows(2)
+        .any(|pair| compare(&pair[0], &pair[1]).is_gt())
+    {
+        anyhow::bail!(Errors::Domain(
+            "⍸ expects its left argument in ascending order".to_string()
+        ));
+    }
+
+    Ok(Array::new(
+        right.shape().to_vec(),
+        right
+            .data()
+            .iter()
+            .map(|scalar| {
+                let count = items.partition_point(|item| compare(item, scalar).is_le());
+                Scalar::from(count as f64)
+            })
+            .collect(),
+    ))
+}
+
+// Expected values are what Dyalog APL 18.2 gives with ⎕IO←1.
+#[cfg(test)]
+mod tests {
+    use super::{compare, grade, grade_collated, interval_index};
+    use crate::runtime::array::{Array, Scalar};
+    use crate::runtime::number::{Int, IntType, Number};
+    use crate::runtime::tests::numbers;
+    use std::cmp::Ordering;
+
+    fn chars(s: &str) -> Array {
+        Array::from_string_literal(s)
+    }
+
+    fn matrix(rows: &[&str]) -> Array {
+        let width = rows[0].chars().count();
+        Array::new(
+            vec![rows.len(), width],
+            rows.iter()
+                .flat_map(|row| row.chars().map(Scalar::Char))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn it_orders_every_array() {
+        let one = Scalar::from(1.0);
+        assert_eq!(compare(&one, &Scalar::from('a')), Ordering::Less);
+        assert_eq!(
+            compare(&Scalar::from('b'), &Scalar::from('a')),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare(&chars("ab").enclose(), &Scalar::from('z')),
+            Ordering::Greater
+        );
+        // 'ab' comes before 'abc', and both before 'ba'
+        assert_eq!(
+            compare(&chars("ab").enclose(), &chars("abc").enclose()),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare(&chars("abc").enclose(), &chars("ba").enclose()),
+            Ordering::Less
+        );
+        // 1b and 1.0 are the same number
+        assert_eq!(compare(&true.into(), &one), Ordering::Equal);
+    }
+
+    #[test]
+    fn it_grades_stably() {
+        // ⍋3 1 2 1 ←→ 2 4 3 1, ⍒3 1 2 1 ←→ 1 3 2 4
+        let ns = numbers(&[3.0, 1.0, 2.0, 1.0]);
+        assert_eq!(grade(&ns, false).unwrap(), numbers(&[2.0, 4.0, 3.0, 1.0]));
+        assert_eq!(grade(&ns, true).unwrap(), numbers(&[1.0, 3.0, 2.0, 4.0]));
+
+        // Rows of a matrix compare item by item
+        let rows = matrix(&["bca", "abc", "bab"]);
+        assert_eq!(grade(&rows, false).unwrap(), numbers(&[2.0, 3.0, 1.0]));
+
+        // Mixed and nested: 'b' (1 2) 3 'a' grades to 3 4 1 2
+        let mixed = Array::vector(vec![
+            'b'.into(),
+            numbers(&[1.0, 2.0]).enclose(),
+            3.0.into(),
+            'a'.into(),
+        ]);
+        assert_eq!(
+            grade(&mixed, false).unwrap(),
+            numbers(&[3.0, 4.0, 1.0, 2.0])
+        );
+        assert!(grade(&Array::scalar(1.0), false).is_err());
+    }
+
+    #[test]
+    fn it_grades_large_integers_exactly() {
+        let big = |n: i128| Scalar::Number(Number::Int(Int::new(n, IntType::I64).unwrap()));
+        let ns = Array::vector(vec![big((1 << 60) + 1), big(1 << 60)]);
+        assert_eq!(grade(&ns, false).unwrap(), numbers(&[2.0, 1.0]));
+    }
+
+    #[test]
+    fn it_grades_by_a_collating_sequence() {
+        // 'cab'⍋'abcd' ←→ 3 1 2 4
+        assert_eq!(
+            grade_collated(&chars("cab"), &chars("abcd"), false).unwrap(),
+            numbers(&[3.0, 1.0, 2.0, 4.0])
+        );
+        let rows = matrix(&["ba", "ab", "bb"]);
+        assert_eq!(
+            grade_collated(&chars("ba"), &rows, true).unwrap(),
+            numbers(&[2.0, 1.0, 3.0])
+        );
+        assert!(grade_collated(&chars("ab"), &numbers(&[1.0]), false).is_err());
+    }
+
+    #[test]
+    fn it_finds_intervals() {
+        // 10 20 30⍸11 1 31 20 ←→ 1 0 3 2
+        assert_eq!(
+            interval_index(
+                &numbers(&[10.0, 20.0, 30.0]),
+                &numbers(&[11.0, 1.0, 31.0, 20.0])
+            )
+            .unwrap(),
+            numbers(&[1.0, 0.0, 3.0, 2.0])
+        );
+        // 'aeiou'⍸'hello' ←→ 2 2 3 3 4
+        assert_eq!(
+            interval_index(&chars("aeiou"), &chars("hello")).unwrap(),
+            numbers(&[2.0, 2.0, 3.0, 3.0, 4.0])
+        );
+        assert!(interval_index(&numbers(&[2.0, 1.0]), &Array::scalar(1.0)).is_err());
+    }
+
+    #[test]
+    fn it_grades_a_million_elements() {
+        let count: u64 = 1_000_000;
+        let ns = numbers(
+            &(0..count)
+                .map(|i| ((i * 7919) % count) as f64)
+                .collect::<Vec<_>>(),
+        );
+        let order = grade(&ns, false).unwrap();
+        let sorted: Vec<f64> = order
+            .data()
+            .iter()
+            .map(|i| {
+                ns.data()[i.as_real().unwrap() as usize - 1]
+                    .as_real()
+                    .unwrap()
+            })
+            .collect();
+        assert!(sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
@@ -0,0 +1,409 @@
+// `?` roll and deal, and the generator they draw from. The generator is
+// set through `⎕RL`, the random link: a seed and the number of an
+// algorithm, as in Dyalog APL.
+//
+//   0  Lehmer's generator with Park and Miller's constants: each state is
+//      16807 times the last, modulo 2147483647. Its draws have 31 bits.
+//   1  The Mersenne Twister MT19937, as published by Matsumoto and
+//      Nishimura in 1998. Its draws have 53 bits. The default.
+//
+// Seeds run from 1 to 2147483646, and a seed of 0 takes one from the clock;
+// `⎕RL` reads back the seed it was set to either way. Setting the same
+// link again replays the same draws, in any session and on any platform.
+// Each session has a generator of its own, which it puts in place in the
+// thread running it while it runs code.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::operators::each;
+use crate::runtime::primitives::format::format_number;
+
+const MODULUS: u64 = (1 << 31) - 1;
+const DEFAULT_SEED: u64 = 16807;
+
+// The largest argument to roll or deal: above it doubles skip integers.
+const LARGEST: f64 = (1u64 << 53) as f64;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Algorithm {
+    Lehmer,
+    MersenneTwister,
+}
+
+impl Algorithm {
+    fn from_number(n: f64) -> anyhow::Result<Self> {
+        match n {
+            0.0 => Ok(Algorithm::Lehmer),
+            1.0 => Ok(Algorithm::MersenneTwister),
+            2.0 => {
+                anyhow::bail!(Errors::Nonce(
+                    "the operating system generator is not supported yet".to_string()
+                ))
+            }
+            n => anyhow::bail!(Errors::Domain(format!(
+                "there is no random generator {}",
+                n
+            ))),
+        }
+    }
+
+    fn number(self) -> f64 {
+        match self {
+            Algorithm::Lehmer => 0.0,
+            Algorithm::MersenneTwister => 1.0,
+        }
+    }
+}
+
+const TWISTER_SIZE: usize = 624;
+
+#[derive(Clone)]
+struct Twister {
+    state: [u32; TWISTER_SIZE],
+    index: usize,
+}
+
+impl Twister {
+    fn new(seed: u32) -> Self {
+        let mut state = [0; TWISTER_SIZE];
+        state[0] = seed;
+        for i in 1..TWISTER_SIZE {
+            let last = state[i - 1];
+            state[i] = 1812433253u32
+                .wrapping_mul(last ^ (last >> 30))
+                .wrapping_add(i as u32);
+        }
+        Twister {
+            state,
+            index: TWISTER_SIZE,
+        }
+    }
+
+    fn twist(&mut self) {
+        for i in 0..TWISTER_SIZE {
+            let y =
+                (self.state[i] & 0x8000_0000) | (self.state[(i + 1) % TWISTER_SIZE] & 0x7fff_ffff);
+            let mut next = self.state[(i + 397) % TWISTER_SIZE] ^ (y >> 1);
+            if y & 1 == 1 {
+                next ^= 0x9908_b0df;
+            }
+            self.state[i] = next;
+        }
+        self.index = 0;
+    }
+
+    fn next(&mut self) -> u32 {
+        if self.index == TWISTER_SIZE {
+            self.twist();
+        }
+        let mut y = self.state[self.index];
+        self.index += 1;
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c_5680;
+        y ^= (y << 15) & 0xefc6_0000;
+        y ^ (y >> 18)
+    }
+}
+
+#[derive(Clone)]
+enum State {
+    Lehmer(u64),
+    Twister(Box<Twister>),
+}
+
+/// The state of `?`'s draws, from the link `⎕RL` last set.
+#[derive(Clone)]
+pub struct Generator {
+    seed: u64,
+    algorithm: Algorithm,
+    state: State,
+}
+
+impl Generator {
+    fn new(seed: u64, algorithm: Algorithm) -> Self {
+        let state = match algorithm {
+            Algorithm::Lehmer => State::Lehmer(seed),
+            Algorithm::MersenneTwister => State::Twister(Box::new(Twister::new(seed as u32))),
+        };
+        Generator {
+            seed,
+            algorithm,
+            state,
+        }
+    }
+
+    // A draw from the open interval (0, 1).
+    fn float(&mut self) -> f64 {
+        match &mut self.state {
+            State::Lehmer(state) => {
+                *state = *state * 16807 % MODULUS;
+                *state as f64 / MODULUS as f64
+            }
+            State::Twister(twister) => loop {
+                let (high, low) = (twister.next() >> 5, twister.next() >> 6);
+                let draw = (high as f64 * 67108864.0 + low as f64) / LARGEST;
+                if draw != 0.0 {
+                    break draw;
+                }
+            },
+        }
+    }
+
+    // A draw from 0 to `n`-1, each as likely as the others so far as the
+    // algorithm's bits allow.
+    fn below(&mut self, n: u64) -> u64 {
+        match &mut self.state {
+            State::Lehmer(_) => ((self.float() * n as f64) as u64).min(n - 1),
+            State::Twister(twister) => {
+                // Rejecting the top of the range that n doesn't divide
+                let zone = u64::MAX - u64::MAX % n;
+                loop {
+                    let draw = (twister.next() as u64) << 32 | twister.next() as u64;
+                    if draw < zone {
+                        break draw % n;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator::new(DEFAULT_SEED, Algorithm::MersenneTwister)
+    }
+}
+
+thread_local! {
+    // The generator of the session running in this thread.
+    static GENERATOR: RefCell<Generator> = RefCell::new(Generator::default());
+}
+
+fn draw<T>(f: impl FnOnce(&mut Generator) -> T) -> T {
+    GENERATOR.with(|generator| f(&mut generator.borrow_mut()))
+}
+
+/// The generator drawn from now, for a session to keep between runs.
+pub fn generator() -> Generator {
+    draw(|generator| generator.clone())
+}
+
+/// Puts a session's generator in place, to draw from until the next.
+pub fn set_generator(generator: Generator) {
+    draw(|current| *current = generator);
+}
+
+/// The value of `⎕RL`: the seed and algorithm last set.
+pub fn link() -> Array {
+    draw(|generator| {
+        Array::vector(vec![
+            Scalar::from(generator.seed as f64),
+            Scalar::from(generator.algorithm.number()),
+        ])
+    })
+}
+
+/// Assigns `⎕RL`: a seed, which keeps the algorithm, or a seed and an
+/// algorithm. Either way the generator starts again from the seed.
+pub fn set_link(value: &Array) -> anyhow::Result<()> {
+    let (seed, algorithm) = match (value.rank(), value.data()) {
+        (0 | 1, [seed]) => (seed, None),
+        (1, [seed, algorithm]) => (seed, Some(algorithm)),
+        _ => anyhow::bail!(Errors::Length("⎕RL is a seed and an algorithm".to_string())),
+    };
+    let algorithm = match algorithm {
+        Some(algorithm) => Algorithm::from_number(algorithm.as_real()?)?,
+        None => draw(|generator| generator.algorithm),
+    };
+    let seed = match seed.as_real()? {
+        0.0 => clock_seed(),
+        seed if seed.fract() == 0.0 && seed >= 1.0 && seed < MODULUS as f64 => seed as u64,
+        seed => anyhow::bail!(Errors::Domain(format!(
+            "random seeds run from 1 to {}, got {}",
+            MODULUS - 1,
+            format_number(seed)
+        ))),
+    };
+    draw(|generator| *generator = Generator::new(seed, algorithm));
+    Ok(())
+}
+
+fn clock_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    (nanos % (MODULUS - 1) as u128) as u64 + 1
+}
+
+// A count for roll or deal: a whole number that doubles hold exactly.
+fn whole(scalar: &Scalar) -> anyhow::Result<u64> {
+    match scalar.as_real()? {
+        n if n.fract() == 0.0 && (0.0..=LARGEST).contains(&n) => Ok(n as u64),
+        n => anyhow::bail!(Errors::Domain(format!(
+            "? expects whole numbers from 0 to 2*53, got {}",
+            format_number(n)
+        ))),
+    }
+}
+
+/// Monadic `?`: roll. Each element `n` of `right` becomes one of the first
+/// `n` indices from `origin` at random, or if it is 0 a number between 0
+/// and 1. Elements are drawn in ravel order.
+pub fn roll(right: Array, origin: usize) -> anyhow::Result<Array> {
+    if !right.is_simple() {
+        return each(&|item| roll(item, origin), right);
+    }
+    let data = right
+        .data()
+        .iter()
+        .map(|scalar| {
+            Ok(match whole(scalar)? {
+                0 => Scalar::from(draw(Generator::float)),
+                n => Scalar::from((draw(|generator| generator.below(n)) + origin as u64) as f64),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Array::new(right.shape().to_vec(), data))
+}
+
+/// Dyadic `?`: deal. `left` indices from the first `right`, all different,
+/// in random order.
+pub fn deal(left: &Array, right: &Array, origin: usize) -> anyhow::Result<Array> {
+    let single = |array: &Array| match (array.rank(), array.data()) {
+        (0 | 1, [n]) => whole(n),
+        _ => anyhow::bail!(Errors::Length(
+            "? expects single numbers to deal".to_string()
+        )),
+    };
+    let (count, size) = (single(left)?, single(right)?);
+    if count > size {
+        anyhow::bail!(Errors::Domain(format!(
+            "cannot deal {} from {}",
+            count, size
+        )));
+    }
+
+    // The first steps of a Fisher-Yates shuffle of ⍳size, keeping only the
+    // positions swapped when there are many more than are dealt
+    let dealt: Vec<u64> = match size <= count.saturating_mul(4) {
+        true => {
+            let mut indices: Vec<u64> = (0..size).collect();
+            for i in 0..count as usize {
+                let j = i + draw(|generator| generator.below(size - i as u64)) as usize;
+                indices.swap(i, j);
+            }
+            indices.truncate(count as usize);
+            indices
+        }
+        false => {
+            let mut swapped = HashMap::new();
+            (0..count)
+                .map(|i| {
+                    let j = i + draw(|generator| generator.below(size - i));
+                    let chosen = *swapped.get(&j).unwrap_or(&j);
+                    swapped.insert(j, *swapped.get(&i).unwrap_or(&i));
+                    chosen
+                })
+                .collect()
+        }
+    };
+    Ok(Array::vector(
+        dealt
+            .into_iter()
+            .map(|i| Scalar::from((i + origin as u64) as f64))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deal, link, roll, set_link, Algorithm, Generator, Twister};
+    use crate::runtime::array::{Array, Scalar};
+    use crate::runtime::tests::numbers;
+
+    fn values(array: &Array) -> Vec<u64> {
+        array
+            .data()
+            .iter()
+            .map(|n| n.as_real().unwrap() as u64)
+            .collect()
+    }
+
+    #[test]
+    fn it_follows_the_published_sequences() {
+        // Park and Miller's check: from a seed of 1 the 10000th state is
+        // 1043618065
+        let mut lehmer = Generator::new(1, Algorithm::Lehmer);
+        let last = (0..10000).map(|_| lehmer.float()).last().unwrap();
+        assert_eq!((last * ((1u64 << 31) - 1) as f64).round(), 1043618065.0);
+
+        // The reference implementation seeded with 5489
+        let mut twister = Twister::new(5489);
+        assert_eq!(twister.next(), 3499211612);
+        // ... and its 10000th output is 4123659995
+        let last = (1..10000).map(|_| twister.next()).last().unwrap();
+        assert_eq!(last, 4123659995);
+    }
+
+    #[test]
+    fn it_replays_from_a_seed() {
+        for algorithm in [0.0, 1.0] {
+            set_link(&numbers(&[42.0, algorithm])).unwrap();
+            let first = roll(Array::new(vec![100], vec![6.0.into(); 100]), 1).unwrap();
+            assert!(values(&first).iter().all(|n| (1..=6).contains(n)));
+            set_link(&numbers(&[42.0, algorithm])).unwrap();
+            let again = roll(Array::new(vec![100], vec![6.0.into(); 100]), 1).unwrap();
+            assert_eq!(first, again);
+        }
+        assert_eq!(link(), numbers(&[42.0, 1.0]));
+
+        // A seed alone keeps the algorithm, and 0 seeds from the clock
+        set_link(&Array::scalar(7.0)).unwrap();
+        assert_eq!(link(), numbers(&[7.0, 1.0]));
+        set_link(&numbers(&[0.0, 0.0])).unwrap();
+        assert_ne!(link().data()[0], Scalar::from(0.0));
+
+        assert!(set_link(&numbers(&[1.5, 1.0])).is_err());
+        assert!(set_link(&numbers(&[1.0, 5.0])).is_err());
+        assert!(set_link(&numbers(&[1.0, 1.0, 1.0])).is_err());
+    }
+
+    #[test]
+    fn it_rolls() {
+        set_link(&Array::scalar(1.0)).unwrap();
+        let floats = roll(Array::new(vec![1000], vec![0.0.into(); 1000]), 1).unwrap();
+        assert!(floats.data().iter().all(|n| {
+            let n = n.as_real().unwrap();
+            n > 0.0 && n < 1.0
+        }));
+        // Every face turns up
+        let dice = values(&roll(Array::new(vec![1000], vec![6.0.into(); 1000]), 0).unwrap());
+        assert!((0..6).all(|face| dice.contains(&face)));
+
+        assert!(roll(Array::scalar(-1.0), 1).is_err());
+        assert!(roll(Array::scalar(2.5), 1).is_err());
+    }
+
+    #[test]
+    fn it_deals() {
+        for (count, size) in [(5.0, 5.0), (10.0, 1000000.0), (0.0, 3.0)] {
+            let mut dealt = values(&deal(&Array::scalar(count), &Array::scalar(size), 1).unwrap());
+            assert_eq!(dealt.len(), count as usize);
+            assert!(dealt.iter().all(|&n| n >= 1 && n as f64 <= size));
+            dealt.sort_unstable();
+            dealt.dedup();
+            assert_eq!(dealt.len(), count as usize);
+        }
+        // 5?5 is a permutation of ⍳5
+        let mut all = values(&deal(&Array::scalar(5.0), &Array::scalar(5.0), 0).unwrap());
+        all.sort_unstable();
+        assert_eq!(all, [0, 1, 2, 3, 4]);
+
+        assert!(deal(&Array::scalar(4.0), &Array::scalar(3.0), 1).is_err());
+        assert!(deal(&numbers(&[1.0, 2.0]), &Array::scalar(3.0), 1).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+// The scalar functions. They pervade: applied to arrays they act on every
+// simple scalar, at any depth, with a scalar on one side extended to the
+// shape of the other.
+
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::number::{Int, IntType, Number};
+use crate::runtime::operators::{each, each_dyadic, reduce};
+use crate::tokenizer::Token;
+
+pub type MonadicScalar = fn(Number) -> anyhow::Result<Number>;
+pub type DyadicScalar = fn(Number, Number) -> anyhow::Result<Number>;
+
+/// The monadic scalar function a glyph stands for.
+pub fn monadic_function(token: &Token) -> Option<MonadicScalar> {
+    Some(match token {
+        Token::Plus => Number::conjugate,
+        Token::Minus => Number::negate,
+        Token::Times => Number::direction,
+        Token::Divide => Number::reciprocal,
+        Token::Star => Number::exp,
+        Token::Log => Number::ln,
+        Token::Stile => Number::magnitude,
+        Token::Circle => Number::pi_times,
+        Token::Tilde => Number::not,
+        Token::ExclamationMark => Number::factorial,
+        Token::Downstile => Number::floor,
+        Token::Upstile => Number::ceiling,
+        _ => return None,
+    })
+}
+
+/// The dyadic scalar function a glyph stands for.
+pub fn dyadic_function(token: &Token) -> Option<DyadicScalar> {
+    Some(match token {
+        Token::Plus => Number::add,
+        Token::Minus => Number::subtract,
+        Token::Times => Number::multiply,
+        Token::Divide => Number::divide,
+        Token::Star => Number::power,
+        Token::Log => Number::log,
+        Token::Stile => Number::residue,
+        Token::Circle => Number::circular,
+        Token::ExclamationMark => Number::binomial,
+        Token::LogicalAND => Number::and,
+        Token::LogicalOR => Number::or,
+        Token::LogicalNAND => Number::nand,
+        Token::LogicalNOR => Number::nor,
+        Token::Equal => Number::equal,
+        Token::NotEqual => Number::not_equal,
+        Token::LessThan => Number::less,
+        Token::LessThanOrEqualTo => Number::less_or_equal,
+        Token::GreaterThan => Number::greater,
+        Token::GreaterThanOrEqualTo => Number::greater_or_equal,
+        Token::Upstile => Number::maximum,
+        Token::Downstile => Number::minimum,
+        _ => return None,
+    })
+}
+
+/// The identity of a dyadic scalar function, what its reduction of an
+/// empty row gives: `x f identity` is `x` for every `x` it applies to.
+pub fn identity(token: &Token) -> Option<Scalar> {
+    Some(match token {
+        Token::Plus | Token::Minus | Token::Stile => Scalar::from(0.0),
+        Token::Times | Token::Divide | Token::Star | Token::ExclamationMark => Scalar::from(1.0),
+        Token::Downstile => Scalar::from(f64::MAX),
+        Token::Upstile => Scalar::from(-f64::MAX),
+        Token::LogicalAND
+        | Token::Equal
+        | Token::LessThanOrEqualTo
+        | Token::GreaterThanOrEqualTo => Scalar::from(true),
+        Token::LogicalOR | Token::NotEqual | Token::LessThan | Token::GreaterThan => {
+            Scalar::from(false)
+        }
+        _ => return None,
+    })
+}
+
+fn simple_number(array: &Array) -> Option<anyhow::Result<Number>> {
+    match (array.is_scalar(), array.data()) {
+        (true, [scalar]) if scalar.is_simple() => Some(scalar.as_number()),
+        _ => None,
+    }
+}
+
+/// Applies a monadic scalar function to every simple scalar in `right`.
+pub fn monadic(function: MonadicScalar, right: &Array) -> anyhow::Result<Array> {
+    pervade(function, right.clone())
+}
+
+fn pervade(function: MonadicScalar, right: Array) -> anyhow::Result<Array> {
+    match simple_number(&right) {
+        Some(n) => Ok(Array::scalar(function(n?)?)),
+        None => each(&|item| pervade(function, item), right),
+    }
+}
+
+/// Applies a dyadic scalar function between corresponding simple scalars of
+/// `left` and `right`.
+pub fn dyadic(function: DyadicScalar, left: &Array, right: &Array) -> anyhow::Result<Array> {
+    pervade_dyadic(function, left.clone(), right.clone())
+}
+
+fn pervade_dyadic(function: DyadicScalar, left: Array, right: Array) -> anyhow::Result<Array> {
+    if let (Some(a), Some(b)) = (simple_number(&left), simple_number(&right)) {
+        return Ok(Array::scalar(function(a?, b?)?));
+    }
+    agree(&left, &right)?;
+    each_dyadic(&|a, b| pervade_dyadic(function, a, b), left, right)
+}
+
+// Two arrays agree if they have the same shape or either is a scalar.
+fn agree(left: &Array, right: &Array) -> anyhow::Result<()> {
+    if !left.is_scalar() && !right.is_scalar() {
+        if left.rank() != right.rank() {
+            anyhow::bail!(Errors::Rank(format!(
+                "arguments of rank {} and {}",
+                left.rank(),
+                right.rank()
+            )));
+        }
+        if left.shape() != right.shape() {
+            anyhow::bail!(Errors::Length(format!(
+                "arguments of shape {:?} and {:?}",
+                left.shape(),
+                right.shape()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Dyadic `=` and `≠`. Unlike the other scalar functions they take
+/// characters too: a character equals only itself, and never a number.
+pub fn equal(left: &Array, right: &Array, equal: bool) -> anyhow::Result<Array> {
+    pervade_equal(equal, left.clone(), right.clone())
+}
+
+fn pervade_equal(equal: bool, left: Array, right: Array) -> anyhow::Result<Array> {
+    if let ([a], [b]) = (left.data(), right.data()) {
+        if left.is_scalar() && right.is_scalar() && a.is_simple() && b.is_simple() {
+            return Ok(Array::scalar(a.matches(b) == equal));
+        }
+    }
+    agree(&left, &right)?;
+    each_dyadic(&|a, b| pervade_equal(equal, a, b), left, right)
+}
+
+/// `+/`: sums along the last axis. Packed booleans are counted a word at a
+/// time, giving the `i64` that adding them one by one would.
+pub fn sum(right: &Array) -> anyhow::Result<Array> {
+    let length = right.shape().last().copied().unwrap_or(1);
+    match right.bits() {
+        Some(bits) if length > 1 => {
+            let shape = right.shape()[..right.rank() - 1].to_vec();
+            let data = (0..bits.len() / length)
+                .map(|row| {
+                    let count = bits.count_ones_in(row * length, (row + 1) * length);
+                    Int::new(count as i128, IntType::I64).map(|n| Scalar::from(Number::Int(n)))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(Array::new(shape, data))
+        }
+        _ => reduce(
+            &|a, b| dyadic(Number::add, &a, &b),
+            right.clone(),
+            identity(&Token::Plus),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dyadic, dyadic_function, equal, monadic, monadic_function, sum};
+    use crate::runtime::array::{Array, Scalar};
+    use crate::runtime::number::Number;
+    use crate::runtime::primitives::format::format;
+    use crate::runtime::tests::numbers;
+    use crate::tokenizer::Token;
+
+    fn complex(re: f64, im: f64) -> Array {
+        Array::scalar(Number::complex(re, im))
+    }
+
+    fn apply(glyph: Token, left: &Array, right: &Array) -> anyhow::Result<Array> {
+        dyadic(dyadic_function(&glyph).unwrap(), left, right)
+    }
+
+    #[test]
+    fn it_extends_scalars_and_pervades() {
+        assert_eq!(
+            apply(Token::Plus, &Array::scalar(1.0), &numbers(&[1.0, 2.0])).unwrap(),
+            numbers(&[2.0, 3.0])
+        );
+        // 10 × (1 2)(3) ←→ (10 20)(30)
+        let nested = Array::vector(vec![numbers(&[1.0, 2.0]).enclose(), 3.0.into()]);
+        assert_eq!(
+            apply(Token::Times, &Array::scalar(10.0), &nested).unwrap(),
+            Array::vector(vec![numbers(&[10.0, 20.0]).enclose(), 30.0.into()])
+        );
+
+        assert!(apply(
+            Token::Plus,
+            &numbers(&[1.0, 2.0]),
+            &numbers(&[1.0, 2.0, 3.0])
+        )
+        .is_err());
+        assert!(apply(Token::Plus, &Array::scalar(1.0), &Array::scalar('a')).is_err());
+        assert_eq!(
+            monadic(monadic_function(&Token::Minus).unwrap(), &numbers(&[]))
+                .unwrap()
+                .shape(),
+            &[0]
+        );
+    }
+
+    #[test]
+    fn it_computes_with_complex_numbers() {
+        let format = |array: anyhow::Result<Array>| format(&array.unwrap()).as_string().unwrap();
+
+        // 1J2 + 3J¯2 ←→ 4
+        assert_eq!(
+            format(apply(Token::Plus, &complex(1.0, 2.0), &complex(3.0, -2.0))),
+            "4"
+        );
+        // 1J2 × 3J4 ←→ ¯5J10
+        assert_eq!(
+            format(apply(Token::Times, &complex(1.0, 2.0), &complex(3.0, 4.0))),
+            "¯5J10"
+        );
+        // ¯4 * 0.5 ←→ 0J2
+        assert_eq!(
+            format(apply(
+                Token::Star,
+                &Array::scalar(-4.0),
+                &Array::scalar(0.5)
+            )),
+            "0J2"
+        );
+        // 0J1 * 2 ←→ ¯1, exactly
+        assert_eq!(
+            format(apply(Token::Star, &complex(0.0, 1.0), &Array::scalar(2.0))),
+            "¯1"
+        );
+        // +3J4 ←→ 3J¯4
+        assert_eq!(
+            format(monadic(
+                monadic_function(&Token::Plus).unwrap(),
+                &complex(3.0, 4.0)
+            )),
+            "3J¯4"
+        );
+        // |3J4 ←→ 5
+        assert_eq!(
+            format(monadic(
+                monadic_function(&Token::Stile).unwrap(),
+                &complex(3.0, 4.0)
+            )),
+            "5"
+        );
+        // 1J2 ÷ 1J2 ←→ 1
+        assert_eq!(
+            format(apply(Token::Divide, &complex(1.0, 2.0), &complex(1.0, 2.0))),
+            "1"
+        );
+        // 9 11○3J4 ←→ 3 4
+        assert_eq!(
+            format(apply(
+                Token::Circle,
+                &numbers(&[9.0, 11.0]),
+                &complex(3.0, 4.0)
+            )),
+            "3 4"
+        );
+        // 1 ¯2J0.5 ←→ 1 ¯2J0.5
+        assert_eq!(
+            format(Ok(Array::vector(vec![
+                1.0.into(),
+                Number::complex(-2.0, 0.5).into()
+            ]))),
+            "1 ¯2J0.5"
+        );
+    }
+
+    #[test]
+    fn it_formats_complex_like_the_literal() {
+        use crate::tokenizer::numeric_literal::NumericLiteral;
+
+        for literal in [
+            NumericLiteral::Complex(10.0, 10.0),
+            NumericLiteral::Complex(-1.5, -2.0),
+        ] {
+            let array = Array::from_literal(&Token::NumericLiteral(literal.clone()))
+                .unwrap()
+                .unwrap();
+            assert_eq!(format(&array).as_string().unwrap(), literal.to_string());
+        }
+    }
+
+    #[test]
+    fn it_sums_packed_booleans_by_counting() {
+        let bits: Vec<Scalar> = (0..300).map(|i| Scalar::from(i % 3 == 0)).collect();
+        let ones = sum(&Array::vector(bits.clone())).unwrap();
+        let added_one_by_one = Array::vector(bits)
+            .into_data()
+            .into_iter()
+            .try_fold(Number::boolean(false), |acc, b| acc.add(b.as_number()?))
+            .unwrap();
+        assert_eq!(ones, Array::scalar(added_one_by_one));
+        assert_eq!(format(&ones).as_string().unwrap(), "100");
+
+        // +/2 3⍴1 1 0 0 1 1b ←→ 2 2
+        let matrix = Array::new(
+            vec![2, 3],
+            [true, true, false, false, true, true]
+                .iter()
+                .map(|b| Scalar::from(*b))
+                .collect(),
+        );
+        assert_eq!(format(&sum(&matrix).unwrap()).as_string().unwrap(), "2 2");
+
+        assert_eq!(sum(&numbers(&[1.0, 2.5])).unwrap(), Array::scalar(3.5));
+        assert_eq!(sum(&numbers(&[])).unwrap(), Array::scalar(0.0));
+    }
+
+    #[test]
+    fn it_compares_characters_for_equality() {
+        // 'abc'='abd' ←→ 1 1 0, 'a'≠1 'a' ←→ 1 0
+        let abc = Array::from_string_literal("abc");
+        assert_eq!(
+            equal(&abc, &Array::from_string_literal("abd"), true).unwrap(),
+            Array::vector(vec![true.into(), true.into(), false.into()])
+        );
+        assert_eq!(
+            equal(
+                &Array::scalar('a'),
+                &Array::vector(vec![1.0.into(), 'a'.into()]),
+                false
+            )
+            .unwrap(),
+            Array::vector(vec![true.into(), false.into()])
+        );
+        assert!(equal(&abc, &Array::from_string_literal("ab"), true).is_err());
+    }
+}
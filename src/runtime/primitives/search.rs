@@ -0,0 +1,461 @@
+// The search and set functions. Elements are found by hashing, so that
+// searching is linear in the size of the arguments rather than quadratic.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+use crate::runtime::primitives::format::format_number;
+use crate::runtime::primitives::structural;
+
+/// A major cell, the elements under one index of the leading axis, as a
+/// hash key. Keys are equal when their elements match, and a number hashes
+/// as its nearest complex double, which matching numbers of any type share.
+#[derive(Debug, Clone, Copy)]
+pub struct Key<'a>(pub &'a [Scalar]);
+
+impl PartialEq for Key<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(other.0).all(|(a, b)| a.matches(b))
+    }
+}
+
+impl Eq for Key<'_> {}
+
+impl Hash for Key<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        fn hash_scalar<H: Hasher>(scalar: &Scalar, state: &mut H) {
+            match scalar {
+                Scalar::Number(n) => {
+                    let z = n.to_complex();
+                    // Adding zero makes ¯0 hash as 0, which it matches
+                    (z.re + 0.0).to_bits().hash(state);
+                    (z.im + 0.0).to_bits().hash(state);
+                }
+                Scalar::Char(c) => c.hash(state),
+                Scalar::Boxed(array) => {
+                    array.shape().hash(state);
+                    array
+                        .data()
+                        .iter()
+                        .for_each(|scalar| hash_scalar(scalar, state));
+                }
+            }
+        }
+        self.0.iter().for_each(|scalar| hash_scalar(scalar, state));
+    }
+}
+
+/// The major cells of `array`, each as its part of the ravel. A scalar is
+/// its own single cell.
+pub fn major_cells(array: &Array) -> Vec<&[Scalar]> {
+    let count = array.tally();
+    let width = match count {
+        0 => 0,
+        count => array.data().len() / count,
+    };
+    (0..count)
+        .map(|i| &array.data()[i * width..(i + 1) * width])
+        .collect()
+}
+
+/// Major cells of `array` put back together along a new leading axis.
+pub fn from_major_cells(array: &Array, cells: &[&[Scalar]]) -> Array {
+    let mut shape = vec![cells.len()];
+    shape.extend_from_slice(array.shape().get(1..).unwrap_or_default());
+    Array::new(shape, cells.concat()).with_prototype(array.prototype())
+}
+
+/// Monadic `∊`: enlist, every simple scalar of a nested array in a vector.
+pub fn enlist(right: Array) -> Array {
+    fn collect(array: Array, output: &mut Vec<Scalar>) {
+        for scalar in array.into_data() {
+            match scalar {
+                Scalar::Boxed(array) => collect(*array, output),
+                scalar => output.push(scalar),
+            }
+        }
+    }
+
+    let mut prototype = right.prototype();
+    while let Scalar::Boxed(array) = prototype {
+        prototype = array.prototype();
+    }
+
+    let mut data = Vec::new();
+    collect(right, &mut data);
+    Array::vector(data).with_prototype(prototype)
+}
+
+/// Dyadic `∊`: membership, a boolean for each element of `left` saying
+/// whether it occurs anywhere in `right`.
+pub fn membership(left: &Array, right: &Array) -> Array {
+    let items: HashSet<Key> = right.data().chunks(1).map(Key).collect();
+    Array::new(
+        left.shape().to_vec(),
+        left.data()
+            .chunks(1)
+            .map(|scalar| items.contains(&Key(scalar)).into())
+            .collect(),
+    )
+}
+
+/// Monadic `⍳`: the first `n` indices.
+pub fn index_generator(right: &Array) -> anyhow::Result<Array> {
+    let n = match right.data() {
+        [n] if right.rank() <= 1 => n.as_real()?,
+        _ => anyhow::bail!(Errors::Length("⍳ expects a single number".to_string())),
+    };
+    if n < 0.0 || n.fract() != 0.0 {
+        anyhow::bail!(Errors::Domain(format!(
+            "⍳ expects a non-negative integer, got {}",
+            format_number(n)
+        )));
+    }
+
+    Ok(Array::vector(
+        (1..=n as usize).map(|i| Scalar::from(i as f64)).collect(),
+    ))
+}
+
+/// Dyadic `⍳`: index of. For each element of `right`, the index of its first
+/// occurrence in the vector `left`, or one past the end if it doesn't occur.
+pub fn index_of(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    if left.rank() > 1 {
+        anyhow::bail!(Errors::Rank("⍳ expects a vector on the left".to_string()));
+    }
+
+    let mut first = HashMap::with_capacity(left.data().len());
+    for (i, scalar) in left.data().chunks(1).enumerate() {
+        first.entry(Key(scalar)).or_insert(i);
+    }
+    let not_found = left.data().len();
+    Ok(Array::new(
+        right.shape().to_vec(),
+        right
+            .data()
+            .chunks(1)
+            .map(|scalar| {
+                let index = first.get(&Key(scalar)).copied().unwrap_or(not_found);
+                Scalar::from((index + 1) as f64)
+            })
+            .collect(),
+    ))
+}
+
+/// Monadic `∪`: unique, the major cells of `right` without repeats, in the
+/// order they first occur.
+pub fn unique(right: &Array) -> Array {
+    let cells = major_cells(right);
+    let mut seen = HashSet::with_capacity(cells.len());
+    let kept: Vec<&[Scalar]> = cells
+        .into_iter()
+        .filter(|cell| seen.insert(Key(cell)))
+        .collect();
+    from_major_cells(right, &kept)
+}
+
+fn expect_vector(array: &Array, glyph: char) -> anyhow::Result<()> {
+    match array.rank() {
+        0 | 1 => Ok(()),
+        _ => anyhow::bail!(Errors::Rank(format!("{} expects vectors", glyph))),
+    }
+}
+
+/// Dyadic `∪`: union, `left` followed by the elements of `right` that are
+/// not in it.
+pub fn union(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    expect_vector(left, '∪')?;
+    expect_vector(right, '∪')?;
+
+    let items: HashSet<Key> = left.data().chunks(1).map(Key).collect();
+    let mut data = left.data().to_vec();
+    data.extend(
+        right
+            .data()
+            .chunks(1)
+            .filter(|scalar| !items.contains(&Key(scalar)))
+            .map(|scalar| scalar[0].clone()),
+    );
+    Ok(Array::vector(data).with_prototype(left.prototype()))
+}
+
+/// Dyadic `∩`: intersection, the elements of `left` that are in `right`.
+pub fn intersection(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    expect_vector(left, '∩')?;
+    expect_vector(right, '∩')?;
+
+    let items: HashSet<Key> = right.data().chunks(1).map(Key).collect();
+    let data = left
+        .data()
+        .chunks(1)
+        .filter(|scalar| items.contains(&Key(scalar)))
+        .map(|scalar| scalar[0].clone())
+        .collect();
+    Ok(Array::vector(data).with_prototype(left.prototype()))
+}
+
+/// Dyadic `⍷`: find, a boolean the shape of `right` marking each place an
+/// occurrence of `left` starts. A `left` of lower rank is found as if it
+/// had leading axes of length 1. Vectors are searched in linear time
+/// (Knuth–Morris–Pratt), anything else by trying every place.
+pub fn find(left: &Array, right: &Array) -> Array {
+    let shape = right.shape().to_vec();
+    let count = right.data().len();
+    if left.rank() > right.rank() {
+        return Array::new(shape, vec![false.into(); count]);
+    }
+
+    if right.rank() == 1 {
+        let pattern = left.data();
+        let mut found = vec![Scalar::from(false); count];
+        match pattern.len() {
+            0 => found.fill(true.into()),
+            length => {
+                for end in knuth_morris_pratt(pattern, right.data()) {
+                    found[end + 1 - length] = true.into();
+                }
+            }
+        }
+        return Array::new(shape, found);
+    }
+
+    let mut pattern_shape = vec![1; right.rank() - left.rank()];
+    pattern_shape.extend_from_slice(left.shape());
+    let offsets: Vec<usize> = structural::indices(&pattern_shape)
+        .map(|index| {
+            index
+                .iter()
+                .zip(&shape)
+                .fold(0, |offset, (i, n)| offset * n + i)
+        })
+        .collect();
+
+    let found = structural::indices(&shape)
+        .map(|start| {
+            let fits = start
+                .iter()
+                .zip(&pattern_shape)
+                .zip(&shape)
+                .all(|((i, p), n)| i + p <= *n);
+            let origin = start
+                .iter()
+                .zip(&shape)
+                .fold(0, |offset, (i, n)| offset * n + i);
+            let found = fits
+                && offsets
+                    .iter()
+                    .zip(left.data())
+                    .all(|(offset, scalar)| right.data()[origin + offset].matches(scalar));
+            Scalar::from(found)
+        })
+        .collect();
+    Array::new(shape, found)
+}
+
+// The positions in `text` at which an occurrence of the non-empty
+// `pattern` ends.
+fn knuth_morris_pratt(pattern: &[Scalar], text: &[Scalar]) -> Vec<usize> {
+    // The length of the longest proper prefix of each prefix of `pattern`
+    // that is also a suffix of it
+    let mut fallback = vec![0; pattern.len()];
+    let mut length = 0;
+    for i in 1..pattern.len() {
+        while length > 0 && !pattern[i].matches(&pattern[length]) {
+            length = fallback[length - 1];
+        }
+        if pattern[i].matches(&pattern[length]) {
+            length += 1;
+        }
+        fallback[i] = length;
+    }
+
+    let mut ends = vec![];
+    let mut matched = 0;
+    for (i, scalar) in text.iter().enumerate() {
+        while matched > 0 && !scalar.matches(&pattern[matched]) {
+            matched = fallback[matched - 1];
+        }
+        if scalar.matches(&pattern[matched]) {
+            matched += 1;
+        }
+        if matched == pattern.len() {
+            ends.push(i);
+            matched = fallback[matched - 1];
+        }
+    }
+    ends
+}
+
+/// Monadic `⍸`: where, the index of each one in a boolean vector, or of
+/// each item repeated as many times as a non-negative integer says. Set
+/// bits are found a word at a time.
+pub fn where_indices(right: &Array) -> anyhow::Result<Array> {
+    if right.rank() != 1 {
+        anyhow::bail!(Errors::Rank("⍸ expects a vector".to_string()));
+    }
+
+    let indices = match right.as_bits() {
+        Some(bits) => bits
+            .ones_positions()
+            .map(|i| Scalar::from((i + 1) as f64))
+            .collect(),
+        None => {
+            let mut indices = Vec::new();
+            for (i, scalar) in right.data().iter().enumerate() {
+                let n = scalar.as_real()?;
+                if n < 0.0 || n.fract() != 0.0 {
+                    anyhow::bail!(Errors::Domain(format!(
+                        "⍸ expects non-negative integers, got {}",
+                        format_number(n)
+                    )));
+                }
+                indices.extend(std::iter::repeat_n(
+                    Scalar::from((i + 1) as f64),
+                    n as usize,
+                ));
+            }
+            indices
+        }
+    };
+    Ok(Array::vector(indices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        enlist, find, index_generator, index_of, intersection, membership, union, unique,
+        where_indices,
+    };
+    use crate::runtime::array::{Array, Scalar};
+    use crate::runtime::tests::numbers;
+
+    fn booleans(bs: &[bool]) -> Array {
+        Array::vector(bs.iter().map(|b| Scalar::from(*b)).collect())
+    }
+
+    #[test]
+    fn it_searches_characters_by_code_point() {
+        let glyphs = Array::from_string_literal("⍴⍳é∊");
+
+        assert_eq!(
+            index_of(&glyphs, &Array::from_string_literal("∊éx")).unwrap(),
+            numbers(&[4.0, 3.0, 5.0])
+        );
+        assert_eq!(
+            index_of(&glyphs, &Array::scalar('⍳')).unwrap(),
+            Array::scalar(2.0)
+        );
+        assert_eq!(
+            membership(&Array::from_string_literal("hé1"), &glyphs),
+            booleans(&[false, true, false])
+        );
+    }
+
+    #[test]
+    fn it_does_not_confuse_characters_and_numbers() {
+        let digits = Array::from_string_literal("123");
+
+        assert_eq!(
+            membership(&numbers(&[1.0, 2.0]), &digits),
+            booleans(&[false, false])
+        );
+        assert_eq!(
+            index_of(&digits, &Array::scalar(1.0)).unwrap(),
+            Array::scalar(4.0)
+        );
+    }
+
+    #[test]
+    fn it_generates_indices() {
+        assert_eq!(
+            index_generator(&Array::scalar(3.0)).unwrap(),
+            numbers(&[1.0, 2.0, 3.0])
+        );
+        assert_eq!(index_generator(&Array::scalar(0.0)).unwrap(), numbers(&[]));
+        assert!(index_generator(&Array::scalar('a')).is_err());
+        assert!(index_generator(&Array::scalar(-1.0)).is_err());
+
+        let matrix = Array::new(vec![1, 2], vec!['a'.into(), 'b'.into()]);
+        assert_eq!(enlist(matrix).shape(), &[2]);
+    }
+
+    #[test]
+    fn it_enlists_nested_arrays() {
+        // ∊1 (2 (3 4)) ←→ 1 2 3 4
+        let inner = Array::vector(vec![2.0.into(), numbers(&[3.0, 4.0]).enclose()]);
+        let nested = Array::vector(vec![1.0.into(), inner.enclose()]);
+        assert_eq!(enlist(nested), numbers(&[1.0, 2.0, 3.0, 4.0]));
+
+        // ∊0⍴⊂'' ←→ ''
+        let empty = Array::vector(vec![]).with_prototype(Array::from_string_literal("").enclose());
+        assert!(enlist(empty).is_char());
+    }
+
+    #[test]
+    fn it_compares_numbers_by_value() {
+        // 1b 2 ∊ 1.0 ←→ 1 0
+        let ones = Array::vector(vec![true.into(), 2.0.into()]);
+        assert_eq!(
+            membership(&ones, &numbers(&[1.0])),
+            booleans(&[true, false])
+        );
+        assert_eq!(
+            index_of(&numbers(&[0.0, 1.0]), &Array::scalar(true)).unwrap(),
+            Array::scalar(2.0)
+        );
+    }
+
+    #[test]
+    fn it_combines_sets() {
+        // ∪3 1 3 ¯0 0 ←→ 3 1 0
+        assert_eq!(
+            unique(&numbers(&[3.0, 1.0, 3.0, -0.0, 0.0])),
+            numbers(&[3.0, 1.0, 0.0])
+        );
+        // 1 2∪2 3 2 ←→ 1 2 3, 3 1 2 1∩1 2 ←→ 1 2 1
+        assert_eq!(
+            union(&numbers(&[1.0, 2.0]), &numbers(&[2.0, 3.0, 2.0])).unwrap(),
+            numbers(&[1.0, 2.0, 3.0])
+        );
+        assert_eq!(
+            intersection(&numbers(&[3.0, 1.0, 2.0, 1.0]), &numbers(&[1.0, 2.0])).unwrap(),
+            numbers(&[1.0, 2.0, 1.0])
+        );
+        // 'aa'⍷'aaab' ←→ 1 1 0 0
+        assert_eq!(
+            find(
+                &Array::from_string_literal("aa"),
+                &Array::from_string_literal("aaab")
+            ),
+            booleans(&[true, true, false, false])
+        );
+    }
+
+    #[test]
+    fn it_finds_where() {
+        let bits: Vec<bool> = (0..200).map(|i| i % 7 == 3).collect();
+        assert_eq!(
+            where_indices(&booleans(&bits)).unwrap(),
+            numbers(
+                &(0..200)
+                    .filter(|i| i % 7 == 3)
+                    .map(|i| (i + 1) as f64)
+                    .collect::<Vec<_>>()
+            )
+        );
+
+        // ⍸2 0 1 ←→ 1 1 3, and 0 1 as reals are booleans too
+        assert_eq!(
+            where_indices(&numbers(&[2.0, 0.0, 1.0])).unwrap(),
+            numbers(&[1.0, 1.0, 3.0])
+        );
+        assert_eq!(
+            where_indices(&numbers(&[0.0, 1.0])).unwrap(),
+            numbers(&[2.0])
+        );
+        assert!(where_indices(&numbers(&[-1.0])).is_err());
+        assert!(where_indices(&Array::scalar(true)).is_err());
+    }
+}
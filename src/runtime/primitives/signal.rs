@@ -0,0 +1,124 @@
+// `⎕SIGNAL`, which raises errors, and `⎕EN` and `⎕DM`, which tell what the
+// last error caught by a `:Trap` or `::` guard was. Until one is caught,
+// `⎕EN` is 0 and `⎕DM` is empty. `⎕DM` holds the name of the error and
+// what went wrong, where Dyalog quotes the line it happened on.
+
+use std::cell::RefCell;
+
+use crate::errors::Errors;
+use crate::runtime::array::Array;
+
+thread_local! {
+    // The last error caught in the session running in this thread.
+    static LAST: RefCell<Option<Errors>> = const { RefCell::new(None) };
+}
+
+/// The last error caught, for a session to keep between runs.
+pub fn last() -> Option<Errors> {
+    LAST.with(|last| last.borrow().clone())
+}
+
+/// Puts a session's last error in place, for `⎕EN` and `⎕DM` to tell of.
+pub fn set_last(error: Option<Errors>) {
+    LAST.with(|last| *last.borrow_mut() = error);
+}
+
+/// The value of `⎕EN`.
+pub fn number() -> Array {
+    let number = LAST.with(|last| last.borrow().as_ref().map_or(0, Errors::number));
+    Array::scalar(number as f64)
+}
+
+/// The value of `⎕DM`.
+pub fn message() -> Array {
+    LAST.with(|last| match &*last.borrow() {
+        Some(error) => Array::vector(vec![
+            Array::from_string_literal(&error.name()).enclose(),
+            Array::from_string_literal(&error.detail()).enclose(),
+        ]),
+        None => Array::vector(vec![]),
+    })
+}
+
+// Error numbers, which are whole and from 0 to 999.
+fn numbers(array: &Array, of: &str) -> anyhow::Result<Vec<u32>> {
+    if array.rank() > 1 {
+        anyhow::bail!(Errors::Rank(format!(
+            "{} takes a vector of error numbers",
+            of
+        )));
+    }
+    array
+        .data()
+        .iter()
+        .map(|scalar| match scalar.as_real()? {
+            n if n.fract() == 0.0 && (0.0..1000.0).contains(&n) => Ok(n as u32),
+            n => anyhow::bail!(Errors::Domain(format!("{} is not an error number", n))),
+        })
+        .collect()
+}
+
+/// The error numbers `array` lists, for a `:Trap` or `::` guard.
+pub fn codes(array: &Array) -> anyhow::Result<Vec<u32>> {
+    numbers(array, "a trap")
+}
+
+/// Whether an error is one of `codes`, noting it for `⎕EN` and `⎕DM` if
+/// it is.
+pub fn caught(error: &anyhow::Error, codes: &[u32]) -> bool {
+    let error = Errors::of(error);
+    let caught = error.trapped_by(codes);
+    if caught {
+        set_last(Some(error));
+    }
+    caught
+}
+
+/// `⎕SIGNAL`: raises the first error numbered on the right, from 1 to 999,
+/// with the message on the left if there is one. An empty right argument
+/// raises nothing.
+pub fn signal(left: Option<&Array>, right: &Array) -> anyhow::Result<Array> {
+    let number = match numbers(right, "⎕SIGNAL")?.first() {
+        Some(0) => anyhow::bail!(Errors::Domain(
+            "⎕SIGNAL takes errors numbered 1 to 999".to_string()
+        )),
+        Some(number) => *number,
+        None => return Ok(Array::vector(vec![])),
+    };
+    let message = match left {
+        Some(left) => match left.as_string() {
+            Some(message) => Some(message),
+            None => anyhow::bail!(Errors::Domain(
+                "⎕SIGNAL takes a message on the left".to_string()
+            )),
+        },
+        None => None,
+    };
+    Err(Errors::signal(number, message).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{caught, message, number, signal};
+    use crate::runtime::array::{Array, Scalar};
+
+    #[test]
+    fn it_notes_what_it_catches() {
+        assert!(number().matches(&Array::scalar(0.0)));
+        let error = signal(
+            Some(&Array::from_string_literal("no")),
+            &Array::scalar(500.0),
+        )
+        .unwrap_err();
+        assert!(!caught(&error, &[11]));
+        assert!(number().matches(&Array::scalar(0.0)));
+        assert!(caught(&error, &[11, 500]));
+        assert!(number().matches(&Array::scalar(500.0)));
+        assert_eq!(
+            message().data()[0],
+            Array::from_string_literal("no").enclose()
+        );
+        assert!(signal(None, &Array::vector(vec![])).is_ok());
+        assert!(signal(None, &Array::vector(vec![Scalar::from(1000.0)])).is_err());
+    }
+}
@@ -0,0 +1,702 @@
+// Kernels for the dyadic scalar functions `+ - × ÷ ⌈ ⌊ | = ≠ < >` between
+// simple arrays of a single fixed-width type: the native integer widths,
+// `i8` to `u64`, singles and doubles. They work on the elements unboxed, as
+// `Lanes`, and give them unboxed too, so that the arrays `⎕INTO` makes are
+// never boxed by a chain of kernels. On x86-64 processors with AVX2, found
+// at run time, the kernels in `avx2` compute a register of elements at a
+// time with explicit instructions, for the functions and widths AVX2 has
+// instructions for. The elements left over, and everything elsewhere, are
+// computed one at a time.
+//
+// A kernel gives what pervading would, to the type of every element and
+// the overflow mode of its width. Whatever it doesn't cover, halves, other
+// widths, mixed types, shapes that don't agree or a NaN where an order is
+// needed, it gives nothing for, leaving it to `scalar::dyadic`.
+
+use std::borrow::Cow;
+
+use crate::errors::Errors;
+use crate::runtime::array::Array;
+use crate::runtime::bits::Bits;
+use crate::runtime::lanes::Lanes;
+use crate::runtime::number::integer::Overflow;
+use crate::runtime::number::IntType;
+use crate::tokenizer::Token;
+
+#[cfg(target_arch = "x86_64")]
+mod avx2;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Maximum,
+    Minimum,
+    Residue,
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+}
+
+impl Op {
+    fn from_token(token: &Token) -> Option<Self> {
+        Some(match token {
+            Token::Plus => Op::Add,
+            Token::Minus => Op::Subtract,
+            Token::Times => Op::Multiply,
+            Token::Divide => Op::Divide,
+            Token::Upstile => Op::Maximum,
+            Token::Downstile => Op::Minimum,
+            Token::Stile => Op::Residue,
+            Token::Equal => Op::Equal,
+            Token::NotEqual => Op::NotEqual,
+            Token::LessThan => Op::Less,
+            Token::GreaterThan => Op::Greater,
+            _ => return None,
+        })
+    }
+}
+
+// An explicit kernel runs over as many elements as fill whole registers,
+// giving how many that was, and leaves the rest. It may only be called
+// where the processor has AVX2, with slices of the same length.
+//
+// An element each of `out` from the elements of `a` and `b`.
+type Zip<T> = unsafe fn(a: &[T], b: &[T], out: &mut [T]) -> usize;
+// A bit each, set in `words`, for the elements of `a` and `b`.
+type Compare<T> = unsafe fn(a: &[T], b: &[T], words: &mut [u64]) -> usize;
+// Whether `+` or `-` overflows for any of the elements.
+type Overflows<T> = unsafe fn(a: &[T], b: &[T]) -> (usize, bool);
+
+// The explicit kernels of a width, for the functions it has them for.
+trait Explicit: Sized {
+    // The function between elements, wrapping integers that overflow.
+    fn lanewise(_: Op) -> Option<Zip<Self>> {
+        None
+    }
+
+    // `+` or `-`, saturating.
+    fn saturating(_: Op) -> Option<Zip<Self>> {
+        None
+    }
+
+    // `= ≠ < >`.
+    fn compare(_: Op) -> Option<Compare<Self>> {
+        None
+    }
+
+    // Whether `+` or `-` overflows.
+    fn overflows(_: Op) -> Option<Overflows<Self>> {
+        None
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for i8 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for i16 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for i32 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for i64 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for u8 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for u16 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for u32 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for u64 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for f32 {}
+#[cfg(not(target_arch = "x86_64"))]
+impl Explicit for f64 {}
+
+#[cfg(target_arch = "x86_64")]
+fn avx2() -> bool {
+    is_x86_feature_detected!("avx2")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn avx2() -> bool {
+    false
+}
+
+// `f` between corresponding elements, by `kernel` as far as it goes.
+fn zip<T: Copy + Default>(
+    a: &[T],
+    b: &[T],
+    kernel: Option<Zip<T>>,
+    f: impl Fn(T, T) -> T,
+) -> Vec<T> {
+    assert_eq!(a.len(), b.len());
+    let mut out = vec![T::default(); a.len()];
+    let done = match kernel {
+        // SAFETY: the processor has AVX2, and the slices are the same length
+        Some(kernel) if avx2() => unsafe { kernel(a, b, &mut out) },
+        _ => 0,
+    };
+    for ((out, &a), &b) in out[done..].iter_mut().zip(&a[done..]).zip(&b[done..]) {
+        *out = f(a, b);
+    }
+    out
+}
+
+// Whether `f` holds between corresponding elements, as bits.
+fn compare<T: Copy>(
+    a: &[T],
+    b: &[T],
+    kernel: Option<Compare<T>>,
+    f: impl Fn(T, T) -> bool,
+) -> Bits {
+    assert_eq!(a.len(), b.len());
+    let mut words = vec![0; a.len().div_ceil(64)];
+    let done = match kernel {
+        // SAFETY: as for `zip`
+        Some(kernel) if avx2() => unsafe { kernel(a, b, &mut words) },
+        _ => 0,
+    };
+    for i in done..a.len() {
+        words[i / 64] |= (f(a[i], b[i]) as u64) << (i % 64);
+    }
+    Bits::from_words(words, a.len())
+}
+
+// Whether `f` holds between any corresponding elements.
+fn any<T: Copy>(a: &[T], b: &[T], kernel: Option<Overflows<T>>, f: impl Fn(T, T) -> bool) -> bool {
+    assert_eq!(a.len(), b.len());
+    let (done, any) = match kernel {
+        // SAFETY: as for `zip`
+        Some(kernel) if avx2() => unsafe { kernel(a, b) },
+        _ => (0, false),
+    };
+    any || a[done..].iter().zip(&b[done..]).any(|(&a, &b)| f(a, b))
+}
+
+// The integers, which compute in their own width.
+trait Integer: Copy + Default + Ord + Explicit {
+    const TYPE: IntType;
+    fn overflowing_add(self, other: Self) -> (Self, bool);
+    fn overflowing_sub(self, other: Self) -> (Self, bool);
+    fn overflowing_mul(self, other: Self) -> (Self, bool);
+    fn saturating_add(self, other: Self) -> Self;
+    fn saturating_sub(self, other: Self) -> Self;
+    fn saturating_mul(self, other: Self) -> Self;
+    fn wide(self) -> i128;
+    fn narrow(wide: i128) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! integer {
+    ($($t:ty: $ty:ident),*) => {
+        $(
+            impl Integer for $t {
+                const TYPE: IntType = IntType::$ty;
+                fn overflowing_add(self, other: Self) -> (Self, bool) {
+                    <$t>::overflowing_add(self, other)
+                }
+                fn overflowing_sub(self, other: Self) -> (Self, bool) {
+                    <$t>::overflowing_sub(self, other)
+                }
+                fn overflowing_mul(self, other: Self) -> (Self, bool) {
+                    <$t>::overflowing_mul(self, other)
+                }
+                fn saturating_add(self, other: Self) -> Self {
+                    <$t>::saturating_add(self, other)
+                }
+                fn saturating_sub(self, other: Self) -> Self {
+                    <$t>::saturating_sub(self, other)
+                }
+                fn saturating_mul(self, other: Self) -> Self {
+                    <$t>::saturating_mul(self, other)
+                }
+                fn wide(self) -> i128 {
+                    self as i128
+                }
+                fn narrow(wide: i128) -> Self {
+                    wide as $t
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+integer!(i8: I8, i16: I16, i32: I32, i64: I64, u8: U8, u16: U16, u32: U32, u64: U64);
+
+// Singles and doubles. Singles compute in their own precision, which for
+// `+ - × ÷` rounds as computing in doubles and rounding back does.
+trait Real:
+    Copy + Default + PartialOrd + Explicit + std::ops::Add<Output = Self> + std::ops::Sub<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    fn mul(self, other: Self) -> Self;
+    fn div(self, other: Self) -> Self;
+    fn is_nan(self) -> bool;
+    fn to_f64(self) -> f64;
+    fn from_f64(n: f64) -> Self;
+}
+
+macro_rules! real {
+    ($($t:ty),*) => {
+        $(
+            impl Real for $t {
+                const ZERO: Self = 0.0;
+                const ONE: Self = 1.0;
+                fn mul(self, other: Self) -> Self {
+                    self * other
+                }
+                fn div(self, other: Self) -> Self {
+                    self / other
+                }
+                fn is_nan(self) -> bool {
+                    <$t>::is_nan(self)
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+                fn from_f64(n: f64) -> Self {
+                    n as $t
+                }
+            }
+        )*
+    };
+}
+
+real!(f32, f64);
+
+// What a kernel computes, before it is an array.
+enum Output<T> {
+    Same(Vec<T>),
+    Doubles(Vec<f64>),
+    Booleans(Bits),
+}
+
+impl<T> Output<T> {
+    fn array(self, shape: Vec<usize>, same: fn(Vec<T>) -> Lanes) -> Array {
+        match self {
+            Output::Same(lanes) => Array::from_lanes(shape, same(lanes)),
+            Output::Doubles(doubles) => Array::from_lanes(shape, Lanes::F64(doubles)),
+            Output::Booleans(bits) => Array::from_bits(shape, bits),
+        }
+    }
+}
+
+// The elements of `array` unboxed, a scalar extended to `count` of them.
+fn lanes(array: &Array, count: usize) -> Option<Cow<'_, Lanes>> {
+    let lanes = array.lanes()?;
+    Some(match array.is_scalar() {
+        true => Cow::Owned(lanes.repeat(count)),
+        false => lanes,
+    })
+}
+
+/// The scalar function `token` between `left` and `right` by a kernel, if
+/// one covers them.
+pub fn dyadic(token: &Token, left: &Array, right: &Array) -> Option<anyhow::Result<Array>> {
+    let op = Op::from_token(token)?;
+    let shape = match (left.is_scalar(), right.is_scalar()) {
+        (true, true) => return None,
+        (true, false) => right.shape(),
+        (false, true) => left.shape(),
+        (false, false) if left.shape() == right.shape() => left.shape(),
+        (false, false) => return None,
+    }
+    .to_vec();
+    let count = shape.iter().product();
+    let (a, b) = (lanes(left, count)?, lanes(right, count)?);
+    Some(match (&*a, &*b) {
+        (Lanes::I8(a), Lanes::I8(b)) => integers(op, a, b)?.map(|out| out.array(shape, Lanes::I8)),
+        (Lanes::I16(a), Lanes::I16(b)) => {
+            integers(op, a, b)?.map(|out| out.array(shape, Lanes::I16))
+        }
+        (Lanes::I32(a), Lanes::I32(b)) => {
+            integers(op, a, b)?.map(|out| out.array(shape, Lanes::I32))
+        }
+        (Lanes::I64(a), Lanes::I64(b)) => {
+            integers(op, a, b)?.map(|out| out.array(shape, Lanes::I64))
+        }
+        (Lanes::U8(a), Lanes::U8(b)) => integers(op, a, b)?.map(|out| out.array(shape, Lanes::U8)),
+        (Lanes::U16(a), Lanes::U16(b)) => {
+            integers(op, a, b)?.map(|out| out.array(shape, Lanes::U16))
+        }
+        (Lanes::U32(a), Lanes::U32(b)) => {
+            integers(op, a, b)?.map(|out| out.array(shape, Lanes::U32))
+        }
+        (Lanes::U64(a), Lanes::U64(b)) => {
+            integers(op, a, b)?.map(|out| out.array(shape, Lanes::U64))
+        }
+        (Lanes::F32(a), Lanes::F32(b)) => reals(op, a, b)?.map(|out| out.array(shape, Lanes::F32)),
+        (Lanes::F64(a), Lanes::F64(b)) => reals(op, a, b)?.map(|out| out.array(shape, Lanes::F64)),
+        _ => return None,
+    })
+}
+
+fn integers<T: Integer>(op: Op, a: &[T], b: &[T]) -> Option<anyhow::Result<Output<T>>> {
+    let same = |lanes| Ok(Output::Same(lanes));
+    Some(match (op, T::TYPE.overflow()) {
+        (Op::Add, Overflow::Checked) => checked(op, a, b, T::overflowing_add),
+        (Op::Add, Overflow::Wrapping) => {
+            same(zip(a, b, T::lanewise(op), |a, b| a.overflowing_add(b).0))
+        }
+        (Op::Add, Overflow::Saturating) => same(zip(a, b, T::saturating(op), T::saturating_add)),
+        (Op::Subtract, Overflow::Checked) => checked(op, a, b, T::overflowing_sub),
+        (Op::Subtract, Overflow::Wrapping) => {
+            same(zip(a, b, T::lanewise(op), |a, b| a.overflowing_sub(b).0))
+        }
+        (Op::Subtract, Overflow::Saturating) => {
+            same(zip(a, b, T::saturating(op), T::saturating_sub))
+        }
+        (Op::Multiply, Overflow::Checked) => checked(op, a, b, T::overflowing_mul),
+        (Op::Multiply, Overflow::Wrapping) => {
+            same(zip(a, b, T::lanewise(op), |a, b| a.overflowing_mul(b).0))
+        }
+        (Op::Multiply, Overflow::Saturating) => same(zip(a, b, None, T::saturating_mul)),
+        // whole numbers divide to doubles
+        (Op::Divide, _) => divide(a, b, T::to_f64).map(Output::Doubles),
+        (Op::Maximum, _) => same(zip(a, b, T::lanewise(op), |a, b| if a < b { b } else { a })),
+        (Op::Minimum, _) => same(zip(a, b, T::lanewise(op), |a, b| if a > b { b } else { a })),
+        // `b` modulo `a`, with the sign of `a`
+        (Op::Residue, _) => same(zip(a, b, None, |a, b| {
+            let (a, b) = (a.wide(), b.wide());
+            if a == 0 {
+                return T::narrow(b);
+            }
+            let remainder = b.rem_euclid(a);
+            T::narrow(match a < 0 && remainder != 0 {
+                true => remainder + a,
+                false => remainder,
+            })
+        })),
+        (Op::Equal, _) => Ok(Output::Booleans(compare(a, b, T::compare(op), |a, b| {
+            a == b
+        }))),
+        (Op::NotEqual, _) => Ok(Output::Booleans(compare(a, b, T::compare(op), |a, b| {
+            a != b
+        }))),
+        (Op::Less, _) => Ok(Output::Booleans(compare(a, b, T::compare(op), |a, b| {
+            a < b
+        }))),
+        (Op::Greater, _) => Ok(Output::Booleans(compare(a, b, T::compare(op), |a, b| {
+            a > b
+        }))),
+    })
+}
+
+// `+ - ×` under a checked overflow mode, which is an error if any element
+// overflows.
+fn checked<T: Integer>(
+    op: Op,
+    a: &[T],
+    b: &[T],
+    overflowing: impl Fn(T, T) -> (T, bool) + Copy,
+) -> anyhow::Result<Output<T>> {
+    match any(a, b, T::overflows(op), |a, b| overflowing(a, b).1) {
+        true => anyhow::bail!(Errors::Domain(format!(
+            "the result does not fit in {}",
+            T::TYPE
+        ))),
+        false => Ok(Output::Same(zip(a, b, T::lanewise(op), |a, b| {
+            overflowing(a, b).0
+        }))),
+    }
+}
+
+// `÷` of integers, as doubles: `0÷0` is 1, and any other division by zero
+// an error.
+fn divide<T: Copy>(a: &[T], b: &[T], double: impl Fn(T) -> f64 + Copy) -> anyhow::Result<Vec<f64>> {
+    if any(a, b, None, |a, b| double(b) == 0.0 && double(a) != 0.0) {
+        anyhow::bail!(Errors::Domain("division by zero".to_string()));
+    }
+    Ok(a.iter()
+        .zip(b)
+        .map(|(&a, &b)| match double(b) == 0.0 {
+            true => 1.0,
+            false => double(a) / double(b),
+        })
+        .collect())
+}
+
+fn reals<T: Real>(op: Op, a: &[T], b: &[T]) -> Option<anyhow::Result<Output<T>>> {
+    // NaN has no order, which pervading reports
+    if matches!(op, Op::Maximum | Op::Minimum | Op::Less | Op::Greater)
+        && any(a, b, None, |a, b| a.is_nan() || b.is_nan())
+    {
+        return None;
+    }
+    let kernel = T::lanewise(op);
+    Some(Ok(match op {
+        Op::Add => Output::Same(zip(a, b, kernel, |a, b| a + b)),
+        Op::Subtract => Output::Same(zip(a, b, kernel, |a, b| a - b)),
+        Op::Multiply => Output::Same(zip(a, b, kernel, T::mul)),
+        Op::Divide => {
+            if any(a, b, None, |a, b| b == T::ZERO && a != T::ZERO) {
+                return Some(Err(anyhow::anyhow!(Errors::Domain(
+                    "division by zero".to_string()
+                ))));
+            }
+            Output::Same(zip(a, b, kernel, |a, b| match b == T::ZERO {
+                true => T::ONE,
+                false => a.div(b),
+            }))
+        }
+        Op::Maximum => Output::Same(zip(a, b, kernel, |a, b| if a < b { b } else { a })),
+        Op::Minimum => Output::Same(zip(a, b, kernel, |a, b| if a > b { b } else { a })),
+        // computed in doubles, as pervading does for singles too
+        Op::Residue => Output::Same(zip(a, b, None, |a, b| {
+            let (a, b) = (a.to_f64(), b.to_f64());
+            match a == 0.0 {
+                true => T::from_f64(b),
+                false => T::from_f64(b - a * (b / a).floor()),
+            }
+        })),
+        Op::Equal => Output::Booleans(compare(a, b, T::compare(op), |a, b| a == b)),
+        Op::NotEqual => Output::Booleans(compare(a, b, T::compare(op), |a, b| a != b)),
+        Op::Less => Output::Booleans(compare(a, b, T::compare(op), |a, b| a < b)),
+        Op::Greater => Output::Booleans(compare(a, b, T::compare(op), |a, b| a > b)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::dyadic;
+    use crate::runtime::array::{Array, Scalar};
+    use crate::runtime::lanes::Lanes;
+    use crate::runtime::number::integer::{set_overflow, Overflow};
+    use crate::runtime::number::{Float, Int, IntType, Number};
+    use crate::runtime::primitives::convert::into;
+    use crate::runtime::primitives::scalar;
+    use crate::runtime::tests::numbers;
+    use crate::tokenizer::Token;
+
+    const TOKENS: [Token; 11] = [
+        Token::Plus,
+        Token::Minus,
+        Token::Times,
+        Token::Divide,
+        Token::Upstile,
+        Token::Downstile,
+        Token::Stile,
+        Token::Equal,
+        Token::NotEqual,
+        Token::LessThan,
+        Token::GreaterThan,
+    ];
+
+    fn ints(ns: &[i128], ty: IntType) -> Array {
+        Array::vector(
+            ns.iter()
+                .map(|n| Scalar::Number(Number::Int(Int::new(*n, ty).unwrap())))
+                .collect(),
+        )
+    }
+
+    // What pervading gives, element by element.
+    fn pervaded(token: &Token, left: &Array, right: &Array) -> anyhow::Result<Array> {
+        match token {
+            Token::Equal => scalar::equal(left, right, true),
+            Token::NotEqual => scalar::equal(left, right, false),
+            token => scalar::dyadic(scalar::dyadic_function(token).unwrap(), left, right),
+        }
+    }
+
+    fn agrees(left: &Array, right: &Array) {
+        for token in &TOKENS {
+            let kernel = dyadic(token, left, right).expect("a kernel");
+            match (kernel, pervaded(token, left, right)) {
+                (Ok(kernel), Ok(pervaded)) => {
+                    assert_eq!(kernel.data(), pervaded.data(), "{:?}", token);
+                    assert_eq!(kernel, pervaded, "{:?}", token);
+                }
+                (Err(kernel), Err(pervaded)) => {
+                    assert_eq!(kernel.to_string(), pervaded.to_string(), "{:?}", token)
+                }
+                (kernel, pervaded) => panic!("{:?}: {:?}, not {:?}", token, kernel, pervaded),
+            }
+        }
+    }
+
+    #[test]
+    fn it_gives_what_pervading_would_at_every_width() {
+        for bits in [8, 16, 32, 64] {
+            let (signed, unsigned) = (
+                IntType::new(true, bits).unwrap(),
+                IntType::new(false, bits).unwrap(),
+            );
+            let max = signed.max();
+            agrees(
+                &ints(&[3, -7, 0, max, -max - 1, 5], signed),
+                &ints(&[2, 2, 0, 1, -1, -5], signed),
+            );
+            agrees(
+                &ints(&[3, -7, 0, 0, 6], signed),
+                &ints(&[2, 3, 0, 4, -3], signed),
+            );
+            agrees(
+                &ints(&[3, 7, 0, 0], unsigned),
+                &ints(&[2, 3, 0, 1], unsigned),
+            );
+            let four = Scalar::Number(Number::Int(Int::new(4, unsigned).unwrap()));
+            agrees(&Array::scalar(four), &ints(&[1, 9], unsigned));
+        }
+        agrees(
+            &numbers(&[1.5, -0.0, 0.0, 7.0, -3.5]),
+            &numbers(&[0.5, 0.0, -0.0, -2.0, 2.0]),
+        );
+        agrees(&numbers(&[1.0, 0.0]), &numbers(&[0.0, 0.0]));
+        let singles = |ns: &[f32]| {
+            Array::vector(
+                ns.iter()
+                    .map(|n| Scalar::Number(Number::Float(Float::Single(*n))))
+                    .collect(),
+            )
+        };
+        agrees(
+            &singles(&[0.1, 3.0, -2.5, 0.0]),
+            &singles(&[0.2, 7.0, 2.0, 0.0]),
+        );
+        agrees(&Array::scalar(2.5), &numbers(&[1.0, 2.5, 4.0]));
+    }
+
+    #[test]
+    fn it_keeps_the_overflow_mode_of_the_width() {
+        let i8 = IntType::new(true, 8).unwrap();
+        let (big, small) = (ints(&[100, -100, 3], i8), ints(&[100, 100, 3], i8));
+        agrees(&big, &small);
+        set_overflow(i8, Overflow::Wrapping);
+        agrees(&big, &small);
+        set_overflow(i8, Overflow::Saturating);
+        agrees(&big, &small);
+    }
+
+    // `count` values of `ty`: small ones, none 0 if `nonzero`, or else
+    // ones from all over its range, the extremes and 0 included.
+    fn values(ty: IntType, count: usize, seed: u64, small: bool) -> Vec<i128> {
+        let mut state = seed;
+        (0..count)
+            .map(|i| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let span = (ty.max() - ty.min() + 1).min(10);
+                match (small, i % 7) {
+                    (true, _) => ty.min().max(-9) + 1 + (state >> 33) as i128 % (span - 1),
+                    (false, 0) => ty.min(),
+                    (false, 1) => ty.max(),
+                    (false, 2) => 0,
+                    (false, _) => ty.min() + (state as i128).rem_euclid(ty.max() - ty.min() + 1),
+                }
+            })
+            .map(|n| if small && n == 0 { 1 } else { n })
+            .collect()
+    }
+
+    // `b` with every fifth element that of `a`, for `=` to find.
+    fn sharing(a: &[i128], mut b: Vec<i128>) -> Vec<i128> {
+        for i in (0..b.len()).step_by(5) {
+            b[i] = a[i];
+        }
+        b
+    }
+
+    #[test]
+    fn it_agrees_a_register_at_a_time() {
+        // long enough for whole registers and some left over at every width
+        let count = 67;
+        for ty in [
+            IntType::I8,
+            IntType::I16,
+            IntType::I32,
+            IntType::I64,
+            IntType::U8,
+            IntType::U16,
+            IntType::U32,
+            IntType::U64,
+        ] {
+            let (small, spread) = (values(ty, count, 1, true), values(ty, count, 2, false));
+            let pairs = [
+                (small.clone(), sharing(&small, values(ty, count, 3, true))),
+                (
+                    spread.clone(),
+                    sharing(&spread, values(ty, count, 4, false)),
+                ),
+                (small.clone(), sharing(&small, values(ty, count, 5, false))),
+            ];
+            for mode in Overflow::MODES {
+                set_overflow(ty, mode);
+                for (a, b) in &pairs {
+                    agrees(&ints(a, ty), &ints(b, ty));
+                }
+            }
+            set_overflow(ty, Overflow::Checked);
+        }
+
+        let doubles =
+            |ns: &[i128]| numbers(&ns.iter().map(|n| *n as f64 / 3.0).collect::<Vec<_>>());
+        let (a, b) = (
+            values(IntType::I16, count, 6, false),
+            values(IntType::I8, count, 7, true),
+        );
+        agrees(&doubles(&a), &doubles(&sharing(&a, b.clone())));
+        let zeros = vec![0; count];
+        let some = (0..count as i128).map(|i| i % 2 * 2).collect::<Vec<_>>();
+        agrees(&doubles(&zeros), &doubles(&some));
+        let singles = |ns: &[i128]| {
+            Array::vector(
+                ns.iter()
+                    .map(|n| Scalar::Number(Number::Float(Float::Single(*n as f32 / 3.0))))
+                    .collect(),
+            )
+        };
+        agrees(&singles(&a), &singles(&sharing(&a, b)));
+        agrees(&singles(&zeros), &singles(&some));
+    }
+
+    #[test]
+    fn it_keeps_results_unboxed() {
+        let typed = into(
+            &Array::from_string_literal("i16"),
+            &numbers(&(1..40).map(f64::from).collect::<Vec<_>>()),
+        )
+        .unwrap();
+        let sum = dyadic(&Token::Plus, &typed, &typed).unwrap().unwrap();
+        assert!(matches!(sum.lanes(), Some(Cow::Borrowed(Lanes::I16(_)))));
+        let twice = dyadic(&Token::Times, &Array::scalar(2.0), &numbers(&[1.0, 2.0]));
+        assert!(matches!(
+            twice.unwrap().unwrap().lanes(),
+            Some(Cow::Borrowed(Lanes::F64(_)))
+        ));
+        let halves = dyadic(&Token::Divide, &sum, &typed).unwrap().unwrap();
+        assert!(matches!(halves.lanes(), Some(Cow::Borrowed(Lanes::F64(_)))));
+        let less = dyadic(&Token::LessThan, &typed, &sum).unwrap().unwrap();
+        assert!(less.bits().is_some());
+    }
+
+    #[test]
+    fn it_leaves_the_rest_to_pervading() {
+        let numbers = Array::vector(vec![Scalar::from(1.0), Scalar::from(f64::NAN)]);
+        assert!(dyadic(&Token::Plus, &numbers, &numbers).is_some());
+        assert!(dyadic(&Token::LessThan, &numbers, &numbers).is_none());
+        let i4 = ints(&[1, 2], IntType::new(true, 4).unwrap());
+        assert!(dyadic(&Token::Plus, &i4, &i4).is_none());
+        let i8 = ints(&[1, 2], IntType::new(true, 8).unwrap());
+        assert!(dyadic(&Token::Plus, &i8, &ints(&[1, 2], IntType::I64)).is_none());
+        assert!(dyadic(
+            &Token::Plus,
+            &i8,
+            &ints(&[1], IntType::new(true, 8).unwrap())
+        )
+        .is_none());
+        assert!(dyadic(&Token::Star, &i8, &i8).is_none());
+        assert!(dyadic(&Token::Plus, &Array::scalar(1.0), &Array::scalar(1.0)).is_none());
+        assert!(dyadic(&Token::Plus, &Array::from_string_literal("ab"), &i8).is_none());
+    }
+}
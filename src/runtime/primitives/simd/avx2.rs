@@ -0,0 +1,422 @@
+// The explicit kernels, in AVX2 instructions on 256-bit registers. Each
+// width has those AVX2 has instructions for: `+` and `-` at every width,
+// saturating at 8 and 16 bits, wrapping `×` at 16 and 32, `⌈` and `⌊` below
+// 64 and the comparisons everywhere, with the overflow of `+` and `-`
+// found a register at a time too. The reals have all of `+ - × ÷ ⌈ ⌊` and
+// the comparisons. AVX2 compares integers only as signed, so unsigned ones
+// have their sign bits flipped first.
+
+use std::arch::x86_64::*;
+
+use super::{Compare, Explicit, Op, Overflows, Zip};
+
+// `$name`, a `Zip` kernel of `$t`s computing `$f` on registers `$a`, `$b`.
+macro_rules! zip {
+    ($name:ident: $t:ty, $load:ident, $store:ident, |$a:ident, $b:ident| $f:expr) => {
+        #[target_feature(enable = "avx2")]
+        unsafe fn $name(a: &[$t], b: &[$t], out: &mut [$t]) -> usize {
+            const LANES: usize = 32 / std::mem::size_of::<$t>();
+            let whole = a.len() / LANES * LANES;
+            for i in (0..whole).step_by(LANES) {
+                let $a = $load(a.as_ptr().add(i) as *const _);
+                let $b = $load(b.as_ptr().add(i) as *const _);
+                $store(out.as_mut_ptr().add(i) as *mut _, $f);
+            }
+            whole
+        }
+    };
+}
+
+// `$name`, a `Compare` kernel of `$t`s with `$mask` the bits for registers
+// `$a` and `$b`, the lowest for the first element.
+macro_rules! compare {
+    ($name:ident: $t:ty, $load:ident, |$a:ident, $b:ident| $mask:expr) => {
+        #[target_feature(enable = "avx2")]
+        unsafe fn $name(a: &[$t], b: &[$t], words: &mut [u64]) -> usize {
+            const LANES: usize = 32 / std::mem::size_of::<$t>();
+            let whole = a.len() / LANES * LANES;
+            for i in (0..whole).step_by(LANES) {
+                let $a = $load(a.as_ptr().add(i) as *const _);
+                let $b = $load(b.as_ptr().add(i) as *const _);
+                // 64 is a multiple of `LANES`, so a register's bits never
+                // cross a word
+                words[i / 64] |= ($mask as u32 as u64) << (i % 64);
+            }
+            whole
+        }
+    };
+}
+
+// `$name`, an `Overflows` kernel of `$t`s with `$flags` the overflow flags
+// of `$wrap`, in the sign bit of each element.
+macro_rules! overflows {
+    ($name:ident: $t:ty, $wrap:ident, $flags:ident, $sign:expr) => {
+        #[target_feature(enable = "avx2")]
+        unsafe fn $name(a: &[$t], b: &[$t]) -> (usize, bool) {
+            const LANES: usize = 32 / std::mem::size_of::<$t>();
+            let whole = a.len() / LANES * LANES;
+            let mut flags = _mm256_setzero_si256();
+            for i in (0..whole).step_by(LANES) {
+                let a = _mm256_loadu_si256(a.as_ptr().add(i) as *const _);
+                let b = _mm256_loadu_si256(b.as_ptr().add(i) as *const _);
+                flags = _mm256_or_si256(flags, $flags(a, b, $wrap(a, b)));
+            }
+            (whole, _mm256_testz_si256(flags, $sign) == 0)
+        }
+    };
+}
+
+// The overflow flags of `+` and `-` giving `r`, in the sign bit of each
+// element, as in Hacker's Delight 2-13.
+
+#[target_feature(enable = "avx2")]
+unsafe fn signed_add(a: __m256i, b: __m256i, r: __m256i) -> __m256i {
+    _mm256_and_si256(_mm256_xor_si256(a, r), _mm256_xor_si256(b, r))
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn signed_sub(a: __m256i, b: __m256i, r: __m256i) -> __m256i {
+    _mm256_and_si256(_mm256_xor_si256(a, b), _mm256_xor_si256(a, r))
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn unsigned_add(a: __m256i, b: __m256i, r: __m256i) -> __m256i {
+    let either = _mm256_or_si256(a, b);
+    _mm256_or_si256(_mm256_and_si256(a, b), _mm256_andnot_si256(r, either))
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn unsigned_sub(a: __m256i, b: __m256i, r: __m256i) -> __m256i {
+    let not_a = _mm256_xor_si256(a, _mm256_set1_epi8(-1));
+    let borrowed = _mm256_and_si256(_mm256_or_si256(not_a, b), r);
+    _mm256_or_si256(_mm256_and_si256(not_a, b), borrowed)
+}
+
+// The top bit of each element of a comparison, in order.
+
+#[target_feature(enable = "avx2")]
+unsafe fn mask8(compared: __m256i) -> i32 {
+    _mm256_movemask_epi8(compared)
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn mask16(compared: __m256i) -> i32 {
+    // packing works within each half, which the permutation puts back
+    let packed = _mm256_packs_epi16(compared, compared);
+    _mm256_movemask_epi8(_mm256_permute4x64_epi64(packed, 0b1000)) & 0xFFFF
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn mask32(compared: __m256i) -> i32 {
+    _mm256_movemask_ps(_mm256_castsi256_ps(compared))
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn mask64(compared: __m256i) -> i32 {
+    _mm256_movemask_pd(_mm256_castsi256_pd(compared))
+}
+
+macro_rules! integer {
+    (
+        $t:ty,
+        add: $add:ident,
+        sub: $sub:ident,
+        eq: $eq:ident,
+        gt: $gt:ident,
+        mask: $mask:ident,
+        lanes: $lanes:expr,
+        flip: $flip:expr,
+        sign: $sign:expr,
+        flags: $add_flags:ident, $sub_flags:ident,
+        saturating: [$($adds:ident, $subs:ident)?],
+        multiply: [$($mul:ident)?],
+        order: [$($max:ident, $min:ident)?]
+    ) => {
+        impl Explicit for $t {
+            fn lanewise(op: Op) -> Option<Zip<Self>> {
+                zip!(add: $t, _mm256_loadu_si256, _mm256_storeu_si256, |a, b| $add(a, b));
+                zip!(sub: $t, _mm256_loadu_si256, _mm256_storeu_si256, |a, b| $sub(a, b));
+                match op {
+                    Op::Add => Some(add as Zip<Self>),
+                    Op::Subtract => Some(sub),
+                    $(Op::Multiply => {
+                        zip!(mul: $t, _mm256_loadu_si256, _mm256_storeu_si256, |a, b| $mul(a, b));
+                        Some(mul)
+                    })?
+                    $(Op::Maximum => {
+                        zip!(max: $t, _mm256_loadu_si256, _mm256_storeu_si256, |a, b| $max(a, b));
+                        Some(max)
+                    })?
+                    $(Op::Minimum => {
+                        zip!(min: $t, _mm256_loadu_si256, _mm256_storeu_si256, |a, b| $min(a, b));
+                        Some(min)
+                    })?
+                    _ => None,
+                }
+            }
+
+            fn saturating(op: Op) -> Option<Zip<Self>> {
+                match op {
+                    $(Op::Add => {
+                        zip!(add: $t, _mm256_loadu_si256, _mm256_storeu_si256, |a, b| $adds(a, b));
+                        Some(add as Zip<Self>)
+                    })?
+                    $(Op::Subtract => {
+                        zip!(sub: $t, _mm256_loadu_si256, _mm256_storeu_si256, |a, b| $subs(a, b));
+                        Some(sub as Zip<Self>)
+                    })?
+                    _ => None,
+                }
+            }
+
+            fn compare(op: Op) -> Option<Compare<Self>> {
+                compare!(eq: $t, _mm256_loadu_si256, |a, b| $mask($eq(a, b)));
+                compare!(ne: $t, _mm256_loadu_si256, |a, b| $mask($eq(a, b)) ^ $lanes);
+                compare!(lt: $t, _mm256_loadu_si256, |a, b| {
+                    $mask($gt(_mm256_xor_si256(b, $flip), _mm256_xor_si256(a, $flip)))
+                });
+                compare!(gt: $t, _mm256_loadu_si256, |a, b| {
+                    $mask($gt(_mm256_xor_si256(a, $flip), _mm256_xor_si256(b, $flip)))
+                });
+                Some(match op {
+                    Op::Equal => eq as Compare<Self>,
+                    Op::NotEqual => ne,
+                    Op::Less => lt,
+                    Op::Greater => gt,
+                    _ => return None,
+                })
+            }
+
+            fn overflows(op: Op) -> Option<Overflows<Self>> {
+                overflows!(add: $t, $add, $add_flags, $sign);
+                overflows!(sub: $t, $sub, $sub_flags, $sign);
+                match op {
+                    Op::Add => Some(add as Overflows<Self>),
+                    Op::Subtract => Some(sub),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+integer!(
+    i8,
+    add: _mm256_add_epi8,
+    sub: _mm256_sub_epi8,
+    eq: _mm256_cmpeq_epi8,
+    gt: _mm256_cmpgt_epi8,
+    mask: mask8,
+    lanes: -1,
+    flip: _mm256_setzero_si256(),
+    sign: _mm256_set1_epi8(i8::MIN),
+    flags: signed_add, signed_sub,
+    saturating: [_mm256_adds_epi8, _mm256_subs_epi8],
+    multiply: [],
+    order: [_mm256_max_epi8, _mm256_min_epi8]
+);
+
+integer!(
+    u8,
+    add: _mm256_add_epi8,
+    sub: _mm256_sub_epi8,
+    eq: _mm256_cmpeq_epi8,
+    gt: _mm256_cmpgt_epi8,
+    mask: mask8,
+    lanes: -1,
+    flip: _mm256_set1_epi8(i8::MIN),
+    sign: _mm256_set1_epi8(i8::MIN),
+    flags: unsigned_add, unsigned_sub,
+    saturating: [_mm256_adds_epu8, _mm256_subs_epu8],
+    multiply: [],
+    order: [_mm256_max_epu8, _mm256_min_epu8]
+);
+
+integer!(
+    i16,
+    add: _mm256_add_epi16,
+    sub: _mm256_sub_epi16,
+    eq: _mm256_cmpeq_epi16,
+    gt: _mm256_cmpgt_epi16,
+    mask: mask16,
+    lanes: 0xFFFF,
+    flip: _mm256_setzero_si256(),
+    sign: _mm256_set1_epi16(i16::MIN),
+    flags: signed_add, signed_sub,
+    saturating: [_mm256_adds_epi16, _mm256_subs_epi16],
+    multiply: [_mm256_mullo_epi16],
+    order: [_mm256_max_epi16, _mm256_min_epi16]
+);
+
+integer!(
+    u16,
+    add: _mm256_add_epi16,
+    sub: _mm256_sub_epi16,
+    eq: _mm256_cmpeq_epi16,
+    gt: _mm256_cmpgt_epi16,
+    mask: mask16,
+    lanes: 0xFFFF,
+    flip: _mm256_set1_epi16(i16::MIN),
+    sign: _mm256_set1_epi16(i16::MIN),
+    flags: unsigned_add, unsigned_sub,
+    saturating: [_mm256_adds_epu16, _mm256_subs_epu16],
+    multiply: [_mm256_mullo_epi16],
+    order: [_mm256_max_epu16, _mm256_min_epu16]
+);
+
+integer!(
+    i32,
+    add: _mm256_add_epi32,
+    sub: _mm256_sub_epi32,
+    eq: _mm256_cmpeq_epi32,
+    gt: _mm256_cmpgt_epi32,
+    mask: mask32,
+    lanes: 0xFF,
+    flip: _mm256_setzero_si256(),
+    sign: _mm256_set1_epi32(i32::MIN),
+    flags: signed_add, signed_sub,
+    saturating: [],
+    multiply: [_mm256_mullo_epi32],
+    order: [_mm256_max_epi32, _mm256_min_epi32]
+);
+
+integer!(
+    u32,
+    add: _mm256_add_epi32,
+    sub: _mm256_sub_epi32,
+    eq: _mm256_cmpeq_epi32,
+    gt: _mm256_cmpgt_epi32,
+    mask: mask32,
+    lanes: 0xFF,
+    flip: _mm256_set1_epi32(i32::MIN),
+    sign: _mm256_set1_epi32(i32::MIN),
+    flags: unsigned_add, unsigned_sub,
+    saturating: [],
+    multiply: [_mm256_mullo_epi32],
+    order: [_mm256_max_epu32, _mm256_min_epu32]
+);
+
+integer!(
+    i64,
+    add: _mm256_add_epi64,
+    sub: _mm256_sub_epi64,
+    eq: _mm256_cmpeq_epi64,
+    gt: _mm256_cmpgt_epi64,
+    mask: mask64,
+    lanes: 0xF,
+    flip: _mm256_setzero_si256(),
+    sign: _mm256_set1_epi64x(i64::MIN),
+    flags: signed_add, signed_sub,
+    saturating: [],
+    multiply: [],
+    order: []
+);
+
+integer!(
+    u64,
+    add: _mm256_add_epi64,
+    sub: _mm256_sub_epi64,
+    eq: _mm256_cmpeq_epi64,
+    gt: _mm256_cmpgt_epi64,
+    mask: mask64,
+    lanes: 0xF,
+    flip: _mm256_set1_epi64x(i64::MIN),
+    sign: _mm256_set1_epi64x(i64::MIN),
+    flags: unsigned_add, unsigned_sub,
+    saturating: [],
+    multiply: [],
+    order: []
+);
+
+// `⌈` and `⌊` take the second operand unless the first is greater or less,
+// which for `a⌈b` is `b` unless `a` is greater or ties, so `b` goes first.
+// `0÷0` is 1, any other division by zero having been raised already.
+macro_rules! real {
+    (
+        $t:ty,
+        load: $load:ident,
+        store: $store:ident,
+        add: $add:ident,
+        sub: $sub:ident,
+        mul: $mul:ident,
+        div: $div:ident,
+        max: $max:ident,
+        min: $min:ident,
+        cmp: $cmp:ident,
+        mask: $mask:ident,
+        splat: $splat:ident,
+        zero: $zero:ident,
+        blend: $blend:ident
+    ) => {
+        impl Explicit for $t {
+            fn lanewise(op: Op) -> Option<Zip<Self>> {
+                zip!(add: $t, $load, $store, |a, b| $add(a, b));
+                zip!(sub: $t, $load, $store, |a, b| $sub(a, b));
+                zip!(mul: $t, $load, $store, |a, b| $mul(a, b));
+                zip!(div: $t, $load, $store, |a, b| {
+                    let by_zero = $cmp(b, $zero(), _CMP_EQ_OQ);
+                    $blend($div(a, b), $splat(1.0), by_zero)
+                });
+                zip!(max: $t, $load, $store, |a, b| $max(b, a));
+                zip!(min: $t, $load, $store, |a, b| $min(b, a));
+                Some(match op {
+                    Op::Add => add as Zip<Self>,
+                    Op::Subtract => sub,
+                    Op::Multiply => mul,
+                    Op::Divide => div,
+                    Op::Maximum => max,
+                    Op::Minimum => min,
+                    _ => return None,
+                })
+            }
+
+            fn compare(op: Op) -> Option<Compare<Self>> {
+                compare!(eq: $t, $load, |a, b| $mask($cmp(a, b, _CMP_EQ_OQ)));
+                compare!(ne: $t, $load, |a, b| $mask($cmp(a, b, _CMP_NEQ_UQ)));
+                compare!(lt: $t, $load, |a, b| $mask($cmp(a, b, _CMP_LT_OQ)));
+                compare!(gt: $t, $load, |a, b| $mask($cmp(a, b, _CMP_GT_OQ)));
+                Some(match op {
+                    Op::Equal => eq as Compare<Self>,
+                    Op::NotEqual => ne,
+                    Op::Less => lt,
+                    Op::Greater => gt,
+                    _ => return None,
+                })
+            }
+        }
+    };
+}
+
+real!(
+    f32,
+    load: _mm256_loadu_ps,
+    store: _mm256_storeu_ps,
+    add: _mm256_add_ps,
+    sub: _mm256_sub_ps,
+    mul: _mm256_mul_ps,
+    div: _mm256_div_ps,
+    max: _mm256_max_ps,
+    min: _mm256_min_ps,
+    cmp: _mm256_cmp_ps,
+    mask: _mm256_movemask_ps,
+    splat: _mm256_set1_ps,
+    zero: _mm256_setzero_ps,
+    blend: _mm256_blendv_ps
+);
+
+real!(
+    f64,
+    load: _mm256_loadu_pd,
+    store: _mm256_storeu_pd,
+    add: _mm256_add_pd,
+    sub: _mm256_sub_pd,
+    mul: _mm256_mul_pd,
+    div: _mm256_div_pd,
+    max: _mm256_max_pd,
+    min: _mm256_min_pd,
+    cmp: _mm256_cmp_pd,
+    mask: _mm256_movemask_pd,
+    splat: _mm256_set1_pd,
+    zero: _mm256_setzero_pd,
+    blend: _mm256_blendv_pd
+);
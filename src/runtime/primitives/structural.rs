@@ -0,0 +1,742 @@
+use crate::errors::Errors;
+use crate::runtime::array::{Array, Scalar};
+
+/// Monadic `⊂`: enclose.
+pub fn enclose(right: Array) -> Array {
+    Array::scalar(right.enclose())
+}
+
+/// Monadic `⊆`: nest, encloses only simple arrays.
+pub fn nest(right: Array) -> Array {
+    if right.is_simple() && !right.is_scalar() {
+        enclose(right)
+    } else {
+        right
+    }
+}
+
+/// Monadic `⊃`: first, the disclosed first element or the prototype of an
+/// empty array.
+pub fn first(right: Array) -> Array {
+    let prototype = right.prototype();
+    match right.into_data().into_iter().next() {
+        Some(first) => first.disclose(),
+        None => prototype.disclose(),
+    }
+}
+
+/// Monadic `≡`: depth.
+pub fn depth(right: &Array) -> Array {
+    Array::scalar(right.depth() as f64)
+}
+
+/// Monadic `≢`: tally.
+pub fn tally(right: &Array) -> Array {
+    Array::scalar(right.tally() as f64)
+}
+
+// Walks every index of `shape` in row-major order.
+pub fn indices(shape: &[usize]) -> impl Iterator<Item = Vec<usize>> + '_ {
+    let count: usize = shape.iter().product();
+    (0..count).map(move |mut n| {
+        let mut index = vec![0; shape.len()];
+        for (axis, length) in shape.iter().enumerate().rev() {
+            index[axis] = n % length;
+            n /= length;
+        }
+        index
+    })
+}
+
+/// Monadic `↑`: mix. Discloses every element into a new array whose trailing
+/// axes are as long as the longest element along each axis, padding shorter
+/// elements (and elements of lower rank, on their leading axes) with their
+/// own fill.
+pub fn mix(right: Array) -> Array {
+    if right.is_simple() {
+        return right;
+    }
+
+    let outer = right.shape().to_vec();
+    let prototype = right.prototype().disclose();
+    let items: Vec<Array> = right
+        .into_data()
+        .into_iter()
+        .map(Scalar::disclose)
+        .collect();
+
+    let rank = items
+        .iter()
+        .map(Array::rank)
+        .max()
+        .unwrap_or(prototype.rank());
+    let padded = |item: &Array| {
+        let mut shape = vec![1; rank - item.rank()];
+        shape.extend_from_slice(item.shape());
+        shape
+    };
+
+    let mut inner = match items.is_empty() {
+        true => padded(&prototype),
+        false => vec![0; rank],
+    };
+    for item in &items {
+        for (length, item_length) in inner.iter_mut().zip(padded(item)) {
+            *length = (*length).max(item_length);
+        }
+    }
+
+    let mut data = Vec::with_capacity(items.len() * inner.iter().product::<usize>());
+    for item in &items {
+        let shape = padded(item);
+        let fill = item.prototype();
+        for index in indices(&inner) {
+            match index.iter().zip(&shape).all(|(i, length)| i < length) {
+                true => {
+                    let offset = index
+                        .iter()
+                        .zip(&shape)
+                        .fold(0, |offset, (i, length)| offset * length + i);
+                    data.push(item.data()[offset].clone());
+                }
+                false => data.push(fill.clone()),
+            }
+        }
+    }
+
+    let mut shape = outer;
+    shape.extend(inner);
+    Array::new(shape, data).with_prototype(prototype.prototype())
+}
+
+// The columns `start..end` of every row along the last axis.
+fn last_axis_slice(array: &Array, start: usize, end: usize) -> Array {
+    let length = *array.shape().last().unwrap();
+    let mut shape = array.shape().to_vec();
+    *shape.last_mut().unwrap() = end - start;
+
+    let data = match length {
+        0 => vec![],
+        _ => array
+            .data()
+            .chunks(length)
+            .flat_map(|row| row[start..end].iter().cloned())
+            .collect(),
+    };
+
+    Array::new(shape, data).with_prototype(array.prototype())
+}
+
+// Reads the left argument of `⊂` or `⊆` as one non-negative count per item
+// along the last axis of `right`, extending a scalar. `extra` is how many
+// more items than that are allowed.
+fn partition_counts(left: &Array, length: usize, extra: usize) -> anyhow::Result<Vec<usize>> {
+    if left.rank() > 1 {
+        anyhow::bail!(Errors::Rank(
+            "partitioning expects a vector on the left".to_string()
+        ));
+    }
+
+    let counts = left
+        .data()
+        .iter()
+        .map(|scalar| {
+            let n = scalar.as_real()?;
+            if n < 0.0 || n.fract() != 0.0 {
+                anyhow::bail!(Errors::Domain(
+                    "partitioning expects non-negative integers".to_string()
+                ));
+            }
+            Ok(n as usize)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    match counts.as_slice() {
+        [count] if left.is_scalar() => Ok(vec![*count; length]),
+        _ if counts.len() >= length && counts.len() <= length + extra => Ok(counts),
+        _ => anyhow::bail!(Errors::Length(format!(
+            "expected {} partition counts, got {}",
+            length,
+            counts.len()
+        ))),
+    }
+}
+
+/// Dyadic `⊂`: partitioned enclose along the last axis. Each item of `left`
+/// says how many partitions start at the corresponding item of `right`;
+/// items before the first partition are dropped. One extra count is allowed
+/// for trailing empty partitions.
+pub fn partitioned_enclose(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    if right.is_scalar() {
+        anyhow::bail!(Errors::Rank(
+            "⊂ expects a non-scalar on the right".to_string()
+        ));
+    }
+
+    let length = *right.shape().last().unwrap();
+    let counts = partition_counts(left, length, 1)?;
+
+    let mut starts = Vec::new();
+    for (i, count) in counts.iter().enumerate() {
+        starts.extend(std::iter::repeat_n(i, *count));
+    }
+
+    let partitions = starts
+        .iter()
+        .enumerate()
+        .map(|(n, start)| {
+            let end = starts.get(n + 1).copied().unwrap_or(length).max(*start);
+            Scalar::Boxed(Box::new(last_axis_slice(right, *start, end)))
+        })
+        .collect();
+
+    Ok(Array::vector(partitions)
+        .with_prototype(Scalar::Boxed(Box::new(last_axis_slice(right, 0, 0)))))
+}
+
+/// Dyadic `⊆`: partition along the last axis. A new partition starts
+/// wherever `left` increases, and items under a `0` are dropped.
+pub fn partition(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    if right.is_scalar() {
+        anyhow::bail!(Errors::Rank(
+            "⊆ expects a non-scalar on the right".to_string()
+        ));
+    }
+
+    let length = *right.shape().last().unwrap();
+    let keys = partition_counts(left, length, 0)?;
+
+    let mut partitions = Vec::new();
+    let mut start = None;
+    for i in 0..=length {
+        let key = keys.get(i).copied().unwrap_or(0);
+        let previous = if i == 0 { 0 } else { keys[i - 1] };
+
+        if key == 0 || key > previous {
+            if let Some(start) = start.take() {
+                partitions.push(Scalar::Boxed(Box::new(last_axis_slice(right, start, i))));
+            }
+        }
+        if key != 0 && start.is_none() {
+            start = Some(i);
+        }
+    }
+
+    Ok(Array::vector(partitions)
+        .with_prototype(Scalar::Boxed(Box::new(last_axis_slice(right, 0, 0)))))
+}
+
+// Reads the left argument of `/` as one integer count per item along an
+// axis of length `length`, extending a scalar.
+fn replicate_counts(left: &Array, length: usize) -> anyhow::Result<Vec<i64>> {
+    if left.rank() > 1 {
+        anyhow::bail!(Errors::Rank("/ expects a vector on the left".to_string()));
+    }
+
+    let counts = left
+        .data()
+        .iter()
+        .map(|scalar| {
+            let n = scalar.as_real()?;
+            if n.fract() != 0.0 {
+                anyhow::bail!(Errors::Domain("/ expects integers on the left".to_string()));
+            }
+            Ok(n as i64)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    match counts.as_slice() {
+        [count] if left.is_scalar() => Ok(vec![*count; length]),
+        _ => Ok(counts),
+    }
+}
+
+/// Dyadic `/`: replicate along the last axis. Each item of `right` is
+/// repeated as many times as the corresponding count in `left`, and a
+/// negative count gives that many fills instead. With a boolean mask this is
+/// compress, which on packed booleans runs a word at a time.
+pub fn replicate(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    let right = match right.is_scalar() {
+        true => right.clone().ravel(),
+        false => right.clone(),
+    };
+    let length = *right.shape().last().unwrap();
+    let counts = replicate_counts(left, length)?;
+
+    // A single item extends to as many as there are counts
+    let right = match (length, counts.len()) {
+        (1, n) if n != 1 => {
+            let items = right
+                .data()
+                .iter()
+                .flat_map(|item| std::iter::repeat_n(item.clone(), n));
+            let mut shape = right.shape().to_vec();
+            *shape.last_mut().unwrap() = n;
+            Array::new(shape, items.collect()).with_prototype(right.prototype())
+        }
+        (length, n) if length == n => right,
+        _ => anyhow::bail!(Errors::Length(format!(
+            "/ expects {} counts, got {}",
+            length,
+            counts.len()
+        ))),
+    };
+
+    if let (Some(mask), Some(bits), 1) = (left.as_bits(), right.bits(), right.rank()) {
+        if mask.len() == bits.len() {
+            let compressed = bits.compress(&mask);
+            return Ok(Array::from_bits(vec![compressed.len()], compressed));
+        }
+    }
+
+    let fill = right.prototype();
+    let mut shape = right.shape().to_vec();
+    *shape.last_mut().unwrap() = counts.iter().map(|n| n.unsigned_abs() as usize).sum();
+
+    let mut data = Vec::with_capacity(shape.iter().product());
+    if !counts.is_empty() {
+        for row in right.data().chunks(counts.len()) {
+            for (item, count) in row.iter().zip(&counts) {
+                let item = if *count < 0 { &fill } else { item };
+                data.extend(std::iter::repeat_n(
+                    item.clone(),
+                    count.unsigned_abs() as usize,
+                ));
+            }
+        }
+    }
+    Ok(Array::new(shape, data).with_prototype(fill))
+}
+
+/// Monadic `⌽`: reverse, along the last axis.
+pub fn reverse(right: &Array) -> Array {
+    let length = right.shape().last().copied().unwrap_or(1);
+    let data = match length {
+        0 => vec![],
+        length => right
+            .data()
+            .chunks(length)
+            .flat_map(|row| row.iter().rev().cloned())
+            .collect(),
+    };
+    Array::new(right.shape().to_vec(), data).with_prototype(right.prototype())
+}
+
+/// `array[indices]`: the elements of a vector at each of the indices, in
+/// the shape of the indices.
+pub fn index(array: &Array, indices: &Array) -> anyhow::Result<Array> {
+    if array.rank() != 1 {
+        anyhow::bail!(Errors::Rank(
+            "indexing takes a vector, or one index for each axis".to_string()
+        ));
+    }
+    let data = indices
+        .data()
+        .iter()
+        .map(|index| {
+            let n = index.as_real()?;
+            match array.data().get((n - 1.0) as usize) {
+                Some(element) if n >= 1.0 && n.fract() == 0.0 => Ok(element.clone()),
+                _ => anyhow::bail!(Errors::Index(format!(
+                    "{} is not an index of a vector of {}",
+                    n,
+                    array.tally()
+                ))),
+            }
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Array::new(indices.shape().to_vec(), data).with_prototype(array.prototype()))
+}
+
+/// Monadic `⍴`: shape.
+pub fn shape(right: &Array) -> Array {
+    Array::vector(
+        right
+            .shape()
+            .iter()
+            .map(|length| Scalar::from(*length as f64))
+            .collect(),
+    )
+}
+
+/// Dyadic `⍴`: reshape. The elements of `right` are taken in order and
+/// reused from the start as often as needed; an empty `right` gives fills.
+pub fn reshape(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    if left.rank() > 1 {
+        anyhow::bail!(Errors::Rank("⍴ expects a vector on the left".to_string()));
+    }
+    let shape = left
+        .data()
+        .iter()
+        .map(|scalar| {
+            let n = scalar.as_real()?;
+            if n < 0.0 || n.fract() != 0.0 {
+                anyhow::bail!(Errors::Domain(
+                    "⍴ expects non-negative integers on the left".to_string()
+                ));
+            }
+            Ok(n as usize)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let count = shape.iter().product();
+    let prototype = right.prototype();
+    let data = match right.data().is_empty() {
+        true => vec![prototype.clone(); count],
+        false => right.data().iter().cycle().take(count).cloned().collect(),
+    };
+    Ok(Array::new(shape, data).with_prototype(prototype))
+}
+
+/// Dyadic `,`: catenate along the last axis. A scalar is extended to a
+/// column, and an argument of rank one less gains a last axis of length 1.
+pub fn catenate(left: &Array, right: &Array) -> anyhow::Result<Array> {
+    let rank = left.rank().max(right.rank()).max(1);
+    let frame = match (left.rank() == rank, right.rank() == rank) {
+        (true, _) => left.shape()[..rank - 1].to_vec(),
+        (false, true) => right.shape()[..rank - 1].to_vec(),
+        (false, false) => vec![],
+    };
+
+    let lift = |array: &Array| -> anyhow::Result<Array> {
+        let mut shape = frame.clone();
+        if array.rank() == rank {
+            return Ok(array.clone());
+        }
+        shape.push(1);
+        match array.rank() {
+            0 => {
+                let count = shape.iter().product();
+                let scalar = array.data()[0].clone();
+                Ok(Array::new(shape, vec![scalar; count]))
+            }
+            r if r + 1 == rank => Ok(Array::new(
+                array.shape().iter().copied().chain([1]).collect(),
+                array.data().to_vec(),
+            )),
+            _ => anyhow::bail!(Errors::Rank(
+                ", expects arguments of rank at most one apart".to_string()
+            )),
+        }
+    };
+    let (left_lifted, right_lifted) = (lift(left)?, lift(right)?);
+    let (&left_length, left_frame) = left_lifted.shape().split_last().unwrap();
+    let (&right_length, right_frame) = right_lifted.shape().split_last().unwrap();
+    if left_frame != right_frame {
+        anyhow::bail!(Errors::Length(format!(
+            ", expects arguments of shape {:?} and {:?} to agree on the leading axes",
+            left.shape(),
+            right.shape()
+        )));
+    }
+
+    let rows: usize = left_frame.iter().product();
+    let mut data = Vec::with_capacity(rows * (left_length + right_length));
+    for row in 0..rows {
+        data.extend_from_slice(&left_lifted.data()[row * left_length..(row + 1) * left_length]);
+        data.extend_from_slice(&right_lifted.data()[row * right_length..(row + 1) * right_length]);
+    }
+
+    let mut shape = left_frame.to_vec();
+    shape.push(left_length + right_length);
+    let prototype = match left.data().is_empty() {
+        true => right.prototype(),
+        false => left.prototype(),
+    };
+    Ok(Array::new(shape, data).with_prototype(prototype))
+}
+
+// Expected values are what Dyalog APL 18.2 gives with ⎕ML←1 and ⎕IO←1.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::tests::numbers;
+
+    fn chars(s: &str) -> Array {
+        Array::from_string_literal(s)
+    }
+
+    fn nested(items: Vec<Array>) -> Array {
+        Array::vector(items.into_iter().map(Array::enclose).collect())
+    }
+
+    #[test]
+    fn it_encloses() {
+        // ⊂5 ←→ 5
+        assert_eq!(enclose(Array::scalar(5.0)), Array::scalar(5.0));
+        // ⍴⊂1 2 3 ←→ ⍬
+        let enclosed = enclose(numbers(&[1.0, 2.0, 3.0]));
+        assert!(enclosed.is_scalar());
+        assert_eq!(first(enclosed), numbers(&[1.0, 2.0, 3.0]));
+        // ⊆'abc' ←→ ⊂'abc', ⊆'ab' 'cd' ←→ 'ab' 'cd'
+        assert_eq!(nest(chars("abc")), enclose(chars("abc")));
+        let strings = nested(vec![chars("ab"), chars("cd")]);
+        assert_eq!(nest(strings.clone()), strings);
+    }
+
+    #[test]
+    fn it_measures_depth() {
+        let depth_of = |array: &Array| depth(array).data()[0].as_real().unwrap();
+
+        assert_eq!(depth_of(&Array::scalar(5.0)), 0.0);
+        assert_eq!(depth_of(&numbers(&[1.0, 2.0])), 1.0);
+        assert_eq!(depth_of(&chars("")), 1.0);
+        assert_eq!(depth_of(&enclose(numbers(&[1.0, 2.0]))), 2.0);
+        assert_eq!(
+            depth_of(&nested(vec![numbers(&[1.0, 2.0]), numbers(&[3.0, 4.0])])),
+            2.0
+        );
+        // ≡1 (2 3) ←→ ¯2
+        assert_eq!(
+            depth_of(&nested(vec![Array::scalar(1.0), numbers(&[2.0, 3.0])])),
+            -2.0
+        );
+        // ≡⊂⊂1 2 ←→ 3
+        assert_eq!(depth_of(&enclose(enclose(numbers(&[1.0, 2.0])))), 3.0);
+        // ≡(1 2)(3 (4 5)) ←→ ¯3
+        assert_eq!(
+            depth_of(&nested(vec![
+                numbers(&[1.0, 2.0]),
+                nested(vec![Array::scalar(3.0), numbers(&[4.0, 5.0])])
+            ])),
+            -3.0
+        );
+    }
+
+    #[test]
+    fn it_tallies() {
+        let tally_of = |array: &Array| tally(array).data()[0].as_real().unwrap();
+
+        assert_eq!(tally_of(&Array::scalar(5.0)), 1.0);
+        assert_eq!(tally_of(&numbers(&[1.0, 2.0, 3.0])), 3.0);
+        assert_eq!(tally_of(&Array::new(vec![2, 3], vec![0.0.into(); 6])), 2.0);
+        assert_eq!(tally_of(&nested(vec![chars("ab"), chars("cde")])), 2.0);
+    }
+
+    #[test]
+    fn it_discloses_the_first_item() {
+        assert_eq!(
+            first(nested(vec![numbers(&[1.0, 2.0]), numbers(&[3.0])])),
+            numbers(&[1.0, 2.0])
+        );
+        assert_eq!(first(Array::scalar(5.0)), Array::scalar(5.0));
+        // ⊃⍬ ←→ 0, ⊃'' ←→ ' '
+        assert_eq!(first(numbers(&[])), Array::scalar(0.0));
+        assert_eq!(first(chars("")), Array::scalar(' '));
+        // ⊃0⍴⊂'ab' ←→ '  '
+        let empty = Array::vector(vec![]).with_prototype(chars("ab").enclose());
+        assert_eq!(first(empty), chars("  "));
+    }
+
+    #[test]
+    fn it_mixes() {
+        // ↑(1 2)(3 4 5) ←→ 2 3⍴1 2 0 3 4 5
+        assert_eq!(
+            mix(nested(vec![
+                numbers(&[1.0, 2.0]),
+                numbers(&[3.0, 4.0, 5.0])
+            ])),
+            Array::new(
+                vec![2, 3],
+                [1.0, 2.0, 0.0, 3.0, 4.0, 5.0]
+                    .iter()
+                    .map(|n| Scalar::from(*n))
+                    .collect()
+            )
+        );
+        // ↑'ab' 'c' ←→ 2 2⍴'abc '
+        assert_eq!(
+            mix(nested(vec![chars("ab"), chars("c")])),
+            Array::new(vec![2, 2], chars("abc ").into_data())
+        );
+        // ↑'ab' 1 ←→ 2 2⍴'ab' 1 0
+        assert_eq!(
+            mix(nested(vec![chars("ab"), Array::scalar(1.0)])),
+            Array::new(
+                vec![2, 2],
+                vec!['a'.into(), 'b'.into(), 1.0.into(), 0.0.into()]
+            )
+        );
+        assert_eq!(mix(numbers(&[1.0, 2.0])), numbers(&[1.0, 2.0]));
+    }
+
+    #[test]
+    fn it_partitions_with_enclose() {
+        // 1 0 1 0 0⊂'abcde' ←→ 'ab' 'cde'
+        assert_eq!(
+            partitioned_enclose(&numbers(&[1.0, 0.0, 1.0, 0.0, 0.0]), &chars("abcde")).unwrap(),
+            nested(vec![chars("ab"), chars("cde")])
+        );
+        // 0 1 0 1 0⊂'abcde' ←→ 'bc' 'de'
+        assert_eq!(
+            partitioned_enclose(&numbers(&[0.0, 1.0, 0.0, 1.0, 0.0]), &chars("abcde")).unwrap(),
+            nested(vec![chars("bc"), chars("de")])
+        );
+        // 2 0 1⊂'abc' ←→ '' 'ab' (,'c')
+        assert_eq!(
+            partitioned_enclose(&numbers(&[2.0, 0.0, 1.0]), &chars("abc")).unwrap(),
+            nested(vec![
+                chars(""),
+                chars("ab"),
+                Array::vector(vec!['c'.into()])
+            ])
+        );
+        // 1 0 0 1⊂'abc' ←→ 'abc' ''
+        assert_eq!(
+            partitioned_enclose(&numbers(&[1.0, 0.0, 0.0, 1.0]), &chars("abc")).unwrap(),
+            nested(vec![chars("abc"), chars("")])
+        );
+        // 1⊂'ab' ←→ (,'a')(,'b')
+        assert_eq!(
+            partitioned_enclose(&Array::scalar(1.0), &chars("ab")).unwrap(),
+            nested(vec![
+                Array::vector(vec!['a'.into()]),
+                Array::vector(vec!['b'.into()])
+            ])
+        );
+        // 1 0 1⊂2 3⍴⍳6 ←→ (2 2⍴1 2 4 5)(2 1⍴3 6)
+        let matrix = Array::new(
+            vec![2, 3],
+            (1..=6).map(|n| Scalar::from(n as f64)).collect(),
+        );
+        assert_eq!(
+            partitioned_enclose(&numbers(&[1.0, 1.0, 0.0]), &matrix).unwrap(),
+            nested(vec![
+                Array::new(vec![2, 1], vec![1.0.into(), 4.0.into()]),
+                Array::new(
+                    vec![2, 2],
+                    vec![2.0.into(), 3.0.into(), 5.0.into(), 6.0.into()]
+                ),
+            ])
+        );
+
+        assert!(partitioned_enclose(&numbers(&[1.0, 0.0]), &chars("abc")).is_err());
+        assert!(partitioned_enclose(&numbers(&[1.0, -1.0, 0.0]), &chars("abc")).is_err());
+        assert!(partitioned_enclose(&numbers(&[1.0]), &Array::scalar(1.0)).is_err());
+    }
+
+    #[test]
+    fn it_partitions() {
+        // 1 1 2 2 0 3⊆'abcdef' ←→ 'ab' 'cd' (,'f')
+        assert_eq!(
+            partition(&numbers(&[1.0, 1.0, 2.0, 2.0, 0.0, 3.0]), &chars("abcdef")).unwrap(),
+            nested(vec![
+                chars("ab"),
+                chars("cd"),
+                Array::vector(vec!['f'.into()])
+            ])
+        );
+        // 1 1 0 1 1⊆'abcde' ←→ 'ab' 'de'
+        assert_eq!(
+            partition(&numbers(&[1.0, 1.0, 0.0, 1.0, 1.0]), &chars("abcde")).unwrap(),
+            nested(vec![chars("ab"), chars("de")])
+        );
+        // 2 1 1⊆'abc' ←→ ,⊂'abc'
+        assert_eq!(
+            partition(&numbers(&[2.0, 1.0, 1.0]), &chars("abc")).unwrap(),
+            nested(vec![chars("abc")])
+        );
+        // 0⊆'abc' ←→ ⍬ (of prototype ⊂'')
+        let empty = partition(&Array::scalar(0.0), &chars("abc")).unwrap();
+        assert_eq!(empty.shape(), &[0]);
+        assert_eq!(first(empty), chars(""));
+
+        assert!(partition(&numbers(&[1.0, 1.0, 1.0, 1.0]), &chars("abc")).is_err());
+    }
+
+    #[test]
+    fn it_replicates() {
+        // 1 0 2 ¯1/'abc' is a LENGTH ERROR, 1 0 2/'abc' ←→ 'acc'
+        assert!(replicate(&numbers(&[1.0, 0.0, 2.0, -1.0]), &chars("abc")).is_err());
+        assert_eq!(
+            replicate(&numbers(&[1.0, 0.0, 2.0]), &chars("abc")).unwrap(),
+            chars("acc")
+        );
+        // 2 ¯1 1/1 2 3 ←→ 1 1 0 3
+        assert_eq!(
+            replicate(&numbers(&[2.0, -1.0, 1.0]), &numbers(&[1.0, 2.0, 3.0])).unwrap(),
+            numbers(&[1.0, 1.0, 0.0, 3.0])
+        );
+        // 3/5 ←→ 5 5 5, and 1 0 1/'a' ←→ 'aa'
+        assert_eq!(
+            replicate(&Array::scalar(3.0), &Array::scalar(5.0)).unwrap(),
+            numbers(&[5.0, 5.0, 5.0])
+        );
+        assert_eq!(
+            replicate(&numbers(&[1.0, 0.0, 1.0]), &Array::scalar('a')).unwrap(),
+            chars("aa")
+        );
+        // 1 0 1/2 3⍴'abcdef' ←→ 2 2⍴'acdf'
+        let matrix = Array::new(vec![2, 3], chars("abcdef").into_data());
+        assert_eq!(
+            replicate(&numbers(&[1.0, 0.0, 1.0]), &matrix).unwrap(),
+            Array::new(vec![2, 2], chars("acdf").into_data())
+        );
+        // 0/'abc' ←→ ''
+        assert!(replicate(&Array::scalar(0.0), &chars("abc"))
+            .unwrap()
+            .is_char());
+        assert!(replicate(&Array::scalar(0.5), &chars("abc")).is_err());
+    }
+
+    #[test]
+    fn it_compresses_packed_booleans() {
+        let booleans = |bs: &[bool]| Array::vector(bs.iter().map(|b| Scalar::from(*b)).collect());
+        let mask: Vec<bool> = (0..200).map(|i| i % 5 != 0).collect();
+        let items: Vec<bool> = (0..200).map(|i| i % 2 == 0).collect();
+
+        let compressed = replicate(&booleans(&mask), &booleans(&items)).unwrap();
+        let expected: Vec<bool> = items
+            .iter()
+            .zip(&mask)
+            .filter(|(_, keep)| **keep)
+            .map(|(item, _)| *item)
+            .collect();
+        assert_eq!(compressed, booleans(&expected));
+        assert!(compressed.bits().is_some());
+    }
+
+    #[test]
+    fn it_reshapes() {
+        // ⍴2 3⍴⍳6 ←→ 2 3
+        let matrix = reshape(&numbers(&[2.0, 3.0]), &numbers(&[1.0, 2.0])).unwrap();
+        assert_eq!(shape(&matrix), numbers(&[2.0, 3.0]));
+        assert_eq!(matrix.data()[5], Scalar::from(2.0));
+        // 3⍴⍬ ←→ 0 0 0, ⍴⍴5 ←→ ,0
+        assert_eq!(
+            reshape(&Array::scalar(3.0), &numbers(&[])).unwrap(),
+            numbers(&[0.0, 0.0, 0.0])
+        );
+        assert_eq!(shape(&shape(&Array::scalar(5.0))), numbers(&[0.0]));
+        assert!(reshape(&Array::scalar(-1.0), &numbers(&[1.0])).is_err());
+    }
+
+    #[test]
+    fn it_catenates() {
+        // 1 2,3 ←→ 1 2 3, 'a','b' ←→ 'ab'
+        assert_eq!(
+            catenate(&numbers(&[1.0, 2.0]), &Array::scalar(3.0)).unwrap(),
+            numbers(&[1.0, 2.0, 3.0])
+        );
+        assert_eq!(
+            catenate(&Array::scalar('a'), &Array::scalar('b')).unwrap(),
+            chars("ab")
+        );
+        // (2 2⍴⍳4),0 ←→ 2 3⍴1 2 0 3 4 0
+        let matrix = reshape(&numbers(&[2.0, 2.0]), &numbers(&[1.0, 2.0, 3.0, 4.0])).unwrap();
+        assert_eq!(
+            catenate(&matrix, &Array::scalar(0.0)).unwrap(),
+            reshape(
+                &numbers(&[2.0, 3.0]),
+                &numbers(&[1.0, 2.0, 0.0, 3.0, 4.0, 0.0])
+            )
+            .unwrap()
+        );
+        // (2 2⍴⍳4),5 6 ←→ 2 3⍴1 2 5 3 4 6
+        assert_eq!(
+            catenate(&matrix, &numbers(&[5.0, 6.0])).unwrap().data()[5],
+            Scalar::from(6.0)
+        );
+        assert!(catenate(&matrix, &numbers(&[5.0, 6.0, 7.0])).is_err());
+    }
+}
@@ -1,8 +1,9 @@
-pub(crate) mod bracket_partitioner;
-mod numeric_literal;
+pub mod bracket_partitioner;
+pub mod numeric_literal;
 
 use crate::errors::Errors;
 use crate::tokenizer::numeric_literal::NumericLiteral;
+use macros::glyph_tokens;
 use std::fmt::Display;
 
 #[derive(Debug, Clone)]
@@ -20,124 +21,182 @@ impl Display for Loc {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum Token {
-    Identifier(String),
-    NumericLiteral(NumericLiteral),
-    StringLiteral(String),
-
-    Comment(String), // ⍝
-
-    OpenRoundBracket,
-    OpenSquareBracket,
-    OpenCurlyBracket,
-    CloseRoundBracket,
-    CloseSquareBracket,
-    CloseCurlyBracket,
-
-    NL, // \n
-
-    Plus,            // +
-    Minus,           // -
-    Times,           // ×
-    Divide,          // ÷
-    Upstile,         // ⌈
-    Downstile,       // ⌊
-    Star,            // *
-    ExclamationMark, // !
-    Stile,           // |
-    Log,             // ⍟
-    Circle,          // ○
-    Domino,          // ⌹
-    UpTack,          // ⊥
-    DownTack,        // ⊤
-    QuestionMark,    // ?
-
-    Tilde,                // ~
-    LogicalAND,           // ∧
-    LogicalOR,            // ∨
-    LogicalNAND,          // ⍲
-    LogicalNOR,           // ⍱
-    LessThan,             // <
-    GreaterThan,          // >
-    LessThanOrEqualTo,    // ≤
-    GreaterThanOrEqualTo, // ≥
-    Equal,                // =
-    NotEqual,             // ≠
-    EqualUnderbar,        // ≡
-    EqualUnderbarSlash,   // ≢
-
-    Rho,              // ⍴
-    Comma,            // ,
-    CommaBar,         // ⍪
-    CircleStile,      // ⌽
-    CircleBar,        // ⊖
-    Transpose,        // ⍉
-    UpArrow,          // ↑
-    DownArrow,        // ↓
-    LeftShoe,         // ⊂
-    LeftShoeUnderbar, // ⊆
-    Epsilon,          // ∊
-
-    Squad,        // ⌷
-    RightShoe,    // ⊃
-    Slash,        // /
-    SlashBar,     // ⌿
-    Backslash,    // \
-    BackslashBar, // ⍀
-    DownShoe,     // ∪
-    UpShoe,       // ∩
-    LeftTack,     // ⊣
-    RightTack,    // ⊢
-
-    Iota,            // ⍳
-    IotaUnderbar,    // ⍸
-    EpsilonUnderbar, // ⍷
-    GradeUp,         // ⍋
-    GradeDown,       // ⍒
-
-    Diaeresis,      // ¨
-    TildeDiaeresis, // ⍨
-    StarDiaeresis,  // ⍣
-    Dot,            // .
-    Jot,            // ∘
-    QuadEqual,      // ⌸
-    JotDiaeresis,   // ⍤
-    CircleDieresis, // ⍥
-    QuadDiamond,    // ⌺
-    At,             // @
-    QuadColon,      // ⍠
-
-    LeftArrow, // ←
-    Zilde,     // ⍬
-    Hydrant,   // ⍎
-    Thorn,     // ⍕
-    Diamond,   // ⋄
-    Del,       // ∇
-    Alpha,     // ⍺
-    Omega,     // ⍵
-
-    Colon, // :
-
-    EOF,
+/// How a glyph takes its arguments, as recorded in the glyph table in the
+/// `macros` crate.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Valence {
+    Niladic,
+    Monadic,
+    Dyadic,
+    Ambivalent,
+    MonadicOperator,
+    DyadicOperator,
+    Syntax,
+}
+
+// The glyph variants (`Plus`, `Rho`, `OpenRoundBracket`, ...) are appended
+// from the glyph table in the `macros` crate, which also derives `Glyph` to
+// give `Token::from_char`, `as_char`, `name`, `valence`, `ascii_alias` and
+// `from_ascii_alias`.
+glyph_tokens! {
+    #[allow(dead_code, clippy::upper_case_acronyms)]
+    #[derive(Debug, PartialEq, Clone)]
+    pub enum Token {
+        Identifier(String),
+        NumericLiteral(NumericLiteral),
+        StringLiteral(String),
+        // A control word such as `:If`, spelled as in `KEYWORDS`.
+        Keyword(String),
+
+        Comment(String), // ⍝
+
+        NL, // \n
+
+        EOF,
+    }
 }
 
 pub type TokenStream = Vec<(Token, Loc)>;
 type Stream<'a> = std::iter::Peekable<std::str::Chars<'a>>;
 
+/// The words that follow a `:` in the control structures of tradfns, and
+/// `:Import`. They are matched regardless of case.
+pub const KEYWORDS: [&str; 23] = [
+    "If",
+    "ElseIf",
+    "Else",
+    "EndIf",
+    "For",
+    "In",
+    "EndFor",
+    "While",
+    "EndWhile",
+    "Repeat",
+    "Until",
+    "EndRepeat",
+    "Select",
+    "Case",
+    "CaseList",
+    "EndSelect",
+    "Trap",
+    "EndTrap",
+    "Leave",
+    "Continue",
+    "Return",
+    "End",
+    "Import",
+];
+
+// The keyword spelled by the letters after a `:`, if they spell one of
+// `words`. The stream is only advanced past it when they do.
+fn keyword_extractor(
+    stream: &mut Stream,
+    output: &mut TokenStream,
+    words: &[&str],
+    line: usize,
+    col: &mut usize,
+) -> bool {
+    let word: String = stream
+        .clone()
+        .skip(1)
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    let keyword = match words
+        .iter()
+        .find(|keyword| keyword.eq_ignore_ascii_case(&word))
+    {
+        Some(keyword) => keyword,
+        None => return false,
+    };
+
+    output.push((Token::Keyword(keyword.to_string()), Loc { line, col: *col }));
+    let length = word.chars().count() + 1;
+    stream.nth(length - 1);
+    *col += length;
+    true
+}
+
+// The glyph spelled by the ASCII alias at the head of the stream, such as
+// `<-` for `←`, taking the longest alias that matches. The stream is only
+// advanced past it when there is one.
+fn alias_extractor(
+    stream: &mut Stream,
+    output: &mut TokenStream,
+    line: usize,
+    col: &mut usize,
+) -> bool {
+    let head: String = stream.clone().take(3).collect();
+    let alias = (1..=head.chars().count()).rev().find_map(|length| {
+        let alias: String = head.chars().take(length).collect();
+        Some((Token::from_ascii_alias(&alias)?, length))
+    });
+    let (glyph, length) = match alias {
+        Some(alias) => alias,
+        None => return false,
+    };
+
+    output.push((glyph, Loc { line, col: *col }));
+    stream.nth(length - 1);
+    *col += length;
+    true
+}
+
+// Digits, the sign, the decimal point, `E`, `J` and `r`, the base prefixes
+// and the type suffixes.
+fn is_numeric_literal_char(c: char) -> bool {
+    matches!(
+        c,
+        '0'..='9'
+            | '¯'
+            | '.'
+            | 'E'
+            | 'J'
+            | 'r'
+            | 'x'
+            | 'o'
+            | 'u'
+            | 'i'
+            | 'f'
+            | 'h'
+            | 'd'
+            | 'c'
+            | 'b'
+            | 'n'
+    )
+}
+
+// Whether `c` carries on the literal `so_far`: hexadecimal literals take the
+// letters `a` to `f` as digits too.
+fn continues_numeric_literal(so_far: &str, c: char) -> bool {
+    let hexadecimal = so_far.trim_start_matches('¯').starts_with("0x");
+    is_numeric_literal_char(c) || (hexadecimal && c.is_ascii_hexdigit())
+}
+
 fn numeric_literal_extractor(
     stream: &mut Stream,
     output: &mut TokenStream,
     line: &mut usize,
     col: &mut usize,
 ) -> anyhow::Result<()> {
+    let start = Loc {
+        col: *col,
+        line: *line,
+    };
     let mut counting_cycle = String::new();
-    while let Some('0'..='9' | 'u' | 'f' | 'i' | 'E' | 'J' | '¯') = stream.peek() {
-        counting_cycle.push(stream.next().unwrap());
+    while let Some(&c) = stream
+        .peek()
+        .filter(|c| continues_numeric_literal(&counting_cycle, **c))
+    {
+        counting_cycle.push(c);
+        stream.next();
         *col += 1;
     }
+    let literal = counting_cycle
+        .parse::<NumericLiteral>()
+        .map_err(|error| Errors::InvalidNumericLiteral(error, start))?;
     output.push((
-        Token::NumericLiteral(counting_cycle.parse::<NumericLiteral>()?),
+        Token::NumericLiteral(literal),
         Loc {
             col: *col - 1,
             line: *line,
@@ -169,6 +228,12 @@ fn string_literal_extractor(
         };
         if token != '\\' && next == '\'' {
             stream.next();
+            // A doubled quote is an escaped quote, as in standard APL.
+            if let Some('\'') = stream.peek() {
+                str.push('\'');
+                *col += 1;
+                continue;
+            }
             break;
         } else if token == '\n' {
             anyhow::bail!(Errors::UnexpectedToken(
@@ -189,7 +254,10 @@ fn string_literal_extractor(
                 'n' => str.push('\n'),
                 '\'' => str.push('\''),
 
-                _ => anyhow::bail!("Unexpected escape character {}", next),
+                _ => anyhow::bail!(Errors::Syntax(format!(
+                    "unexpected escape character {}",
+                    next
+                ))),
             }
         } else if next != '\\' {
             str.push(next);
@@ -218,14 +286,8 @@ fn identifier_extractor(
         let next = *next;
 
         match next {
-            '+' | '-' | '×' | '÷' | '⌈' | '⌊' | '*' | '!' | '|' | '⍟' | '○' | '⌹' | '⊥' | '⊤'
-            | '?' | '~' | '∧' | '∨' | '⍲' | '⍱' | '<' | '>' | '≤' | '≥' | '=' | '≠' | '≡' | '≢'
-            | '⍴' | ',' | '⍪' | '⌽' | '⊖' | '⍉' | '↑' | '↓' | '⊂' | '⊆' | '∊' | '⌷' | '⊃' | '/'
-            | '⌿' | '\\' | '⍀' | '∪' | '∩' | '⊣' | '⊢' | '⍳' | '⍸' | '⍷' | '⍋' | '⍒' | '¨'
-            | '⍨' | '⍣' | '.' | '∘' | '⌸' | '⍤' | '⍥' | '⌺' | '@' | '⍠' | '←' | '⍬' | '⍎' | '⍕'
-            | '⋄' | '∇' | '⍺' | '⍵' | '{' | '}' | '(' | ')' | '[' | ']' | ':' | ' ' | '\t' => {
-                break
-            }
+            ' ' | '\t' | '\n' => break,
+            c if Token::from_char(c).is_some() => break,
             _ => {
                 str.push(stream.next().unwrap());
                 *col += 1;
@@ -249,11 +311,23 @@ pub fn tokenize(str: String) -> anyhow::Result<TokenStream> {
 
     let mut stream: Stream = str.chars().peekable();
 
+    // Control words only start a statement outside dfns, where a colon is a
+    // guard, and `:In` only follows a `:For`.
+    let mut depth = 0usize;
+    let mut statement = true;
+    let mut for_clause = false;
+
     while let Some(token) = stream.peek() {
         let token = *token;
+        let tokens = output.len();
+        let words: &[&str] = match (depth, statement, for_clause) {
+            (0, true, _) => &KEYWORDS,
+            (0, false, true) => &["In"],
+            _ => &[],
+        };
 
         match token {
-            '0'..='9' => {
+            '0'..='9' | '¯' => {
                 numeric_literal_extractor(&mut stream, &mut output, &mut line, &mut col)?;
             }
             '\'' => {
@@ -267,415 +341,39 @@ pub fn tokenize(str: String) -> anyhow::Result<TokenStream> {
                 col = 1;
                 stream.next();
             }
-            '+' => {
-                output.push((Token::Plus, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '-' => {
-                output.push((Token::Minus, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '×' => {
-                output.push((Token::Times, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '÷' => {
-                output.push((Token::Divide, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⌈' => {
-                output.push((Token::Upstile, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⌊' => {
-                output.push((Token::Downstile, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '*' => {
-                output.push((Token::Star, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '!' => {
-                output.push((Token::ExclamationMark, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '|' => {
-                output.push((Token::Stile, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍟' => {
-                output.push((Token::Log, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '○' => {
-                output.push((Token::Circle, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⌹' => {
-                output.push((Token::Domino, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⊥' => {
-                output.push((Token::UpTack, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⊤' => {
-                output.push((Token::DownTack, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '?' => {
-                output.push((Token::QuestionMark, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '~' => {
-                output.push((Token::Tilde, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '∧' => {
-                output.push((Token::LogicalAND, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '∨' => {
-                output.push((Token::LogicalOR, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍲' => {
-                output.push((Token::LogicalNAND, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍱' => {
-                output.push((Token::LogicalNOR, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '<' => {
-                output.push((Token::LessThan, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '>' => {
-                output.push((Token::GreaterThan, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '≤' => {
-                output.push((Token::LessThanOrEqualTo, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '≥' => {
-                output.push((Token::GreaterThanOrEqualTo, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '=' => {
-                output.push((Token::Equal, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '≠' => {
-                output.push((Token::NotEqual, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '≡' => {
-                output.push((Token::EqualUnderbar, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '≢' => {
-                output.push((Token::EqualUnderbarSlash, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍴' => {
-                output.push((Token::Rho, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            ',' => {
-                output.push((Token::Comma, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍪' => {
-                output.push((Token::CommaBar, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⌽' => {
-                output.push((Token::CircleStile, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⊖' => {
-                output.push((Token::CircleBar, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍉' => {
-                output.push((Token::Transpose, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '↑' => {
-                output.push((Token::UpArrow, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '↓' => {
-                output.push((Token::DownArrow, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⊂' => {
-                output.push((Token::LeftShoe, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⊆' => {
-                output.push((Token::LeftShoeUnderbar, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '∊' => {
-                output.push((Token::Epsilon, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⌷' => {
-                output.push((Token::Squad, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⊃' => {
-                output.push((Token::RightShoe, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '/' => {
-                output.push((Token::Slash, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⌿' => {
-                output.push((Token::SlashBar, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '\\' => {
-                output.push((Token::Backslash, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍀' => {
-                output.push((Token::BackslashBar, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '∪' => {
-                output.push((Token::DownShoe, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '∩' => {
-                output.push((Token::UpShoe, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⊣' => {
-                output.push((Token::LeftTack, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⊢' => {
-                output.push((Token::RightTack, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍳' => {
-                output.push((Token::Iota, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍸' => {
-                output.push((Token::IotaUnderbar, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍷' => {
-                output.push((Token::EpsilonUnderbar, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍋' => {
-                output.push((Token::GradeUp, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍒' => {
-                output.push((Token::GradeDown, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '¨' => {
-                output.push((Token::Diaeresis, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍨' => {
-                output.push((Token::TildeDiaeresis, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍣' => {
-                output.push((Token::StarDiaeresis, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '.' => {
-                output.push((Token::Dot, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '∘' => {
-                output.push((Token::Jot, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⌸' => {
-                output.push((Token::QuadEqual, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍤' => {
-                output.push((Token::JotDiaeresis, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍥' => {
-                output.push((Token::CircleDieresis, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⌺' => {
-                output.push((Token::QuadDiamond, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '@' => {
-                output.push((Token::At, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍠' => {
-                output.push((Token::QuadColon, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '←' => {
-                output.push((Token::LeftArrow, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍬' => {
-                output.push((Token::Zilde, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍎' => {
-                output.push((Token::Hydrant, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍕' => {
-                output.push((Token::Thorn, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⋄' => {
-                output.push((Token::Diamond, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '∇' => {
-                output.push((Token::Del, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍺' => {
-                output.push((Token::Alpha, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '⍵' => {
-                output.push((Token::Omega, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '{' => {
-                output.push((Token::OpenCurlyBracket, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '}' => {
-                output.push((Token::CloseCurlyBracket, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '(' => {
-                output.push((Token::OpenRoundBracket, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            ')' => {
-                output.push((Token::CloseRoundBracket, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            '[' => {
-                output.push((Token::OpenSquareBracket, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            ']' => {
-                output.push((Token::CloseSquareBracket, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
-            ':' => {
-                output.push((Token::Colon, Loc { line, col }));
-                col += 1;
-                stream.next();
-            }
             ' ' | '\t' => {
                 col += 1;
                 stream.next();
             }
-            _ => {
-                identifier_extractor(&mut stream, &mut output, &mut line, &mut col);
+            ':' if keyword_extractor(&mut stream, &mut output, words, line, &mut col) => {}
+            c if c.is_ascii_punctuation()
+                && alias_extractor(&mut stream, &mut output, line, &mut col) => {}
+            c => match Token::from_char(c) {
+                Some(glyph) => {
+                    output.push((glyph, Loc { line, col }));
+                    col += 1;
+                    stream.next();
+                }
+                None => identifier_extractor(&mut stream, &mut output, &mut line, &mut col),
+            },
+        }
+
+        match output[tokens..].last() {
+            Some((Token::NL | Token::Diamond, _)) => {
+                statement = true;
+                for_clause = false;
+            }
+            Some((token, _)) => {
+                statement = false;
+                match token {
+                    Token::Keyword(word) if word == "For" => for_clause = true,
+                    Token::OpenCurlyBracket => depth += 1,
+                    Token::CloseCurlyBracket => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
             }
+            None => {}
         }
-        // match *token {}
     }
 
     Ok(output)
@@ -689,105 +387,34 @@ pub fn destream(stream: TokenStream) -> String {
         match token {
             Token::Identifier(s) => out.push_str(s.as_ref()),
             Token::StringLiteral(s) => {
-                if let Some('0'..='9' | 'u' | 'f' | 'i' | 'E' | 'J' | '¯' | '\'') =
-                    out.chars().last()
+                if out
+                    .chars()
+                    .last()
+                    .is_some_and(|c| is_numeric_literal_char(c) || c == '\'')
                 {
                     out.push(' ');
                 }
-                out.push_str(format!("'{}'", s.replace("\n", "\\n")).as_ref());
+                out.push_str(format!("'{}'", s.replace('\'', "''").replace("\n", "\\n")).as_ref());
+            }
+            Token::Keyword(s) => {
+                out.push(':');
+                out.push_str(s.as_ref());
             }
             Token::Comment(s) => out.push_str(s.as_ref()),
             Token::NumericLiteral(s) => {
-                if let Some('0'..='9' | 'u' | 'f' | 'i' | 'E' | 'J' | '¯' | '\'') =
-                    out.chars().last()
+                if out
+                    .chars()
+                    .last()
+                    .is_some_and(|c| is_numeric_literal_char(c) || c == '\'')
                 {
                     out.push(' ');
                 }
                 out.push_str(s.to_string().as_ref());
             }
 
-            Token::Plus => out.push('+'),
-            Token::Minus => out.push('-'),
-            Token::Times => out.push('×'),
-            Token::Divide => out.push('÷'),
-            Token::Upstile => out.push('⌈'),
-            Token::Downstile => out.push('⌊'),
-            Token::Star => out.push('*'),
-            Token::ExclamationMark => out.push('!'),
-            Token::Stile => out.push('|'),
-            Token::Log => out.push('⍟'),
-            Token::Circle => out.push('○'),
-            Token::Domino => out.push('⌹'),
-            Token::UpTack => out.push('⊥'),
-            Token::DownTack => out.push('⊤'),
-            Token::QuestionMark => out.push('?'),
-            Token::Tilde => out.push('~'),
-            Token::LogicalAND => out.push('∧'),
-            Token::LogicalOR => out.push('∨'),
-            Token::LogicalNAND => out.push('⍲'),
-            Token::LogicalNOR => out.push('⍱'),
-            Token::LessThan => out.push('<'),
-            Token::GreaterThan => out.push('>'),
-            Token::LessThanOrEqualTo => out.push('≤'),
-            Token::GreaterThanOrEqualTo => out.push('≥'),
-            Token::Equal => out.push('='),
-            Token::NotEqual => out.push('≠'),
-            Token::EqualUnderbar => out.push('≡'),
-            Token::EqualUnderbarSlash => out.push('≢'),
-            Token::Rho => out.push('⍴'),
-            Token::Comma => out.push(','),
-            Token::CommaBar => out.push('⍪'),
-            Token::CircleStile => out.push('⌽'),
-            Token::CircleBar => out.push('⊖'),
-            Token::Transpose => out.push('⍉'),
-            Token::UpArrow => out.push('↑'),
-            Token::DownArrow => out.push('↓'),
-            Token::LeftShoe => out.push('⊂'),
-            Token::LeftShoeUnderbar => out.push('⊆'),
-            Token::Epsilon => out.push('∊'),
-            Token::Squad => out.push('⌷'),
-            Token::RightShoe => out.push('⊃'),
-            Token::Slash => out.push('/'),
-            Token::SlashBar => out.push('⌿'),
-            Token::Backslash => out.push('\\'),
-            Token::BackslashBar => out.push('⍀'),
-            Token::DownShoe => out.push('∪'),
-            Token::UpShoe => out.push('∩'),
-            Token::LeftTack => out.push('⊣'),
-            Token::RightTack => out.push('⊢'),
-            Token::Iota => out.push('⍳'),
-            Token::IotaUnderbar => out.push('⍸'),
-            Token::EpsilonUnderbar => out.push('⍷'),
-            Token::GradeUp => out.push('⍋'),
-            Token::GradeDown => out.push('⍒'),
-            Token::Diaeresis => out.push('¨'),
-            Token::TildeDiaeresis => out.push('⍨'),
-            Token::StarDiaeresis => out.push('⍣'),
-            Token::Dot => out.push('.'),
-            Token::Jot => out.push('∘'),
-            Token::QuadEqual => out.push('⌸'),
-            Token::JotDiaeresis => out.push('⍤'),
-            Token::CircleDieresis => out.push('⍥'),
-            Token::QuadDiamond => out.push('⌺'),
-            Token::At => out.push('@'),
-            Token::QuadColon => out.push('⍠'),
-            Token::LeftArrow => out.push('←'),
-            Token::Zilde => out.push('⍬'),
-            Token::Hydrant => out.push('⍎'),
-            Token::Thorn => out.push('⍕'),
-            Token::Diamond => out.push('⋄'),
-            Token::Del => out.push('∇'),
-            Token::Alpha => out.push('⍺'),
-            Token::Omega => out.push('⍵'),
-            Token::OpenCurlyBracket => out.push('{'),
-            Token::CloseCurlyBracket => out.push('}'),
-            Token::OpenRoundBracket => out.push('('),
-            Token::CloseRoundBracket => out.push(')'),
-            Token::OpenSquareBracket => out.push('['),
-            Token::CloseSquareBracket => out.push(']'),
-            Token::Colon => out.push(':'),
-
-            _ => todo!(),
+            Token::NL => out.push('\n'),
+            Token::EOF => {}
+            token => out.extend(token.as_char()),
         }
     }
 
@@ -878,6 +505,146 @@ mod tests {
         print_locs(src);
     }
 
+    #[test]
+    fn it_round_trips_every_glyph() {
+        let src: String = Token::GLYPHS.iter().collect();
+        let stream = tokenize(src.clone()).unwrap();
+
+        assert_eq!(stream.len(), Token::GLYPHS.len());
+        for ((token, _), glyph) in stream.iter().zip(Token::GLYPHS) {
+            assert_eq!(Token::from_char(*glyph).as_ref(), Some(token));
+        }
+        assert_eq!(destream(stream), src);
+    }
+
+    #[test]
+    fn it_round_trips_lines() {
+        let src = "x←1 2\ny←x+1\n\n'it''s'⋄y".to_string();
+        let mut stream = tokenize(src.clone()).unwrap();
+        stream.push((Token::EOF, Loc { line: 4, col: 9 }));
+        assert_eq!(destream(stream), src);
+    }
+
+    #[test]
+    fn it_tokenizes_quote_escapes() {
+        let stream = tokenize("'it''s' 'don\\'t' '''' ''".to_string()).unwrap();
+        let strings: Vec<_> = stream
+            .iter()
+            .map(|(token, _)| match token {
+                Token::StringLiteral(s) => s.as_str(),
+                token => panic!("expected a string literal, got {:?}", token),
+            })
+            .collect();
+
+        assert_eq!(strings, vec!["it's", "don't", "'", ""]);
+        assert_eq!(destream(stream), "'it''s' 'don''t' '''' ''");
+    }
+
+    #[test]
+    fn it_tokenizes_keywords() {
+        let stream = tokenize(":if x ⋄ :endif ⋄ {⍵:1} ⋄ :Iffy".to_string()).unwrap();
+        let tokens: Vec<_> = stream.iter().map(|(token, _)| token.clone()).collect();
+
+        assert_eq!(tokens[0], Token::Keyword("If".to_string()));
+        assert_eq!(tokens[1], Token::Identifier("x".to_string()));
+        assert_eq!(tokens[3], Token::Keyword("EndIf".to_string()));
+        // A guard, and a colon before a word that is no keyword
+        assert_eq!(tokens[7], Token::Colon);
+        assert_eq!(tokens[11], Token::Colon);
+        assert_eq!(destream(stream), ":Ifx⋄:EndIf⋄{⍵:1}⋄:Iffy");
+    }
+
+    #[test]
+    fn it_only_takes_control_words_where_a_statement_starts() {
+        let stream = tokenize(":For i :In x ⋄ {⍵:in ⋄ ⍵:If} ⋄ x:in".to_string()).unwrap();
+        let keywords: Vec<_> = stream
+            .iter()
+            .filter_map(|(token, _)| match token {
+                Token::Keyword(word) => Some(word.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(keywords, vec!["For", "In"]);
+        assert_eq!(destream(stream), ":Fori:Inx⋄{⍵:in⋄⍵:If}⋄x:in");
+    }
+
+    #[test]
+    fn it_tokenizes_ascii_aliases() {
+        let stream = tokenize("x<-3%4 ⋄ x!==y ⋄ x!=y ⋄ 3!4 ⋄ x<y".to_string()).unwrap();
+        assert_eq!(destream(stream), "x←3÷4⋄x≢y⋄x≠y⋄3!4⋄x<y");
+    }
+
+    #[test]
+    fn it_rejects_literals_too_wide_for_their_volume() {
+        assert!(tokenize("1 2 15u4".to_string()).is_ok());
+        assert!(tokenize("1 2 16u4".to_string()).is_err());
+        assert!(tokenize("1234E2u4".to_string()).is_err());
+    }
+
+    #[test]
+    fn it_tokenizes_float_suffixes() {
+        let stream = tokenize("0.5h 1.25 2d 3f 1b".to_string()).unwrap();
+        let literals: Vec<_> = stream.iter().map(|(token, _)| token.clone()).collect();
+
+        assert_eq!(
+            literals,
+            vec![
+                Token::NumericLiteral(NumericLiteral::Float(4, 0.5)),
+                Token::NumericLiteral(NumericLiteral::Auto(1.25)),
+                Token::NumericLiteral(NumericLiteral::Float(6, 2.0)),
+                Token::NumericLiteral(NumericLiteral::Float(5, 3.0)),
+                Token::NumericLiteral(NumericLiteral::Boolean(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_tokenizes_exact_literals() {
+        let stream = tokenize("1r3+10n".to_string()).unwrap();
+        let source: String = stream
+            .iter()
+            .map(|(token, _)| match token {
+                Token::NumericLiteral(n) => n.to_string(),
+                token => token.as_char().unwrap().to_string(),
+            })
+            .collect();
+
+        assert_eq!(stream.len(), 3);
+        assert_eq!(source, "1r3+10n");
+    }
+
+    #[test]
+    fn it_tokenizes_based_literals() {
+        let stream = tokenize("0xFFu8+0b1010u4×0o17i 0xbeef".to_string()).unwrap();
+        let literals: Vec<_> = stream
+            .iter()
+            .filter_map(|(token, _)| match token {
+                Token::NumericLiteral(n) => Some(n.clone()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            literals,
+            vec![
+                NumericLiteral::Uint(8, 255),
+                NumericLiteral::Uint(4, 10),
+                NumericLiteral::SysInt(15),
+                NumericLiteral::Auto(48879.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_where_a_bad_literal_is() {
+        let error = tokenize("1 2\n3 4 256u8".to_string()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "256u8 does not fit in u8, in the literal at 2:5"
+        );
+    }
+
     #[test]
     fn it_tokenizes_literal() {
         println!(
@@ -1,7 +1,9 @@
+use crate::errors::Errors;
 use crate::tokenizer::{Loc, Token, TokenStream};
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
-pub(crate) enum Partitioner {
+pub enum Partitioner {
     ExpressionSeperator,
 
     Expression(TokenStream),
@@ -11,7 +13,7 @@ pub(crate) enum Partitioner {
     CurlyContainer(PartitionStream),
 }
 
-pub(crate) type PartitionStream = Vec<Partitioner>;
+pub type PartitionStream = Vec<Partitioner>;
 
 fn process(
     token_stream: &mut std::slice::Iter<(Token, Loc)>,
@@ -21,8 +23,10 @@ fn process(
 ) -> Result<(), (Token, Loc)> {
     let mut expression: TokenStream = Vec::with_capacity(16);
     let mut statement = Vec::with_capacity(8);
+    let mut last = Loc { line: 1, col: 1 };
 
     while let Some((token, loc)) = token_stream.next() {
+        last = loc.clone();
         if fail_on.contains(token) {
             return Err((token.clone(), loc.clone()));
         }
@@ -100,12 +104,12 @@ fn process(
                 statement.push(Partitioner::ExpressionSeperator);
             }
             Token::NL => {
-                if expression.len() > 0 {
+                if !expression.is_empty() {
                     statement.push(Partitioner::Expression(expression.clone()));
                     expression.clear();
                     // TODO: should this be clone?
                 }
-                if statement.len() > 0 {
+                if !statement.is_empty() {
                     output.push(Partitioner::Statement(statement.clone()));
                     statement.clear();
                 }
@@ -113,18 +117,26 @@ fn process(
             _ => expression.push((token.clone(), loc.clone())),
         }
     }
-    // Unreachable
+    // Running out of tokens ends the last statement, unless a bracket is
+    // still open.
+    if stop_on != Token::EOF {
+        return Err((Token::EOF, last));
+    }
+    if !expression.is_empty() {
+        statement.push(Partitioner::Expression(expression));
+    }
+    if !statement.is_empty() {
+        output.push(Partitioner::Statement(statement));
+    }
     Ok(())
 }
 
-pub(crate) fn tokenize_to_partition<'a>(
-    token_stream: TokenStream,
-) -> anyhow::Result<PartitionStream> {
+pub fn tokenize_to_partition(token_stream: TokenStream) -> anyhow::Result<PartitionStream> {
     let mut token_stream = token_stream.iter();
     let mut output = Vec::with_capacity(128);
 
     if let Err((token, loc)) = process(&mut token_stream, &mut output, Token::EOF, vec![]) {
-        anyhow::bail!("Unexpected token {:?} at {}", token, loc)
+        anyhow::bail!(Errors::UnexpectedToken(token.clone(), loc.clone()))
         // Err(BaseErr::new(
         //     "Unexpected token", // format!("Unexpected token {:?} at {:?}", token, loc).as_str() as &'a str,
         // ))
@@ -133,7 +145,8 @@ pub(crate) fn tokenize_to_partition<'a>(
     }
 }
 
-pub(crate) fn deserialize_to_string(partition_stream: PartitionStream) -> String {
+#[allow(dead_code)]
+pub(crate) fn deserialize_to_string(_partition_stream: PartitionStream) -> String {
     "".to_string()
 }
 
@@ -151,4 +164,16 @@ mod tests {
 
         println!("{:?}", tokenize_to_partition(stream).unwrap());
     }
+
+    #[test]
+    fn it_keeps_the_last_line_and_rejects_open_brackets() {
+        let partition = |source: &str| tokenize_to_partition(tokenize(source.to_string()).unwrap());
+
+        assert_eq!(partition("1+2\n3×4").unwrap().len(), 2);
+        assert_eq!(
+            partition("(1+2").unwrap_err().to_string(),
+            "Unexpected token EOF at 1:4"
+        );
+        assert!(partition("{⍵+1").is_err());
+    }
 }
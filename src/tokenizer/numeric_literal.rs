@@ -1,8 +1,19 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Zero;
+use thiserror::Error;
+
+use crate::runtime::number::Precision;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum NumericLiteral {
     Complex(f64, f64),
     Float(u8, f64),
 
+    // `123n`, an integer of any size, and `1r3`, an exact rational.
+    Big(BigInt),
+    Rational(BigRational),
+
     SysUint(u64),
     Uint(u8, u64),
     SysInt(i64),
@@ -12,177 +23,461 @@ pub enum NumericLiteral {
     Boolean(bool),
 }
 
-fn parse_atomic_floating_point(s: &str) -> anyhow::Result<f64> {
-    let mut chars = s.chars();
-    let sign = chars.next().unwrap();
+/// Why a literal could not be read. Positions count characters from the
+/// start of the literal, from 0.
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum NumericLiteralError {
+    #[error("empty numeric literal")]
+    Empty,
+    #[error("unexpected {found:?} at {position} in {literal}{}", suggest(.found, .suggestion))]
+    UnexpectedChar {
+        literal: String,
+        position: usize,
+        found: char,
+        suggestion: Option<char>,
+    },
+    #[error("{literal} is missing digits at {position}")]
+    MissingDigits { literal: String, position: usize },
+    #[error("{signature}{volume} at {position} in {literal} is not a type, {}", volumes(.signature))]
+    InvalidVolume {
+        literal: String,
+        position: usize,
+        signature: char,
+        volume: String,
+    },
+    #[error("the {suffix} suffix at {position} in {literal} is not supported yet")]
+    Unsupported {
+        literal: String,
+        position: usize,
+        suffix: char,
+    },
+    #[error("{literal} does not fit in {bound}")]
+    DoesNotFit { literal: String, bound: String },
+    #[error("{literal} has a zero denominator at {position}")]
+    ZeroDenominator { literal: String, position: usize },
+    #[error("{literal} has too many digits")]
+    TooManyDigits { literal: String },
+}
 
-    if sign == '¯' {
-        Ok(-chars.as_str().parse::<f64>()?)
-    } else {
-        Ok(s.parse::<f64>()?)
+type Result<T> = std::result::Result<T, NumericLiteralError>;
+
+// What people reach for out of habit from other languages, and what APL
+// writes instead: J's `_5` and everyone else's `-5` are `¯5` here.
+fn suggestion(found: char) -> Option<char> {
+    match found {
+        '-' | '−' | '_' => Some('¯'),
+        'e' => Some('E'),
+        'j' => Some('J'),
+        _ => None,
     }
 }
 
-fn parse_atomic_integer(s: &str) -> anyhow::Result<i64> {
-    let mut chars = s.chars();
-    let sign = chars.next().unwrap();
+fn suggest(found: &char, suggestion: &Option<char>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(", did you mean {} instead of {}", suggestion, found),
+        None => String::new(),
+    }
+}
 
-    if sign == '¯' {
-        Ok(-chars.as_str().parse::<i64>()?)
-    } else {
-        Ok(s.parse::<i64>()?)
+fn volumes(signature: &char) -> &'static str {
+    match signature {
+        'f' => "floats are f4, f5 and f6",
+        _ => "integers are 1 to 64 bits wide",
     }
 }
 
-fn parse_atomic_unsigned(s: &str) -> anyhow::Result<u64> {
-    Ok(s.parse::<u64>()?)
+// A piece of a literal, its mantissa, exponent, numerator and so on, that
+// knows where it sits so that errors can point into the whole literal.
+#[derive(Clone, Copy)]
+struct Part<'a> {
+    literal: &'a str,
+    start: usize,
+    end: usize,
 }
 
-fn parse_exponentiated_float(s: &str) -> anyhow::Result<f64> {
-    Ok(if let Some((a, b)) = s.split_once('E') {
-        let a = parse_atomic_floating_point(a)?;
-        let b = parse_atomic_integer(b)?;
-        a * 10f64.powi(b as i32)
-    } else {
-        parse_atomic_floating_point(s)?
-    })
+impl<'a> Part<'a> {
+    fn whole(literal: &'a str) -> Self {
+        Part {
+            literal,
+            start: 0,
+            end: literal.len(),
+        }
+    }
+
+    fn text(self) -> &'a str {
+        &self.literal[self.start..self.end]
+    }
+
+    fn split_at(self, at: usize) -> (Self, Self) {
+        let at = self.start + at;
+        (Part { end: at, ..self }, Part { start: at, ..self })
+    }
+
+    fn split_once(self, c: char) -> Option<(Self, Self)> {
+        let (before, after) = self.split_at(self.text().find(c)?);
+        Some((before, after.split_at(c.len_utf8()).1))
+    }
+
+    // The character position of the byte offset `at`.
+    fn position(self, at: usize) -> usize {
+        self.literal[..at].chars().count()
+    }
+
+    fn unexpected(self, at: usize) -> NumericLiteralError {
+        let found = self.literal[at..].chars().next().unwrap();
+        NumericLiteralError::UnexpectedChar {
+            literal: self.literal.to_string(),
+            position: self.position(at),
+            found,
+            suggestion: suggestion(found),
+        }
+    }
+
+    fn invalid_volume(self, signature: char) -> NumericLiteralError {
+        NumericLiteralError::InvalidVolume {
+            literal: self.literal.to_string(),
+            position: self.position(self.start),
+            signature,
+            volume: self.text().to_string(),
+        }
+    }
+
+    fn does_not_fit(self, bound: impl std::fmt::Display) -> NumericLiteralError {
+        NumericLiteralError::DoesNotFit {
+            literal: self.literal.to_string(),
+            bound: bound.to_string(),
+        }
+    }
+
+    // Checks that the part is digits, after a `¯` if `signed` and with one
+    // decimal point among them if `fractional`, so that what is left to go
+    // wrong in parsing it is its size.
+    fn check_digits(self, signed: bool, fractional: bool) -> Result<()> {
+        let (mut digits, mut point) = (0, false);
+        for (i, c) in self.text().char_indices() {
+            match c {
+                '0'..='9' => digits += 1,
+                '¯' if signed && i == 0 => {}
+                '.' if fractional && !point => point = true,
+                _ => return Err(self.unexpected(self.start + i)),
+            }
+        }
+        match digits {
+            0 => Err(NumericLiteralError::MissingDigits {
+                literal: self.literal.to_string(),
+                position: self.position(self.end),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn negative(self) -> bool {
+        self.text().starts_with('¯')
+    }
+
+    fn magnitude(self) -> &'a str {
+        self.text().trim_start_matches('¯')
+    }
+
+    fn split_exponent(self) -> (Self, Option<Self>) {
+        match self.split_once('E') {
+            Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+            None => (self, None),
+        }
+    }
 }
-fn parse_exponentiated_unsigned(s: &str) -> anyhow::Result<u64> {
-    Ok(if let Some((a, b)) = s.split_once('E') {
-        let a = parse_atomic_unsigned(a)?;
-        let b = parse_atomic_unsigned(b)?;
-        a * 10u64.pow(b as u32)
+
+// `1.5` or `¯2.5E¯3`, rounded once from the digits as written.
+fn parse_float(part: Part) -> Result<f64> {
+    let (mantissa, exponent) = part.split_exponent();
+    mantissa.check_digits(true, true)?;
+    let mut decimal = mantissa.text().replace('¯', "-");
+    if let Some(exponent) = exponent {
+        exponent.check_digits(true, false)?;
+        decimal.push('e');
+        decimal.push_str(&exponent.text().replace('¯', "-"));
+    }
+    // Checked to be digits, so only the size can go wrong: overly long
+    // exponents go to 0 or ∞, and ∞ is no number a literal can write.
+    let n = decimal.parse::<f64>().unwrap();
+    match n.is_finite() {
+        true => Ok(n),
+        false => Err(part.does_not_fit("f6")),
+    }
+}
+
+// `¯12E3`, an integer before its range is checked. Exponents can not be
+// negative.
+fn parse_integer(part: Part) -> Result<i128> {
+    let (mantissa, exponent) = part.split_exponent();
+    mantissa.check_digits(true, false)?;
+    let too_big = || part.does_not_fit("64 bits");
+
+    let magnitude = mantissa
+        .magnitude()
+        .parse::<i128>()
+        .map_err(|_| too_big())?;
+    let scale = match exponent {
+        Some(exponent) => {
+            exponent.check_digits(false, false)?;
+            let exponent = exponent.text().parse::<u32>().map_err(|_| too_big())?;
+            10i128.checked_pow(exponent).ok_or_else(too_big)?
+        }
+        None => 1,
+    };
+    let value = magnitude.checked_mul(scale).ok_or_else(too_big)?;
+    Ok(if mantissa.negative() { -value } else { value })
+}
+
+// Past this many digits a big integer literal is surely a mistake, and
+// would take a long time to build.
+const MAX_BIG_EXPONENT: u32 = 100_000;
+
+fn parse_big_integer(part: Part) -> Result<BigInt> {
+    let (mantissa, exponent) = part.split_exponent();
+    mantissa.check_digits(true, false)?;
+    let exponent = match exponent {
+        Some(exponent) => {
+            exponent.check_digits(false, false)?;
+            exponent
+                .text()
+                .parse::<u32>()
+                .ok()
+                .filter(|&exponent| exponent <= MAX_BIG_EXPONENT)
+                .ok_or_else(|| NumericLiteralError::TooManyDigits {
+                    literal: part.literal.to_string(),
+                })?
+        }
+        None => 0,
+    };
+    let magnitude =
+        mantissa.magnitude().parse::<BigInt>().unwrap() * BigInt::from(10).pow(exponent);
+    Ok(if mantissa.negative() {
+        -magnitude
     } else {
-        parse_atomic_unsigned(s)?
+        magnitude
     })
 }
-fn parse_exponentiated_int(s: &str) -> anyhow::Result<i64> {
-    Ok(if let Some((a, b)) = s.split_once('E') {
-        let a = parse_atomic_integer(a)?;
-        let b = parse_atomic_unsigned(b)?;
-        a * 10i64.pow(b as u32)
-    } else {
-        parse_atomic_integer(s)?
+
+fn parse_rational(numerator: Part, denominator: Part) -> Result<BigRational> {
+    let (n, d) = (
+        parse_big_integer(numerator)?,
+        parse_big_integer(denominator)?,
+    );
+    if d.is_zero() {
+        return Err(NumericLiteralError::ZeroDenominator {
+            literal: denominator.literal.to_string(),
+            position: denominator.position(denominator.start),
+        });
+    }
+    Ok(BigRational::new(n, d))
+}
+
+// Integer volumes are widths in bits: `u4` holds 0 to 15, `i4` ¯8 to 7.
+fn check_width(part: Part, value: i128, signed: bool, volume: u8) -> Result<()> {
+    let (min, max) = match signed {
+        true => (-(1i128 << (volume - 1)), (1i128 << (volume - 1)) - 1),
+        false => (0, (1i128 << volume) - 1),
+    };
+    if value < min || value > max {
+        let signature = if signed { 'i' } else { 'u' };
+        return Err(part.does_not_fit(format!("{}{}", signature, volume)));
+    }
+    Ok(())
+}
+
+// The `u` or `i` of an integer with the volume after it, if any, as a
+// literal of that type. The volume defaults to 64.
+fn integer_of_type(
+    literal: Part,
+    value: i128,
+    signature: char,
+    volume: Part,
+) -> Result<NumericLiteral> {
+    let signed = signature == 'i';
+    let width = match volume.text() {
+        "" => 64,
+        digits => digits
+            .parse::<u8>()
+            .ok()
+            .filter(|width| (1..=64).contains(width))
+            .ok_or_else(|| volume.invalid_volume(signature))?,
+    };
+    check_width(literal, value, signed, width)?;
+    Ok(match (signed, volume.text().is_empty()) {
+        (false, true) => NumericLiteral::SysUint(value as u64),
+        (true, true) => NumericLiteral::SysInt(value as i64),
+        (false, false) => NumericLiteral::Uint(width, value as u64),
+        (true, false) => NumericLiteral::Int(width, value as i64),
     })
 }
 
-enum ExtractSignatureAndVolumeResult {
-    Auto,
-    Signature(char),
-    SignatureAndVolume(char, u8),
+// Splits a literal written in another base, `0xFF`, `0b1010` or `0o17`
+// with an optional `¯`, into its sign, radix and the rest. `0b` on its own
+// is the boolean literal, not an empty binary one.
+fn split_radix_prefix(part: Part) -> Option<(bool, u32, Part)> {
+    let negative = part.negative();
+    let skip = if negative { '¯'.len_utf8() } else { 0 };
+    let radix = match part.text().get(skip..skip + 2)? {
+        "0x" => 16,
+        "0o" => 8,
+        "0b" => 2,
+        _ => return None,
+    };
+    let rest = part.split_at(skip + 2).1;
+    match rest.text().chars().next()?.is_digit(radix) {
+        true => Some((negative, radix, rest)),
+        false => None,
+    }
 }
 
-fn extract_signature_and_volume_and_base(
-    s: &str,
-) -> anyhow::Result<(String, ExtractSignatureAndVolumeResult)> {
-    use ExtractSignatureAndVolumeResult::*;
-    let mut signature = None;
-    let mut volume = String::new();
-    let mut return_string = String::new();
-
-    for char in s.chars() {
-        if let Some(_) = signature {
-            volume.push(char);
-        } else if char == 'b' {
-            return Ok((return_string, Signature('b')));
-        } else if char == 'd' {
-            return Ok((return_string, SignatureAndVolume('f', 6)));
-        } else if char == 'h' {
-            return Ok((return_string, SignatureAndVolume('f', 4)));
-        } else if char == 'c' {
-            return Ok((return_string, SignatureAndVolume('c', 7)));
-        } else if char == 'f' {
-            signature = Some('f');
-        } else if char == 'i' {
-            signature = Some('i');
-        } else if char == 'u' {
-            signature = Some('u');
-        } else {
-            return_string.push(char)
+// A literal in another base. Only integers can be written this way, with
+// the same `u`, `i` and `n` suffixes as decimal ones; it becomes the same
+// literal as its decimal equivalent.
+fn parse_based(literal: Part, negative: bool, radix: u32, rest: Part) -> Result<NumericLiteral> {
+    let split = rest
+        .text()
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(rest.text().len());
+    let (digits, suffix) = rest.split_at(split);
+    let signature = suffix.text().chars().next();
+    if !matches!(signature, None | Some('u' | 'i' | 'n')) {
+        return Err(suffix.unexpected(suffix.start));
+    }
+    let volume = suffix.split_at(suffix.text().len().min(1)).1;
+
+    if signature == Some('n') {
+        if !volume.text().is_empty() {
+            return Err(volume.unexpected(volume.start));
         }
+        let magnitude = BigInt::parse_bytes(digits.text().as_bytes(), radix).unwrap();
+        return Ok(NumericLiteral::Big(if negative {
+            -magnitude
+        } else {
+            magnitude
+        }));
     }
+    let magnitude =
+        u64::from_str_radix(digits.text(), radix).map_err(|_| literal.does_not_fit("64 bits"))?;
+    let value = if negative {
+        -(magnitude as i128)
+    } else {
+        magnitude as i128
+    };
 
-    if let Some(signature) = signature {
-        return Ok((
-            return_string,
-            if signature == 'f' && volume.len() == 0 {
-                SignatureAndVolume('f', 5)
-            } else if volume.len() == 0 {
-                Signature(signature)
-            } else {
-                SignatureAndVolume(signature, volume.parse::<u8>()?)
-            },
-        ));
+    match signature {
+        Some(signature) => integer_of_type(literal, value, signature, volume),
+        None => Ok(NumericLiteral::Auto(value as f64)),
     }
-    // if (let Some('f') = signature ) && volume.len() == 0{}
+}
+
+// The letters that start a type suffix. Only `f`, `u` and `i` take a volume
+// after them; `d` and `h` are `f6` and `f4`.
+const SUFFIXES: &[char] = &['b', 'n', 'd', 'h', 'c', 'f', 'i', 'u'];
+
+fn parse_decimal(literal: Part) -> Result<NumericLiteral> {
+    let at = match literal.text().find(SUFFIXES) {
+        Some(at) => at,
+        None => return Ok(NumericLiteral::Auto(parse_float(literal)?)),
+    };
+    let (number, suffix) = literal.split_at(at);
+    // The suffix letters are all ASCII
+    let (signature, volume) = suffix.split_at(1);
+    let signature = signature.text().chars().next().unwrap();
 
-    Ok((return_string, Auto))
+    if signature == 'c' {
+        return Err(NumericLiteralError::Unsupported {
+            literal: literal.literal.to_string(),
+            position: suffix.position(suffix.start),
+            suffix: signature,
+        });
+    }
+    if matches!(signature, 'b' | 'n' | 'd' | 'h') && !volume.text().is_empty() {
+        return Err(volume.unexpected(volume.start));
+    }
+
+    match signature {
+        'b' => match parse_integer(number)? {
+            value @ (0 | 1) => Ok(NumericLiteral::Boolean(value == 1)),
+            _ => Err(literal.does_not_fit("b")),
+        },
+        'n' => Ok(NumericLiteral::Big(parse_big_integer(number)?)),
+        'u' | 'i' => integer_of_type(literal, parse_integer(number)?, signature, volume),
+        _ => {
+            let invalid = || volume.invalid_volume('f');
+            let volume = match (signature, volume.text()) {
+                ('d', _) => 6,
+                ('h', _) => 4,
+                (_, "") => 5,
+                (_, digits) => digits.parse::<u8>().map_err(|_| invalid())?,
+            };
+            let precision = Precision::from_volume(volume).map_err(|_| invalid())?;
+            match precision.round(parse_float(number)?) {
+                n if n.is_finite() => Ok(NumericLiteral::Float(volume, n)),
+                _ => Err(literal.does_not_fit(format!("f{}", volume))),
+            }
+        }
+    }
 }
 
 impl std::str::FromStr for NumericLiteral {
-    type Err = anyhow::Error;
+    type Err = NumericLiteralError;
 
-    fn from_str(s: &str) -> anyhow::Result<Self> {
-        if let Some((a, b)) = s.split_once('J') {
+    fn from_str(s: &str) -> Result<Self> {
+        let literal = Part::whole(s);
+        if s.is_empty() {
+            Err(NumericLiteralError::Empty)
+        } else if let Some((negative, radix, rest)) = split_radix_prefix(literal) {
+            parse_based(literal, negative, radix, rest)
+        } else if let Some((numerator, denominator)) = literal.split_once('r') {
+            Ok(NumericLiteral::Rational(parse_rational(
+                numerator,
+                denominator,
+            )?))
+        } else if let Some((real, imaginary)) = literal.split_once('J') {
             Ok(NumericLiteral::Complex(
-                parse_exponentiated_float(a)?,
-                parse_exponentiated_float(b)?,
+                parse_float(real)?,
+                parse_float(imaginary)?,
             ))
         } else {
-            let (s, vol_sig_auto) = extract_signature_and_volume_and_base(s)?;
-
-            match vol_sig_auto {
-                ExtractSignatureAndVolumeResult::Auto => {
-                    Ok(NumericLiteral::Auto(parse_exponentiated_float(s.as_str())?))
-                }
-                ExtractSignatureAndVolumeResult::Signature(signature) => match signature {
-                    'u' => Ok(NumericLiteral::SysUint(parse_exponentiated_unsigned(
-                        s.as_str(),
-                    )?)),
-                    'i' => Ok(NumericLiteral::SysInt(parse_exponentiated_int(s.as_str())?)),
-                    'b' => Ok(NumericLiteral::Boolean(s != "0")),
-                    _ => panic!("Un implemented literal"),
-                },
-                ExtractSignatureAndVolumeResult::SignatureAndVolume(signature, volume) => {
-                    match signature {
-                        'u' => Ok(NumericLiteral::Uint(
-                            volume,
-                            parse_exponentiated_unsigned(s.as_str())?,
-                        )),
-                        'i' => Ok(NumericLiteral::Int(
-                            volume,
-                            parse_exponentiated_int(s.as_str())?,
-                        )),
-                        'f' => Ok(NumericLiteral::Float(
-                            volume,
-                            parse_exponentiated_float(s.as_str())?,
-                        )),
-                        _ => panic!("Un implemented literal"),
-                    }
-                }
-            }
+            parse_decimal(literal)
         }
     }
 }
 
-impl ToString for NumericLiteral {
-    fn to_string(&self) -> String {
+// Writes a number with APL's high minus, so that the output lexes back to
+// the same literal.
+fn high_minus(n: impl std::fmt::Display) -> String {
+    n.to_string().replace('-', "¯")
+}
+
+impl std::fmt::Display for NumericLiteral {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
-            NumericLiteral::Complex(a, b) => format!("{}J{}", a, b),
-            NumericLiteral::Float(size, n) => format!("{}f{}", n, size),
-            NumericLiteral::SysUint(n) => format!("{}u", n),
-            NumericLiteral::SysInt(n) => format!("{}i", n),
-            NumericLiteral::Uint(size, n) => format!("{}u{}", n, size),
-            NumericLiteral::Int(size, n) => format!("{}i{}", n, size),
-            NumericLiteral::Auto(n) => format!("{}", n),
-            NumericLiteral::Boolean(true) => "1b".to_string(),
-            NumericLiteral::Boolean(false) => "0b".to_string(),
+            NumericLiteral::Complex(a, b) => {
+                write!(formatter, "{}J{}", high_minus(a), high_minus(b))
+            }
+            NumericLiteral::Float(size, n) => write!(formatter, "{}f{}", high_minus(n), size),
+            NumericLiteral::Big(ref n) => write!(formatter, "{}n", high_minus(n)),
+            NumericLiteral::Rational(ref q) => write!(
+                formatter,
+                "{}r{}",
+                high_minus(q.numer()),
+                high_minus(q.denom())
+            ),
+            NumericLiteral::SysUint(n) => write!(formatter, "{}u", n),
+            NumericLiteral::SysInt(n) => write!(formatter, "{}i", high_minus(n)),
+            NumericLiteral::Uint(size, n) => write!(formatter, "{}u{}", n, size),
+            NumericLiteral::Int(size, n) => write!(formatter, "{}i{}", high_minus(n), size),
+            NumericLiteral::Auto(n) => write!(formatter, "{}", high_minus(n)),
+            NumericLiteral::Boolean(true) => formatter.write_str("1b"),
+            NumericLiteral::Boolean(false) => formatter.write_str("0b"),
         }
     }
 }
 #[cfg(test)]
 mod tests {
-    use super::NumericLiteral;
+    use super::{NumericLiteral, NumericLiteralError};
     #[test]
     fn it_parses_integer_complex() {
         assert_eq!(
@@ -217,6 +512,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_writes_complex_with_high_minus() {
+        for source in ["10J10", "1.5J¯2", "¯3J0.25"] {
+            let literal = source.parse::<NumericLiteral>().unwrap();
+            assert_eq!(literal.to_string(), source);
+        }
+    }
+
     #[test]
     fn it_parses_simple_auto() {
         assert_eq!(
@@ -236,72 +539,124 @@ mod tests {
             NumericLiteral::Float(5, 1234e2f64),
             "1234E2f".parse::<NumericLiteral>().unwrap()
         );
+        // Rounded to the declared precision as they are parsed
         assert_eq!(
-            NumericLiteral::Float(5, 1234e-2f64),
+            NumericLiteral::Float(5, 1234e-2f32 as f64),
             "1234E¯2f".parse::<NumericLiteral>().unwrap()
         );
 
         assert_eq!(
-            NumericLiteral::Float(4, 1234e-2f64),
+            NumericLiteral::Float(4, 12.34375),
             "1234E¯2f4".parse::<NumericLiteral>().unwrap()
         );
         assert_eq!(
-            NumericLiteral::Float(5, 1234e-2f64),
+            NumericLiteral::Float(4, 12.34375),
+            "1234E¯2h".parse::<NumericLiteral>().unwrap()
+        );
+        assert_eq!(
+            NumericLiteral::Float(5, 1234e-2f32 as f64),
             "1234E¯2f5".parse::<NumericLiteral>().unwrap()
         );
+        assert_eq!(
+            NumericLiteral::Float(6, 1234e-2f64),
+            "1234E¯2d".parse::<NumericLiteral>().unwrap()
+        );
+        assert!("1f3".parse::<NumericLiteral>().is_err());
+        assert!("1f7".parse::<NumericLiteral>().is_err());
+    }
+
+    #[test]
+    fn it_rejects_floats_too_large_for_their_precision() {
+        for (source, bound) in [
+            ("1E400", "f6"),
+            ("¯1E400", "f6"),
+            ("1E400J1", "f6"),
+            ("1J¯1E400", "f6"),
+            ("1E400d", "f6"),
+            ("1E39f", "f5"),
+            ("1E5h", "f4"),
+            ("¯7E4f4", "f4"),
+        ] {
+            assert_eq!(
+                source.parse::<NumericLiteral>().unwrap_err().to_string(),
+                format!("{} does not fit in {}", source, bound)
+            );
+        }
+        // The largest of each still reads, and writes back the same
+        for source in ["1.7976931348623157E308", "3.4028235E38f", "65504h"] {
+            let literal = source.parse::<NumericLiteral>().unwrap();
+            assert_eq!(literal.to_string().parse(), Ok(literal));
+        }
     }
 
     #[test]
     fn it_parses_unsigned() {
         assert_eq!(
-            NumericLiteral::Uint(4, 123400u64),
-            "1234E2u4".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Uint(32, 123400u64),
+            "1234E2u32".parse::<NumericLiteral>().unwrap()
         );
         assert_eq!(
-            NumericLiteral::Uint(4, 12340u64),
-            "1234E1u4".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Uint(16, 12340u64),
+            "1234E1u16".parse::<NumericLiteral>().unwrap()
         );
         assert_eq!(
-            NumericLiteral::Uint(3, 12330u64),
-            "1233E1u3".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Uint(4, 15u64),
+            "15u4".parse::<NumericLiteral>().unwrap()
         );
-        // assert_eq!(
-        //     anyhow::Error::new("invalid u64 value"),
-        //     "1233E¯2u3".parse::<NumericLiteral>().unwrap_err()
-        // );
+        assert_eq!(
+            NumericLiteral::Uint(64, u64::MAX),
+            "18446744073709551615u64".parse::<NumericLiteral>().unwrap()
+        );
+        assert!("1233E¯2u3".parse::<NumericLiteral>().is_err());
     }
 
     #[test]
     fn it_parses_ints() {
         assert_eq!(
-            NumericLiteral::Int(4, 123400i64),
-            "1234E2i4".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Int(32, 123400i64),
+            "1234E2i32".parse::<NumericLiteral>().unwrap()
         );
         assert_eq!(
-            NumericLiteral::Int(4, 12340i64),
-            "1234E1i4".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Int(16, 12340i64),
+            "1234E1i16".parse::<NumericLiteral>().unwrap()
         );
         assert_eq!(
-            NumericLiteral::Int(3, 12330i64),
-            "1233E1i3".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Int(32, -123400i64),
+            "¯1234E2i32".parse::<NumericLiteral>().unwrap()
         );
-        // assert_eq!(
-        //     anyhow::Error::new("invalid u64 value"),
-        //     "1233E¯2i3".parse::<NumericLiteral>().unwrap_err()
-        // );
         assert_eq!(
-            NumericLiteral::Int(4, -123400i64),
-            "¯1234E2i4".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Int(4, 7i64),
+            "7i4".parse::<NumericLiteral>().unwrap()
         );
         assert_eq!(
-            NumericLiteral::Int(4, -12340i64),
-            "¯1234E1i4".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Int(4, -8i64),
+            "¯8i4".parse::<NumericLiteral>().unwrap()
         );
+        assert!("1233E¯2i3".parse::<NumericLiteral>().is_err());
+    }
+
+    #[test]
+    fn it_range_checks_integer_volumes() {
+        for source in [
+            "1234E2u4", "1234E1u4", "16u4", "1u0", "1u65", "¯1u8", "8i4", "¯9i4", "2i1", "1E20u",
+            "1E19i", "256u8",
+        ] {
+            assert!(
+                source.parse::<NumericLiteral>().is_err(),
+                "{} should be out of range",
+                source
+            );
+        }
         assert_eq!(
-            NumericLiteral::Int(3, -12330i64),
-            "¯1233E1i3".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Int(1, -1i64),
+            "¯1i1".parse::<NumericLiteral>().unwrap()
+        );
+        assert_eq!(
+            NumericLiteral::Uint(1, 1u64),
+            "1u1".parse::<NumericLiteral>().unwrap()
         );
     }
+
     #[test]
     fn it_parses_system_types() {
         assert_eq!(
@@ -321,13 +676,214 @@ mod tests {
             "1b".parse::<NumericLiteral>().unwrap()
         );
         assert_eq!(
-            NumericLiteral::Boolean(true),
-            "2b".parse::<NumericLiteral>().unwrap()
+            NumericLiteral::Boolean(false),
+            "0b".parse::<NumericLiteral>().unwrap()
         );
 
+        // Only 0 and 1 are booleans, as only they fit in u1
+        for literal in ["2b", "¯1b", "0.5b", "1E1b"] {
+            assert!(literal.parse::<NumericLiteral>().is_err(), "{}", literal);
+        }
         assert_eq!(
-            NumericLiteral::Boolean(false),
-            "0b".parse::<NumericLiteral>().unwrap()
+            "2b".parse::<NumericLiteral>().unwrap_err().to_string(),
+            "2b does not fit in b"
+        );
+    }
+
+    #[test]
+    fn it_parses_big_integers_and_rationals() {
+        use num_bigint::BigInt;
+        use num_rational::BigRational;
+
+        let big = |s: &str| s.parse::<BigInt>().unwrap();
+        assert_eq!(
+            "123456789012345678901234567890n"
+                .parse::<NumericLiteral>()
+                .unwrap(),
+            NumericLiteral::Big(big("123456789012345678901234567890"))
+        );
+        assert_eq!(
+            "¯2E30n".parse::<NumericLiteral>().unwrap(),
+            NumericLiteral::Big(big("-2000000000000000000000000000000"))
+        );
+        // 2r¯4 ←→ ¯1r2, in lowest terms
+        assert_eq!(
+            "2r¯4".parse::<NumericLiteral>().unwrap(),
+            NumericLiteral::Rational(BigRational::new(big("-1"), big("2")))
+        );
+        assert!("1r0".parse::<NumericLiteral>().is_err());
+        assert!("1.5n".parse::<NumericLiteral>().is_err());
+        assert!("1E1000000n".parse::<NumericLiteral>().is_err());
+
+        for source in ["¯12n", "¯1r3"] {
+            let literal = source.parse::<NumericLiteral>().unwrap();
+            assert_eq!(literal.to_string(), source);
+        }
+    }
+
+    #[test]
+    fn it_parses_other_bases() {
+        let parse = |s: &str| s.parse::<NumericLiteral>();
+
+        assert_eq!(parse("0xFFu8").unwrap(), NumericLiteral::Uint(8, 255));
+        assert_eq!(parse("0xffu8").unwrap(), NumericLiteral::Uint(8, 255));
+        assert_eq!(parse("0b1010u4").unwrap(), NumericLiteral::Uint(4, 10));
+        assert_eq!(parse("0o17i").unwrap(), NumericLiteral::SysInt(15));
+        assert_eq!(parse("¯0x80i8").unwrap(), NumericLiteral::Int(8, -128));
+        assert_eq!(parse("0x10").unwrap(), NumericLiteral::Auto(16.0));
+        assert_eq!(
+            parse("0xFFFFFFFFFFFFFFFFu").unwrap(),
+            NumericLiteral::SysUint(u64::MAX)
+        );
+        assert_eq!(
+            parse("0x1000000000000000000n").unwrap(),
+            NumericLiteral::Big(num_bigint::BigInt::from(1u128 << 72))
+        );
+
+        // Widths are checked like decimal ones
+        assert!(parse("0x100u8").is_err());
+        assert!(parse("0b10000u4").is_err());
+        assert!(parse("¯0x81i8").is_err());
+        assert!(parse("¯0x1u").is_err());
+        assert!(parse("0x10000000000000000u").is_err());
+        // Only integers: the `f` of `0x1f` is a digit, and there is no `h`
+        assert_eq!(parse("0x1f").unwrap(), NumericLiteral::Auto(31.0));
+        assert!(parse("0b1h").is_err());
+        assert!(parse("0o8").is_err());
+
+        // `0b` alone is still false
+        assert_eq!(parse("0b").unwrap(), NumericLiteral::Boolean(false));
+    }
+
+    #[test]
+    fn it_writes_based_literals_in_decimal() {
+        for (source, canonical) in [("0xFFu8", "255u8"), ("¯0b101i", "¯5i"), ("0o777n", "511n")] {
+            let literal = source.parse::<NumericLiteral>().unwrap();
+            assert_eq!(literal.to_string(), canonical);
+            assert_eq!(canonical.parse::<NumericLiteral>().unwrap(), literal);
+        }
+    }
+
+    #[test]
+    fn it_points_into_the_literal() {
+        use NumericLiteralError::*;
+        let parse = |s: &str| s.parse::<NumericLiteral>().unwrap_err();
+        let unexpected = |literal: &str, position, found, suggestion| UnexpectedChar {
+            literal: literal.to_string(),
+            position,
+            found,
+            suggestion,
+        };
+        let missing = |literal: &str, position| MissingDigits {
+            literal: literal.to_string(),
+            position,
+        };
+
+        assert_eq!(parse(""), Empty);
+        assert_eq!(parse("-5"), unexpected("-5", 0, '-', Some('¯')));
+        assert_eq!(parse("2J-1"), unexpected("2J-1", 2, '-', Some('¯')));
+        assert_eq!(parse("1e5"), unexpected("1e5", 1, 'e', Some('E')));
+        assert_eq!(parse("1JJ2"), unexpected("1JJ2", 2, 'J', None));
+        // Positions are in characters, not bytes
+        assert_eq!(parse("¯1.2.3"), unexpected("¯1.2.3", 4, '.', None));
+        assert_eq!(parse("1E"), missing("1E", 2));
+        assert_eq!(parse("¯"), missing("¯", 1));
+        assert_eq!(parse("r3"), missing("r3", 0));
+        assert_eq!(parse("1bx"), unexpected("1bx", 2, 'x', None));
+        assert_eq!(parse("0b1h"), unexpected("0b1h", 3, 'h', None));
+        assert_eq!(
+            parse("1c"),
+            Unsupported {
+                literal: "1c".to_string(),
+                position: 1,
+                suffix: 'c'
+            }
+        );
+        assert_eq!(
+            parse("1f3"),
+            InvalidVolume {
+                literal: "1f3".to_string(),
+                position: 2,
+                signature: 'f',
+                volume: "3".to_string()
+            }
+        );
+        assert_eq!(
+            parse("1u65"),
+            InvalidVolume {
+                literal: "1u65".to_string(),
+                position: 2,
+                signature: 'u',
+                volume: "65".to_string()
+            }
+        );
+        assert_eq!(
+            parse("256u8"),
+            DoesNotFit {
+                literal: "256u8".to_string(),
+                bound: "u8".to_string()
+            }
+        );
+        assert_eq!(
+            parse("1r¯0"),
+            ZeroDenominator {
+                literal: "1r¯0".to_string(),
+                position: 2
+            }
+        );
+
+        assert_eq!(
+            parse("-5").to_string(),
+            "unexpected '-' at 0 in -5, did you mean ¯ instead of -"
+        );
+        assert_eq!(
+            parse("1f3").to_string(),
+            "f3 at 2 in 1f3 is not a type, floats are f4, f5 and f6"
+        );
+    }
+
+    #[test]
+    fn it_never_panics() {
+        // Every string of up to four of these, which covers each suffix,
+        // separator and sign in each position, and some overlong ones.
+        let alphabet = [
+            '0', '1', '¯', '.', 'E', 'J', 'r', 'x', 'b', 'n', 'u', 'i', 'f', 'c', '-', 'e',
+        ];
+        let mut sources = vec![String::new()];
+        for _ in 0..4 {
+            sources = sources
+                .iter()
+                .flat_map(|source| {
+                    alphabet.iter().map(move |&c| {
+                        let mut longer = source.clone();
+                        longer.push(c);
+                        longer
+                    })
+                })
+                .collect();
+            for source in &sources {
+                if let Ok(literal) = source.parse::<NumericLiteral>() {
+                    let written = literal.to_string();
+                    assert_eq!(written.parse(), Ok(literal), "{}", source);
+                }
+            }
+        }
+        // Oversized parts are errors, or 0 for a float's exponent
+        for source in [
+            "1E99999999999999999999i",
+            "1E4294967296n",
+            "99999999999999999999999999999999999999999i",
+            "0x1FFFFFFFFFFFFFFFFi",
+            "1f256",
+            "1u256",
+            "1i0",
+            "1E99999999999999999999",
+        ] {
+            assert!(source.parse::<NumericLiteral>().is_err(), "{}", source);
+        }
+        assert_eq!(
+            "1E¯99999999999999999999".parse::<NumericLiteral>().unwrap(),
+            NumericLiteral::Auto(0.0)
         );
     }
 }
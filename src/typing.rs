@@ -1 +1,2 @@
 pub(crate) mod nameclass_map_extractor;
+pub(crate) mod numeric;
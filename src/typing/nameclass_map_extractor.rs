@@ -1,14 +1,15 @@
+#[allow(dead_code)]
 pub(crate) fn construct_nameclass_map(
     token_stream: &crate::tokenizer::bracket_partitioner::PartitionStream,
 ) {
-    for i in token_stream {}
+    for _i in token_stream {}
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::normalize_apl_code;
-    use crate::tokenize;
-    use crate::tokenize_to_partition;
+    use crate::normalizer::normalize_apl_code;
+    use crate::tokenizer::bracket_partitioner::tokenize_to_partition;
+    use crate::tokenizer::tokenize;
 
     #[test]
     fn experiment_simple() {
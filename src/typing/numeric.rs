@@ -0,0 +1,66 @@
+use crate::runtime::number::Precision;
+use crate::tokenizer::numeric_literal::NumericLiteral;
+
+/// The float type a strand of numeric literals is stored in: the narrowest
+/// precision common to the literals that declare one. Literals without a
+/// suffix take it on, so `1.5h 2 3` is three halves. `None` if no literal
+/// declares a precision.
+pub(crate) fn strand_precision<'a>(
+    literals: impl IntoIterator<Item = &'a NumericLiteral>,
+) -> anyhow::Result<Option<Precision>> {
+    let mut precision: Option<Precision> = None;
+    for literal in literals {
+        if let NumericLiteral::Float(volume, _) = literal {
+            let declared = Precision::from_volume(*volume)?;
+            precision = Some(precision.map_or(declared, |p| p.common(declared)));
+        }
+    }
+    Ok(precision)
+}
+
+/// Gives a literal without a suffix the strand's precision.
+pub(crate) fn settle(literal: &NumericLiteral, precision: Option<Precision>) -> NumericLiteral {
+    match (literal, precision) {
+        (NumericLiteral::Auto(n), Some(precision)) => {
+            NumericLiteral::Float(precision.volume(), precision.round(*n))
+        }
+        _ => literal.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{settle, strand_precision};
+    use crate::runtime::number::Precision;
+    use crate::tokenizer::numeric_literal::NumericLiteral;
+
+    fn literals(source: &str) -> Vec<NumericLiteral> {
+        source.split(' ').map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn it_picks_the_narrowest_common_precision() {
+        let precision = |source| strand_precision(&literals(source)).unwrap();
+
+        assert_eq!(precision("1 2 3"), None);
+        assert_eq!(precision("1.5h 2 3"), Some(Precision::Half));
+        assert_eq!(precision("1.5h 2f 3"), Some(Precision::Single));
+        assert_eq!(precision("1.5h 2d 3u8"), Some(Precision::Double));
+    }
+
+    #[test]
+    fn it_settles_unsuffixed_literals() {
+        assert_eq!(
+            settle(&NumericLiteral::Auto(0.1), Some(Precision::Half)),
+            NumericLiteral::Float(4, 0.0999755859375)
+        );
+        assert_eq!(
+            settle(&NumericLiteral::Auto(0.1), None),
+            NumericLiteral::Auto(0.1)
+        );
+        assert_eq!(
+            settle(&NumericLiteral::SysInt(1), Some(Precision::Half)),
+            NumericLiteral::SysInt(1)
+        );
+    }
+}